@@ -15,13 +15,18 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::path::{Path, PathBuf};
+
 use adw::prelude::*;
 use glib::subclass::types::ObjectSubclassIsExt;
 use glib::Object;
 use gtk::gio::{self, ActionEntryBuilder, Settings};
 use gtk::prelude::ActionMapExtManual;
+use sha2::{Digest, Sha256};
 
 use crate::config::{APP_ID, BASE_ID, RESOURCE_PATH};
+use crate::entities::{ClientOptions, HttpVersion, ProxyMode, ProxySettings};
+use crate::history::HistoryStore;
 use crate::win::CarteroWindow;
 
 #[macro_export]
@@ -87,6 +92,7 @@ mod imp {
             obj.set_accels_for_action("win.save-as", &[accelerator!("<Shift>s")]);
             obj.set_accels_for_action("win.close", &[accelerator!("w")]);
             obj.set_accels_for_action("win.request", &[accelerator!("Return")]);
+            obj.set_accels_for_action("win.preferences", &[accelerator!("comma")]);
             obj.set_accels_for_action("app.quit", &[accelerator!("q")]);
             obj.set_accels_for_action("win.show-help-overlay", &[accelerator!("question")]);
             obj.setup_app_actions();
@@ -149,6 +155,84 @@ impl CarteroApplication {
         self.imp().settings.get_or_init(|| Settings::new(BASE_ID))
     }
 
+    /// The preferences of the HTTP client, used for any option that a request
+    /// does not override.
+    pub fn client_options(&self) -> ClientOptions {
+        let settings = self.settings();
+        let http_version = match settings.string("http-version").as_str() {
+            "1.1" => HttpVersion::Http11,
+            "2" => HttpVersion::Http2,
+            _ => HttpVersion::Auto,
+        };
+        ClientOptions {
+            connect_timeout: Some(settings.uint("connect-timeout").into()),
+            timeout: Some(settings.uint("request-timeout").into()),
+            follow_redirects: Some(settings.boolean("follow-redirects")),
+            max_redirects: Some(settings.uint("max-redirects")),
+            verify_tls: Some(settings.boolean("verify-tls")),
+            http_version: Some(http_version),
+            proxy: Some(self.proxy_settings()),
+        }
+    }
+
+    /// The history of requests, which lives in the data directory of the user.
+    pub fn history(&self) -> HistoryStore {
+        let directory = glib::user_data_dir().join("cartero");
+        let limit = self.settings().uint("history-limit");
+        HistoryStore::new(&directory, limit as usize)
+    }
+
+    /// The file that keeps the cookies of the requests sent from the given
+    /// collection, or of the requests that do not belong to any collection.
+    pub fn cookie_jar_path(&self, collection: Option<&Path>) -> PathBuf {
+        let directory = glib::user_data_dir().join("cartero").join("cookies");
+        let name = match collection {
+            Some(root) => {
+                let digest = Sha256::digest(root.to_string_lossy().as_bytes());
+                let hex: String = digest[..8].iter().map(|b| format!("{b:02x}")).collect();
+                format!("{hex}.toml")
+            }
+            None => String::from("default.toml"),
+        };
+        directory.join(name)
+    }
+
+    /// The proxy configured in the preferences.
+    pub fn proxy_settings(&self) -> ProxySettings {
+        let settings = self.settings();
+        let mode = match settings.string("proxy-mode").as_str() {
+            "direct" => ProxyMode::Direct,
+            "manual" => ProxyMode::Manual,
+            _ => ProxyMode::System,
+        };
+        ProxySettings {
+            mode,
+            url: settings.string("proxy-url").into(),
+            username: settings.string("proxy-username").into(),
+            password: settings.string("proxy-password").into(),
+            no_proxy: settings
+                .strv("proxy-no-proxy")
+                .iter()
+                .map(|host| host.to_string())
+                .collect(),
+        }
+    }
+
+    pub fn set_proxy_settings(&self, proxy: &ProxySettings) -> Result<(), glib::BoolError> {
+        let settings = self.settings();
+        let mode = match proxy.mode {
+            ProxyMode::System => "system",
+            ProxyMode::Direct => "direct",
+            ProxyMode::Manual => "manual",
+        };
+        settings.set_string("proxy-mode", mode)?;
+        settings.set_string("proxy-url", &proxy.url)?;
+        settings.set_string("proxy-username", &proxy.username)?;
+        settings.set_string("proxy-password", &proxy.password)?;
+        settings.set_strv("proxy-no-proxy", proxy.no_proxy.as_slice())?;
+        Ok(())
+    }
+
     fn setup_app_actions(&self) {
         let quit = ActionEntryBuilder::new("quit")
             .activate(glib::clone!(@weak self as app => move |_, _, _| {
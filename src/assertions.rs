@@ -0,0 +1,252 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Evaluation of the tests stored in a request file against a response.
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::entities::{Assertion, ResponseData};
+
+/// The outcome of evaluating a single assertion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssertionResult {
+    pub assertion: Assertion,
+    pub passed: bool,
+    /// Explains why the assertion failed, when it did.
+    pub details: Option<String>,
+}
+
+impl AssertionResult {
+    fn pass(assertion: &Assertion) -> Self {
+        Self {
+            assertion: assertion.clone(),
+            passed: true,
+            details: None,
+        }
+    }
+
+    fn fail(assertion: &Assertion, details: String) -> Self {
+        Self {
+            assertion: assertion.clone(),
+            passed: false,
+            details: Some(details),
+        }
+    }
+
+    fn check(assertion: &Assertion, passed: bool, details: impl FnOnce() -> String) -> Self {
+        if passed {
+            Self::pass(assertion)
+        } else {
+            Self::fail(assertion, details())
+        }
+    }
+}
+
+/// The JSON body of the response is only parsed when an assertion requires it.
+pub(crate) struct JsonBody<'a> {
+    response: &'a ResponseData,
+    parsed: Option<Option<Value>>,
+}
+
+impl<'a> JsonBody<'a> {
+    pub(crate) fn new(response: &'a ResponseData) -> Self {
+        Self {
+            response,
+            parsed: None,
+        }
+    }
+
+    pub(crate) fn value(&mut self) -> Option<&Value> {
+        let response = self.response;
+        self.parsed
+            .get_or_insert_with(|| serde_json::from_slice(&response.body).ok())
+            .as_ref()
+    }
+}
+
+/// Textual representation used to compare JSON values with a pattern.
+pub(crate) fn json_to_text(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn evaluate_with(
+    assertion: &Assertion,
+    response: &ResponseData,
+    json: &mut JsonBody,
+) -> AssertionResult {
+    match assertion {
+        Assertion::StatusEquals(code) => {
+            AssertionResult::check(assertion, response.status_code == *code, || {
+                format!("got {}", response.status_code)
+            })
+        }
+        Assertion::StatusInRange { min, max } => {
+            let passed = (*min..=*max).contains(&response.status_code);
+            AssertionResult::check(assertion, passed, || {
+                format!("got {}", response.status_code)
+            })
+        }
+        Assertion::HeaderPresent(name) => {
+            AssertionResult::check(assertion, response.headers.header(name).is_some(), || {
+                "header is missing".into()
+            })
+        }
+        Assertion::HeaderEquals { name, value } => match response.headers.header(name) {
+            Some(values) => {
+                AssertionResult::check(assertion, values.contains(&value.as_str()), || {
+                    format!("got {:?}", values.join(", "))
+                })
+            }
+            None => AssertionResult::fail(assertion, "header is missing".into()),
+        },
+        Assertion::JsonExists(pointer)
+        | Assertion::JsonEquals { pointer, .. }
+        | Assertion::JsonMatches { pointer, .. } => {
+            let Some(body) = json.value() else {
+                return AssertionResult::fail(assertion, "body is not valid JSON".into());
+            };
+            let Some(found) = body.pointer(pointer) else {
+                return AssertionResult::fail(assertion, "no value at this pointer".into());
+            };
+            match assertion {
+                Assertion::JsonEquals { value, .. } => {
+                    AssertionResult::check(assertion, found == value, || format!("got {found}"))
+                }
+                Assertion::JsonMatches { pattern, .. } => match Regex::new(pattern) {
+                    Ok(regex) => {
+                        let text = json_to_text(found);
+                        AssertionResult::check(assertion, regex.is_match(&text), || {
+                            format!("got {found}")
+                        })
+                    }
+                    Err(_) => AssertionResult::fail(assertion, "invalid regular expression".into()),
+                },
+                _ => AssertionResult::pass(assertion),
+            }
+        }
+        Assertion::BodyContains(text) => {
+            let body = String::from_utf8_lossy(&response.body);
+            AssertionResult::check(assertion, body.contains(text.as_str()), || {
+                "text not found in the body".into()
+            })
+        }
+        Assertion::MaxDuration(max) => {
+            AssertionResult::check(assertion, response.duration <= *max, || {
+                format!("took {} ms", response.duration)
+            })
+        }
+    }
+}
+
+/// Evaluates a single assertion against the given response.
+pub fn evaluate(assertion: &Assertion, response: &ResponseData) -> AssertionResult {
+    evaluate_with(assertion, response, &mut JsonBody::new(response))
+}
+
+/// Evaluates every assertion against the given response, keeping the same order.
+pub fn evaluate_all(assertions: &[Assertion], response: &ResponseData) -> Vec<AssertionResult> {
+    let mut json = JsonBody::new(response);
+    assertions
+        .iter()
+        .map(|assertion| evaluate_with(assertion, response, &mut json))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{evaluate, evaluate_all};
+    use crate::entities::{Assertion, KeyValueTable, ResponseData};
+
+    fn response(status_code: u32, body: &str) -> ResponseData {
+        ResponseData {
+            status_code,
+            duration: 120,
+            size: body.len(),
+            headers: KeyValueTable::new(&[("Content-Type", "application/json").into()]),
+            body: Vec::from(body),
+        }
+    }
+
+    #[test]
+    fn test_status_assertions() {
+        let ok = response(204, "");
+        assert!(evaluate(&Assertion::StatusEquals(204), &ok).passed);
+        assert!(evaluate(&Assertion::StatusInRange { min: 200, max: 299 }, &ok).passed);
+
+        let failed = evaluate(&Assertion::StatusEquals(200), &ok);
+        assert!(!failed.passed);
+        assert_eq!(failed.details.as_deref(), Some("got 204"));
+    }
+
+    #[test]
+    fn test_header_assertions() {
+        let res = response(200, "");
+        assert!(evaluate(&Assertion::HeaderPresent("content-type".into()), &res).passed);
+        assert!(!evaluate(&Assertion::HeaderPresent("ETag".into()), &res).passed);
+
+        let equals = Assertion::HeaderEquals {
+            name: "Content-Type".into(),
+            value: "application/json".into(),
+        };
+        assert!(evaluate(&equals, &res).passed);
+    }
+
+    #[test]
+    fn test_json_assertions() {
+        let res = response(200, r#"{"data": {"id": 3, "name": "cartero"}}"#);
+        let results = evaluate_all(
+            &[
+                Assertion::JsonExists("/data/id".into()),
+                Assertion::JsonEquals {
+                    pointer: "/data/id".into(),
+                    value: json!(3),
+                },
+                Assertion::JsonMatches {
+                    pointer: "/data/name".into(),
+                    pattern: "^cart".into(),
+                },
+                Assertion::JsonExists("/data/missing".into()),
+                Assertion::JsonMatches {
+                    pointer: "/data/name".into(),
+                    pattern: "(".into(),
+                },
+            ],
+            &res,
+        );
+        let passed: Vec<bool> = results.iter().map(|r| r.passed).collect();
+        assert_eq!(passed, vec![true, true, true, false, false]);
+
+        let not_json = response(200, "<html></html>");
+        let result = evaluate(&Assertion::JsonExists("/data".into()), &not_json);
+        assert_eq!(result.details.as_deref(), Some("body is not valid JSON"));
+    }
+
+    #[test]
+    fn test_body_and_duration_assertions() {
+        let res = response(200, "Hello world");
+        assert!(evaluate(&Assertion::BodyContains("world".into()), &res).passed);
+        assert!(!evaluate(&Assertion::BodyContains("moon".into()), &res).passed);
+        assert!(evaluate(&Assertion::MaxDuration(500), &res).passed);
+        assert!(!evaluate(&Assertion::MaxDuration(100), &res).passed);
+    }
+}
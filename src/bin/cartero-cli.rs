@@ -0,0 +1,498 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Headless runner for Cartero request files.
+//!
+//! This binary does not depend on GTK, so that saved requests can be executed in
+//! environments without a display, such as continuous integration servers.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use futures_lite::Future;
+use isahc::RequestExt;
+
+use cartero::assertions::AssertionResult;
+use cartero::client::{read_isahc_response, timed, RequestError, TokenCache};
+use cartero::collection::Collection;
+use cartero::entities::{Environment, ResponseData};
+use cartero::error::CarteroError;
+use cartero::runner::{
+    collection_items, load_data_file, RunResult, RunStep, Runner, RunnerItem, RunnerOptions,
+    REQUEST_TIMEOUT, STREAM_DURATION,
+};
+
+/// Every request was sent and got a successful (1xx, 2xx or 3xx) response.
+const EXIT_OK: u8 = 0;
+
+/// At least one request got a 4xx or 5xx response, or failed one of its tests.
+const EXIT_HTTP_FAILURE: u8 = 1;
+
+/// At least one request could not be sent at all.
+const EXIT_REQUEST_FAILURE: u8 = 2;
+
+/// The command line was not valid (same value as EX_USAGE in sysexits.h).
+const EXIT_USAGE: u8 = 64;
+
+const USAGE: &str = "Usage: cartero-cli [OPTIONS] FILE...
+
+Sends the requests stored in the given .cartero files and prints the responses.
+A folder of a collection stands for every request in it, in collection order.
+
+Options:
+  -e, --environment NAME  Use the variables of an environment of the collection
+  -n, --iterations N      Send every request N times
+  -d, --data FILE         Bind the variables of a CSV or JSON file, one row for
+                          every iteration
+      --delay MS          Wait MS milliseconds between two requests
+      --stop-on-failure   Stop after the first request that fails
+  -i, --include           Print the response headers
+  -s, --silent            Only print the status line of every response
+  -h, --help              Print this help and exit
+  -V, --version           Print the version and exit
+
+Requests with a [[tests]] section are checked against their tests instead
+of their status code, and the outcome of every test is printed.
+
+Values taken from a response by an [[extract]] section are available to the
+requests that follow in the same run. They are not written to the collection.
+
+When a data file is given without --iterations, the requests are sent once for
+every row of the file. A summary is printed when more than one request is sent.
+
+Exit status:
+  0   Every request got a successful response
+  1   At least one request got a 4xx or 5xx response or failed a test
+  2   At least one request could not be sent
+  64  The command line is not valid";
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Options {
+    environment: Option<String>,
+    iterations: Option<u32>,
+    data: Option<PathBuf>,
+    delay: u64,
+    stop_on_failure: bool,
+    include: bool,
+    silent: bool,
+    files: Vec<PathBuf>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Run(Options),
+    Help,
+    Version,
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{option} requires a number, got {value:?}"))
+}
+
+fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<Command, String> {
+    let mut options = Options::default();
+    let mut args = args.peekable();
+    let mut only_files = false;
+
+    while let Some(arg) = args.next() {
+        if only_files || !arg.starts_with('-') || arg == "-" {
+            options.files.push(PathBuf::from(arg));
+            continue;
+        }
+        // Long options also accept their value after an equals sign.
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = |what: &str| match inline.clone().or_else(|| args.next()) {
+            Some(value) => Ok(value),
+            None => Err(format!("{name} requires {what}")),
+        };
+        match name {
+            "--" => only_files = true,
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-i" | "--include" => options.include = true,
+            "-s" | "--silent" => options.silent = true,
+            "--stop-on-failure" => options.stop_on_failure = true,
+            "-e" | "--environment" => options.environment = Some(value("an environment name")?),
+            "-d" | "--data" => options.data = Some(PathBuf::from(value("a data file")?)),
+            "-n" | "--iterations" => {
+                options.iterations = Some(parse_number(name, &value("a number")?)?)
+            }
+            "--delay" => options.delay = parse_number(name, &value("a number")?)?,
+            _ => return Err(format!("unknown option: {arg}")),
+        }
+    }
+
+    if options.files.is_empty() {
+        return Err("no request files given".into());
+    }
+    Ok(Command::Run(options))
+}
+
+/// Builds the environment used to send the file, with the variables of the
+/// collection that contains it under the ones of the requested environment.
+fn load_environment(file: &Path, name: Option<&str>) -> Result<Option<Environment>, CarteroError> {
+    let file = file.canonicalize()?;
+    let folder = file.parent().ok_or(CarteroError::NotACollection)?;
+    let collection = match Collection::find_root(folder) {
+        Some(root) => Some(Collection::open(&root)?),
+        None => None,
+    };
+    let environment = match (name, &collection) {
+        (Some(name), Some(collection)) => Some(collection.load_environment(name)?),
+        (Some(_), None) => return Err(CarteroError::NotACollection),
+        (None, _) => None,
+    };
+    Ok(match &collection {
+        Some(collection) => collection.scoped_environment(environment),
+        None => environment,
+    })
+}
+
+/// Turns the paths given in the command line into the requests of the run.
+fn load_items(files: &[PathBuf]) -> Result<Vec<RunnerItem>, CarteroError> {
+    let mut items = Vec::new();
+    for path in files {
+        if path.is_dir() {
+            let folder = path.canonicalize()?;
+            let root = Collection::find_root(&folder).ok_or(CarteroError::NotACollection)?;
+            let collection = Collection::open(&root)?;
+            items.extend(collection_items(&collection, &folder)?);
+        } else {
+            items.push(RunnerItem::new(&path.display().to_string(), path));
+        }
+    }
+    Ok(items)
+}
+
+/// A future that completes once the given time has passed. There is no event
+/// loop with timers here, so a thread waits for it.
+fn timer(duration: Duration) -> impl Future<Output = ()> {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    std::thread::spawn(move || {
+        std::thread::sleep(duration);
+        let _ = sender.send(());
+    });
+    async move {
+        let _ = receiver.await;
+    }
+}
+
+/// Sends the request of the step. Requests that take too long are given up,
+/// and event streams are only listened to for a while.
+fn send_step(
+    runner: &mut Runner,
+    step: &RunStep,
+    options: &Options,
+) -> Result<ResponseData, CarteroError> {
+    let file = runner.item(step).path.clone();
+    let environment = load_environment(&file, options.environment.as_deref())?;
+    let mut request = runner.prepare(step, environment.as_ref())?;
+
+    futures_lite::future::block_on(async {
+        request
+            .authorize(TokenCache::shared(), |url| {
+                eprintln!("Open the following URL to authorize the request:\n{url}");
+            })
+            .await?;
+        let request = isahc::Request::try_from(request)?;
+        let exchange = async {
+            let start = Instant::now();
+            let mut response = request
+                .send_async()
+                .await
+                .map_err(RequestError::NetworkError)?;
+            let stop = timer(STREAM_DURATION);
+            Ok(read_isahc_response(&mut response, &start, stop).await?)
+        };
+        timed(exchange, timer(REQUEST_TIMEOUT)).await
+    })
+}
+
+fn print_response<W: Write>(
+    out: &mut W,
+    response: &ResponseData,
+    options: &Options,
+) -> std::io::Result<()> {
+    writeln!(
+        out,
+        "HTTP {} ({} s, {} bytes)",
+        response.status_code,
+        response.seconds(),
+        response.size
+    )?;
+    if options.silent {
+        return Ok(());
+    }
+    if options.include {
+        for header in response.headers.iter() {
+            writeln!(out, "{}: {}", header.name, header.value)?;
+        }
+        writeln!(out)?;
+    }
+    out.write_all(&response.body)?;
+    if !response.body.ends_with(b"\n") {
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn print_results<W: Write>(out: &mut W, results: &[AssertionResult]) -> std::io::Result<()> {
+    for result in results {
+        let outcome = if result.passed { "PASS" } else { "FAIL" };
+        match &result.details {
+            Some(details) => writeln!(out, "{outcome} {} ({details})", result.assertion)?,
+            None => writeln!(out, "{outcome} {}", result.assertion)?,
+        }
+    }
+    Ok(())
+}
+
+/// Prints a table with the outcome of every request, followed by the totals.
+fn print_summary<W: Write>(out: &mut W, runner: &Runner) -> std::io::Result<()> {
+    let results = runner.results();
+    let iterations = results.iter().any(|result| result.iteration > 1);
+    let width = results
+        .iter()
+        .map(|result| result.item.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("REQUEST".len());
+
+    let row =
+        |out: &mut W, iteration: &str, name: &str, status: &str, time: &str, outcome: &str| {
+            if iterations {
+                write!(out, "{iteration:>4}  ")?;
+            }
+            writeln!(out, "{name:<width$}  {status:>6}  {time:>9}  {outcome}")
+        };
+    row(out, "#", "REQUEST", "STATUS", "TIME", "RESULT")?;
+    for result in results {
+        let status = match result.status_code {
+            Some(code) => code.to_string(),
+            None => "-".into(),
+        };
+        let time = match result.duration {
+            Some(duration) => format!("{duration} ms"),
+            None => "-".into(),
+        };
+        let outcome = match (result.passed, &result.error) {
+            (true, _) => "PASS",
+            (false, None) => "FAIL",
+            (false, Some(_)) => "ERROR",
+        };
+        let iteration = result.iteration.to_string();
+        row(out, &iteration, &result.item.name, &status, &time, outcome)?;
+    }
+
+    let summary = runner.summary();
+    write!(
+        out,
+        "\n{} passed, {} failed",
+        summary.passed, summary.failed
+    )?;
+    if summary.skipped > 0 {
+        write!(out, ", {} skipped", summary.skipped)?;
+    }
+    writeln!(out, " ({} ms)", summary.duration)
+}
+
+/// The exit status that a single request deserves.
+fn exit_status(result: &RunResult) -> u8 {
+    match (&result.error, result.passed) {
+        (Some(_), _) => EXIT_REQUEST_FAILURE,
+        (None, false) => EXIT_HTTP_FAILURE,
+        (None, true) => EXIT_OK,
+    }
+}
+
+fn run(options: &Options) -> u8 {
+    let items = match load_items(&options.files) {
+        Ok(items) => items,
+        Err(e) => {
+            eprintln!("cartero-cli: {e}");
+            return EXIT_REQUEST_FAILURE;
+        }
+    };
+    let data = match &options.data {
+        Some(path) => match load_data_file(path) {
+            Ok(rows) => rows,
+            Err(e) => {
+                eprintln!("cartero-cli: {}: {e}", path.display());
+                return EXIT_REQUEST_FAILURE;
+            }
+        },
+        None => Vec::new(),
+    };
+    let mut runner = Runner::new(
+        items,
+        RunnerOptions {
+            iterations: options.iterations,
+            delay: Duration::from_millis(options.delay),
+            stop_on_failure: options.stop_on_failure,
+            data,
+        },
+    );
+
+    let mut status = EXIT_OK;
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    let show_names = runner.total_steps() > 1;
+
+    while let Some(step) = runner.next_step() {
+        std::thread::sleep(step.delay);
+        let name = runner.item(&step).name.clone();
+        if show_names {
+            let _ = if step.iteration > 1 {
+                writeln!(out, "==> {name} (iteration {}) <==", step.iteration)
+            } else {
+                writeln!(out, "==> {name} <==")
+            };
+        }
+        let outcome = send_step(&mut runner, &step, options);
+        if let Ok(response) = &outcome {
+            if print_response(&mut out, response, options).is_err() {
+                return EXIT_REQUEST_FAILURE;
+            }
+        }
+        let result = runner.complete(&step, outcome.as_ref());
+        if print_results(&mut out, &result.tests).is_err() {
+            return EXIT_REQUEST_FAILURE;
+        }
+        let _ = out.flush();
+        if let Some(error) = &result.error {
+            eprintln!("cartero-cli: {name}: {error}");
+        }
+        for extraction in &result.extractions {
+            if let Err(e) = &extraction.value {
+                let variable = &extraction.extraction.variable;
+                eprintln!("cartero-cli: {name}: cannot extract {variable}: {e}");
+            }
+        }
+        status = status.max(exit_status(result));
+        if show_names {
+            let _ = writeln!(out);
+        }
+    }
+
+    if show_names && print_summary(&mut out, &runner).is_err() {
+        return EXIT_REQUEST_FAILURE;
+    }
+    status
+}
+
+fn main() -> ExitCode {
+    match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Run(options)) => ExitCode::from(run(&options)),
+        Ok(Command::Help) => {
+            println!("{USAGE}");
+            ExitCode::from(EXIT_OK)
+        }
+        Ok(Command::Version) => {
+            println!("cartero-cli {}", env!("CARGO_PKG_VERSION"));
+            ExitCode::from(EXIT_OK)
+        }
+        Err(message) => {
+            eprintln!("cartero-cli: {message}");
+            eprintln!("{USAGE}");
+            ExitCode::from(EXIT_USAGE)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{parse_args, Command, Options};
+
+    fn args(list: &[&str]) -> impl Iterator<Item = String> {
+        list.iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_parse_files_and_flags() {
+        let command = parse_args(args(&[
+            "-i",
+            "a.cartero",
+            "--environment",
+            "prod",
+            "b.cartero",
+        ]));
+        let expected = Options {
+            environment: Some("prod".into()),
+            include: true,
+            silent: false,
+            files: vec![PathBuf::from("a.cartero"), PathBuf::from("b.cartero")],
+            ..Default::default()
+        };
+        assert_eq!(command, Ok(Command::Run(expected)));
+    }
+
+    #[test]
+    fn test_parse_runner_options() {
+        let command = parse_args(args(&[
+            "-n",
+            "3",
+            "--delay=500",
+            "--data",
+            "users.csv",
+            "--stop-on-failure",
+            "--environment=prod",
+            "api",
+        ]));
+        let expected = Options {
+            environment: Some("prod".into()),
+            iterations: Some(3),
+            data: Some(PathBuf::from("users.csv")),
+            delay: 500,
+            stop_on_failure: true,
+            files: vec![PathBuf::from("api")],
+            ..Default::default()
+        };
+        assert_eq!(command, Ok(Command::Run(expected)));
+
+        assert!(parse_args(args(&["--iterations", "many", "api"])).is_err());
+        assert!(parse_args(args(&["--delay"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_requires_files() {
+        assert!(parse_args(args(&["-s"])).is_err());
+        assert!(parse_args(args(&["-e"])).is_err());
+        assert!(parse_args(args(&["--bogus", "a.cartero"])).is_err());
+        assert_eq!(parse_args(args(&["--help"])), Ok(Command::Help));
+    }
+
+    #[test]
+    fn test_parse_double_dash() {
+        let command = parse_args(args(&["--", "-weird.cartero"]));
+        let Ok(Command::Run(options)) = command else {
+            panic!("expected a run command");
+        };
+        assert_eq!(options.files, vec![PathBuf::from("-weird.cartero")]);
+    }
+}
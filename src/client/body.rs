@@ -0,0 +1,196 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Bodies of the requests, which may include the content of files.
+//!
+//! Files are not read when the request is bound, only their length is taken.
+//! Their content is streamed from the disk while the request is sent, so that
+//! big uploads do not have to fit in memory or block the interface.
+
+use std::fs::File;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+
+use futures_lite::io::AssertAsync;
+use isahc::AsyncBody;
+
+use super::RequestError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BodyPart {
+    Bytes(Vec<u8>),
+    File { path: PathBuf, length: u64 },
+}
+
+impl BodyPart {
+    fn len(&self) -> u64 {
+        match self {
+            Self::Bytes(bytes) => bytes.len() as u64,
+            Self::File { length, .. } => *length,
+        }
+    }
+
+    fn reader(&self) -> Result<Box<dyn Read + Send + Sync>, RequestError> {
+        match self {
+            Self::Bytes(bytes) => Ok(Box::new(Cursor::new(bytes.clone()))),
+            Self::File { path, length } => {
+                let file = File::open(path).map_err(|_| unreadable(path))?;
+                // Never send more than announced, in case the file grew.
+                Ok(Box::new(file.take(*length)))
+            }
+        }
+    }
+}
+
+fn unreadable(path: &Path) -> RequestError {
+    RequestError::UnreadableFile(path.display().to_string())
+}
+
+/// The body of a bound request, made of bytes and of files that are read while
+/// the request is sent.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct RequestBody {
+    parts: Vec<BodyPart>,
+}
+
+impl From<Vec<u8>> for RequestBody {
+    fn from(value: Vec<u8>) -> Self {
+        let mut body = Self::default();
+        body.push_bytes(value);
+        body
+    }
+}
+
+impl RequestBody {
+    /// A body with the content of the given file.
+    pub fn file(path: &Path) -> Result<Self, RequestError> {
+        let mut body = Self::default();
+        body.push_file(path)?;
+        Ok(body)
+    }
+
+    pub fn push_bytes(&mut self, bytes: impl Into<Vec<u8>>) {
+        let bytes = bytes.into();
+        if bytes.is_empty() {
+            return;
+        }
+        match self.parts.last_mut() {
+            Some(BodyPart::Bytes(last)) => last.extend(bytes),
+            _ => self.parts.push(BodyPart::Bytes(bytes)),
+        }
+    }
+
+    /// Adds the content of a file to the body. The file is opened to make sure
+    /// that it can be read, but its content is not read until the body is sent.
+    pub fn push_file(&mut self, path: &Path) -> Result<(), RequestError> {
+        let metadata = File::open(path)
+            .and_then(|file| file.metadata())
+            .map_err(|_| unreadable(path))?;
+        if !metadata.is_file() {
+            return Err(unreadable(path));
+        }
+        self.parts.push(BodyPart::File {
+            path: path.to_path_buf(),
+            length: metadata.len(),
+        });
+        Ok(())
+    }
+
+    /// The size of the body, in bytes.
+    pub fn len(&self) -> u64 {
+        self.parts.iter().map(BodyPart::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the content of the body as long as it is kept in memory, which
+    /// is the case unless it includes the content of some file.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self.parts.as_slice() {
+            [] => Some(&[]),
+            [BodyPart::Bytes(bytes)] => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Opens the files of the body and returns a reader for the whole content.
+    pub fn reader(&self) -> Result<impl Read + Send + Sync, RequestError> {
+        let mut reader: Box<dyn Read + Send + Sync> = Box::new(std::io::empty());
+        for part in &self.parts {
+            reader = Box::new(reader.chain(part.reader()?));
+        }
+        Ok(reader)
+    }
+
+    /// Converts the body into one that the HTTP client can send.
+    pub fn into_async_body(mut self) -> Result<AsyncBody, RequestError> {
+        if self.as_bytes().is_none() {
+            let length = self.len();
+            let reader = AssertAsync::new(self.reader()?);
+            return Ok(AsyncBody::from_reader_sized(reader, length));
+        }
+        // Bodies kept in memory can be sent again, such as after a redirection.
+        match self.parts.pop() {
+            Some(BodyPart::Bytes(bytes)) => Ok(AsyncBody::from(bytes)),
+            _ => Ok(AsyncBody::empty()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::RequestBody;
+    use crate::client::RequestError;
+
+    #[test]
+    fn test_body_with_files() {
+        let path = std::env::temp_dir().join(format!("cartero-body-part-{}", std::process::id()));
+        std::fs::write(&path, "world").unwrap();
+
+        let mut body = RequestBody::from(b"Hello, ".to_vec());
+        body.push_file(&path).unwrap();
+        body.push_bytes("!");
+        assert_eq!(body.len(), 13);
+        assert!(body.as_bytes().is_none());
+
+        let mut content = String::new();
+        body.reader().unwrap().read_to_string(&mut content).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(content, "Hello, world!");
+    }
+
+    #[test]
+    fn test_body_in_memory() {
+        let mut body = RequestBody::from(b"a=1".to_vec());
+        body.push_bytes("&b=2");
+        assert_eq!(body.as_bytes(), Some(&b"a=1&b=2"[..]));
+        assert!(RequestBody::default().is_empty());
+    }
+
+    #[test]
+    fn test_missing_files_are_reported() {
+        let mut body = RequestBody::default();
+        let result = body.push_file("/nonexistent/cartero.bin".as_ref());
+        assert!(matches!(result, Err(RequestError::UnreadableFile(_))));
+        let result = body.push_file(&std::env::temp_dir());
+        assert!(matches!(result, Err(RequestError::UnreadableFile(_))));
+    }
+}
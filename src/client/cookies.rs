@@ -0,0 +1,534 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! A cookie jar that keeps the cookies set by the servers, so that they are
+//! sent back in the following requests (RFC 6265).
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use url::{Host, Url};
+
+use super::BoundRequest;
+use crate::entities::KeyValueTable;
+use crate::error::CarteroError;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+
+    /// The domain of the cookie, without the leading dot.
+    pub domain: String,
+    pub path: String,
+
+    /// When the cookie stops being valid. Session cookies do not expire.
+    pub expires: Option<SystemTime>,
+    pub secure: bool,
+    pub http_only: bool,
+
+    /// Whether the cookie is only sent to the exact host that set it, rather
+    /// than to every subdomain of its domain.
+    pub host_only: bool,
+}
+
+impl Cookie {
+    /// Parses the value of a Set-Cookie header received from the given URL.
+    /// Returns `None` if the header is malformed or the server is not allowed
+    /// to set the cookie for the domain it claims.
+    pub fn parse(header: &str, url: &Url, now: SystemTime) -> Option<Cookie> {
+        let host = url.host_str()?.to_lowercase();
+        let mut parts = header.split(';');
+        let (name, value) = parts.next()?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+        let mut cookie = Cookie {
+            name: name.to_string(),
+            value: value.trim().trim_matches('"').to_string(),
+            domain: host.clone(),
+            path: default_path(url),
+            host_only: true,
+            ..Default::default()
+        };
+        let mut max_age = None;
+        let mut expires = None;
+        for attribute in parts {
+            let (key, value) = match attribute.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (attribute.trim(), ""),
+            };
+            match key.to_lowercase().as_str() {
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_lowercase();
+                    if !domain_matches(&host, &domain) {
+                        return None;
+                    }
+                    // Addresses and top-level domains such as "com" are only
+                    // accepted when they are the host itself.
+                    let is_address = !matches!(url.host(), Some(Host::Domain(_)));
+                    if domain != host && (is_address || !domain.contains('.')) {
+                        return None;
+                    }
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                }
+                "path" if value.starts_with('/') => cookie.path = value.to_string(),
+                "max-age" => max_age = value.parse::<i64>().ok(),
+                "expires" => expires = parse_http_date(value),
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                _ => {}
+            }
+        }
+        // A date too far away to be represented is the same as no date at all.
+        cookie.expires = match max_age {
+            Some(seconds) if seconds <= 0 => Some(UNIX_EPOCH),
+            Some(seconds) => now.checked_add(Duration::from_secs(seconds as u64)),
+            None => expires,
+        };
+        Some(cookie)
+    }
+
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    /// Whether the cookie has to be sent in a request to the given URL.
+    pub fn matches(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str().map(str::to_lowercase) else {
+            return false;
+        };
+        let domain_ok = if self.host_only {
+            host == self.domain
+        } else {
+            domain_matches(&host, &self.domain)
+        };
+        let secure_ok = !self.secure || matches!(url.scheme(), "https" | "wss");
+        domain_ok && secure_ok && path_matches(url.path(), &self.path)
+    }
+
+    fn same_identity(&self, other: &Cookie) -> bool {
+        self.name == other.name && self.domain == other.domain && self.path == other.path
+    }
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.ends_with(domain)
+            && host[..host.len() - domain.len()].ends_with('.')
+            && host.parse::<std::net::IpAddr>().is_err())
+}
+
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+/// The directory of the path of the URL, used when the cookie has no path.
+fn default_path(url: &Url) -> String {
+    let path = url.path();
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(index) => path[..index].to_string(),
+    }
+}
+
+/// Parses the dates used by the Expires attribute, such as
+/// `Wed, 21 Oct 2015 07:28:00 GMT` or `Wed, 21-Oct-15 07:28:00 GMT`.
+pub fn parse_http_date(value: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let (mut day, mut month, mut year, mut time) = (None, None, None, None);
+    for token in value.split([' ', ',', '-']).filter(|t| !t.is_empty()) {
+        let lower = token.to_lowercase();
+        if token.contains(':') && time.is_none() {
+            let fields: Vec<u64> = token.split(':').filter_map(|f| f.parse().ok()).collect();
+            if let [hours @ 0..=23, minutes @ 0..=59, seconds @ 0..=60] = fields[..] {
+                time = Some(hours * 3600 + minutes * 60 + seconds);
+            }
+        } else if let Some(index) = MONTHS.iter().position(|m| lower.starts_with(m)) {
+            month = month.or(Some(index as u32 + 1));
+        } else if let Ok(number) = token.parse::<u32>() {
+            match token.len() {
+                1 | 2 if day.is_none() => day = Some(number),
+                2 => {
+                    year = Some(if number < 70 {
+                        2000 + number
+                    } else {
+                        1900 + number
+                    })
+                }
+                4 => year = Some(number),
+                _ => {}
+            }
+        }
+    }
+    let (day, month, year, time) = (day?, month?, year?, time?);
+    if !(1..=31).contains(&day) || year < 1970 {
+        return None;
+    }
+    let days = days_from_civil(year as i64, month, day);
+    let seconds = (days as u64).checked_mul(86400)?.checked_add(time)?;
+    UNIX_EPOCH.checked_add(Duration::from_secs(seconds))
+}
+
+/// Days since 1970-01-01 of the given date in the Gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The cookies kept between requests.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+    pub fn cookies(&self) -> &[Cookie] {
+        &self.cookies
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    /// Adds a cookie, replacing the one with the same name, domain and path.
+    /// A cookie that is already expired only removes the previous one.
+    pub fn insert(&mut self, cookie: Cookie, now: SystemTime) {
+        self.cookies.retain(|c| !c.same_identity(&cookie));
+        if !cookie.is_expired(now) {
+            self.cookies.push(cookie);
+        }
+    }
+
+    pub fn remove(&mut self, cookie: &Cookie) {
+        self.cookies.retain(|c| !c.same_identity(cookie));
+    }
+
+    pub fn clear(&mut self) {
+        self.cookies.clear();
+    }
+
+    /// Keeps the cookies set by the Set-Cookie headers of a response.
+    pub fn store_response(&mut self, url: &str, headers: &KeyValueTable, now: SystemTime) {
+        let Ok(url) = Url::parse(url) else {
+            return;
+        };
+        for header in headers.iter() {
+            if header.name.eq_ignore_ascii_case("set-cookie") {
+                if let Some(cookie) = Cookie::parse(&header.value, &url, now) {
+                    self.insert(cookie, now);
+                }
+            }
+        }
+    }
+
+    /// The value of the Cookie header for a request to the given URL, if any
+    /// cookie has to be sent. Cookies with longer paths are listed first.
+    pub fn header_for(&self, url: &str, now: SystemTime) -> Option<String> {
+        let url = Url::parse(url).ok()?;
+        let mut cookies: Vec<&Cookie> = self
+            .cookies
+            .iter()
+            .filter(|c| !c.is_expired(now) && c.matches(&url))
+            .collect();
+        if cookies.is_empty() {
+            return None;
+        }
+        cookies.sort_by_key(|c| std::cmp::Reverse(c.path.len()));
+        let pairs: Vec<String> = cookies
+            .iter()
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect();
+        Some(pairs.join("; "))
+    }
+
+    /// Adds the Cookie header to the request, unless it already sets one by hand.
+    pub fn apply(&self, request: &mut BoundRequest, now: SystemTime) {
+        let has_cookie = request
+            .headers
+            .keys()
+            .any(|name| name.eq_ignore_ascii_case("cookie"));
+        if has_cookie {
+            return;
+        }
+        if let Some(header) = self.header_for(&request.url, now) {
+            request.headers.insert("Cookie".into(), header);
+        }
+    }
+
+    /// Reads the jar stored in the given file. A missing file is an empty jar.
+    pub fn load(path: &Path, now: SystemTime) -> Result<CookieJar, CarteroError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(CookieJar::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let file = toml::from_str::<CookieJarFile>(&contents)?;
+        let cookies = file
+            .cookies
+            .into_iter()
+            .map(Cookie::from)
+            .filter(|c| !c.is_expired(now))
+            .collect();
+        Ok(CookieJar { cookies })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), CarteroError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = CookieJarFile {
+            cookies: self.cookies.iter().cloned().map(FileCookie::from).collect(),
+        };
+        fs::write(path, toml::to_string(&file)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct FileCookie {
+    name: String,
+    value: String,
+    domain: String,
+    path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires: Option<u64>,
+    #[serde(default)]
+    secure: bool,
+    #[serde(default)]
+    http_only: bool,
+    #[serde(default)]
+    host_only: bool,
+}
+
+#[derive(Deserialize, Serialize, Default)]
+struct CookieJarFile {
+    #[serde(default)]
+    cookies: Vec<FileCookie>,
+}
+
+impl From<FileCookie> for Cookie {
+    fn from(value: FileCookie) -> Self {
+        Cookie {
+            name: value.name,
+            value: value.value,
+            domain: value.domain,
+            path: value.path,
+            expires: value
+                .expires
+                .and_then(|seconds| UNIX_EPOCH.checked_add(Duration::from_secs(seconds))),
+            secure: value.secure,
+            http_only: value.http_only,
+            host_only: value.host_only,
+        }
+    }
+}
+
+impl From<Cookie> for FileCookie {
+    fn from(value: Cookie) -> Self {
+        FileCookie {
+            name: value.name,
+            value: value.value,
+            domain: value.domain,
+            path: value.path,
+            expires: value.expires.map(|expires| {
+                expires
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+            }),
+            secure: value.secure,
+            http_only: value.http_only,
+            host_only: value.host_only,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use url::Url;
+
+    use super::{parse_http_date, Cookie, CookieJar};
+    use crate::client::BoundRequest;
+    use crate::entities::KeyValueTable;
+
+    fn now() -> SystemTime {
+        // 2024-06-01 00:00:00 UTC.
+        UNIX_EPOCH + Duration::from_secs(1_717_200_000)
+    }
+
+    #[test]
+    fn test_parse_http_date() {
+        let expected = UNIX_EPOCH + Duration::from_secs(1_445_412_480);
+        assert_eq!(
+            parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(expected)
+        );
+        assert_eq!(
+            parse_http_date("Wed, 21-Oct-15 07:28:00 GMT"),
+            Some(expected)
+        );
+        assert_eq!(
+            parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT"),
+            Some(UNIX_EPOCH)
+        );
+        assert_eq!(parse_http_date("tomorrow"), None);
+        assert_eq!(
+            parse_http_date("Wed, 21 Oct 2015 18446744073709551615:00:00 GMT"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_cookie() {
+        let url = Url::parse("https://api.example.com/v1/login").unwrap();
+        let cookie = Cookie::parse(
+            "session=abc123; Domain=.example.com; Path=/; Max-Age=3600; Secure; HttpOnly",
+            &url,
+            now(),
+        )
+        .unwrap();
+        assert_eq!(cookie.name, "session");
+        assert_eq!(cookie.value, "abc123");
+        assert_eq!(cookie.domain, "example.com");
+        assert_eq!(cookie.path, "/");
+        assert_eq!(cookie.expires, Some(now() + Duration::from_secs(3600)));
+        assert!(cookie.secure && cookie.http_only && !cookie.host_only);
+
+        let cookie = Cookie::parse("theme=dark", &url, now()).unwrap();
+        assert_eq!(cookie.domain, "api.example.com");
+        assert_eq!(cookie.path, "/v1");
+        assert!(cookie.host_only);
+        assert_eq!(cookie.expires, None);
+
+        let cookie = Cookie::parse("forever=1; Max-Age=9223372036854775807", &url, now()).unwrap();
+        assert_eq!(cookie.expires, None);
+        assert!(!cookie.is_expired(now()));
+
+        assert!(Cookie::parse("evil=1; Domain=other.com", &url, now()).is_none());
+        assert!(Cookie::parse("evil=1; Domain=com", &url, now()).is_none());
+        assert!(Cookie::parse("evil=1; Domain=.COM", &url, now()).is_none());
+        assert!(Cookie::parse("no-value", &url, now()).is_none());
+
+        let url = Url::parse("http://localhost:8080/").unwrap();
+        let cookie = Cookie::parse("local=1; Domain=localhost", &url, now()).unwrap();
+        assert_eq!(cookie.domain, "localhost");
+        let url = Url::parse("http://10.0.0.1/").unwrap();
+        assert!(Cookie::parse("ip=1; Domain=0.0.1", &url, now()).is_none());
+        assert!(Cookie::parse("ip=1; Domain=10.0.0.1", &url, now()).is_some());
+    }
+
+    #[test]
+    fn test_jar_sends_matching_cookies() {
+        let mut jar = CookieJar::default();
+        let headers = KeyValueTable::new(&[
+            ("Set-Cookie", "session=abc; Path=/; Domain=example.com").into(),
+            ("Set-Cookie", "token=xyz; Path=/v1; Secure").into(),
+            ("Set-Cookie", "old=1; Expires=Thu, 01 Jan 1970 00:00:00 GMT").into(),
+            ("Content-Type", "text/plain").into(),
+        ]);
+        jar.store_response("https://api.example.com/v1/login", &headers, now());
+        assert_eq!(jar.cookies().len(), 2);
+
+        let header = jar.header_for("https://api.example.com/v1/users", now());
+        assert_eq!(header.as_deref(), Some("token=xyz; session=abc"));
+        let header = jar.header_for("http://api.example.com/v1/users", now());
+        assert_eq!(header.as_deref(), Some("session=abc"));
+        let header = jar.header_for("https://www.example.com/", now());
+        assert_eq!(header.as_deref(), Some("session=abc"));
+        assert_eq!(jar.header_for("https://example.org/", now()), None);
+        assert_eq!(
+            jar.header_for("https://api.example.com/v10", now()),
+            Some("session=abc".into())
+        );
+
+        let logout = KeyValueTable::new(&[(
+            "set-cookie",
+            "session=; Path=/; Domain=example.com; Max-Age=0",
+        )
+            .into()]);
+        jar.store_response("https://api.example.com/logout", &logout, now());
+        assert_eq!(jar.cookies().len(), 1);
+        assert_eq!(jar.header_for("https://later.example.com/", now()), None);
+
+        let expired = now() + Duration::from_secs(7200);
+        let mut jar = CookieJar::default();
+        let headers = KeyValueTable::new(&[("Set-Cookie", "short=1; Max-Age=60").into()]);
+        jar.store_response("https://example.com/", &headers, now());
+        assert_eq!(jar.header_for("https://example.com/", expired), None);
+    }
+
+    #[test]
+    fn test_jar_apply() {
+        let mut jar = CookieJar::default();
+        let headers = KeyValueTable::new(&[("Set-Cookie", "session=abc").into()]);
+        jar.store_response("https://example.com/login", &headers, now());
+
+        let mut request = BoundRequest {
+            url: "https://example.com/users".into(),
+            ..Default::default()
+        };
+        jar.apply(&mut request, now());
+        assert_eq!(request.headers["Cookie"], "session=abc");
+
+        let mut request = BoundRequest {
+            url: "https://example.com/users".into(),
+            headers: [("cookie".to_string(), "custom=1".to_string())].into(),
+            ..Default::default()
+        };
+        jar.apply(&mut request, now());
+        assert_eq!(request.headers.len(), 1);
+        assert_eq!(request.headers["cookie"], "custom=1");
+    }
+
+    #[test]
+    fn test_jar_persistence() {
+        let path =
+            std::env::temp_dir().join(format!("cartero-cookies-{}.toml", std::process::id()));
+        let mut jar = CookieJar::default();
+        let headers = KeyValueTable::new(&[
+            ("Set-Cookie", "session=abc; Max-Age=3600; HttpOnly").into(),
+            ("Set-Cookie", "theme=dark").into(),
+        ]);
+        jar.store_response("https://example.com/", &headers, now());
+        jar.save(&path).unwrap();
+
+        assert_eq!(CookieJar::load(&path, now()).unwrap(), jar);
+        let later = CookieJar::load(&path, now() + Duration::from_secs(7200)).unwrap();
+        assert_eq!(later.cookies().len(), 1);
+        assert_eq!(later.cookies()[0].name, "theme");
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(CookieJar::load(&path, now()).unwrap().is_empty());
+    }
+}
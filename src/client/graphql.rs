@@ -0,0 +1,232 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! GraphQL requests, which are sent to the server as a JSON document.
+
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::entities::{EndpointData, RequestMethod, RequestPayload};
+
+/// Asks the server for the names of every type, field and enum value in the schema.
+pub const INTROSPECTION_QUERY: &str = "query IntrospectionQuery {
+  __schema {
+    types {
+      name
+      fields(includeDeprecated: true) { name }
+      inputFields { name }
+      enumValues(includeDeprecated: true) { name }
+    }
+  }
+}";
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum GraphQLError {
+    #[error("The GraphQL variables are not a valid JSON object")]
+    InvalidVariables,
+
+    #[error("The server did not answer with a GraphQL schema")]
+    InvalidSchema,
+}
+
+/// Builds the JSON document sent as the body of a GraphQL request.
+///
+/// The variables are written as a JSON object and may be left empty. The
+/// operation name is only sent when the query defines more than one operation.
+pub fn graphql_body(
+    query: &str,
+    variables: &str,
+    operation_name: Option<&str>,
+) -> Result<String, GraphQLError> {
+    let mut document = Map::new();
+    document.insert("query".into(), Value::String(query.into()));
+    if !variables.trim().is_empty() {
+        let variables: Value =
+            serde_json::from_str(variables).map_err(|_| GraphQLError::InvalidVariables)?;
+        if !variables.is_object() && !variables.is_null() {
+            return Err(GraphQLError::InvalidVariables);
+        }
+        document.insert("variables".into(), variables);
+    }
+    if let Some(operation_name) = operation_name.filter(|name| !name.is_empty()) {
+        document.insert("operationName".into(), Value::String(operation_name.into()));
+    }
+    Ok(Value::Object(document).to_string())
+}
+
+/// Turns the endpoint into the request that fetches the schema of the server,
+/// keeping the URL, headers, variables and authentication of the endpoint.
+pub fn introspection_endpoint(endpoint: &EndpointData) -> EndpointData {
+    EndpointData {
+        method: RequestMethod::Post,
+        body: RequestPayload::GraphQL {
+            query: INTROSPECTION_QUERY.into(),
+            variables: String::new(),
+            operation_name: None,
+        },
+        ..endpoint.clone()
+    }
+}
+
+/// A type of a GraphQL schema, reduced to the names that may be used in a query.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GraphQLType {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+/// The parts of a GraphQL schema used to complete queries.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GraphQLSchema {
+    pub types: Vec<GraphQLType>,
+}
+
+impl GraphQLSchema {
+    /// Reads the response to the [`INTROSPECTION_QUERY`].
+    pub fn parse(body: &[u8]) -> Result<Self, GraphQLError> {
+        let document: Value =
+            serde_json::from_slice(body).map_err(|_| GraphQLError::InvalidSchema)?;
+        let types = document
+            .pointer("/data/__schema/types")
+            .and_then(Value::as_array)
+            .ok_or(GraphQLError::InvalidSchema)?;
+        let names = |value: &Value, key: &str| -> Vec<String> {
+            value[key]
+                .as_array()
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|item| item["name"].as_str())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let types = types
+            .iter()
+            .filter_map(|item| {
+                let name = item["name"].as_str()?.to_string();
+                let mut fields = names(item, "fields");
+                fields.extend(names(item, "inputFields"));
+                fields.extend(names(item, "enumValues"));
+                Some(GraphQLType { name, fields })
+            })
+            .collect();
+        Ok(Self { types })
+    }
+
+    /// Every type, field and enum value, leaving out the ones used by introspection.
+    pub fn completion_words(&self) -> Vec<String> {
+        let mut words: Vec<String> = self
+            .types
+            .iter()
+            .filter(|item| !item.name.starts_with("__"))
+            .flat_map(|item| std::iter::once(&item.name).chain(item.fields.iter()))
+            .filter(|word| !word.starts_with("__"))
+            .cloned()
+            .collect();
+        words.sort();
+        words.dedup();
+        words
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    #[test]
+    fn test_graphql_body() {
+        let body = graphql_body(
+            "query User($id: ID!) { user(id: $id) { name } }",
+            "{\"id\": 4}",
+            Some("User"),
+        )
+        .unwrap();
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "query": "query User($id: ID!) { user(id: $id) { name } }",
+                "variables": {"id": 4},
+                "operationName": "User",
+            })
+        );
+    }
+
+    #[test]
+    fn test_graphql_body_without_variables() {
+        let body = graphql_body("{ me { name } }", "  ", Some("")).unwrap();
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body, json!({"query": "{ me { name } }"}));
+        assert_eq!(
+            graphql_body("{ me }", "[1, 2]", None),
+            Err(GraphQLError::InvalidVariables)
+        );
+        assert_eq!(
+            graphql_body("{ me }", "{ nope", None),
+            Err(GraphQLError::InvalidVariables)
+        );
+    }
+
+    #[test]
+    fn test_parse_schema() {
+        let response = json!({
+            "data": {
+                "__schema": {
+                    "types": [
+                        {
+                            "name": "Query",
+                            "fields": [{"name": "user"}, {"name": "users"}],
+                            "inputFields": null,
+                            "enumValues": null
+                        },
+                        {
+                            "name": "User",
+                            "fields": [{"name": "name"}, {"name": "role"}],
+                            "inputFields": null,
+                            "enumValues": null
+                        },
+                        {
+                            "name": "Role",
+                            "fields": null,
+                            "inputFields": null,
+                            "enumValues": [{"name": "ADMIN"}]
+                        },
+                        {
+                            "name": "__Type",
+                            "fields": [{"name": "kind"}],
+                            "inputFields": null,
+                            "enumValues": null
+                        }
+                    ]
+                }
+            }
+        });
+        let schema = GraphQLSchema::parse(response.to_string().as_bytes()).unwrap();
+        assert_eq!(schema.types.len(), 4);
+        assert_eq!(
+            schema.completion_words(),
+            vec!["ADMIN", "Query", "Role", "User", "name", "role", "user", "users"]
+        );
+        assert_eq!(
+            GraphQLSchema::parse(b"{\"errors\": []}"),
+            Err(GraphQLError::InvalidSchema)
+        );
+    }
+}
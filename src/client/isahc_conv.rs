@@ -15,19 +15,34 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use crate::entities::{RequestMethod, ResponseData};
+use crate::entities::{
+    ClientOptions, HttpVersion, KeyValueTable, ProxyMode, ProxySettings, RequestMethod,
+    ResponseData,
+};
 
-use super::{BoundRequest, RequestError};
+use super::{is_event_stream, BoundRequest, RequestError, SseEvent, SseParser};
 use futures_lite::io::AsyncReadExt;
+use futures_lite::{future, Future};
 use isahc::{
-    http::{HeaderName, HeaderValue},
-    AsyncBody, Body,
+    auth::{Authentication, Credentials},
+    config::{Configurable, RedirectPolicy, SslOption, VersionNegotiation},
+    http::{HeaderName, HeaderValue, Uri},
+    AsyncBody, Body, ResponseExt,
+};
+use std::{
+    io::Read,
+    str::FromStr,
+    time::{Duration, Instant},
 };
-use std::{io::Read, str::FromStr, time::Instant};
 
-impl From<&RequestMethod> for isahc::http::Method {
-    fn from(value: &RequestMethod) -> Self {
-        match value {
+/// How many redirections are followed when the options do not set a limit.
+const DEFAULT_MAX_REDIRECTS: u32 = 10;
+
+impl TryFrom<&RequestMethod> for isahc::http::Method {
+    type Error = RequestError;
+
+    fn try_from(value: &RequestMethod) -> Result<Self, Self::Error> {
+        let method = match value {
             RequestMethod::Head => isahc::http::Method::HEAD,
             RequestMethod::Get => isahc::http::Method::GET,
             RequestMethod::Post => isahc::http::Method::POST,
@@ -36,15 +51,19 @@ impl From<&RequestMethod> for isahc::http::Method {
             RequestMethod::Options => isahc::http::Method::OPTIONS,
             RequestMethod::Delete => isahc::http::Method::DELETE,
             RequestMethod::Trace => isahc::http::Method::TRACE,
-        }
+            RequestMethod::Custom(name) => isahc::http::Method::from_bytes(name.as_bytes())
+                .map_err(|_| RequestError::InvalidHttpVerb)?,
+        };
+        Ok(method)
     }
 }
 
-impl TryFrom<BoundRequest> for isahc::Request<Vec<u8>> {
+impl TryFrom<BoundRequest> for isahc::Request<AsyncBody> {
     type Error = RequestError;
 
     fn try_from(req: BoundRequest) -> Result<Self, Self::Error> {
-        let mut builder = isahc::Request::builder().uri(&req.url).method(&req.method);
+        let method = isahc::http::Method::try_from(&req.method)?;
+        let mut builder = isahc::Request::builder().uri(&req.url).method(method);
         let Some(headers) = builder.headers_mut() else {
             return Err(RequestError::InvalidHeaders);
         };
@@ -53,12 +72,94 @@ impl TryFrom<BoundRequest> for isahc::Request<Vec<u8>> {
             let value = HeaderValue::from_str(v)?;
             headers.insert(key, value);
         }
-        let body = req.body.unwrap_or_default();
+        if let Some((username, password)) = req.digest_credentials {
+            builder = builder
+                .authentication(Authentication::digest())
+                .credentials(Credentials::new(username, password));
+        }
+        builder = apply_client_options(builder, &req.options)?;
+        let body = match req.body {
+            Some(body) => body.into_async_body()?,
+            None => AsyncBody::empty(),
+        };
         let req = builder.body(body)?;
         Ok(req)
     }
 }
 
+fn redirect_policy(options: &ClientOptions) -> Option<RedirectPolicy> {
+    match options.follow_redirects? {
+        true => Some(RedirectPolicy::Limit(
+            options.max_redirects.unwrap_or(DEFAULT_MAX_REDIRECTS),
+        )),
+        false => Some(RedirectPolicy::None),
+    }
+}
+
+/// Parses the URL of a manual proxy, which must use one of the schemes supported by the client.
+fn proxy_uri(proxy: &ProxySettings) -> Result<Uri, RequestError> {
+    let uri = Uri::from_str(proxy.url.trim()).map_err(|_| RequestError::InvalidProxy)?;
+    match uri.scheme_str() {
+        Some("http" | "https" | "socks4" | "socks4a" | "socks5" | "socks5h") => Ok(uri),
+        _ => Err(RequestError::InvalidProxy),
+    }
+}
+
+/// Configures the proxy of the request. In system mode the client looks for the
+/// proxy in the usual environment variables, such as `https_proxy` or `no_proxy`.
+fn apply_proxy<T: Configurable>(mut builder: T, proxy: &ProxySettings) -> Result<T, RequestError> {
+    match proxy.mode {
+        ProxyMode::System => {}
+        ProxyMode::Direct => builder = builder.proxy(None),
+        ProxyMode::Manual => {
+            builder = builder.proxy(Some(proxy_uri(proxy)?));
+            if !proxy.no_proxy.is_empty() {
+                builder = builder.proxy_blacklist(proxy.no_proxy.iter().cloned());
+            }
+            if !proxy.username.is_empty() {
+                builder = builder
+                    .proxy_authentication(Authentication::basic() | Authentication::digest())
+                    .proxy_credentials(Credentials::new(&proxy.username, &proxy.password));
+            }
+        }
+    }
+    Ok(builder)
+}
+
+/// Configures the request with the given options. Settings that are missing,
+/// as well as timeouts set to zero, keep the defaults of the client.
+fn apply_client_options<T: Configurable>(
+    mut builder: T,
+    options: &ClientOptions,
+) -> Result<T, RequestError> {
+    if let Some(timeout) = options.connect_timeout.filter(|&t| t > 0) {
+        builder = builder.connect_timeout(Duration::from_secs(timeout));
+    }
+    if let Some(timeout) = options.timeout.filter(|&t| t > 0) {
+        builder = builder.timeout(Duration::from_secs(timeout));
+    }
+    if let Some(policy) = redirect_policy(options) {
+        builder = builder.redirect_policy(policy);
+    }
+    if options.verify_tls == Some(false) {
+        builder = builder.ssl_options(
+            SslOption::DANGER_ACCEPT_INVALID_CERTS | SslOption::DANGER_ACCEPT_INVALID_HOSTS,
+        );
+    }
+    if let Some(version) = options.http_version {
+        let negotiation = match version {
+            HttpVersion::Auto => VersionNegotiation::latest_compatible(),
+            HttpVersion::Http11 => VersionNegotiation::http11(),
+            HttpVersion::Http2 => VersionNegotiation::http2(),
+        };
+        builder = builder.version_negotiation(negotiation);
+    }
+    if let Some(proxy) = &options.proxy {
+        builder = apply_proxy(builder, proxy)?;
+    }
+    Ok(builder)
+}
+
 impl TryFrom<&mut isahc::Response<Body>> for ResponseData {
     type Error = RequestError;
 
@@ -89,12 +190,8 @@ impl TryFrom<&mut isahc::Response<Body>> for ResponseData {
     }
 }
 
-pub async fn extract_isahc_response(
-    value: &mut isahc::Response<AsyncBody>,
-    start: &Instant,
-) -> Result<ResponseData, RequestError> {
-    let status_code: u32 = value.status().as_u16() as u32;
-    let headers = value
+fn response_headers(value: &isahc::Response<AsyncBody>) -> KeyValueTable {
+    value
         .headers()
         .iter()
         .map(|(k, v)| {
@@ -102,7 +199,30 @@ pub async fn extract_isahc_response(
             let header_value = String::from(v.to_str().unwrap());
             (header_name, header_value).into()
         })
-        .collect();
+        .collect()
+}
+
+/// Whether the response is a Server-Sent Events stream, which has to be read
+/// with [`stream_isahc_events`] since it may never end.
+pub fn is_isahc_event_stream(value: &isahc::Response<AsyncBody>) -> bool {
+    is_event_stream(&response_headers(value))
+}
+
+/// The URL that answered the request, which differs from the one the request
+/// was sent to when redirections were followed.
+pub fn effective_url(value: &isahc::Response<AsyncBody>, url: &str) -> String {
+    match value.effective_uri() {
+        Some(uri) => uri.to_string(),
+        None => url.to_string(),
+    }
+}
+
+pub async fn extract_isahc_response(
+    value: &mut isahc::Response<AsyncBody>,
+    start: &Instant,
+) -> Result<ResponseData, RequestError> {
+    let status_code: u32 = value.status().as_u16() as u32;
+    let headers = response_headers(value);
     let body = {
         let mut buffer = Vec::new();
         let body = value.body_mut();
@@ -118,3 +238,213 @@ pub async fn extract_isahc_response(
         body,
     })
 }
+
+/// Reads the whole response, unless it is a Server-Sent Events stream. Those
+/// are read until the server closes them or the `stop` future completes.
+pub async fn read_isahc_response<S>(
+    value: &mut isahc::Response<AsyncBody>,
+    start: &Instant,
+    stop: S,
+) -> Result<ResponseData, RequestError>
+where
+    S: Future<Output = ()>,
+{
+    if is_isahc_event_stream(value) {
+        stream_isahc_events(value, start, stop, |_| {}).await
+    } else {
+        extract_isahc_response(value, start).await
+    }
+}
+
+/// Reads a Server-Sent Events response as it arrives, passing every event to
+/// the given callback.
+///
+/// Since these streams may never end, the body is read until either the server
+/// closes the connection or the `stop` future completes. The returned response
+/// holds everything that was received until then.
+pub async fn stream_isahc_events<S, F>(
+    value: &mut isahc::Response<AsyncBody>,
+    start: &Instant,
+    stop: S,
+    mut on_event: F,
+) -> Result<ResponseData, RequestError>
+where
+    S: Future<Output = ()>,
+    F: FnMut(SseEvent),
+{
+    let status_code: u32 = value.status().as_u16() as u32;
+    let headers = response_headers(value);
+    let mut parser = SseParser::new();
+    let mut body = Vec::new();
+    let mut chunk = [0u8; 8192];
+    futures_lite::pin!(stop);
+    loop {
+        let read = future::or(
+            async {
+                stop.as_mut().await;
+                None
+            },
+            async { Some(value.body_mut().read(&mut chunk).await) },
+        )
+        .await;
+        let count = match read {
+            Some(Ok(0)) | None => break,
+            Some(Ok(count)) => count,
+            Some(Err(e)) => return Err(e.into()),
+        };
+        body.extend_from_slice(&chunk[..count]);
+        for event in parser.feed(&chunk[..count]) {
+            on_event(event);
+        }
+    }
+    Ok(ResponseData {
+        duration: start.elapsed().as_millis(),
+        size: body.len(),
+        status_code,
+        headers,
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use futures_lite::future;
+    use isahc::config::RedirectPolicy;
+    use isahc::AsyncBody;
+
+    use super::{proxy_uri, read_isahc_response, redirect_policy, stream_isahc_events};
+    use crate::client::{BoundRequest, RequestError};
+    use crate::entities::{ClientOptions, ProxyMode, ProxySettings, RequestMethod};
+
+    fn event_stream(body: AsyncBody) -> isahc::Response<AsyncBody> {
+        isahc::Response::builder()
+            .header("Content-Type", "text/event-stream")
+            .body(body)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_stream_events_until_the_end() {
+        let mut response = event_stream(AsyncBody::from("data: a\n\ndata: b\n\n"));
+        let mut events = Vec::new();
+        let data = future::block_on(stream_isahc_events(
+            &mut response,
+            &Instant::now(),
+            future::pending(),
+            |event| events.push(event.data),
+        ))
+        .unwrap();
+        assert_eq!(events, vec!["a", "b"]);
+        assert_eq!(data.body, b"data: a\n\ndata: b\n\n");
+        assert_eq!(data.size, data.body.len());
+    }
+
+    #[test]
+    fn test_stream_events_can_be_stopped() {
+        let endless = futures_lite::io::repeat(b'x');
+        let mut response = event_stream(AsyncBody::from_reader(endless));
+        let data = future::block_on(stream_isahc_events(
+            &mut response,
+            &Instant::now(),
+            future::ready(()),
+            |_| panic!("no events expected"),
+        ))
+        .unwrap();
+        assert!(data.body.is_empty());
+        assert_eq!(
+            data.headers.header("content-type"),
+            Some(vec!["text/event-stream"])
+        );
+    }
+
+    #[test]
+    fn test_read_response() {
+        let endless = futures_lite::io::repeat(b'x');
+        let mut response = event_stream(AsyncBody::from_reader(endless));
+        let data = future::block_on(read_isahc_response(
+            &mut response,
+            &Instant::now(),
+            future::ready(()),
+        ))
+        .unwrap();
+        assert!(data.body.is_empty());
+
+        let mut response = isahc::Response::new(AsyncBody::from("Hello"));
+        let data = future::block_on(read_isahc_response(
+            &mut response,
+            &Instant::now(),
+            future::ready(()),
+        ))
+        .unwrap();
+        assert_eq!(data.body, b"Hello");
+    }
+
+    #[test]
+    fn test_redirect_policy() {
+        assert_eq!(redirect_policy(&ClientOptions::default()), None);
+        let options = ClientOptions {
+            follow_redirects: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(redirect_policy(&options), Some(RedirectPolicy::Limit(10)));
+        let options = ClientOptions {
+            follow_redirects: Some(true),
+            max_redirects: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(redirect_policy(&options), Some(RedirectPolicy::Limit(3)));
+        let options = ClientOptions {
+            follow_redirects: Some(false),
+            max_redirects: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(redirect_policy(&options), Some(RedirectPolicy::None));
+    }
+
+    #[test]
+    fn test_custom_method() {
+        let method = RequestMethod::Custom("MKCOL".into());
+        let method = isahc::http::Method::try_from(&method).unwrap();
+        assert_eq!(method.as_str(), "MKCOL");
+
+        let method = RequestMethod::Custom("NOT A VERB".into());
+        assert!(isahc::http::Method::try_from(&method).is_err());
+    }
+
+    #[test]
+    fn test_proxy_uri() {
+        let proxy = |url: &str| ProxySettings {
+            mode: ProxyMode::Manual,
+            url: url.into(),
+            ..Default::default()
+        };
+        let uri = proxy_uri(&proxy("http://proxy.example.com:3128")).unwrap();
+        assert_eq!(uri.host(), Some("proxy.example.com"));
+        assert_eq!(uri.port_u16(), Some(3128));
+        assert!(proxy_uri(&proxy(" socks5://127.0.0.1:1080 ")).is_ok());
+        assert!(proxy_uri(&proxy("")).is_err());
+        assert!(proxy_uri(&proxy("ftp://proxy.example.com")).is_err());
+        assert!(proxy_uri(&proxy("proxy.example.com:3128")).is_err());
+
+        let request = BoundRequest {
+            url: "https://www.example.com".into(),
+            method: RequestMethod::Get,
+            headers: Default::default(),
+            body: None,
+            digest_credentials: None,
+            auth_header: None,
+            auth_query: None,
+            oauth2: None,
+            options: ClientOptions {
+                proxy: Some(proxy("not a proxy")),
+                ..Default::default()
+            },
+        };
+        assert!(matches!(
+            isahc::Request::try_from(request),
+            Err(RequestError::InvalidProxy)
+        ));
+    }
+}
@@ -15,31 +15,58 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use formdata::FormData;
+use futures_lite::future;
 use isahc::http::header::{InvalidHeaderName, InvalidHeaderValue};
 use srtemplate::SrTemplate;
 use std::{
     collections::HashMap,
-    io::{BufWriter, Write},
+    future::Future,
+    path::{Path, PathBuf},
 };
 use thiserror::Error;
 
 use crate::{
-    entities::{EndpointData, KeyValueTable, RawEncoding, RequestMethod, RequestPayload},
+    entities::{
+        ApiKeyLocation, ClientOptions, EndpointData, Environment, KeyValueTable, MultipartFile,
+        OAuth2Config, RequestAuth, RequestMethod, RequestPayload,
+    },
     error::CarteroError,
 };
 
-#[derive(Default, Debug, Clone)]
+use super::{
+    generate_boundary, graphql_body, guess_content_type, multipart_body, BoundFile, RequestBody,
+};
+
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct BoundRequest {
     pub url: String,
     pub method: RequestMethod,
     pub headers: HashMap<String, String>,
-    pub body: Option<Vec<u8>>,
+    pub body: Option<RequestBody>,
+
+    /// Username and password for digest authentication, which requires a challenge
+    /// from the server and therefore cannot be expressed as a plain header.
+    pub digest_credentials: Option<(String, String)>,
+
+    /// The header set by the authentication, such as the one with the API key, so
+    /// that it can be told apart from the headers written by hand.
+    pub auth_header: Option<String>,
+
+    /// The query parameter added to the URL by the authentication, such as the
+    /// one with the API key.
+    pub auth_query: Option<String>,
+
+    /// OAuth 2.0 settings, with every variable already rendered. The access token
+    /// is fetched by [`BoundRequest::authorize`] right before sending the request.
+    pub oauth2: Option<OAuth2Config>,
+
+    /// Settings of the HTTP client, such as timeouts or redirections.
+    pub options: ClientOptions,
 }
 
 #[derive(Default, Debug, Clone)]
 struct BoundBody {
-    content: Vec<u8>,
+    content: RequestBody,
     boundary: String,
 }
 
@@ -60,18 +87,29 @@ fn bind_urlencoded_payload(
         })
         .collect::<Result<Vec<(String, String)>, CarteroError>>()?;
     let body = serde_urlencoded::to_string(pairs).map_err(|_| RequestError::InvalidPayload)?;
-    let content = Vec::from(body.as_str());
     Ok(Some(BoundBody {
-        content,
+        content: RequestBody::from(body.into_bytes()),
         boundary: String::default(),
     }))
 }
 
+/// Resolves a path written in a request. Relative paths are resolved against the
+/// given directory, or against the working directory if there is none.
+pub fn resolve_path(base_dir: Option<&Path>, path: &str) -> PathBuf {
+    let path = PathBuf::from(path);
+    match base_dir {
+        Some(base_dir) if path.is_relative() => base_dir.join(path),
+        _ => path,
+    }
+}
+
 fn bind_multipart_payload(
     params: &KeyValueTable,
+    files: &[MultipartFile],
     processor: &SrTemplate,
+    base_dir: Option<&Path>,
 ) -> Result<Option<BoundBody>, CarteroError> {
-    if params.is_empty() {
+    if params.is_empty() && files.is_empty() {
         return Ok(None);
     }
     let pairs: Vec<(String, String)> = params
@@ -83,21 +121,34 @@ fn bind_multipart_payload(
             Ok((key, value))
         })
         .collect::<Result<Vec<(String, String)>, CarteroError>>()?;
-    let formdata = FormData {
-        fields: pairs,
-        files: vec![],
-    };
-    let boundary = formdata::generate_boundary();
+    let files: Vec<BoundFile> = files
+        .iter()
+        .filter(|file| file.active)
+        .map(|file| {
+            let path = resolve_path(base_dir, &processor.render(&file.path)?);
+            let filename = match &file.filename {
+                Some(filename) => processor.render(filename)?,
+                None => path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            };
+            let content_type = match &file.content_type {
+                Some(content_type) => processor.render(content_type)?,
+                None => guess_content_type(&path).to_string(),
+            };
+            Ok(BoundFile {
+                name: processor.render(&file.name)?,
+                path,
+                filename,
+                content_type,
+            })
+        })
+        .collect::<Result<Vec<BoundFile>, CarteroError>>()?;
+    let boundary = generate_boundary();
 
-    let mut stream = BufWriter::new(Vec::new());
-    formdata::write_formdata(&mut stream, &boundary, &formdata)
-        .map_err(|_| RequestError::InvalidPayload)?;
-    stream.flush().map_err(RequestError::IOError)?;
-    let copy = stream.get_ref().clone();
-    Ok(Some(BoundBody {
-        content: copy,
-        boundary: String::from_utf8_lossy(&boundary).to_string(),
-    }))
+    let content = multipart_body(&boundary, &pairs, &files)?;
+    Ok(Some(BoundBody { content, boundary }))
 }
 
 fn bind_raw_payload(
@@ -109,9 +160,41 @@ fn bind_raw_payload(
     }
     let processable_body = String::from_utf8_lossy(body);
     let processed_body = processor.render(processable_body)?;
-    let body = Vec::from(processed_body.as_str());
     Ok(Some(BoundBody {
-        content: body,
+        content: RequestBody::from(processed_body.into_bytes()),
+        boundary: String::default(),
+    }))
+}
+
+fn bind_file_payload(
+    path: &str,
+    processor: &SrTemplate,
+    base_dir: Option<&Path>,
+) -> Result<Option<BoundBody>, CarteroError> {
+    let path = resolve_path(base_dir, &processor.render(path)?);
+    Ok(Some(BoundBody {
+        content: RequestBody::file(&path)?,
+        boundary: String::default(),
+    }))
+}
+
+fn bind_graphql_payload(
+    query: &str,
+    variables: &str,
+    operation_name: &Option<String>,
+    processor: &SrTemplate,
+) -> Result<Option<BoundBody>, CarteroError> {
+    let operation_name = match operation_name {
+        Some(name) => Some(processor.render(name)?),
+        None => None,
+    };
+    let body = graphql_body(
+        &processor.render(query)?,
+        &processor.render(variables)?,
+        operation_name.as_deref(),
+    )?;
+    Ok(Some(BoundBody {
+        content: RequestBody::from(body.into_bytes()),
         boundary: String::default(),
     }))
 }
@@ -119,43 +202,141 @@ fn bind_raw_payload(
 fn bind_payload(
     body: &RequestPayload,
     processor: &SrTemplate,
+    base_dir: Option<&Path>,
 ) -> Result<Option<BoundBody>, CarteroError> {
     match body {
         RequestPayload::None => Ok(None),
         RequestPayload::Urlencoded(payload) => bind_urlencoded_payload(payload, processor),
-        RequestPayload::Multipart { params } => bind_multipart_payload(params, processor),
+        RequestPayload::Multipart { params, files } => {
+            bind_multipart_payload(params, files, processor, base_dir)
+        }
         RequestPayload::Raw {
             content,
             encoding: _,
         } => bind_raw_payload(content, processor),
+        RequestPayload::File { path } => bind_file_payload(path, processor, base_dir),
+        RequestPayload::GraphQL {
+            query,
+            variables,
+            operation_name,
+        } => bind_graphql_payload(query, variables, operation_name, processor),
     }
 }
 
-impl TryFrom<EndpointData> for BoundRequest {
-    type Error = CarteroError;
+/// The outcome of rendering the authentication settings of an endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoundAuth {
+    None,
+    Header(String, String),
+    Query(String, String),
+    Digest(String, String),
+    OAuth2(OAuth2Config),
+}
 
-    fn try_from(value: EndpointData) -> Result<Self, Self::Error> {
-        let processor = value.template_processor();
+impl BoundAuth {
+    /// Renders the credentials using the given function, which usually resolves variables.
+    pub fn bind<F>(auth: &RequestAuth, render: F) -> Result<Self, CarteroError>
+    where
+        F: Fn(&str) -> Result<String, CarteroError>,
+    {
+        let bound = match auth {
+            RequestAuth::None => Self::None,
+            RequestAuth::Basic { username, password } => {
+                let credentials = format!("{}:{}", render(username)?, render(password)?);
+                let credentials = base64::encode(credentials.as_bytes());
+                Self::Header("Authorization".into(), format!("Basic {credentials}"))
+            }
+            RequestAuth::Bearer { token } => {
+                Self::Header("Authorization".into(), format!("Bearer {}", render(token)?))
+            }
+            RequestAuth::ApiKey {
+                name,
+                value,
+                location,
+            } => match location {
+                ApiKeyLocation::Header => Self::Header(render(name)?, render(value)?),
+                ApiKeyLocation::Query => Self::Query(render(name)?, render(value)?),
+            },
+            RequestAuth::Digest { username, password } => {
+                Self::Digest(render(username)?, render(password)?)
+            }
+            RequestAuth::OAuth2(config) => Self::OAuth2(OAuth2Config {
+                grant: config.grant,
+                token_url: render(&config.token_url)?,
+                authorization_url: render(&config.authorization_url)?,
+                client_id: render(&config.client_id)?,
+                client_secret: render(&config.client_secret)?,
+                scope: render(&config.scope)?,
+            }),
+        };
+        Ok(bound)
+    }
+}
+
+/// Appends a parameter to the query string of the URL.
+pub fn append_query_pair(url: &str, name: &str, value: &str) -> String {
+    let pair = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair(name, value)
+        .finish();
+    let (base, fragment) = match url.split_once('#') {
+        Some((base, fragment)) => (base, Some(fragment)),
+        None => (url, None),
+    };
+    let separator = if !base.contains('?') {
+        "?"
+    } else if base.ends_with('?') || base.ends_with('&') {
+        ""
+    } else {
+        "&"
+    };
+    match fragment {
+        Some(fragment) => format!("{base}{separator}{pair}#{fragment}"),
+        None => format!("{base}{separator}{pair}"),
+    }
+}
+
+impl BoundRequest {
+    /// Binds the endpoint, resolving variables using the given environment.
+    ///
+    /// Variables defined in the endpoint override the ones defined in the
+    /// environment when both have the same name.
+    pub fn bind(
+        value: EndpointData,
+        environment: Option<&Environment>,
+    ) -> Result<Self, CarteroError> {
+        Self::bind_relative_to(value, environment, None)
+    }
+
+    /// Binds the endpoint like [`BoundRequest::bind`], resolving the relative paths
+    /// of the files sent in the body against the given directory, which usually is
+    /// the directory that contains the request file.
+    pub fn bind_relative_to(
+        value: EndpointData,
+        environment: Option<&Environment>,
+        base_dir: Option<&Path>,
+    ) -> Result<Self, CarteroError> {
+        let processor = value.template_processor(environment);
 
-        let url = processor.render(&value.url)?;
+        let mut url = processor.render(&value.url)?;
         let method = value.method.clone();
 
-        let body = bind_payload(&value.body, &processor)?;
+        let body = bind_payload(&value.body, &processor, base_dir)?;
         let content_type = match value.body {
             RequestPayload::None => None,
             RequestPayload::Urlencoded(_) => Some("application/x-www-form-urlencoded".to_string()),
-            RequestPayload::Multipart { params: _ } => Some(format!(
+            RequestPayload::Multipart { .. } => Some(format!(
                 "multipart/form-data; boundary={}",
                 body.clone().unwrap_or_default().boundary
             )),
             RequestPayload::Raw {
                 ref encoding,
                 content: _,
-            } => match encoding {
-                RawEncoding::OctetStream => Some("application/octet-stream".into()),
-                RawEncoding::Xml => Some("application/xml".into()),
-                RawEncoding::Json => Some("application/json".into()),
-            },
+            } => Some(encoding.content_type().into()),
+            RequestPayload::File { ref path } => {
+                let path = processor.render(path)?;
+                Some(guess_content_type(Path::new(&path)).into())
+            }
+            RequestPayload::GraphQL { .. } => Some("application/json".into()),
         };
 
         let mut base_headers = HashMap::new();
@@ -172,17 +353,74 @@ impl TryFrom<EndpointData> for BoundRequest {
                 Ok((header_name, header_value))
             })
             .collect();
-        let headers = headers?;
+        let mut headers = headers?;
+
+        // Headers written by hand take precedence over the ones set by the authentication.
+        let auth = BoundAuth::bind(&value.auth, |text| Ok(processor.render(text)?))?;
+        let mut digest_credentials = None;
+        let mut auth_header = None;
+        let mut auth_query = None;
+        let mut oauth2 = None;
+        match auth {
+            BoundAuth::None => {}
+            BoundAuth::Header(name, value) => {
+                let exists = headers.keys().any(|key| key.eq_ignore_ascii_case(&name));
+                if !exists {
+                    headers.insert(name.clone(), value);
+                    auth_header = Some(name);
+                }
+            }
+            BoundAuth::Query(name, value) => {
+                url = append_query_pair(&url, &name, &value);
+                auth_query = Some(name);
+            }
+            BoundAuth::Digest(username, password) => {
+                digest_credentials = Some((username, password));
+            }
+            BoundAuth::OAuth2(config) => oauth2 = Some(config),
+        }
 
         Ok(Self {
             url,
             method,
             headers,
             body: body.map(|b| b.content),
+            digest_credentials,
+            auth_header,
+            auth_query,
+            oauth2,
+            options: bind_options(&value.options, environment, &processor)?,
         })
     }
 }
 
+/// Takes the proxy from the environment unless the request sets its own, and
+/// renders the variables used in the proxy settings.
+fn bind_options(
+    options: &ClientOptions,
+    environment: Option<&Environment>,
+    processor: &SrTemplate,
+) -> Result<ClientOptions, CarteroError> {
+    let mut options = match environment {
+        Some(environment) => options.or(&environment.client_options()),
+        None => options.clone(),
+    };
+    if let Some(proxy) = options.proxy.as_mut() {
+        proxy.url = processor.render(&proxy.url)?;
+        proxy.username = processor.render(&proxy.username)?;
+        proxy.password = processor.render(&proxy.password)?;
+    }
+    Ok(options)
+}
+
+impl TryFrom<EndpointData> for BoundRequest {
+    type Error = CarteroError;
+
+    fn try_from(value: EndpointData) -> Result<Self, Self::Error> {
+        Self::bind(value, None)
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum RequestError {
     #[error("Illegal HTTP verb")]
@@ -208,14 +446,61 @@ pub enum RequestError {
 
     #[error("Unknown I/O error")]
     IOError(#[from] std::io::Error),
+
+    #[error("Cannot read the file {0}")]
+    UnreadableFile(String),
+
+    #[error("The request was cancelled")]
+    Cancelled,
+
+    #[error("The request took too long")]
+    TimedOut,
+
+    #[error("Invalid proxy URL")]
+    InvalidProxy,
+}
+
+/// Runs the request until it finishes or `cancel` completes, whichever happens
+/// first. Cancelling drops the request, which closes the connection.
+pub async fn cancellable<T, F, C>(request: F, cancel: C) -> Result<T, CarteroError>
+where
+    F: Future<Output = Result<T, CarteroError>>,
+    C: Future<Output = ()>,
+{
+    future::or(request, async {
+        cancel.await;
+        Err(RequestError::Cancelled.into())
+    })
+    .await
+}
+
+/// Runs the request until it finishes or `timer` completes, in which case it
+/// fails with [`RequestError::TimedOut`].
+pub async fn timed<T, F, C>(request: F, timer: C) -> Result<T, CarteroError>
+where
+    F: Future<Output = Result<T, CarteroError>>,
+    C: Future<Output = ()>,
+{
+    future::or(request, async {
+        timer.await;
+        Err(RequestError::TimedOut.into())
+    })
+    .await
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::entities::KeyValueTable;
+    use crate::entities::{KeyValue, KeyValueTable, ProxyMode, ProxySettings, RawEncoding};
 
     use super::*;
 
+    fn body_bytes(bound: &BoundRequest) -> Vec<u8> {
+        let mut content = Vec::new();
+        let mut reader = bound.body.as_ref().unwrap().reader().unwrap();
+        std::io::Read::read_to_end(&mut reader, &mut content).unwrap();
+        content
+    }
+
     #[test]
     pub fn test_bind_of_parameters_urlencoded() {
         // Build a request.
@@ -243,6 +528,7 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         // Bind the request.
@@ -254,7 +540,7 @@ mod tests {
             bound.headers["Content-Type"],
             "application/x-www-form-urlencoded"
         );
-        assert_eq!(bound.body, Some(Vec::from(b"name=John&surname=Smith")));
+        assert_eq!(body_bytes(&bound), b"name=John&surname=Smith");
     }
 
     #[test]
@@ -276,6 +562,7 @@ mod tests {
         let variables = KeyValueTable::new(&variables);
         let body = RequestPayload::Multipart {
             params: KeyValueTable::new(&[("name", "John").into(), ("surname", "Smith").into()]),
+            files: vec![],
         };
         let endpoint = EndpointData {
             url,
@@ -283,6 +570,7 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         // Bind the request.
@@ -293,7 +581,7 @@ mod tests {
 
         let content_type = bound.headers["Content-Type"].clone();
         assert!(content_type.starts_with("multipart/form-data; boundary="));
-        let body = bound.body.unwrap();
+        let body = body_bytes(&bound);
         let body = String::from_utf8_lossy(&body);
         assert!(body.contains("name=\"name\""));
         assert!(body.contains("name=\"surname\""));
@@ -301,6 +589,103 @@ mod tests {
         assert!(body.contains("Smith"));
     }
 
+    #[test]
+    fn test_bind_of_multipart_files() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("cartero-bind-{}.png", std::process::id()));
+        std::fs::write(&path, "PNG").unwrap();
+
+        let endpoint = EndpointData {
+            url: "https://example.com/upload".into(),
+            method: RequestMethod::Post,
+            variables: KeyValueTable::new(&[("DIR", dir.to_str().unwrap()).into()]),
+            body: RequestPayload::Multipart {
+                params: KeyValueTable::default(),
+                files: vec![
+                    MultipartFile {
+                        name: "avatar".into(),
+                        path: format!(
+                            "{{{{DIR}}}}/{}",
+                            path.file_name().unwrap().to_str().unwrap()
+                        ),
+                        ..Default::default()
+                    },
+                    MultipartFile {
+                        name: "ignored".into(),
+                        path: "/nonexistent".into(),
+                        active: false,
+                        ..Default::default()
+                    },
+                ],
+            },
+            ..Default::default()
+        };
+        let bound = BoundRequest::try_from(endpoint).unwrap();
+        let body = String::from_utf8(body_bytes(&bound)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let filename = path.file_name().unwrap().to_str().unwrap();
+        assert!(body.contains(&format!("name=\"avatar\"; filename=\"{filename}\"")));
+        assert!(body.contains("Content-Type: image/png\r\n\r\nPNG\r\n"));
+        assert!(!body.contains("ignored"));
+    }
+
+    #[test]
+    fn test_bind_of_graphql() {
+        let endpoint = EndpointData {
+            url: "https://example.com/graphql".into(),
+            method: RequestMethod::Post,
+            variables: KeyValueTable::new(&[("USER", "42").into()]),
+            body: RequestPayload::GraphQL {
+                query: "query User($id: ID!) { user(id: $id) { name } }".into(),
+                variables: "{\"id\": \"{{USER}}\"}".into(),
+                operation_name: None,
+            },
+            ..Default::default()
+        };
+        let bound = BoundRequest::try_from(endpoint).unwrap();
+        assert_eq!(
+            bound.headers.get("Content-Type"),
+            Some(&"application/json".to_string())
+        );
+        let body: serde_json::Value = serde_json::from_slice(&body_bytes(&bound)).unwrap();
+        assert_eq!(body["variables"]["id"], "42");
+        assert_eq!(
+            body["query"],
+            "query User($id: ID!) { user(id: $id) { name } }"
+        );
+    }
+
+    #[test]
+    fn test_bind_of_file_relative_to_base_dir() {
+        let dir = std::env::temp_dir();
+        let filename = format!("cartero-body-{}.bin", std::process::id());
+        let contents: Vec<u8> = vec![0x00, 0xff, 0xfe, 0x80, b'{', b'{'];
+        std::fs::write(dir.join(&filename), &contents).unwrap();
+
+        let endpoint = EndpointData {
+            url: "https://example.com/upload".into(),
+            method: RequestMethod::Put,
+            body: RequestPayload::File { path: filename },
+            ..Default::default()
+        };
+        let bound = BoundRequest::bind_relative_to(endpoint.clone(), None, Some(&dir)).unwrap();
+        let missing = BoundRequest::bind(endpoint, None);
+        let body = body_bytes(&bound);
+        std::fs::remove_file(dir.join(format!("cartero-body-{}.bin", std::process::id()))).unwrap();
+
+        // The file is read while the request is sent, not when it is bound.
+        let bound_body = bound.body.as_ref().unwrap();
+        assert!(bound_body.as_bytes().is_none());
+        assert_eq!(bound_body.len(), contents.len() as u64);
+        assert_eq!(body, contents);
+        assert_eq!(
+            bound.headers.get("Content-Type"),
+            Some(&"application/octet-stream".to_string())
+        );
+        assert!(missing.is_err());
+    }
+
     #[test]
     pub fn test_bind_of_parameters_json() {
         // Build a request.
@@ -328,6 +713,7 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         // Bind the request.
@@ -337,7 +723,7 @@ mod tests {
         assert_eq!(bound.headers["Authorization"], "Bearer 789078907890");
         assert_eq!(bound.headers["Content-Type"], "application/json");
 
-        let body = bound.body.unwrap();
+        let body = body_bytes(&bound);
         let body = String::from_utf8_lossy(&body);
         assert_eq!(body, "{\"hello\": \"world\"}");
     }
@@ -369,6 +755,7 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         // Bind the request.
@@ -378,7 +765,7 @@ mod tests {
         assert_eq!(bound.headers["Authorization"], "Bearer 789078907890");
         assert_eq!(bound.headers["Content-Type"], "application/xml");
 
-        let body = bound.body.unwrap();
+        let body = body_bytes(&bound);
         let body = String::from_utf8_lossy(&body);
         assert_eq!(body, "<envelope>1234</envelope>");
     }
@@ -410,6 +797,7 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         // Bind the request.
@@ -419,7 +807,7 @@ mod tests {
         assert_eq!(bound.headers["Authorization"], "Bearer 789078907890");
         assert_eq!(bound.headers["Content-Type"], "application/octet-stream");
 
-        let body = bound.body.unwrap();
+        let body = body_bytes(&bound);
         let body = String::from_utf8_lossy(&body);
         assert_eq!(body, "12341234");
     }
@@ -452,6 +840,7 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         // Bind the request.
@@ -461,7 +850,7 @@ mod tests {
         assert_eq!(bound.headers["Authorization"], "Bearer 789078907890");
         assert_eq!(bound.headers["Content-Type"], "application/ld+json");
 
-        let body = bound.body.unwrap();
+        let body = body_bytes(&bound);
         let body = String::from_utf8_lossy(&body);
         assert_eq!(body, "{\"hello\": \"world\"}");
     }
@@ -482,6 +871,7 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         let bound = BoundRequest::try_from(endpoint).unwrap();
@@ -504,12 +894,155 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         let bound = BoundRequest::try_from(endpoint).unwrap();
         assert_eq!(bound.headers["Accept"], "application/json")
     }
 
+    #[test]
+    fn test_bind_with_environment_variables() {
+        let endpoint = EndpointData {
+            url: "https://{{API_ROOT}}/v1/books".into(),
+            headers: KeyValueTable::new(&[("Authorization", "Bearer {{TOKEN}}").into()]),
+            variables: KeyValueTable::new(&[("TOKEN", "local-token").into()]),
+            ..Default::default()
+        };
+        let environment = Environment {
+            name: "staging".into(),
+            variables: KeyValueTable::new(&[
+                ("API_ROOT", "staging.example.com").into(),
+                ("TOKEN", "staging-token").into(),
+            ]),
+            proxy: None,
+        };
+
+        let bound = BoundRequest::bind(endpoint, Some(&environment)).unwrap();
+        assert_eq!(bound.url, "https://staging.example.com/v1/books");
+        assert_eq!(bound.headers["Authorization"], "Bearer local-token");
+    }
+
+    #[test]
+    fn test_bind_ignores_inactive_environment_variables() {
+        let endpoint = EndpointData {
+            url: "https://{{API_ROOT}}/v1/books".into(),
+            ..Default::default()
+        };
+        let environment = Environment {
+            name: "production".into(),
+            variables: KeyValueTable::new(&[KeyValue {
+                name: "API_ROOT".into(),
+                value: "api.example.com".into(),
+                active: false,
+                secret: true,
+            }]),
+            proxy: None,
+        };
+
+        assert!(BoundRequest::bind(endpoint, Some(&environment)).is_err());
+    }
+
+    #[test]
+    fn test_bind_takes_proxy_from_environment() {
+        let proxy = ProxySettings {
+            mode: ProxyMode::Manual,
+            url: "http://{{PROXY_HOST}}:3128".into(),
+            username: "{{PROXY_USER}}".into(),
+            ..Default::default()
+        };
+        let environment = Environment {
+            name: "office".into(),
+            variables: KeyValueTable::new(&[
+                ("PROXY_HOST", "proxy.corp.example.com").into(),
+                ("PROXY_USER", "jdoe").into(),
+            ]),
+            proxy: Some(proxy),
+        };
+        let endpoint = EndpointData {
+            url: "https://www.example.com".into(),
+            ..Default::default()
+        };
+
+        let bound = BoundRequest::bind(endpoint.clone(), Some(&environment)).unwrap();
+        let proxy = bound.options.proxy.unwrap();
+        assert_eq!(proxy.url, "http://proxy.corp.example.com:3128");
+        assert_eq!(proxy.username, "jdoe");
+
+        let endpoint = EndpointData {
+            options: ClientOptions {
+                proxy: Some(ProxySettings {
+                    mode: ProxyMode::Direct,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..endpoint
+        };
+        let bound = BoundRequest::bind(endpoint, Some(&environment)).unwrap();
+        assert_eq!(bound.options.proxy.unwrap().mode, ProxyMode::Direct);
+    }
+
+    #[test]
+    fn test_bind_with_auth() {
+        let endpoint = EndpointData {
+            url: "https://example.com/books?page=2".into(),
+            variables: KeyValueTable::new(&[("PASSWORD", "secret").into()]),
+            auth: RequestAuth::Basic {
+                username: "admin".into(),
+                password: "{{PASSWORD}}".into(),
+            },
+            ..Default::default()
+        };
+        let bound = BoundRequest::bind(endpoint, None).unwrap();
+        assert_eq!(bound.headers["Authorization"], "Basic YWRtaW46c2VjcmV0");
+        assert_eq!(bound.auth_header.as_deref(), Some("Authorization"));
+
+        let endpoint = EndpointData {
+            url: "https://example.com/books?page=2".into(),
+            auth: RequestAuth::ApiKey {
+                name: "api_key".into(),
+                value: "a b".into(),
+                location: ApiKeyLocation::Query,
+            },
+            ..Default::default()
+        };
+        let bound = BoundRequest::bind(endpoint, None).unwrap();
+        assert_eq!(bound.url, "https://example.com/books?page=2&api_key=a+b");
+        assert!(!bound.headers.contains_key("Authorization"));
+        assert_eq!(bound.auth_header, None);
+        assert_eq!(bound.auth_query.as_deref(), Some("api_key"));
+
+        let endpoint = EndpointData {
+            url: "https://example.com".into(),
+            auth: RequestAuth::Digest {
+                username: "admin".into(),
+                password: "secret".into(),
+            },
+            ..Default::default()
+        };
+        let bound = BoundRequest::bind(endpoint, None).unwrap();
+        assert_eq!(
+            bound.digest_credentials,
+            Some(("admin".into(), "secret".into()))
+        );
+    }
+
+    #[test]
+    fn test_bind_auth_does_not_override_header() {
+        let endpoint = EndpointData {
+            url: "https://example.com".into(),
+            headers: KeyValueTable::new(&[("authorization", "Token manual").into()]),
+            auth: RequestAuth::Bearer {
+                token: "automatic".into(),
+            },
+            ..Default::default()
+        };
+        let bound = BoundRequest::bind(endpoint, None).unwrap();
+        assert_eq!(bound.headers.len(), 1);
+        assert_eq!(bound.headers["authorization"], "Token manual");
+    }
+
     #[test]
     #[should_panic]
     pub fn test_panics_if_wrong_variable() {
@@ -534,9 +1067,36 @@ mod tests {
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         // Bind the request.
         let _ = BoundRequest::try_from(endpoint).unwrap();
     }
+
+    #[test]
+    fn test_cancellable() {
+        let outcome = future::block_on(cancellable(async { Ok(1) }, future::pending()));
+        assert_eq!(outcome.unwrap(), 1);
+
+        let outcome: Result<(), CarteroError> =
+            future::block_on(cancellable(future::pending(), future::ready(())));
+        assert!(matches!(
+            outcome,
+            Err(CarteroError::Request(RequestError::Cancelled))
+        ));
+    }
+
+    #[test]
+    fn test_timed() {
+        let outcome = future::block_on(timed(async { Ok(1) }, future::pending()));
+        assert_eq!(outcome.unwrap(), 1);
+
+        let outcome: Result<(), CarteroError> =
+            future::block_on(timed(future::pending(), future::ready(())));
+        assert!(matches!(
+            outcome,
+            Err(CarteroError::Request(RequestError::TimedOut))
+        ));
+    }
 }
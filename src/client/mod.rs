@@ -15,8 +15,25 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+mod body;
+mod cookies;
+mod graphql;
 mod isahc_conv;
 mod local;
+mod multipart;
+mod oauth2;
+mod sse;
+mod websocket;
 
-pub use isahc_conv::extract_isahc_response;
+pub use body::*;
+pub use cookies::*;
+pub use graphql::*;
+pub use isahc_conv::{
+    effective_url, extract_isahc_response, is_isahc_event_stream, read_isahc_response,
+    stream_isahc_events,
+};
 pub use local::*;
+pub use multipart::*;
+pub use oauth2::*;
+pub use sse::*;
+pub use websocket::*;
@@ -0,0 +1,166 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Encoding of `multipart/form-data` bodies, as described by RFC 7578.
+
+use std::path::{Path, PathBuf};
+
+use rand::distributions::{Alphanumeric, DistString};
+
+use super::{RequestBody, RequestError};
+
+/// A file part whose settings have already been rendered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundFile {
+    pub name: String,
+    pub path: PathBuf,
+    pub filename: String,
+    pub content_type: String,
+}
+
+pub fn generate_boundary() -> String {
+    let random = Alphanumeric.sample_string(&mut rand::thread_rng(), 24);
+    format!("----CarteroBoundary{random}")
+}
+
+/// Guesses the content type of a file from its extension.
+pub fn guess_content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "txt" | "text" | "log" => "text/plain",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "js" | "mjs" => "text/javascript",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "yaml" | "yml" => "application/yaml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/vnd.microsoft.icon",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Escapes a value of the Content-Disposition header the same way browsers do.
+fn escape(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Builds the multipart body. The content of the files is not part of the body
+/// yet; it is streamed from the disk while the request is sent, so that the files
+/// do not have to be loaded in memory first.
+pub fn multipart_body(
+    boundary: &str,
+    fields: &[(String, String)],
+    files: &[BoundFile],
+) -> Result<RequestBody, RequestError> {
+    let mut body = RequestBody::default();
+    for (name, value) in fields {
+        body.push_bytes(format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{value}\r\n",
+            escape(name)
+        ));
+    }
+    for file in files {
+        body.push_bytes(format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
+             Content-Type: {}\r\n\r\n",
+            escape(&file.name),
+            escape(&file.filename),
+            file.content_type
+        ));
+        body.push_file(&file.path)?;
+        body.push_bytes("\r\n");
+    }
+    body.push_bytes(format!("--{boundary}--\r\n"));
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn test_write_multipart_with_files() {
+        let path =
+            std::env::temp_dir().join(format!("cartero-multipart-{}.txt", std::process::id()));
+        std::fs::write(&path, "Hello").unwrap();
+
+        let fields = vec![("name".to_string(), "cartero".to_string())];
+        let files = vec![BoundFile {
+            name: "up\"load".into(),
+            path: path.clone(),
+            filename: "greeting.txt".into(),
+            content_type: "text/plain".into(),
+        }];
+        let body = multipart_body("XX", &fields, &files).unwrap();
+        let mut content = String::new();
+        body.reader().unwrap().read_to_string(&mut content).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(body.len(), content.len() as u64);
+        assert_eq!(
+            content,
+            "--XX\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\ncartero\r\n\
+             --XX\r\nContent-Disposition: form-data; name=\"up%22load\"; filename=\"greeting.txt\"\r\n\
+             Content-Type: text/plain\r\n\r\nHello\r\n--XX--\r\n"
+        );
+    }
+
+    #[test]
+    fn test_missing_files_are_reported() {
+        let files = vec![BoundFile {
+            name: "upload".into(),
+            path: "/nonexistent/cartero.bin".into(),
+            filename: "cartero.bin".into(),
+            content_type: "application/octet-stream".into(),
+        }];
+        let result = multipart_body("XX", &[], &files);
+        assert!(matches!(result, Err(RequestError::UnreadableFile(_))));
+    }
+
+    #[test]
+    fn test_guess_content_type() {
+        assert_eq!(guess_content_type(Path::new("photo.JPG")), "image/jpeg");
+        assert_eq!(
+            guess_content_type(Path::new("data")),
+            "application/octet-stream"
+        );
+    }
+}
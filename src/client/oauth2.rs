@@ -0,0 +1,702 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Acquisition of OAuth 2.0 access tokens.
+//!
+//! Tokens are kept in memory only, keyed by the settings used to obtain them, so that
+//! every request that shares an authorization server reuses the same token until it
+//! expires. Expired tokens are refreshed when the server handed out a refresh token.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use futures_lite::io::AsyncReadExt;
+use isahc::{http::header, Request, RequestExt};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::entities::{OAuth2Config, OAuth2Grant};
+use crate::error::CarteroError;
+
+use super::BoundRequest;
+
+/// Tokens that expire in less than this are considered expired already, so that
+/// they do not expire while the request is in flight.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// How long to wait for the user to complete the authorization in the browser.
+const AUTHORIZATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How long to wait for a connection to the callback server to send its request.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest request line accepted by the callback server.
+const MAX_REQUEST_LINE: u64 = 8192;
+
+const CALLBACK_PATH: &str = "/callback";
+
+const CALLBACK_PAGE: &str = "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+    <title>Cartero</title></head><body><p>The authorization has finished. \
+    You can close this window and go back to Cartero.</p></body></html>";
+
+#[derive(Error, Debug)]
+pub enum OAuth2Error {
+    #[error("The token endpoint rejected the request: {0}")]
+    TokenRejected(String),
+
+    #[error("The token endpoint returned an invalid response")]
+    InvalidResponse,
+
+    #[error("The authorization was denied: {0}")]
+    AuthorizationDenied(String),
+
+    #[error("The authorization response does not belong to this request")]
+    StateMismatch,
+
+    #[error("The authorization was not completed in time")]
+    AuthorizationTimeout,
+
+    #[error("Invalid authorization URL")]
+    InvalidUrl(#[from] url::ParseError),
+
+    #[error("Cannot reach the token endpoint")]
+    NetworkError(#[from] isahc::error::Error),
+
+    #[error("Cannot build the token request")]
+    HttpError(#[from] isahc::http::Error),
+
+    #[error("Error while waiting for the authorization")]
+    IOError(#[from] std::io::Error),
+}
+
+/// An access token issued by an authorization server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OAuth2Token {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: Option<SystemTime>,
+}
+
+impl OAuth2Token {
+    /// Tokens without an expiration date are valid until the server rejects them.
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| now + EXPIRY_MARGIN >= expires_at)
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    expires_in: Option<u64>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// Tokens obtained during this session.
+#[derive(Default)]
+pub struct TokenCache {
+    tokens: Mutex<HashMap<String, OAuth2Token>>,
+}
+
+impl TokenCache {
+    /// The cache shared by every request of the application.
+    pub fn shared() -> &'static TokenCache {
+        static CACHE: OnceLock<TokenCache> = OnceLock::new();
+        CACHE.get_or_init(TokenCache::default)
+    }
+
+    fn key(config: &OAuth2Config) -> String {
+        format!(
+            "{:?} {} {} {}",
+            config.grant, config.token_url, config.client_id, config.scope
+        )
+    }
+
+    pub fn get(&self, config: &OAuth2Config) -> Option<OAuth2Token> {
+        let tokens = self.tokens.lock().unwrap();
+        tokens.get(&Self::key(config)).cloned()
+    }
+
+    pub fn insert(&self, config: &OAuth2Config, token: OAuth2Token) {
+        let mut tokens = self.tokens.lock().unwrap();
+        tokens.insert(Self::key(config), token);
+    }
+
+    pub fn remove(&self, config: &OAuth2Config) {
+        let mut tokens = self.tokens.lock().unwrap();
+        tokens.remove(&Self::key(config));
+    }
+
+    /// Returns the cached access token, if it has not expired yet.
+    pub fn valid_token(&self, config: &OAuth2Config) -> Option<String> {
+        self.get(config)
+            .filter(|token| !token.is_expired(SystemTime::now()))
+            .map(|token| token.access_token)
+    }
+}
+
+fn base64_url(data: &[u8]) -> String {
+    base64::encode_config(data, base64::URL_SAFE_NO_PAD)
+}
+
+/// Computes the S256 code challenge of a PKCE code verifier (RFC 7636).
+pub fn pkce_challenge(verifier: &str) -> String {
+    base64_url(&Sha256::digest(verifier.as_bytes()))
+}
+
+fn random_string() -> String {
+    base64_url(&rand::random::<[u8; 32]>())
+}
+
+/// Sends a request to the token endpoint and parses the issued token.
+async fn request_token(
+    config: &OAuth2Config,
+    params: &[(&str, &str)],
+) -> Result<OAuth2Token, OAuth2Error> {
+    let mut params = params.to_vec();
+    let mut request = Request::post(&config.token_url)
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .header(header::ACCEPT, "application/json");
+    if config.client_secret.is_empty() {
+        // Public clients only identify themselves.
+        params.push(("client_id", &config.client_id));
+    } else {
+        // The client credentials are URL encoded before joining them (RFC 6749, 2.3.1).
+        let encode = |value: &str| -> String {
+            url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+        };
+        let credentials = format!(
+            "{}:{}",
+            encode(&config.client_id),
+            encode(&config.client_secret)
+        );
+        let credentials = base64::encode(credentials.as_bytes());
+        request = request.header(header::AUTHORIZATION, format!("Basic {credentials}"));
+    }
+    let body = serde_urlencoded::to_string(&params).map_err(|_| OAuth2Error::InvalidResponse)?;
+
+    let mut response = request.body(body)?.send_async().await?;
+    let mut content = Vec::new();
+    response.body_mut().read_to_end(&mut content).await?;
+    let status = response.status();
+    let parsed: Result<TokenResponse, _> = serde_json::from_slice(&content);
+
+    match parsed {
+        Ok(TokenResponse {
+            access_token: Some(access_token),
+            refresh_token,
+            expires_in,
+            ..
+        }) if status.is_success() => Ok(OAuth2Token {
+            access_token,
+            refresh_token,
+            expires_at: expires_in.map(|secs| SystemTime::now() + Duration::from_secs(secs)),
+        }),
+        Ok(TokenResponse {
+            error: Some(error),
+            error_description,
+            ..
+        }) => Err(OAuth2Error::TokenRejected(
+            error_description.unwrap_or(error),
+        )),
+        _ if !status.is_success() => Err(OAuth2Error::TokenRejected(status.to_string())),
+        _ => Err(OAuth2Error::InvalidResponse),
+    }
+}
+
+async fn client_credentials(config: &OAuth2Config) -> Result<OAuth2Token, OAuth2Error> {
+    let mut params = vec![("grant_type", "client_credentials")];
+    if !config.scope.is_empty() {
+        params.push(("scope", &config.scope));
+    }
+    request_token(config, &params).await
+}
+
+async fn refresh(config: &OAuth2Config, refresh_token: &str) -> Result<OAuth2Token, OAuth2Error> {
+    let mut token = request_token(
+        config,
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ],
+    )
+    .await?;
+    // Servers may keep the refresh token and not send it again.
+    if token.refresh_token.is_none() {
+        token.refresh_token = Some(refresh_token.to_string());
+    }
+    Ok(token)
+}
+
+/// An authorization code request waiting for the browser to come back to the
+/// loopback redirect URI.
+pub struct AuthorizationRequest {
+    pub url: String,
+    redirect_uri: String,
+    verifier: String,
+    state: String,
+    listener: TcpListener,
+}
+
+impl AuthorizationRequest {
+    /// Starts listening on a random port of the loopback interface and builds the
+    /// URL that the user has to open in the browser.
+    pub fn start(config: &OAuth2Config) -> Result<Self, OAuth2Error> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let port = listener.local_addr()?.port();
+        let redirect_uri = format!("http://127.0.0.1:{port}{CALLBACK_PATH}");
+        let verifier = random_string();
+        let state = random_string();
+
+        let mut url = url::Url::parse(&config.authorization_url)?;
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_pair("response_type", "code")
+                .append_pair("client_id", &config.client_id)
+                .append_pair("redirect_uri", &redirect_uri)
+                .append_pair("state", &state)
+                .append_pair("code_challenge", &pkce_challenge(&verifier))
+                .append_pair("code_challenge_method", "S256");
+            if !config.scope.is_empty() {
+                query.append_pair("scope", &config.scope);
+            }
+        }
+
+        Ok(Self {
+            url: url.into(),
+            redirect_uri,
+            verifier,
+            state,
+            listener,
+        })
+    }
+
+    /// Waits for the authorization code and exchanges it for a token.
+    pub async fn finish(self, config: &OAuth2Config) -> Result<OAuth2Token, OAuth2Error> {
+        let Self {
+            redirect_uri,
+            verifier,
+            state,
+            listener,
+            ..
+        } = self;
+
+        // Accepting connections blocks, so it happens in a separate thread.
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(wait_for_code(&listener, &state, AUTHORIZATION_TIMEOUT));
+        });
+        let code = receiver
+            .await
+            .map_err(|_| OAuth2Error::AuthorizationTimeout)??;
+
+        request_token(
+            config,
+            &[
+                ("grant_type", "authorization_code"),
+                ("code", &code),
+                ("redirect_uri", &redirect_uri),
+                ("code_verifier", &verifier),
+            ],
+        )
+        .await
+    }
+}
+
+/// Parses the request line of the redirect, returning None if it is not the callback.
+fn parse_callback(request_line: &str, state: &str) -> Option<Result<String, OAuth2Error>> {
+    let target = request_line.split_whitespace().nth(1)?;
+    let url = url::Url::parse(&format!("http://127.0.0.1{target}")).ok()?;
+    if url.path() != CALLBACK_PATH {
+        return None;
+    }
+    let query: HashMap<_, _> = url.query_pairs().into_owned().collect();
+    if query.get("state").map(String::as_str) != Some(state) {
+        return Some(Err(OAuth2Error::StateMismatch));
+    }
+    if let Some(error) = query.get("error") {
+        let reason = query.get("error_description").unwrap_or(error);
+        return Some(Err(OAuth2Error::AuthorizationDenied(reason.clone())));
+    }
+    match query.get("code") {
+        Some(code) => Some(Ok(code.clone())),
+        None => Some(Err(OAuth2Error::AuthorizationDenied(
+            "no code was given".into(),
+        ))),
+    }
+}
+
+fn answer(mut stream: &TcpStream, status: &str, body: &str) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/html; charset=utf-8\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+/// Reads the request line sent through a connection to the callback server.
+///
+/// Connections that fail, that are too slow or that do not send text are
+/// ignored, so that they cannot stop the browser from reaching the server.
+fn read_request_line(stream: &TcpStream, timeout: Duration) -> Option<String> {
+    stream.set_nonblocking(false).ok()?;
+    stream.set_read_timeout(Some(timeout)).ok()?;
+    let mut request_line = String::new();
+    BufReader::new(stream.take(MAX_REQUEST_LINE))
+        .read_line(&mut request_line)
+        .ok()?;
+    Some(request_line)
+}
+
+fn wait_for_code(
+    listener: &TcpListener,
+    state: &str,
+    timeout: Duration,
+) -> Result<String, OAuth2Error> {
+    listener.set_nonblocking(true)?;
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(OAuth2Error::AuthorizationTimeout);
+        }
+        let stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            // Either nobody is connecting yet or the connection was dropped
+            // before it could be accepted; both are worth waiting for.
+            Err(_) => {
+                std::thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+        };
+        let Some(request_line) = read_request_line(&stream, remaining.min(CONNECTION_TIMEOUT))
+        else {
+            continue;
+        };
+        match parse_callback(&request_line, state) {
+            Some(result) => {
+                let _ = answer(&stream, "200 OK", CALLBACK_PAGE);
+                return result;
+            }
+            // Browsers also ask for things such as the favicon.
+            None => {
+                let _ = answer(&stream, "404 Not Found", "");
+            }
+        }
+    }
+}
+
+/// Returns a valid access token, reusing the cached one whenever possible.
+///
+/// The authorization code grant calls `open_browser` with the URL that the user has
+/// to visit in order to grant access.
+pub async fn access_token<F>(
+    config: &OAuth2Config,
+    cache: &TokenCache,
+    open_browser: F,
+) -> Result<String, OAuth2Error>
+where
+    F: FnOnce(&str),
+{
+    if let Some(token) = cache.get(config) {
+        if !token.is_expired(SystemTime::now()) {
+            return Ok(token.access_token);
+        }
+        if let Some(refresh_token) = &token.refresh_token {
+            if let Ok(token) = refresh(config, refresh_token).await {
+                cache.insert(config, token.clone());
+                return Ok(token.access_token);
+            }
+        }
+        // The refresh token was rejected too, so start over.
+        cache.remove(config);
+    }
+
+    let token = match config.grant {
+        OAuth2Grant::ClientCredentials => client_credentials(config).await?,
+        OAuth2Grant::AuthorizationCode => {
+            let request = AuthorizationRequest::start(config)?;
+            open_browser(&request.url);
+            request.finish(config).await?
+        }
+    };
+    cache.insert(config, token.clone());
+    Ok(token.access_token)
+}
+
+impl BoundRequest {
+    /// Fetches the OAuth 2.0 access token, if the request needs one, and adds it to
+    /// the headers. Headers written by hand take precedence.
+    pub async fn authorize<F>(
+        &mut self,
+        cache: &TokenCache,
+        open_browser: F,
+    ) -> Result<(), CarteroError>
+    where
+        F: FnOnce(&str),
+    {
+        let Some(config) = &self.oauth2 else {
+            return Ok(());
+        };
+        let exists = self
+            .headers
+            .keys()
+            .any(|key| key.eq_ignore_ascii_case("Authorization"));
+        if !exists {
+            let token = access_token(config, cache, open_browser).await?;
+            self.headers
+                .insert("Authorization".into(), format!("Bearer {token}"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread::JoinHandle;
+    use std::time::{Duration, SystemTime};
+
+    use futures_lite::future::block_on;
+
+    use super::*;
+
+    /// Serves the given responses, one per connection, and returns the received
+    /// requests once every response has been sent.
+    fn mock_token_server(
+        responses: Vec<(&'static str, &'static str)>,
+    ) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/token", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                    request.push_str(&line);
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut content = vec![0; length];
+                reader.read_exact(&mut content).unwrap();
+                request.push_str(&String::from_utf8(content).unwrap());
+                requests.push(request);
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    fn config(token_url: &str) -> OAuth2Config {
+        OAuth2Config {
+            grant: OAuth2Grant::ClientCredentials,
+            token_url: token_url.into(),
+            client_id: "cartero".into(),
+            client_secret: "s3cr3t".into(),
+            scope: "read write".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_pkce_challenge() {
+        // Example from the appendix B of RFC 7636.
+        let verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+        assert_eq!(
+            pkce_challenge(verifier),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn test_client_credentials_token_is_cached() {
+        let (url, server) = mock_token_server(vec![(
+            "200 OK",
+            r#"{"access_token": "abc", "token_type": "Bearer", "expires_in": 3600}"#,
+        )]);
+        let config = config(&url);
+        let cache = TokenCache::default();
+
+        let token = block_on(access_token(&config, &cache, |_| {})).unwrap();
+        assert_eq!(token, "abc");
+        let token = block_on(access_token(&config, &cache, |_| {})).unwrap();
+        assert_eq!(token, "abc");
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("POST /token"));
+        assert!(requests[0].contains("grant_type=client_credentials&scope=read+write"));
+        // base64("cartero:s3cr3t")
+        assert!(requests[0].contains("Basic Y2FydGVybzpzM2NyM3Q="));
+    }
+
+    #[test]
+    fn test_expired_token_is_refreshed() {
+        let (url, server) = mock_token_server(vec![(
+            "200 OK",
+            r#"{"access_token": "new", "token_type": "Bearer", "expires_in": 3600}"#,
+        )]);
+        let config = config(&url);
+        let cache = TokenCache::default();
+        cache.insert(
+            &config,
+            OAuth2Token {
+                access_token: "old".into(),
+                refresh_token: Some("refresh-me".into()),
+                expires_at: Some(SystemTime::now() - Duration::from_secs(1)),
+            },
+        );
+
+        let token = block_on(access_token(&config, &cache, |_| {})).unwrap();
+        assert_eq!(token, "new");
+        let cached = cache.get(&config).unwrap();
+        assert_eq!(cached.refresh_token.as_deref(), Some("refresh-me"));
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].contains("grant_type=refresh_token&refresh_token=refresh-me"));
+    }
+
+    #[test]
+    fn test_token_errors_are_reported() {
+        let (url, server) = mock_token_server(vec![(
+            "400 Bad Request",
+            r#"{"error": "invalid_client", "error_description": "Unknown client"}"#,
+        )]);
+        let config = config(&url);
+        let cache = TokenCache::default();
+
+        let error = block_on(access_token(&config, &cache, |_| {})).unwrap_err();
+        assert!(matches!(error, OAuth2Error::TokenRejected(reason) if reason == "Unknown client"));
+        assert!(cache.get(&config).is_none());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_authorization_code_flow() {
+        let (url, server) = mock_token_server(vec![(
+            "200 OK",
+            r#"{"access_token": "user-token", "token_type": "Bearer"}"#,
+        )]);
+        let config = OAuth2Config {
+            grant: OAuth2Grant::AuthorizationCode,
+            authorization_url: "https://auth.example.com/authorize?audience=api".into(),
+            client_secret: "".into(),
+            ..config(&url)
+        };
+        let cache = TokenCache::default();
+
+        // Plays the role of the browser, which is redirected back to Cartero.
+        let browser = |url: &str| {
+            let url = url::Url::parse(url).unwrap();
+            let query: HashMap<_, _> = url.query_pairs().into_owned().collect();
+            assert_eq!(query["audience"], "api");
+            assert_eq!(query["code_challenge_method"], "S256");
+            let redirect = url::Url::parse(&query["redirect_uri"]).unwrap();
+            let target = format!("{}?code=xyz&state={}", redirect.path(), query["state"]);
+            let address = format!("127.0.0.1:{}", redirect.port().unwrap());
+            std::thread::spawn(move || {
+                let mut stream = TcpStream::connect(address).unwrap();
+                write!(stream, "GET {target} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+                let mut response = String::new();
+                stream.read_to_string(&mut response).unwrap();
+                assert!(response.starts_with("HTTP/1.1 200 OK"));
+            });
+        };
+
+        let token = block_on(access_token(&config, &cache, browser)).unwrap();
+        assert_eq!(token, "user-token");
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].contains("grant_type=authorization_code&code=xyz"));
+        assert!(requests[0].contains("code_verifier="));
+        assert!(requests[0].contains("client_id=cartero"));
+        assert!(!requests[0].contains("Authorization: Basic"));
+    }
+
+    #[test]
+    fn test_callback_server_skips_bad_connections() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let browser = std::thread::spawn(move || {
+            // Connects and leaves without a word.
+            drop(TcpStream::connect(address).unwrap());
+            // Sends something that is not even text.
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(b"\xff\xfe\xfd\r\n").unwrap();
+            drop(stream);
+            // Finally, the real redirect.
+            let mut stream = TcpStream::connect(address).unwrap();
+            write!(stream, "GET /callback?code=xyz&state=abc HTTP/1.1\r\n\r\n").unwrap();
+        });
+
+        let code = wait_for_code(&listener, "abc", Duration::from_secs(10)).unwrap();
+        assert_eq!(code, "xyz");
+        browser.join().unwrap();
+    }
+
+    #[test]
+    fn test_callback_server_times_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        // A connection that never sends its request cannot keep the server waiting.
+        let _silent = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let result = wait_for_code(&listener, "abc", Duration::from_millis(300));
+        assert!(matches!(result, Err(OAuth2Error::AuthorizationTimeout)));
+    }
+
+    #[test]
+    fn test_parse_callback() {
+        let result = parse_callback("GET /favicon.ico HTTP/1.1", "abc");
+        assert!(result.is_none());
+        let result = parse_callback("GET /callback?code=1&state=other HTTP/1.1", "abc");
+        assert!(matches!(result, Some(Err(OAuth2Error::StateMismatch))));
+        let result = parse_callback(
+            "GET /callback?error=access_denied&state=abc HTTP/1.1",
+            "abc",
+        );
+        assert!(matches!(
+            result,
+            Some(Err(OAuth2Error::AuthorizationDenied(reason))) if reason == "access_denied"
+        ));
+    }
+}
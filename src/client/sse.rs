@@ -0,0 +1,186 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Server-Sent Events, as sent by servers that answer with `text/event-stream`.
+//!
+//! The stream is parsed as it arrives, following the rules of the
+//! [HTML specification](https://html.spec.whatwg.org/multipage/server-sent-events.html).
+
+use crate::entities::KeyValueTable;
+
+/// An event dispatched by the server.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// The last event ID seen in the stream, which may come from a previous event.
+    pub id: Option<String>,
+    /// The type of the event, when it is not the default "message".
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// Whether the headers announce a Server-Sent Events stream.
+pub fn is_event_stream(headers: &KeyValueTable) -> bool {
+    headers.header("content-type").is_some_and(|values| {
+        values.iter().any(|value| {
+            let essence = value.split(';').next().unwrap_or_default();
+            essence.trim().eq_ignore_ascii_case("text/event-stream")
+        })
+    })
+}
+
+/// Splits a stream of bytes into events. Chunks may end at any point, even
+/// in the middle of a line, since the incomplete line is kept until the rest
+/// of it arrives.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    last_id: Option<String>,
+    event: Option<String>,
+    data: Vec<String>,
+    /// Whether the last chunk ended with a CR, so a LF at the start of the next
+    /// one belongs to the same line break.
+    pending_cr: bool,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Processes a chunk of the stream and returns the events it completes.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        for &byte in chunk {
+            if self.pending_cr {
+                self.pending_cr = false;
+                if byte == b'\n' {
+                    continue;
+                }
+            }
+            match byte {
+                b'\r' | b'\n' => {
+                    self.pending_cr = byte == b'\r';
+                    let line = std::mem::take(&mut self.buffer);
+                    let line = String::from_utf8_lossy(&line);
+                    if let Some(event) = self.process_line(&line) {
+                        events.push(event);
+                    }
+                }
+                byte => self.buffer.push(byte),
+            }
+        }
+        events
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            "id" if !value.contains('\0') => self.last_id = Some(value.to_string()),
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        if self.data.is_empty() {
+            return None;
+        }
+        let data = std::mem::take(&mut self.data).join("\n");
+        Some(SseEvent {
+            id: self.last_id.clone(),
+            event: event.filter(|name| !name.is_empty()),
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::KeyValue;
+
+    #[test]
+    fn test_is_event_stream() {
+        let headers = KeyValueTable::new(&[KeyValue::from((
+            "Content-Type",
+            "text/event-stream; charset=utf-8",
+        ))]);
+        assert!(is_event_stream(&headers));
+        let headers = KeyValueTable::new(&[KeyValue::from(("Content-Type", "text/plain"))]);
+        assert!(!is_event_stream(&headers));
+        assert!(!is_event_stream(&KeyValueTable::default()));
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let mut parser = SseParser::new();
+        let stream =
+            b": keep-alive\n\nid: 1\nevent: update\ndata: first\ndata: line\n\ndata:second\n\n";
+        let events = parser.feed(stream);
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    id: Some("1".into()),
+                    event: Some("update".into()),
+                    data: "first\nline".into(),
+                },
+                SseEvent {
+                    id: Some("1".into()),
+                    event: None,
+                    data: "second".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_events_split_across_chunks() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed(b"data: hel").is_empty());
+        assert!(parser.feed(b"lo\r").is_empty());
+        let events = parser.feed(b"\n\r\n");
+        assert_eq!(
+            events,
+            vec![SseEvent {
+                id: None,
+                event: None,
+                data: "hello".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_events_without_data_are_not_dispatched() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed(b"event: ping\n\nretry: 1000\n\n").is_empty());
+        let events = parser.feed(b"data: {\"a\": 1}\n\n");
+        assert_eq!(events[0].event, None);
+        assert_eq!(events[0].data, "{\"a\": 1}");
+    }
+}
@@ -0,0 +1,382 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! WebSocket connections, which run on a background thread so that the user
+//! interface keeps responding while waiting for messages from the server.
+
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::handshake::client::Request;
+use tungstenite::http::{HeaderName, HeaderValue};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+use url::Url;
+
+use crate::entities::{Environment, WebSocketData, WebSocketMessageKind};
+use crate::error::CarteroError;
+
+/// How long the connection waits for new messages before checking whether
+/// there is something to send.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long to wait for the server to acknowledge a close frame.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum WebSocketError {
+    #[error("The URL is not a ws:// or wss:// URL")]
+    InvalidUrl,
+
+    #[error("Invalid header: {0}")]
+    InvalidHeader(String),
+
+    #[error("The binary message is not valid base64")]
+    InvalidBase64,
+}
+
+/// A WebSocket connection whose URL and headers have been rendered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundWebSocket {
+    pub url: String,
+    pub headers: HashMap<String, String>,
+}
+
+impl BoundWebSocket {
+    /// Renders the URL and the headers, using the given environment for the
+    /// variables that are not defined in the connection itself.
+    pub fn bind(
+        value: &WebSocketData,
+        environment: Option<&Environment>,
+    ) -> Result<Self, CarteroError> {
+        let processor = value.template_processor(environment);
+        let url = processor.render(&value.url)?;
+        match Url::parse(&url) {
+            Ok(parsed) if parsed.scheme() == "ws" || parsed.scheme() == "wss" => {}
+            _ => return Err(WebSocketError::InvalidUrl.into()),
+        }
+        let mut headers = HashMap::new();
+        for (name, value) in value.process_headers() {
+            headers.insert(processor.render(&name)?, processor.render(&value)?);
+        }
+        Ok(Self { url, headers })
+    }
+
+    fn client_request(&self) -> Result<Request, WebSocketError> {
+        let mut request = self
+            .url
+            .as_str()
+            .into_client_request()
+            .map_err(|_| WebSocketError::InvalidUrl)?;
+        for (name, value) in &self.headers {
+            let header_name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| WebSocketError::InvalidHeader(name.clone()))?;
+            let header_value = HeaderValue::from_str(value)
+                .map_err(|_| WebSocketError::InvalidHeader(name.clone()))?;
+            request.headers_mut().insert(header_name, header_value);
+        }
+        Ok(request)
+    }
+}
+
+/// A data message sent or received through a WebSocket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebSocketFrame {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl WebSocketFrame {
+    /// Builds the frame for a message written by the user. Binary messages are
+    /// written as base64.
+    pub fn from_draft(kind: WebSocketMessageKind, draft: &str) -> Result<Self, WebSocketError> {
+        match kind {
+            WebSocketMessageKind::Text => Ok(Self::Text(draft.to_string())),
+            WebSocketMessageKind::Binary => base64::decode(draft.trim())
+                .map(Self::Binary)
+                .map_err(|_| WebSocketError::InvalidBase64),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Text(text) => text.len(),
+            Self::Binary(data) => data.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The contents of the frame as shown in the message log. JSON documents
+    /// are pretty-printed and binary data is shown as base64.
+    pub fn display_text(&self) -> String {
+        match self {
+            Self::Text(text) => pretty_json(text).unwrap_or_else(|| text.clone()),
+            Self::Binary(data) => base64::encode(data),
+        }
+    }
+}
+
+impl From<WebSocketFrame> for Message {
+    fn from(value: WebSocketFrame) -> Self {
+        match value {
+            WebSocketFrame::Text(text) => Message::Text(text),
+            WebSocketFrame::Binary(data) => Message::Binary(data),
+        }
+    }
+}
+
+/// Pretty-prints the text if it is a JSON object or array.
+pub fn pretty_json(text: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(text).ok()?;
+    if !value.is_object() && !value.is_array() {
+        return None;
+    }
+    serde_json::to_string_pretty(&value).ok()
+}
+
+/// Something that happened to the connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebSocketEvent {
+    Opened,
+    Received(WebSocketFrame),
+    /// The connection was closed, with the reason given by the server, if any.
+    Closed(Option<String>),
+    Failed(String),
+}
+
+enum Command {
+    Send(WebSocketFrame),
+    Close,
+}
+
+/// An open WebSocket connection. The connection is closed when dropped.
+pub struct WebSocketConnection {
+    commands: Sender<Command>,
+}
+
+impl WebSocketConnection {
+    /// Connects to the server on a background thread. Whatever happens to the
+    /// connection is reported through the returned receiver.
+    pub fn open(
+        websocket: &BoundWebSocket,
+    ) -> Result<(Self, UnboundedReceiver<WebSocketEvent>), WebSocketError> {
+        let request = websocket.client_request()?;
+        let (commands, command_receiver) = mpsc::channel();
+        let (events, event_receiver) = tokio::sync::mpsc::unbounded_channel();
+        std::thread::spawn(move || run_connection(request, command_receiver, events));
+        Ok((Self { commands }, event_receiver))
+    }
+
+    /// Queues a frame to be sent. Returns false if the connection is closed.
+    pub fn send(&self, frame: WebSocketFrame) -> bool {
+        self.commands.send(Command::Send(frame)).is_ok()
+    }
+
+    pub fn close(&self) {
+        let _ = self.commands.send(Command::Close);
+    }
+}
+
+impl Drop for WebSocketConnection {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+fn set_poll_timeout(socket: &WebSocket<MaybeTlsStream<TcpStream>>) -> std::io::Result<()> {
+    match socket.get_ref() {
+        MaybeTlsStream::Plain(stream) => stream.set_read_timeout(Some(POLL_INTERVAL)),
+        MaybeTlsStream::NativeTls(stream) => stream.get_ref().set_read_timeout(Some(POLL_INTERVAL)),
+        _ => Ok(()),
+    }
+}
+
+fn run_connection(
+    request: Request,
+    commands: Receiver<Command>,
+    events: UnboundedSender<WebSocketEvent>,
+) {
+    let mut socket = match tungstenite::connect(request) {
+        Ok((socket, _)) => socket,
+        Err(e) => {
+            let _ = events.send(WebSocketEvent::Failed(e.to_string()));
+            return;
+        }
+    };
+    if let Err(e) = set_poll_timeout(&socket) {
+        let _ = events.send(WebSocketEvent::Failed(e.to_string()));
+        return;
+    }
+    let _ = events.send(WebSocketEvent::Opened);
+
+    let mut closing_since: Option<Instant> = None;
+    let mut reason = None;
+    loop {
+        while closing_since.is_none() {
+            match commands.try_recv() {
+                Ok(Command::Send(frame)) => {
+                    if let Err(e) = socket.send(frame.into()) {
+                        let _ = events.send(WebSocketEvent::Failed(e.to_string()));
+                        return;
+                    }
+                }
+                Ok(Command::Close) | Err(TryRecvError::Disconnected) => {
+                    let _ = socket.close(None);
+                    closing_since = Some(Instant::now());
+                }
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+        if closing_since.is_some_and(|since| since.elapsed() > CLOSE_TIMEOUT) {
+            let _ = events.send(WebSocketEvent::Closed(reason));
+            return;
+        }
+
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let _ = events.send(WebSocketEvent::Received(WebSocketFrame::Text(text)));
+            }
+            Ok(Message::Binary(data)) => {
+                let _ = events.send(WebSocketEvent::Received(WebSocketFrame::Binary(data)));
+            }
+            Ok(Message::Close(frame)) => {
+                reason = frame
+                    .map(|frame| frame.reason.to_string())
+                    .filter(|reason| !reason.is_empty());
+            }
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                let _ = events.send(WebSocketEvent::Closed(reason));
+                return;
+            }
+            Err(e) => {
+                let _ = events.send(WebSocketEvent::Failed(e.to_string()));
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use tungstenite::Message;
+
+    use super::*;
+    use crate::entities::{KeyValue, KeyValueTable};
+
+    fn websocket(url: &str) -> WebSocketData {
+        WebSocketData {
+            url: url.into(),
+            headers: KeyValueTable::new(&[KeyValue::from(("X-Token", "{{TOKEN}}"))]),
+            variables: KeyValueTable::new(&[
+                KeyValue::from(("HOST", "localhost")),
+                KeyValue::from(("TOKEN", "secret")),
+            ]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_bind_renders_url_and_headers() {
+        let bound = BoundWebSocket::bind(&websocket("wss://{{HOST}}/feed"), None).unwrap();
+        assert_eq!(bound.url, "wss://localhost/feed");
+        assert_eq!(bound.headers.get("X-Token"), Some(&"secret".to_string()));
+    }
+
+    #[test]
+    fn test_bind_rejects_other_schemes() {
+        let result = BoundWebSocket::bind(&websocket("https://{{HOST}}/feed"), None);
+        assert!(matches!(
+            result,
+            Err(CarteroError::WebSocket(WebSocketError::InvalidUrl))
+        ));
+    }
+
+    #[test]
+    fn test_frames_from_draft() {
+        assert_eq!(
+            WebSocketFrame::from_draft(WebSocketMessageKind::Text, "hello"),
+            Ok(WebSocketFrame::Text("hello".into()))
+        );
+        assert_eq!(
+            WebSocketFrame::from_draft(WebSocketMessageKind::Binary, "AAEC\n"),
+            Ok(WebSocketFrame::Binary(vec![0, 1, 2]))
+        );
+        assert_eq!(
+            WebSocketFrame::from_draft(WebSocketMessageKind::Binary, "not base64!"),
+            Err(WebSocketError::InvalidBase64)
+        );
+    }
+
+    #[test]
+    fn test_display_text() {
+        let frame = WebSocketFrame::Text(r#"{"a":[1,2]}"#.into());
+        assert_eq!(frame.display_text(), "{\n  \"a\": [\n    1,\n    2\n  ]\n}");
+        let frame = WebSocketFrame::Text("42".into());
+        assert_eq!(frame.display_text(), "42");
+        let frame = WebSocketFrame::Binary(vec![0, 1, 2]);
+        assert_eq!(frame.display_text(), "AAEC");
+    }
+
+    #[test]
+    fn test_echo_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut socket = tungstenite::accept(stream).unwrap();
+            let message = socket.read().unwrap();
+            socket.send(message).unwrap();
+            loop {
+                match socket.read() {
+                    Ok(Message::Close(_)) => {}
+                    Ok(_) => {}
+                    Err(_) => break,
+                }
+            }
+        });
+
+        let bound = BoundWebSocket {
+            url: format!("ws://127.0.0.1:{port}"),
+            headers: HashMap::new(),
+        };
+        let (connection, mut events) = WebSocketConnection::open(&bound).unwrap();
+        assert_eq!(events.blocking_recv(), Some(WebSocketEvent::Opened));
+        assert!(connection.send(WebSocketFrame::Text("ping".into())));
+        assert_eq!(
+            events.blocking_recv(),
+            Some(WebSocketEvent::Received(WebSocketFrame::Text(
+                "ping".into()
+            )))
+        );
+        drop(connection);
+        assert_eq!(events.blocking_recv(), Some(WebSocketEvent::Closed(None)));
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,677 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::entities::{
+    CollectionMetadata, EndpointData, Environment, KeyValueTable, VariableScope,
+};
+use crate::error::CarteroError;
+use crate::extraction::ExtractionResult;
+
+/// The file that marks a directory as the root of a collection.
+pub const MANIFEST_FILE_NAME: &str = "collection.toml";
+
+/// The extension used by request files.
+pub const REQUEST_EXTENSION: &str = "cartero";
+
+/// The suffix used by environment files, which are stored in the root of the collection.
+pub const ENVIRONMENT_SUFFIX: &str = ".env.cartero";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CollectionNode {
+    Folder {
+        name: String,
+        path: PathBuf,
+        children: Vec<CollectionNode>,
+    },
+    Request {
+        name: String,
+        path: PathBuf,
+    },
+}
+
+impl CollectionNode {
+    pub fn name(&self) -> &str {
+        match self {
+            CollectionNode::Folder { name, .. } => name,
+            CollectionNode::Request { name, .. } => name,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            CollectionNode::Folder { path, .. } => path,
+            CollectionNode::Request { path, .. } => path,
+        }
+    }
+
+    pub fn is_folder(&self) -> bool {
+        matches!(self, CollectionNode::Folder { .. })
+    }
+}
+
+fn is_request_file(path: &Path) -> bool {
+    path.is_file()
+        && path.extension().is_some_and(|ext| ext == REQUEST_EXTENSION)
+        && environment_name(path).is_none()
+}
+
+/// Returns the name of the environment if the path points to an environment file.
+fn environment_name(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    let name = file_name.strip_suffix(ENVIRONMENT_SUFFIX)?;
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+/// Lists the names of the environments stored in the root of the collection.
+fn scan_environments(root: &Path) -> Result<Vec<String>, CarteroError> {
+    let mut environments = Vec::new();
+    for entry in fs::read_dir(root)? {
+        let path = entry?.path();
+        if !path.is_file() || is_hidden(&path) {
+            continue;
+        }
+        if let Some(name) = environment_name(&path) {
+            environments.push(name);
+        }
+    }
+    environments.sort();
+    Ok(environments)
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.'))
+}
+
+/// Scans a directory of the collection, returning folders first and then requests.
+///
+/// Folders are always listed, even if empty, so that they can be used as a target
+/// for new requests. Any other file that is not a request is ignored. Symbolic
+/// links to directories are not followed, since they could point back to one of
+/// their parents and the scan would never end.
+fn scan_directory(dir: &Path) -> Result<Vec<CollectionNode>, CarteroError> {
+    let mut folders = Vec::new();
+    let mut requests = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if is_hidden(&path) {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let children = scan_directory(&path)?;
+            folders.push(CollectionNode::Folder {
+                name,
+                path,
+                children,
+            });
+        } else if is_request_file(&path) {
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+            requests.push(CollectionNode::Request { name, path });
+        }
+    }
+
+    folders.sort_by(|a, b| a.name().cmp(b.name()));
+    requests.sort_by(|a, b| a.name().cmp(b.name()));
+    folders.extend(requests);
+    Ok(folders)
+}
+
+fn flatten_requests(nodes: &[CollectionNode], into: &mut Vec<PathBuf>) {
+    for node in nodes {
+        match node {
+            CollectionNode::Folder { children, .. } => flatten_requests(children, into),
+            CollectionNode::Request { path, .. } => into.push(path.clone()),
+        }
+    }
+}
+
+/// Names given to folders and requests must be a single path component.
+fn validate_name(name: &str) -> Result<(), CarteroError> {
+    let name = name.trim();
+    if name.is_empty()
+        || name.starts_with('.')
+        || name.contains('/')
+        || name.contains('\\')
+        || name.contains(std::path::MAIN_SEPARATOR)
+    {
+        return Err(CarteroError::InvalidItemName);
+    }
+    Ok(())
+}
+
+/// A directory that groups many request files into folders.
+///
+/// Every request in a collection is still a plain .cartero file, so that the whole
+/// collection can be tracked in a version control system. The root directory of the
+/// collection contains a manifest file that holds the collection metadata.
+#[derive(Debug, Clone)]
+pub struct Collection {
+    root: PathBuf,
+    metadata: CollectionMetadata,
+    children: Vec<CollectionNode>,
+    environments: Vec<String>,
+}
+
+impl Collection {
+    /// Turns the given directory into a collection, creating it if it doesn't exist.
+    pub fn create(root: &Path, name: &str) -> Result<Self, CarteroError> {
+        fs::create_dir_all(root)?;
+        let manifest = root.join(MANIFEST_FILE_NAME);
+        if manifest.exists() {
+            return Err(CarteroError::ItemAlreadyExists);
+        }
+        let metadata = CollectionMetadata {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let contents = crate::file::store_collection_toml(&metadata)?;
+        fs::write(&manifest, contents)?;
+        Self::open(root)
+    }
+
+    /// Opens the collection whose manifest is placed in the given directory.
+    pub fn open(root: &Path) -> Result<Self, CarteroError> {
+        let root = root.canonicalize()?;
+        let manifest = root.join(MANIFEST_FILE_NAME);
+        if !manifest.is_file() {
+            return Err(CarteroError::NotACollection);
+        }
+        let contents = fs::read_to_string(&manifest)?;
+        let metadata = crate::file::parse_collection_toml(&contents)?;
+        let children = scan_directory(&root)?;
+        let environments = scan_environments(&root)?;
+        Ok(Self {
+            root,
+            metadata,
+            children,
+            environments,
+        })
+    }
+
+    /// Looks for the root of the collection that contains the given path, if any.
+    pub fn find_root(path: &Path) -> Option<PathBuf> {
+        path.ancestors()
+            .find(|dir| dir.join(MANIFEST_FILE_NAME).is_file())
+            .map(Path::to_path_buf)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn name(&self) -> &str {
+        &self.metadata.name
+    }
+
+    pub fn metadata(&self) -> &CollectionMetadata {
+        &self.metadata
+    }
+
+    pub fn children(&self) -> &[CollectionNode] {
+        &self.children
+    }
+
+    /// Returns the path of every request in the collection, in tree order.
+    pub fn requests(&self) -> Vec<PathBuf> {
+        let mut requests = Vec::new();
+        flatten_requests(&self.children, &mut requests);
+        requests
+    }
+
+    /// Returns the names of the environments available in this collection.
+    pub fn environments(&self) -> &[String] {
+        &self.environments
+    }
+
+    /// Re-reads the manifest and the directory tree of the collection.
+    pub fn refresh(&mut self) -> Result<(), CarteroError> {
+        let contents = fs::read_to_string(self.root.join(MANIFEST_FILE_NAME))?;
+        self.metadata = crate::file::parse_collection_toml(&contents)?;
+        self.children = scan_directory(&self.root)?;
+        self.environments = scan_environments(&self.root)?;
+        Ok(())
+    }
+
+    fn environment_path(&self, name: &str) -> PathBuf {
+        self.root
+            .join(format!("{}{ENVIRONMENT_SUFFIX}", name.trim()))
+    }
+
+    /// Reads the environment with the given name from the disk.
+    pub fn load_environment(&self, name: &str) -> Result<Environment, CarteroError> {
+        validate_name(name)?;
+        let contents = fs::read_to_string(self.environment_path(name))?;
+        crate::file::parse_environment_toml(name.trim(), &contents)
+    }
+
+    /// Writes the given environment, replacing the previous file if it exists.
+    pub fn save_environment(&mut self, environment: &Environment) -> Result<(), CarteroError> {
+        validate_name(&environment.name)?;
+        let contents = crate::file::store_environment_toml(environment)?;
+        fs::write(self.environment_path(&environment.name), contents)?;
+        self.refresh()
+    }
+
+    /// Adds the variables of the collection under the ones of the given
+    /// environment, which take precedence. When there is no environment but the
+    /// collection has variables, an environment with just those is returned.
+    pub fn scoped_environment(&self, environment: Option<Environment>) -> Option<Environment> {
+        let variables = &self.metadata.variables;
+        if variables.is_empty() {
+            return environment;
+        }
+        match environment {
+            Some(environment) => Some(environment.over(variables)),
+            None => Some(Environment {
+                name: self.metadata.name.clone(),
+                variables: variables.clone(),
+                proxy: None,
+            }),
+        }
+    }
+
+    /// Stores the values extracted from a response as variables.
+    ///
+    /// Values scoped to the environment go to the given environment. They go to
+    /// the collection instead when no environment is active, like the values that
+    /// are scoped to the collection. Failed extractions are skipped. Since the
+    /// values usually are tokens or session identifiers, they are stored as
+    /// secret variables.
+    pub fn store_extracted(
+        &mut self,
+        environment: Option<&str>,
+        results: &[ExtractionResult],
+    ) -> Result<(), CarteroError> {
+        let mut environment = match environment {
+            Some(name) => Some(self.load_environment(name)?),
+            None => None,
+        };
+        let mut metadata = self.metadata.clone();
+        let mut environment_changed = false;
+        for result in results {
+            let Ok(value) = &result.value else {
+                continue;
+            };
+            let name = &result.extraction.variable;
+            match (result.extraction.scope, environment.as_mut()) {
+                (VariableScope::Environment, Some(environment)) => {
+                    environment.variables.set_secret(name, value);
+                    environment_changed = true;
+                }
+                _ => metadata.variables.set_secret(name, value),
+            }
+        }
+
+        if metadata != self.metadata {
+            let contents = crate::file::store_collection_toml(&metadata)?;
+            fs::write(self.root.join(MANIFEST_FILE_NAME), contents)?;
+            self.metadata = metadata;
+        }
+        match environment {
+            Some(environment) if environment_changed => self.save_environment(&environment),
+            _ => Ok(()),
+        }
+    }
+
+    /// Creates a new environment without variables.
+    pub fn create_environment(&mut self, name: &str) -> Result<Environment, CarteroError> {
+        validate_name(name)?;
+        if self.environment_path(name).exists() {
+            return Err(CarteroError::ItemAlreadyExists);
+        }
+        let environment = Environment {
+            name: name.trim().to_string(),
+            variables: KeyValueTable::default(),
+            proxy: None,
+        };
+        self.save_environment(&environment)?;
+        Ok(environment)
+    }
+
+    pub fn delete_environment(&mut self, name: &str) -> Result<(), CarteroError> {
+        validate_name(name)?;
+        fs::remove_file(self.environment_path(name))?;
+        self.refresh()
+    }
+
+    /// Makes sure that the given path belongs to this collection.
+    fn contained_path(&self, path: &Path) -> Result<PathBuf, CarteroError> {
+        let path = path.canonicalize()?;
+        if path.starts_with(&self.root) {
+            Ok(path)
+        } else {
+            Err(CarteroError::NotACollection)
+        }
+    }
+
+    fn contained_folder(&self, path: &Path) -> Result<PathBuf, CarteroError> {
+        let path = self.contained_path(path)?;
+        if path.is_dir() {
+            Ok(path)
+        } else {
+            Err(CarteroError::InvalidItemName)
+        }
+    }
+
+    /// Creates a new empty request in the given folder of the collection.
+    pub fn create_request(&mut self, folder: &Path, name: &str) -> Result<PathBuf, CarteroError> {
+        self.add_request(folder, name, &EndpointData::default())
+    }
+
+    /// Creates a new request in the given folder of the collection with the given contents.
+    pub fn add_request(
+        &mut self,
+        folder: &Path,
+        name: &str,
+        endpoint: &EndpointData,
+    ) -> Result<PathBuf, CarteroError> {
+        validate_name(name)?;
+        let folder = self.contained_folder(folder)?;
+        let path = folder.join(format!("{}.{REQUEST_EXTENSION}", name.trim()));
+        if path.exists() {
+            return Err(CarteroError::ItemAlreadyExists);
+        }
+        let contents = crate::file::store_toml(endpoint)?;
+        fs::write(&path, contents)?;
+        self.refresh()?;
+        Ok(path)
+    }
+
+    /// Creates a new folder inside the given folder of the collection.
+    pub fn create_folder(&mut self, parent: &Path, name: &str) -> Result<PathBuf, CarteroError> {
+        validate_name(name)?;
+        let parent = self.contained_folder(parent)?;
+        let path = parent.join(name.trim());
+        if path.exists() {
+            return Err(CarteroError::ItemAlreadyExists);
+        }
+        fs::create_dir(&path)?;
+        self.refresh()?;
+        Ok(path)
+    }
+
+    /// Renames a request or a folder, keeping it in the same parent folder.
+    ///
+    /// The extension of request files is preserved, so the new name should not
+    /// contain it.
+    pub fn rename(&mut self, path: &Path, new_name: &str) -> Result<PathBuf, CarteroError> {
+        validate_name(new_name)?;
+        let path = self.contained_path(path)?;
+        if path == self.root {
+            return Err(CarteroError::InvalidItemName);
+        }
+        let parent = path.parent().ok_or(CarteroError::InvalidItemName)?;
+        let target = if path.is_dir() {
+            parent.join(new_name.trim())
+        } else {
+            parent.join(format!("{}.{REQUEST_EXTENSION}", new_name.trim()))
+        };
+        if target.exists() {
+            return Err(CarteroError::ItemAlreadyExists);
+        }
+        fs::rename(&path, &target)?;
+        self.refresh()?;
+        Ok(target)
+    }
+
+    /// Moves a request or a folder into another folder of the collection.
+    pub fn move_to(&mut self, path: &Path, folder: &Path) -> Result<PathBuf, CarteroError> {
+        let path = self.contained_path(path)?;
+        let folder = self.contained_folder(folder)?;
+        if path == self.root || folder.starts_with(&path) {
+            return Err(CarteroError::InvalidItemName);
+        }
+        let file_name = path.file_name().ok_or(CarteroError::InvalidItemName)?;
+        let target = folder.join(file_name);
+        if target.exists() {
+            return Err(CarteroError::ItemAlreadyExists);
+        }
+        fs::rename(&path, &target)?;
+        self.refresh()?;
+        Ok(target)
+    }
+
+    /// Deletes a request or a folder, including every request inside the folder.
+    pub fn delete(&mut self, path: &Path) -> Result<(), CarteroError> {
+        let path = self.contained_path(path)?;
+        if path == self.root {
+            return Err(CarteroError::InvalidItemName);
+        }
+        if path.is_dir() {
+            fs::remove_dir_all(&path)?;
+        } else {
+            fs::remove_file(&path)?;
+        }
+        self.refresh()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::{Collection, CollectionNode};
+    use crate::entities::{Extraction, ExtractionSource, VariableScope};
+    use crate::error::CarteroError;
+    use crate::extraction::{ExtractionError, ExtractionResult};
+
+    fn temp_collection_dir() -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let count = COUNTER.fetch_add(1, Ordering::SeqCst);
+        let dir = std::env::temp_dir().join(format!(
+            "cartero-collection-test-{}-{count}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    pub fn test_create_and_open_collection() {
+        let dir = temp_collection_dir();
+        let collection = Collection::create(&dir, "My API").unwrap();
+        assert_eq!(collection.name(), "My API");
+        assert!(collection.children().is_empty());
+
+        let reopened = Collection::open(&dir).unwrap();
+        assert_eq!(reopened.name(), "My API");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    pub fn test_open_without_manifest() {
+        let dir = temp_collection_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let result = Collection::open(&dir);
+        assert!(matches!(result, Err(CarteroError::NotACollection)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    pub fn test_scan_sorts_folders_first() {
+        let dir = temp_collection_dir();
+        let mut collection = Collection::create(&dir, "API").unwrap();
+        let root = collection.root().to_path_buf();
+        collection.create_request(&root, "zeta").unwrap();
+        collection.create_request(&root, "alpha").unwrap();
+        let users = collection.create_folder(&root, "users").unwrap();
+        collection.create_request(&users, "list").unwrap();
+        fs::write(root.join("notes.txt"), "ignored").unwrap();
+
+        let names: Vec<&str> = collection.children().iter().map(|n| n.name()).collect();
+        assert_eq!(names, vec!["users", "alpha", "zeta"]);
+        match &collection.children()[0] {
+            CollectionNode::Folder { children, .. } => {
+                assert_eq!(children.len(), 1);
+                assert_eq!(children[0].name(), "list");
+            }
+            _ => panic!("expected a folder"),
+        }
+        assert_eq!(collection.requests().len(), 3);
+
+        let request = crate::file::parse_toml(
+            &fs::read_to_string(root.join("users").join("list.cartero")).unwrap(),
+        );
+        assert!(request.is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    pub fn test_scan_skips_symlinked_folders() {
+        let dir = temp_collection_dir();
+        let mut collection = Collection::create(&dir, "API").unwrap();
+        let root = collection.root().to_path_buf();
+        let users = collection.create_folder(&root, "users").unwrap();
+        collection.create_request(&users, "list").unwrap();
+        std::os::unix::fs::symlink(&root, users.join("loop")).unwrap();
+
+        collection.refresh().unwrap();
+        let names: Vec<&str> = collection.children().iter().map(|n| n.name()).collect();
+        assert_eq!(names, vec!["users"]);
+        assert_eq!(collection.requests(), vec![users.join("list.cartero")]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    pub fn test_rename_move_and_delete() {
+        let dir = temp_collection_dir();
+        let mut collection = Collection::create(&dir, "API").unwrap();
+        let root = collection.root().to_path_buf();
+        let request = collection.create_request(&root, "login").unwrap();
+        let folder = collection.create_folder(&root, "auth").unwrap();
+
+        let renamed = collection.rename(&request, "sign-in").unwrap();
+        assert_eq!(renamed, root.join("sign-in.cartero"));
+        assert!(!request.exists());
+
+        let moved = collection.move_to(&renamed, &folder).unwrap();
+        assert_eq!(moved, folder.join("sign-in.cartero"));
+        assert!(moved.exists());
+
+        assert!(collection.move_to(&folder, &folder).is_err());
+
+        collection.delete(&folder).unwrap();
+        assert!(!moved.exists());
+        assert!(collection.children().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    pub fn test_invalid_names() {
+        let dir = temp_collection_dir();
+        let mut collection = Collection::create(&dir, "API").unwrap();
+        let root = collection.root().to_path_buf();
+        assert!(collection.create_request(&root, "").is_err());
+        assert!(collection.create_request(&root, "../escape").is_err());
+        assert!(collection.create_folder(&root, ".hidden").is_err());
+
+        collection.create_request(&root, "twice").unwrap();
+        let twice = collection.create_request(&root, "twice");
+        assert!(matches!(twice, Err(CarteroError::ItemAlreadyExists)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    pub fn test_environments_are_not_requests() {
+        let dir = temp_collection_dir();
+        let mut collection = Collection::create(&dir, "API").unwrap();
+        let root = collection.root().to_path_buf();
+        collection.create_request(&root, "login").unwrap();
+
+        let mut staging = collection.create_environment("staging").unwrap();
+        staging
+            .variables
+            .push(("API_ROOT", "staging.example.com").into());
+        collection.save_environment(&staging).unwrap();
+        collection.create_environment("dev").unwrap();
+
+        assert_eq!(collection.environments(), &["dev", "staging"]);
+        assert_eq!(collection.requests(), vec![root.join("login.cartero")]);
+
+        let loaded = collection.load_environment("staging").unwrap();
+        assert_eq!(loaded, staging);
+
+        collection.delete_environment("dev").unwrap();
+        assert_eq!(collection.environments(), &["staging"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    pub fn test_store_extracted_variables() {
+        let dir = temp_collection_dir();
+        let mut collection = Collection::create(&dir, "API").unwrap();
+        let mut staging = collection.create_environment("staging").unwrap();
+        staging.variables.push(("TOKEN", "old").into());
+        collection.save_environment(&staging).unwrap();
+
+        let result = |variable: &str, scope, value| ExtractionResult {
+            extraction: Extraction {
+                variable: variable.into(),
+                source: ExtractionSource::Header("X-Value".into()),
+                scope,
+            },
+            value,
+        };
+        let results = [
+            result("TOKEN", VariableScope::Environment, Ok("new".into())),
+            result("USER", VariableScope::Collection, Ok("7".into())),
+            result(
+                "MISSING",
+                VariableScope::Collection,
+                Err(ExtractionError::NotFound),
+            ),
+        ];
+        collection
+            .store_extracted(Some("staging"), &results)
+            .unwrap();
+
+        let staging = collection.load_environment("staging").unwrap();
+        assert_eq!(staging.variables.len(), 1);
+        assert_eq!(staging.variables[0].value, "new");
+        assert!(staging.variables[0].secret);
+        let reopened = Collection::open(&dir).unwrap();
+        assert_eq!(reopened.metadata().variables.len(), 1);
+        assert_eq!(reopened.metadata().variables[0].name, "USER");
+        assert!(reopened.metadata().variables[0].secret);
+
+        // Without an environment, everything goes to the collection.
+        collection.store_extracted(None, &results[..1]).unwrap();
+        let scoped = collection.scoped_environment(None).unwrap();
+        let names: Vec<&str> = scoped.variables.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["USER", "TOKEN"]);
+
+        // The variables of the environment hide the ones of the collection.
+        let staging = collection.scoped_environment(Some(staging)).unwrap();
+        let values: Vec<&str> = staging.variables.iter().map(|v| v.value.as_str()).collect();
+        assert_eq!(values, vec!["7", "new"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
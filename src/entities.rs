@@ -22,8 +22,6 @@ use std::{
 
 use srtemplate::SrTemplate;
 
-use crate::objects::KeyValueItem;
-
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct KeyValue {
     pub name: String,
@@ -68,19 +66,9 @@ impl From<(&str, &str)> for KeyValue {
     }
 }
 
-impl From<KeyValueItem> for KeyValue {
-    fn from(value: KeyValueItem) -> Self {
-        Self {
-            name: value.header_name().clone(),
-            value: value.header_value().clone(),
-            active: value.active(),
-            secret: value.secret(),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Default, Eq, PartialEq, glib::Boxed)]
-#[boxed_type(name = "GKeyValueTable")]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "gui", derive(glib::Boxed))]
+#[cfg_attr(feature = "gui", boxed_type(name = "GKeyValueTable"))]
 pub struct KeyValueTable(Vec<KeyValue>);
 
 impl KeyValueTable {
@@ -99,6 +87,26 @@ impl KeyValueTable {
         hash_map
     }
 
+    /// Gives a value to the variable with the given name, activating it, or
+    /// adds the variable if the table does not have it yet.
+    pub fn set(&mut self, name: &str, value: &str) {
+        match self.0.iter_mut().find(|kv| kv.name == name) {
+            Some(entry) => {
+                entry.value = value.to_string();
+                entry.active = true;
+            }
+            None => self.0.push((name, value).into()),
+        }
+    }
+
+    /// Like [`KeyValueTable::set`], but also marks the variable as secret.
+    pub fn set_secret(&mut self, name: &str, value: &str) {
+        self.set(name, value);
+        if let Some(entry) = self.0.iter_mut().find(|kv| kv.name == name) {
+            entry.secret = true;
+        }
+    }
+
     pub fn header(&self, key: &str) -> Option<Vec<&str>> {
         let compare_key: String = key.to_lowercase();
         let mut headers: Vec<&str> = self
@@ -155,11 +163,21 @@ pub enum RequestMethod {
     Options,
     Head,
     Trace,
+
+    /// Any other method, such as the WebDAV ones. It is sent as written.
+    Custom(String),
+}
+
+/// Whether the character may be part of a method name (RFC 9110, 5.6.2).
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
 }
 
 impl TryFrom<&str> for RequestMethod {
     type Error = ();
 
+    /// Standard methods are recognised regardless of their case. Any other
+    /// method is kept verbatim, as long as it is a valid token.
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value.to_lowercase().as_str() {
             "get" => Ok(RequestMethod::Get),
@@ -170,14 +188,17 @@ impl TryFrom<&str> for RequestMethod {
             "options" => Ok(RequestMethod::Options),
             "head" => Ok(RequestMethod::Head),
             "trace" => Ok(RequestMethod::Trace),
+            _ if !value.is_empty() && value.chars().all(is_token_char) => {
+                Ok(RequestMethod::Custom(value.to_string()))
+            }
             _ => Err(()),
         }
     }
 }
 
-impl From<RequestMethod> for &str {
-    fn from(val: RequestMethod) -> Self {
-        match val {
+impl RequestMethod {
+    pub fn as_str(&self) -> &str {
+        match self {
             RequestMethod::Get => "GET",
             RequestMethod::Post => "POST",
             RequestMethod::Put => "PUT",
@@ -186,14 +207,17 @@ impl From<RequestMethod> for &str {
             RequestMethod::Head => "HEAD",
             RequestMethod::Options => "OPTIONS",
             RequestMethod::Trace => "TRACE",
+            RequestMethod::Custom(name) => name,
         }
     }
 }
 
 impl From<RequestMethod> for String {
     fn from(value: RequestMethod) -> String {
-        let string: &str = value.into();
-        String::from(string)
+        match value {
+            RequestMethod::Custom(name) => name,
+            standard => String::from(standard.as_str()),
+        }
     }
 }
 
@@ -203,6 +227,81 @@ pub enum RawEncoding {
     Xml,
     #[default]
     OctetStream,
+    Text,
+    Html,
+    Yaml,
+    JavaScript,
+    Csv,
+    /// Any other format, sent using the given content type.
+    Custom(String),
+}
+
+impl RawEncoding {
+    /// The value of the Content-Type header used to send a body in this encoding.
+    pub fn content_type(&self) -> &str {
+        match self {
+            RawEncoding::Json => "application/json",
+            RawEncoding::Xml => "application/xml",
+            RawEncoding::OctetStream => "application/octet-stream",
+            RawEncoding::Text => "text/plain",
+            RawEncoding::Html => "text/html",
+            RawEncoding::Yaml => "application/yaml",
+            RawEncoding::JavaScript => "text/javascript",
+            RawEncoding::Csv => "text/csv",
+            RawEncoding::Custom(content_type) => content_type,
+        }
+    }
+
+    /// Picks the encoding that matches a content type. Parameters such as the
+    /// charset are ignored, and unknown types are kept as a custom encoding.
+    pub fn from_content_type(content_type: &str) -> Self {
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+        match essence.as_str() {
+            "" | "application/octet-stream" => RawEncoding::OctetStream,
+            "text/plain" => RawEncoding::Text,
+            "text/html" => RawEncoding::Html,
+            "text/csv" => RawEncoding::Csv,
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                RawEncoding::Yaml
+            }
+            "text/javascript" | "application/javascript" | "application/x-javascript" => {
+                RawEncoding::JavaScript
+            }
+            essence if essence.contains("json") => RawEncoding::Json,
+            essence if essence.contains("xml") => RawEncoding::Xml,
+            essence => RawEncoding::Custom(essence.to_string()),
+        }
+    }
+}
+
+/// A file sent as a part of a multipart form. The content is read when the request
+/// is sent, so the file may change between requests.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MultipartFile {
+    pub name: String,
+    pub path: String,
+    /// The file name announced to the server, instead of the name of the file in disk.
+    pub filename: Option<String>,
+    /// The content type of the part, guessed from the extension when missing.
+    pub content_type: Option<String>,
+    pub active: bool,
+}
+
+impl Default for MultipartFile {
+    fn default() -> Self {
+        Self {
+            name: String::default(),
+            path: String::default(),
+            filename: None,
+            content_type: None,
+            active: true,
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
@@ -212,11 +311,246 @@ pub enum RequestPayload {
     Urlencoded(KeyValueTable),
     Multipart {
         params: KeyValueTable,
+        files: Vec<MultipartFile>,
     },
     Raw {
         encoding: RawEncoding,
         content: Vec<u8>,
     },
+    /// The contents of a file in disk, read when the request is sent. Relative
+    /// paths are resolved against the directory that contains the request file.
+    File {
+        path: String,
+    },
+    /// A GraphQL query, sent as a JSON document. The variables are written as JSON.
+    GraphQL {
+        query: String,
+        variables: String,
+        operation_name: Option<String>,
+    },
+}
+
+/// Where an API key is placed when it is sent to the server.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum ApiKeyLocation {
+    #[default]
+    Header,
+    Query,
+}
+
+/// The OAuth 2.0 grant used to obtain an access token.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum OAuth2Grant {
+    #[default]
+    ClientCredentials,
+    /// Authorization code with PKCE, using a loopback address as the redirect URI.
+    AuthorizationCode,
+}
+
+/// How to obtain an access token from an OAuth 2.0 authorization server.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct OAuth2Config {
+    pub grant: OAuth2Grant,
+    pub token_url: String,
+    /// Only used by the authorization code grant.
+    pub authorization_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scope: String,
+}
+
+/// The credentials used to authenticate a request.
+///
+/// Every value may contain variables, which are rendered when the request is bound.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub enum RequestAuth {
+    #[default]
+    None,
+    Basic {
+        username: String,
+        password: String,
+    },
+    Bearer {
+        token: String,
+    },
+    ApiKey {
+        name: String,
+        value: String,
+        location: ApiKeyLocation,
+    },
+    Digest {
+        username: String,
+        password: String,
+    },
+    OAuth2(OAuth2Config),
+}
+
+/// A check that is evaluated against the response of a request.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Assertion {
+    StatusEquals(u32),
+    StatusInRange {
+        min: u32,
+        max: u32,
+    },
+    HeaderPresent(String),
+    HeaderEquals {
+        name: String,
+        value: String,
+    },
+    JsonExists(String),
+    JsonEquals {
+        pointer: String,
+        value: serde_json::Value,
+    },
+    JsonMatches {
+        pointer: String,
+        pattern: String,
+    },
+    BodyContains(String),
+    MaxDuration(u128),
+}
+
+impl std::fmt::Display for Assertion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Assertion::StatusEquals(code) => write!(f, "Status code is {code}"),
+            Assertion::StatusInRange { min, max } => {
+                write!(f, "Status code is between {min} and {max}")
+            }
+            Assertion::HeaderPresent(name) => write!(f, "Header {name} is present"),
+            Assertion::HeaderEquals { name, value } => write!(f, "Header {name} is {value}"),
+            Assertion::JsonExists(pointer) => write!(f, "JSON value at {pointer} exists"),
+            Assertion::JsonEquals { pointer, value } => {
+                write!(f, "JSON value at {pointer} is {value}")
+            }
+            Assertion::JsonMatches { pointer, pattern } => {
+                write!(f, "JSON value at {pointer} matches {pattern}")
+            }
+            Assertion::BodyContains(text) => write!(f, "Body contains {text:?}"),
+            Assertion::MaxDuration(ms) => write!(f, "Response takes at most {ms} ms"),
+        }
+    }
+}
+
+/// Where the value of an extraction rule is taken from.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ExtractionSource {
+    /// A JSON pointer such as `/data/token`, or a simple JSONPath such as
+    /// `$.data.token`, evaluated against the JSON body.
+    Json(String),
+
+    /// An XPath expression evaluated against the XML body.
+    XPath(String),
+
+    /// The value of a response header.
+    Header(String),
+
+    /// A regular expression matched against the body. The first capture group
+    /// is extracted if there is one, or the whole match otherwise.
+    Regex(String),
+}
+
+/// Which variables receive the values extracted from a response.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum VariableScope {
+    /// The active environment, or the collection when there is none.
+    #[default]
+    Environment,
+    Collection,
+}
+
+/// A rule that stores a value of the response in a variable, so that the
+/// requests sent later can use it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Extraction {
+    pub variable: String,
+    pub source: ExtractionSource,
+    pub scope: VariableScope,
+}
+
+impl std::fmt::Display for ExtractionSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtractionSource::Json(path) => write!(f, "JSON value at {path}"),
+            ExtractionSource::XPath(path) => write!(f, "XML value at {path}"),
+            ExtractionSource::Header(name) => write!(f, "Header {name}"),
+            ExtractionSource::Regex(pattern) => write!(f, "Body matching {pattern}"),
+        }
+    }
+}
+
+/// The version of HTTP to use when talking to the server.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum HttpVersion {
+    /// Negotiate the newest version supported by both sides.
+    #[default]
+    Auto,
+    Http11,
+    Http2,
+}
+
+/// How the HTTP client reaches the servers.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum ProxyMode {
+    /// Use the proxy configured in the system, if any.
+    #[default]
+    System,
+
+    /// Connect to the server without any proxy.
+    Direct,
+
+    /// Use the proxy given in the settings.
+    Manual,
+}
+
+/// Settings of the proxy used to send a request.
+///
+/// The URL may use the `http`, `https` or `socks5` schemes. The username and
+/// password are only sent when the proxy asks for them.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct ProxySettings {
+    pub mode: ProxyMode,
+    pub url: String,
+    pub username: String,
+    pub password: String,
+
+    /// Hosts that are reached without the proxy even in manual mode.
+    pub no_proxy: Vec<String>,
+}
+
+/// Settings of the HTTP client used to send a request.
+///
+/// Every setting is optional, so that a request only overrides the global
+/// preferences that it sets. Timeouts are given in seconds.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct ClientOptions {
+    pub connect_timeout: Option<u64>,
+    pub timeout: Option<u64>,
+    pub follow_redirects: Option<bool>,
+    pub max_redirects: Option<u32>,
+    pub verify_tls: Option<bool>,
+    pub http_version: Option<HttpVersion>,
+    pub proxy: Option<ProxySettings>,
+}
+
+impl ClientOptions {
+    /// Returns these options, taking the ones that are not set from `defaults`.
+    pub fn or(&self, defaults: &ClientOptions) -> ClientOptions {
+        ClientOptions {
+            connect_timeout: self.connect_timeout.or(defaults.connect_timeout),
+            timeout: self.timeout.or(defaults.timeout),
+            follow_redirects: self.follow_redirects.or(defaults.follow_redirects),
+            max_redirects: self.max_redirects.or(defaults.max_redirects),
+            verify_tls: self.verify_tls.or(defaults.verify_tls),
+            http_version: self.http_version.or(defaults.http_version),
+            proxy: self.proxy.clone().or_else(|| defaults.proxy.clone()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &ClientOptions::default()
+    }
 }
 
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
@@ -226,28 +560,140 @@ pub struct EndpointData {
     pub headers: KeyValueTable,
     pub variables: KeyValueTable,
     pub body: RequestPayload,
+    pub auth: RequestAuth,
+    pub tests: Vec<Assertion>,
+    pub extractions: Vec<Extraction>,
+    pub options: ClientOptions,
 }
 
 impl EndpointData {
-    pub fn template_processor(&self) -> SrTemplate {
-        let context = SrTemplate::default();
-        for item in self.variables.iter() {
+    /// Builds the template processor used to render the request.
+    ///
+    /// Active variables from the given environment are registered first, so
+    /// that any variable defined in the request itself takes precedence.
+    pub fn template_processor(&self, environment: Option<&Environment>) -> SrTemplate {
+        build_template_processor(&self.variables, environment)
+    }
+
+    pub fn process_headers(&self) -> HashMap<String, String> {
+        active_headers(&self.headers)
+    }
+}
+
+fn build_template_processor<'a>(
+    variables: &KeyValueTable,
+    environment: Option<&Environment>,
+) -> SrTemplate<'a> {
+    let context = SrTemplate::default();
+    if let Some(environment) = environment {
+        for item in environment.variables.iter().filter(|item| item.active) {
             context.add_variable(item.name.clone(), &item.value);
         }
-        context
+    }
+    for item in variables.iter() {
+        context.add_variable(item.name.clone(), &item.value);
+    }
+    context
+}
+
+fn active_headers(table: &KeyValueTable) -> HashMap<String, String> {
+    let mut headers = HashMap::new();
+    for item in table.iter() {
+        if item.active {
+            headers.insert(item.name.clone(), item.value.clone());
+        }
+    }
+    headers
+}
+
+/// How the draft message of a WebSocket is sent.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum WebSocketMessageKind {
+    #[default]
+    Text,
+    /// The draft is written as base64 and decoded before sending it.
+    Binary,
+}
+
+/// A WebSocket connection, as saved in a file.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct WebSocketData {
+    pub url: String,
+    pub headers: KeyValueTable,
+    pub variables: KeyValueTable,
+    /// The message that was being written the last time the file was saved.
+    pub message: String,
+    pub message_kind: WebSocketMessageKind,
+}
+
+impl WebSocketData {
+    /// Builds the template processor used to render the URL and the headers.
+    pub fn template_processor(&self, environment: Option<&Environment>) -> SrTemplate {
+        build_template_processor(&self.variables, environment)
     }
 
     pub fn process_headers(&self) -> HashMap<String, String> {
-        let mut headers = HashMap::new();
-        for item in self.headers.iter() {
-            if item.active {
-                headers.insert(item.name.clone(), item.value.clone());
-            }
+        active_headers(&self.headers)
+    }
+}
+
+/// Any of the kinds of items that can be stored in a request file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ItemData {
+    Endpoint(Box<EndpointData>),
+    WebSocket(WebSocketData),
+}
+
+/// A named set of variables that can be applied to every request of a collection.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Environment {
+    pub name: String,
+    pub variables: KeyValueTable,
+
+    /// Proxy used by the requests sent with this environment, unless they set their own.
+    pub proxy: Option<ProxySettings>,
+}
+
+impl Environment {
+    /// Returns this environment with the given variables added under its own,
+    /// so that the variables of the environment take precedence.
+    pub fn over(&self, variables: &KeyValueTable) -> Environment {
+        let mut merged: Vec<KeyValue> = variables
+            .iter()
+            .filter(|item| {
+                !self
+                    .variables
+                    .iter()
+                    .any(|own| own.active && own.name == item.name)
+            })
+            .cloned()
+            .collect();
+        merged.extend(self.variables.iter().cloned());
+        Environment {
+            variables: KeyValueTable::new(&merged),
+            ..self.clone()
+        }
+    }
+
+    /// The client options that this environment gives to the requests sent with it.
+    pub fn client_options(&self) -> ClientOptions {
+        ClientOptions {
+            proxy: self.proxy.clone(),
+            ..Default::default()
         }
-        headers
     }
 }
 
+/// Metadata stored in the manifest of a collection.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct CollectionMetadata {
+    pub name: String,
+
+    /// Variables shared by every request of the collection, whatever the
+    /// active environment is.
+    pub variables: KeyValueTable,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct ResponseData {
     pub status_code: u32,
@@ -292,7 +738,68 @@ impl ResponseData {
 mod tests {
     use crate::entities::{KeyValue, RequestMethod};
 
-    use super::{KeyValueTable, ResponseData};
+    use super::{
+        ClientOptions, HttpVersion, KeyValueTable, ProxyMode, ProxySettings, RawEncoding,
+        ResponseData,
+    };
+
+    #[test]
+    fn test_client_options_override_defaults() {
+        let defaults = ClientOptions {
+            connect_timeout: Some(10),
+            timeout: Some(30),
+            follow_redirects: Some(true),
+            max_redirects: Some(10),
+            verify_tls: Some(true),
+            http_version: Some(HttpVersion::Auto),
+            proxy: Some(ProxySettings::default()),
+        };
+        let request = ClientOptions {
+            timeout: Some(5),
+            verify_tls: Some(false),
+            proxy: Some(ProxySettings {
+                mode: ProxyMode::Direct,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let options = request.or(&defaults);
+        assert_eq!(options.connect_timeout, Some(10));
+        assert_eq!(options.timeout, Some(5));
+        assert_eq!(options.follow_redirects, Some(true));
+        assert_eq!(options.verify_tls, Some(false));
+        assert_eq!(options.http_version, Some(HttpVersion::Auto));
+        assert_eq!(options.proxy.map(|p| p.mode), Some(ProxyMode::Direct));
+        assert!(ClientOptions::default().is_empty());
+        assert!(!request.is_empty());
+    }
+
+    #[test]
+    fn test_raw_encoding_from_content_type() {
+        let cases = [
+            ("application/json", RawEncoding::Json),
+            ("application/ld+json; charset=utf-8", RawEncoding::Json),
+            ("text/xml", RawEncoding::Xml),
+            ("text/plain; charset=utf-8", RawEncoding::Text),
+            ("TEXT/HTML", RawEncoding::Html),
+            ("application/x-yaml", RawEncoding::Yaml),
+            ("application/javascript", RawEncoding::JavaScript),
+            ("text/csv", RawEncoding::Csv),
+            ("", RawEncoding::OctetStream),
+            (
+                "application/vnd.cartero+toml",
+                RawEncoding::Custom("application/vnd.cartero+toml".into()),
+            ),
+        ];
+        for (content_type, expected) in cases {
+            assert_eq!(RawEncoding::from_content_type(content_type), expected);
+        }
+        assert_eq!(RawEncoding::Yaml.content_type(), "application/yaml");
+        assert_eq!(
+            RawEncoding::Custom("text/x-ini".into()).content_type(),
+            "text/x-ini"
+        );
+    }
 
     #[test]
     fn test_response_is_json() {
@@ -363,7 +870,17 @@ mod tests {
         assert!(RequestMethod::try_from("GET").is_ok_and(|x| x == RequestMethod::Get));
         assert!(RequestMethod::try_from("post").is_ok_and(|x| x == RequestMethod::Post));
         assert!(RequestMethod::try_from("Patch").is_ok_and(|x| x == RequestMethod::Patch));
-        assert!(RequestMethod::try_from("Juan").is_err());
+        assert_eq!(
+            RequestMethod::try_from("PROPFIND"),
+            Ok(RequestMethod::Custom("PROPFIND".into()))
+        );
+        assert_eq!(
+            RequestMethod::try_from("Juan").map(String::from),
+            Ok("Juan".into())
+        );
+        assert!(RequestMethod::try_from("").is_err());
+        assert!(RequestMethod::try_from("NOT A VERB").is_err());
+        assert!(RequestMethod::try_from("LINK\n").is_err());
     }
 
     #[test]
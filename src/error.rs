@@ -1,7 +1,9 @@
 use srtemplate::SrTemplateError;
 use thiserror::Error;
 
-use crate::client::RequestError;
+use crate::client::{GraphQLError, OAuth2Error, RequestError, WebSocketError};
+use crate::import::ImportError;
+use crate::runner::RunnerError;
 
 #[derive(Debug, Error)]
 pub enum CarteroError {
@@ -34,4 +36,46 @@ pub enum CarteroError {
 
     #[error("Outdated schema, please update the software")]
     OutdatedSchema,
+
+    #[error("Unsupported kind of request file, please update the software")]
+    UnsupportedItemKind,
+
+    #[error("The file does not contain an HTTP request")]
+    NotAnEndpoint,
+
+    #[error("The folder is not a Cartero collection")]
+    NotACollection,
+
+    #[error("Invalid name for a collection item")]
+    InvalidItemName,
+
+    #[error("An item with that name already exists")]
+    ItemAlreadyExists,
+
+    #[error("Invalid test definition")]
+    InvalidAssertion,
+
+    #[error("Cannot import the request: {0}")]
+    Import(#[from] ImportError),
+
+    #[error("The request cannot be exported in this format")]
+    UnsupportedExport,
+
+    #[error("Send the request before exporting its response")]
+    NoResponse,
+
+    #[error("Cannot record the request in the history")]
+    InvalidHistoryEntry,
+
+    #[error("Cannot obtain the OAuth 2.0 token: {0}")]
+    OAuth2(#[from] OAuth2Error),
+
+    #[error("{0}")]
+    GraphQL(#[from] GraphQLError),
+
+    #[error("{0}")]
+    WebSocket(#[from] WebSocketError),
+
+    #[error("{0}")]
+    Runner(#[from] RunnerError),
 }
@@ -0,0 +1,246 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Writes a request and its response as an HTTP Archive (HAR) 1.2 file.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+use crate::client::{BoundRequest, RequestBody};
+use crate::entities::{KeyValueTable, ResponseData};
+use crate::error::CarteroError;
+
+/// Formats a moment in time as an ISO 8601 date in UTC, as required by HAR.
+fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let millis = since_epoch.subsec_millis();
+    let (days, seconds) = ((seconds / 86400) as i64, seconds % 86400);
+
+    // Converts the days since the epoch into a civil date (Howard Hinnant's algorithm).
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{millis:03}Z",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn name_values<'a>(pairs: impl Iterator<Item = (&'a str, &'a str)>) -> Value {
+    pairs
+        .map(|(name, value)| json!({"name": name, "value": value}))
+        .collect()
+}
+
+fn header_value<'a>(headers: &'a [(&'a str, &'a str)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| *value)
+}
+
+/// HAR stores text bodies as they are, and binary bodies encoded in base64.
+fn content_text(body: &[u8]) -> (String, Option<&'static str>) {
+    match std::str::from_utf8(body) {
+        Ok(text) => (text.to_string(), None),
+        Err(_) => (base64::encode(body), Some("base64")),
+    }
+}
+
+fn request_entry(request: &BoundRequest) -> Value {
+    let mut headers: Vec<(&str, &str)> = request
+        .headers
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+    headers.sort();
+
+    let query: Vec<(String, String)> = url::Url::parse(&request.url)
+        .map(|url| url.query_pairs().into_owned().collect())
+        .unwrap_or_default();
+    let body_size = request.body.as_ref().map_or(0, RequestBody::len);
+
+    let mut entry = json!({
+        "method": String::from(request.method.clone()),
+        "url": request.url,
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": name_values(headers.iter().copied()),
+        "queryString": name_values(query.iter().map(|(k, v)| (k.as_str(), v.as_str()))),
+        "headersSize": -1,
+        "bodySize": body_size,
+    });
+    // The content of the files sent in the body is not included.
+    if let Some(body) = request.body.as_ref().and_then(RequestBody::as_bytes) {
+        let mime_type = header_value(&headers, "Content-Type").unwrap_or("");
+        entry["postData"] = json!({
+            "mimeType": mime_type,
+            "text": String::from_utf8_lossy(body),
+        });
+    }
+    entry
+}
+
+fn response_entry(response: &ResponseData) -> Value {
+    let headers: &KeyValueTable = &response.headers;
+    let mime_type = headers
+        .header("Content-Type")
+        .and_then(|values| values.first().map(|value| value.to_string()))
+        .unwrap_or_default();
+    let status_text = isahc::http::StatusCode::from_u16(response.status_code as u16)
+        .ok()
+        .and_then(|code| code.canonical_reason())
+        .unwrap_or("");
+    let (text, encoding) = content_text(&response.body);
+
+    let mut content = json!({
+        "size": response.body.len(),
+        "mimeType": mime_type,
+        "text": text,
+    });
+    if let Some(encoding) = encoding {
+        content["encoding"] = Value::from(encoding);
+    }
+    let location = headers
+        .header("Location")
+        .and_then(|values| values.first().map(|value| value.to_string()))
+        .unwrap_or_default();
+
+    json!({
+        "status": response.status_code,
+        "statusText": status_text,
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": name_values(headers.iter().map(|h| (h.name.as_str(), h.value.as_str()))),
+        "content": content,
+        "redirectURL": location,
+        "headersSize": -1,
+        "bodySize": response.size,
+    })
+}
+
+/// Writes a HAR archive with a single entry made of the given request and response.
+///
+/// Cartero does not measure every phase of the request, so the whole duration is
+/// reported as the time spent waiting for the response.
+pub fn export_har(
+    request: &BoundRequest,
+    response: &ResponseData,
+    started: SystemTime,
+) -> Result<String, CarteroError> {
+    let duration = response.duration as u64;
+    let archive = json!({
+        "log": {
+            "version": "1.2",
+            "creator": {
+                "name": "Cartero",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "entries": [{
+                "startedDateTime": format_timestamp(started),
+                "time": duration,
+                "request": request_entry(request),
+                "response": response_entry(response),
+                "cache": {},
+                "timings": {
+                    "blocked": -1,
+                    "dns": -1,
+                    "connect": -1,
+                    "ssl": -1,
+                    "send": 0,
+                    "wait": duration,
+                    "receive": 0,
+                },
+            }],
+        },
+    });
+    serde_json::to_string_pretty(&archive).map_err(|_| CarteroError::UnsupportedExport)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{export_har, format_timestamp};
+    use crate::client::{BoundRequest, RequestBody};
+    use crate::entities::{KeyValueTable, RequestMethod, ResponseData};
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        let time = UNIX_EPOCH + Duration::from_millis(1_709_210_096_789);
+        assert_eq!(format_timestamp(time), "2024-02-29T12:34:56.789Z");
+    }
+
+    #[test]
+    fn test_export_har_entry() {
+        let request = BoundRequest {
+            url: "https://example.com/users?page=2".into(),
+            method: RequestMethod::Post,
+            headers: HashMap::from([("Content-Type".into(), "application/json".into())]),
+            body: Some(RequestBody::from(Vec::from(r#"{"name":"john"}"#))),
+            ..Default::default()
+        };
+        let response = ResponseData {
+            status_code: 201,
+            duration: 42,
+            size: 2,
+            headers: KeyValueTable::new(&[("content-type", "application/json").into()]),
+            body: Vec::from("{}"),
+        };
+        let har = export_har(&request, &response, UNIX_EPOCH).unwrap();
+        let har: serde_json::Value = serde_json::from_str(&har).unwrap();
+        let entry = &har["log"]["entries"][0];
+        assert_eq!(har["log"]["version"], "1.2");
+        assert_eq!(entry["time"], 42);
+        assert_eq!(entry["request"]["method"], "POST");
+        assert_eq!(entry["request"]["queryString"][0]["value"], "2");
+        assert_eq!(entry["request"]["postData"]["text"], r#"{"name":"john"}"#);
+        assert_eq!(entry["response"]["status"], 201);
+        assert_eq!(entry["response"]["statusText"], "Created");
+        assert_eq!(entry["response"]["content"]["mimeType"], "application/json");
+        assert_eq!(entry["response"]["content"]["text"], "{}");
+    }
+
+    #[test]
+    fn test_export_har_binary_body() {
+        let response = ResponseData {
+            status_code: 200,
+            duration: 1,
+            size: 2,
+            headers: KeyValueTable::default(),
+            body: vec![0xff, 0xfe],
+        };
+        let har = export_har(&BoundRequest::default(), &response, UNIX_EPOCH).unwrap();
+        let har: serde_json::Value = serde_json::from_str(&har).unwrap();
+        let content = &har["log"]["entries"][0]["response"]["content"];
+        assert_eq!(content["encoding"], "base64");
+        assert_eq!(content["text"], "//4=");
+    }
+}
@@ -0,0 +1,588 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Conversion of Cartero endpoints into commands and code snippets that perform
+//! the same request outside of the application.
+
+pub mod har;
+mod shell;
+mod snippets;
+
+use std::path::Path;
+
+use srtemplate::SrTemplate;
+
+use crate::client::{append_query_pair, graphql_body, guess_content_type, TokenCache};
+use crate::entities::{
+    ApiKeyLocation, EndpointData, Environment, KeyValue, OAuth2Config, RequestAuth, RequestPayload,
+};
+use crate::error::CarteroError;
+
+/// The value that replaces secrets when they are masked.
+pub const SECRET_MASK: &str = "********";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Curl,
+    Httpie,
+    Wget,
+    Rust,
+    Python,
+    JavaScript,
+}
+
+impl TryFrom<&str> for ExportFormat {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "curl" => Ok(ExportFormat::Curl),
+            "httpie" => Ok(ExportFormat::Httpie),
+            "wget" => Ok(ExportFormat::Wget),
+            "rust" => Ok(ExportFormat::Rust),
+            "python" => Ok(ExportFormat::Python),
+            "javascript" => Ok(ExportFormat::JavaScript),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// Leave the `{{VARIABLES}}` in place instead of replacing them with their values.
+    pub keep_variables: bool,
+
+    /// Replace the values of headers and variables marked as secret with a mask.
+    pub mask_secrets: bool,
+}
+
+/// A request where every value has already been rendered and is ready to be written.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct ExportedRequest {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: ExportedBody,
+    auth: ExportedAuth,
+}
+
+/// Authentication schemes that every tool knows how to apply on its own.
+///
+/// Other schemes are exported as plain headers or query parameters.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+enum ExportedAuth {
+    #[default]
+    None,
+    Basic(String, String),
+    Digest(String, String),
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+enum ExportedBody {
+    #[default]
+    None,
+    Urlencoded(Vec<(String, String)>),
+    Multipart(Vec<(String, String)>, Vec<ExportedFile>),
+    Raw(String),
+    /// The path of a file whose contents are sent as the body.
+    File(String),
+}
+
+/// A file of a multipart body, which the exported command reads from the disk.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct ExportedFile {
+    name: String,
+    path: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+}
+
+impl ExportedFile {
+    /// The file name announced to the server, which defaults to the name in disk.
+    fn filename(&self) -> String {
+        match &self.filename {
+            Some(filename) => filename.clone(),
+            None => Path::new(&self.path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl ExportedRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Mirrors [`EndpointData::template_processor`], masking the secret variables if requested.
+fn template_processor(
+    endpoint: &EndpointData,
+    environment: Option<&Environment>,
+    options: &ExportOptions,
+) -> SrTemplate<'static> {
+    let context = SrTemplate::default();
+    let environment_variables = environment
+        .into_iter()
+        .flat_map(|environment| environment.variables.iter())
+        .filter(|item| item.active);
+    for item in environment_variables.chain(endpoint.variables.iter()) {
+        let value = if options.mask_secrets && item.secret {
+            SECRET_MASK
+        } else {
+            item.value.as_str()
+        };
+        context.add_variable(item.name.clone(), &value);
+    }
+    context
+}
+
+struct Renderer<'a> {
+    processor: SrTemplate<'static>,
+    options: &'a ExportOptions,
+}
+
+impl Renderer<'_> {
+    fn render(&self, text: &str) -> Result<String, CarteroError> {
+        if self.options.keep_variables {
+            Ok(text.to_string())
+        } else {
+            Ok(self.processor.render(text)?)
+        }
+    }
+
+    fn render_pairs(&self, table: &[KeyValue]) -> Result<Vec<(String, String)>, CarteroError> {
+        table
+            .iter()
+            .filter(|item| item.active)
+            .map(|item| {
+                let name = self.render(&item.name)?;
+                let value = if self.options.mask_secrets && item.secret {
+                    SECRET_MASK.to_string()
+                } else {
+                    self.render(&item.value)?
+                };
+                Ok((name, value))
+            })
+            .collect()
+    }
+
+    /// Renders a credential, which is always considered a secret.
+    fn render_secret(&self, text: &str) -> Result<String, CarteroError> {
+        if self.options.mask_secrets {
+            Ok(SECRET_MASK.to_string())
+        } else {
+            self.render(text)
+        }
+    }
+}
+
+/// Headers written by hand take precedence over the ones set by the authentication.
+fn add_auth_header(headers: &mut Vec<(String, String)>, name: String, value: String) {
+    let exists = headers
+        .iter()
+        .any(|(key, _)| key.eq_ignore_ascii_case(&name));
+    if !exists {
+        headers.push((name, value));
+    }
+}
+
+fn prepare(
+    endpoint: &EndpointData,
+    environment: Option<&Environment>,
+    options: &ExportOptions,
+) -> Result<ExportedRequest, CarteroError> {
+    let renderer = Renderer {
+        processor: template_processor(endpoint, environment, options),
+        options,
+    };
+
+    let mut headers = renderer.render_pairs(&endpoint.headers)?;
+    let body = match &endpoint.body {
+        RequestPayload::None => ExportedBody::None,
+        RequestPayload::Urlencoded(table) => {
+            ExportedBody::Urlencoded(renderer.render_pairs(table)?)
+        }
+        RequestPayload::Multipart { params, files } => {
+            let files = files
+                .iter()
+                .filter(|file| file.active)
+                .map(|file| {
+                    let render_option = |value: &Option<String>| match value {
+                        Some(value) => renderer.render(value).map(Some),
+                        None => Ok(None),
+                    };
+                    Ok(ExportedFile {
+                        name: renderer.render(&file.name)?,
+                        path: renderer.render(&file.path)?,
+                        filename: render_option(&file.filename)?,
+                        content_type: render_option(&file.content_type)?,
+                    })
+                })
+                .collect::<Result<Vec<ExportedFile>, CarteroError>>()?;
+            ExportedBody::Multipart(renderer.render_pairs(params)?, files)
+        }
+        RequestPayload::Raw { encoding, content } => {
+            let content_type = encoding.content_type();
+            let has_content_type = headers
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case("Content-Type"));
+            if !has_content_type {
+                headers.push(("Content-Type".into(), content_type.into()));
+            }
+            ExportedBody::Raw(renderer.render(&String::from_utf8_lossy(content))?)
+        }
+        RequestPayload::File { path } => {
+            let path = renderer.render(path)?;
+            let has_content_type = headers
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case("Content-Type"));
+            if !has_content_type {
+                let content_type = guess_content_type(Path::new(&path));
+                headers.push(("Content-Type".into(), content_type.into()));
+            }
+            ExportedBody::File(path)
+        }
+        RequestPayload::GraphQL {
+            query,
+            variables,
+            operation_name,
+        } => {
+            let has_content_type = headers
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case("Content-Type"));
+            if !has_content_type {
+                headers.push(("Content-Type".into(), "application/json".into()));
+            }
+            let operation_name = match operation_name {
+                Some(name) => Some(renderer.render(name)?),
+                None => None,
+            };
+            ExportedBody::Raw(graphql_body(
+                &renderer.render(query)?,
+                &renderer.render(variables)?,
+                operation_name.as_deref(),
+            )?)
+        }
+    };
+
+    let mut url = renderer.render(&endpoint.url)?;
+    let auth = match &endpoint.auth {
+        RequestAuth::Basic { username, password } => ExportedAuth::Basic(
+            renderer.render(username)?,
+            renderer.render_secret(password)?,
+        ),
+        RequestAuth::Digest { username, password } => ExportedAuth::Digest(
+            renderer.render(username)?,
+            renderer.render_secret(password)?,
+        ),
+        RequestAuth::Bearer { token } => {
+            let value = format!("Bearer {}", renderer.render_secret(token)?);
+            add_auth_header(&mut headers, "Authorization".into(), value);
+            ExportedAuth::None
+        }
+        RequestAuth::ApiKey {
+            name,
+            value,
+            location,
+        } => {
+            let name = renderer.render(name)?;
+            let value = renderer.render_secret(value)?;
+            match location {
+                ApiKeyLocation::Header => add_auth_header(&mut headers, name, value),
+                ApiKeyLocation::Query => url = append_query_pair(&url, &name, &value),
+            }
+            ExportedAuth::None
+        }
+        RequestAuth::OAuth2(config) => {
+            // Only a token obtained before can be exported, since fetching one may
+            // require the user to sign in. Otherwise, the mask acts as a placeholder.
+            let processor = &renderer.processor;
+            let config = OAuth2Config {
+                token_url: processor.render(&config.token_url)?,
+                client_id: processor.render(&config.client_id)?,
+                scope: processor.render(&config.scope)?,
+                ..config.clone()
+            };
+            let token = match TokenCache::shared().valid_token(&config) {
+                Some(token) if !renderer.options.mask_secrets => token,
+                _ => SECRET_MASK.to_string(),
+            };
+            add_auth_header(
+                &mut headers,
+                "Authorization".into(),
+                format!("Bearer {token}"),
+            );
+            ExportedAuth::None
+        }
+        RequestAuth::None => ExportedAuth::None,
+    };
+
+    Ok(ExportedRequest {
+        method: String::from(endpoint.method.clone()),
+        url,
+        headers,
+        body,
+        auth,
+    })
+}
+
+/// Writes the endpoint in the given format, so that it can be pasted somewhere else.
+pub fn export_endpoint(
+    endpoint: &EndpointData,
+    environment: Option<&Environment>,
+    format: ExportFormat,
+    options: &ExportOptions,
+) -> Result<String, CarteroError> {
+    let request = prepare(endpoint, environment, options)?;
+    let output = match format {
+        ExportFormat::Curl => shell::curl(&request),
+        ExportFormat::Httpie => shell::httpie(&request),
+        ExportFormat::Wget => shell::wget(&request)?,
+        ExportFormat::Rust => snippets::rust(&request)?,
+        ExportFormat::Python => snippets::python(&request),
+        ExportFormat::JavaScript => snippets::javascript(&request)?,
+    };
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_endpoint, ExportFormat, ExportOptions};
+    use crate::entities::{
+        EndpointData, Environment, KeyValue, KeyValueTable, MultipartFile, RawEncoding,
+        RequestAuth, RequestMethod, RequestPayload,
+    };
+
+    fn endpoint() -> EndpointData {
+        EndpointData {
+            url: "{{BASE}}/users".into(),
+            method: RequestMethod::Post,
+            headers: KeyValueTable::new(&[KeyValue {
+                name: "Authorization".into(),
+                value: "Bearer {{TOKEN}}".into(),
+                active: true,
+                secret: false,
+            }]),
+            body: RequestPayload::Raw {
+                encoding: RawEncoding::Json,
+                content: Vec::from(r#"{"name": "it's me"}"#),
+            },
+            ..Default::default()
+        }
+    }
+
+    fn environment() -> Environment {
+        Environment {
+            name: "dev".into(),
+            variables: KeyValueTable::new(&[
+                ("BASE", "https://example.com").into(),
+                KeyValue {
+                    name: "TOKEN".into(),
+                    value: "abc123".into(),
+                    active: true,
+                    secret: true,
+                },
+            ]),
+            proxy: None,
+        }
+    }
+
+    #[test]
+    fn test_export_curl() {
+        let env = environment();
+        let output = export_endpoint(
+            &endpoint(),
+            Some(&env),
+            ExportFormat::Curl,
+            &ExportOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            output,
+            "curl -X POST 'https://example.com/users' \\\n  \
+             -H 'Authorization: Bearer abc123' \\\n  \
+             -H 'Content-Type: application/json' \\\n  \
+             --data-raw '{\"name\": \"it'\\''s me\"}'"
+        );
+    }
+
+    #[test]
+    fn test_export_masks_secrets() {
+        let env = environment();
+        let options = ExportOptions {
+            mask_secrets: true,
+            ..Default::default()
+        };
+        let output =
+            export_endpoint(&endpoint(), Some(&env), ExportFormat::Httpie, &options).unwrap();
+        assert!(output.contains("'Authorization:Bearer ********'"));
+        assert!(!output.contains("abc123"));
+    }
+
+    #[test]
+    fn test_export_auth() {
+        let endpoint = EndpointData {
+            url: "https://example.com".into(),
+            auth: RequestAuth::Basic {
+                username: "admin".into(),
+                password: "{{TOKEN}}".into(),
+            },
+            ..Default::default()
+        };
+        let env = environment();
+        let options = ExportOptions::default();
+        let output = export_endpoint(&endpoint, Some(&env), ExportFormat::Curl, &options).unwrap();
+        assert!(output.contains("-u 'admin:abc123'"));
+        let output =
+            export_endpoint(&endpoint, Some(&env), ExportFormat::Python, &options).unwrap();
+        assert!(output.contains("auth=(\"admin\", \"abc123\")"));
+
+        let options = ExportOptions {
+            mask_secrets: true,
+            ..Default::default()
+        };
+        let output = export_endpoint(&endpoint, Some(&env), ExportFormat::Curl, &options).unwrap();
+        assert!(output.contains("-u 'admin:********'"));
+    }
+
+    #[test]
+    fn test_export_keeps_variables() {
+        let options = ExportOptions {
+            keep_variables: true,
+            ..Default::default()
+        };
+        let output = export_endpoint(&endpoint(), None, ExportFormat::Python, &options).unwrap();
+        assert!(output.contains("\"{{BASE}}/users\""));
+        assert!(output.contains("\"Bearer {{TOKEN}}\""));
+    }
+
+    #[test]
+    fn test_export_forms() {
+        let endpoint = EndpointData {
+            url: "https://example.com/login".into(),
+            method: RequestMethod::Post,
+            body: RequestPayload::Urlencoded(KeyValueTable::new(&[
+                ("user", "john").into(),
+                ("password", "a b").into(),
+            ])),
+            ..Default::default()
+        };
+        let options = ExportOptions::default();
+        let wget = export_endpoint(&endpoint, None, ExportFormat::Wget, &options).unwrap();
+        assert!(wget.contains("--body-data='user=john&password=a+b'"));
+        let js = export_endpoint(&endpoint, None, ExportFormat::JavaScript, &options).unwrap();
+        assert!(js.contains(
+            "body: new URLSearchParams([[\"user\", \"john\"], [\"password\", \"a b\"]]),"
+        ));
+        let rust = export_endpoint(&endpoint, None, ExportFormat::Rust, &options).unwrap();
+        assert!(rust.contains(".form(&[(\"user\", \"john\"), (\"password\", \"a b\")])"));
+    }
+
+    #[test]
+    fn test_export_wget_rejects_multipart() {
+        let endpoint = EndpointData {
+            url: "https://example.com/upload".into(),
+            method: RequestMethod::Post,
+            body: RequestPayload::Multipart {
+                params: KeyValueTable::new(&[("name", "cartero").into()]),
+                files: vec![MultipartFile {
+                    name: "logo".into(),
+                    path: "/tmp/logo.png".into(),
+                    content_type: Some("image/png".into()),
+                    ..Default::default()
+                }],
+            },
+            ..Default::default()
+        };
+        let options = ExportOptions::default();
+        assert!(export_endpoint(&endpoint, None, ExportFormat::Wget, &options).is_err());
+        let curl = export_endpoint(&endpoint, None, ExportFormat::Curl, &options).unwrap();
+        assert!(curl.contains("--form-string 'name=cartero'"));
+        assert!(curl.contains("-F 'logo=@/tmp/logo.png;type=image/png'"));
+        let python = export_endpoint(&endpoint, None, ExportFormat::Python, &options).unwrap();
+        assert!(python.contains(
+            "(\"logo\", (\"logo.png\", open(\"/tmp/logo.png\", \"rb\"), \"image/png\"))"
+        ));
+    }
+
+    #[test]
+    fn test_export_graphql() {
+        let endpoint = EndpointData {
+            url: "https://example.com/graphql".into(),
+            method: RequestMethod::Post,
+            body: RequestPayload::GraphQL {
+                query: "{ me { name } }".into(),
+                variables: String::new(),
+                operation_name: None,
+            },
+            ..Default::default()
+        };
+        let options = ExportOptions::default();
+        let curl = export_endpoint(&endpoint, None, ExportFormat::Curl, &options).unwrap();
+        assert!(curl.contains("-H 'Content-Type: application/json'"));
+        assert!(curl.contains(r#"--data-raw '{"query":"{ me { name } }"}'"#));
+    }
+
+    #[test]
+    fn test_export_custom_method() {
+        let endpoint = EndpointData {
+            url: "https://example.com/dav".into(),
+            method: RequestMethod::Custom("PROPFIND".into()),
+            ..Default::default()
+        };
+        let options = ExportOptions::default();
+        let export = |format| export_endpoint(&endpoint, None, format, &options).unwrap();
+        assert!(export(ExportFormat::Curl).starts_with("curl -X PROPFIND "));
+        assert!(export(ExportFormat::Rust).contains(
+            ".request(reqwest::Method::from_bytes(b\"PROPFIND\")?, \"https://example.com/dav\")"
+        ));
+        assert!(export(ExportFormat::Python).contains("\"PROPFIND\""));
+
+        let endpoint = EndpointData {
+            method: RequestMethod::Delete,
+            ..endpoint
+        };
+        let rust = export_endpoint(&endpoint, None, ExportFormat::Rust, &options).unwrap();
+        assert!(rust.contains(".request(reqwest::Method::DELETE, "));
+    }
+
+    #[test]
+    fn test_export_file_body() {
+        let endpoint = EndpointData {
+            url: "https://example.com/upload".into(),
+            method: RequestMethod::Put,
+            body: RequestPayload::File {
+                path: "logo.png".into(),
+            },
+            ..Default::default()
+        };
+        let options = ExportOptions::default();
+        let export = |format| export_endpoint(&endpoint, None, format, &options).unwrap();
+        let curl = export(ExportFormat::Curl);
+        assert!(curl.contains("-H 'Content-Type: image/png'"));
+        assert!(curl.contains("--data-binary '@logo.png'"));
+        assert!(export(ExportFormat::Wget).contains("--body-file='logo.png'"));
+        assert!(export(ExportFormat::Httpie).contains("'@logo.png'"));
+        assert!(export(ExportFormat::Python).contains("data=open(\"logo.png\", \"rb\")"));
+        assert!(export(ExportFormat::JavaScript).contains("body: await openAsBlob(\"logo.png\")"));
+    }
+}
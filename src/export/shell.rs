@@ -0,0 +1,224 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Commands for the tools usually found in a terminal.
+
+use crate::error::CarteroError;
+
+use super::{ExportedAuth, ExportedBody, ExportedFile, ExportedRequest};
+
+/// Quotes a word so that a POSIX shell passes it verbatim to the command.
+fn quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+fn join_lines(lines: Vec<String>) -> String {
+    lines.join(" \\\n  ")
+}
+
+/// Writes the value of a `-F` option of curl that uploads a file.
+fn curl_file(file: &ExportedFile) -> String {
+    // Paths with separators have to be quoted, otherwise curl splits them.
+    let path = if file.path.contains([';', ',', '"']) {
+        format!(
+            "\"{}\"",
+            file.path.replace('\\', "\\\\").replace('"', "\\\"")
+        )
+    } else {
+        file.path.clone()
+    };
+    let mut value = format!("{}=@{path}", file.name);
+    if let Some(filename) = &file.filename {
+        value.push_str(&format!(";filename={filename}"));
+    }
+    if let Some(content_type) = &file.content_type {
+        value.push_str(&format!(";type={content_type}"));
+    }
+    value
+}
+
+fn encode_pairs(pairs: &[(String, String)]) -> String {
+    url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs)
+        .finish()
+}
+
+pub fn curl(request: &ExportedRequest) -> String {
+    let first = match request.method.as_str() {
+        "GET" => format!("curl {}", quote(&request.url)),
+        "HEAD" => format!("curl --head {}", quote(&request.url)),
+        method => format!("curl -X {method} {}", quote(&request.url)),
+    };
+    let mut lines = vec![first];
+    for (name, value) in &request.headers {
+        lines.push(format!("-H {}", quote(&format!("{name}: {value}"))));
+    }
+    match &request.auth {
+        ExportedAuth::None => {}
+        ExportedAuth::Basic(username, password) => {
+            lines.push(format!("-u {}", quote(&format!("{username}:{password}"))));
+        }
+        ExportedAuth::Digest(username, password) => {
+            lines.push(format!(
+                "--digest -u {}",
+                quote(&format!("{username}:{password}"))
+            ));
+        }
+    }
+    match &request.body {
+        ExportedBody::None => {}
+        ExportedBody::Urlencoded(pairs) => {
+            lines.push(format!("--data-raw {}", quote(&encode_pairs(pairs))));
+        }
+        ExportedBody::Multipart(pairs, files) => {
+            for (name, value) in pairs {
+                lines.push(format!(
+                    "--form-string {}",
+                    quote(&format!("{name}={value}"))
+                ));
+            }
+            for file in files {
+                lines.push(format!("-F {}", quote(&curl_file(file))));
+            }
+        }
+        ExportedBody::Raw(content) => lines.push(format!("--data-raw {}", quote(content))),
+        ExportedBody::File(path) => {
+            lines.push(format!("--data-binary {}", quote(&format!("@{path}"))))
+        }
+    }
+    join_lines(lines)
+}
+
+pub fn httpie(request: &ExportedRequest) -> String {
+    let mode = match request.body {
+        ExportedBody::Urlencoded(_) => "--form ",
+        ExportedBody::Multipart(..) => "--multipart ",
+        _ => "",
+    };
+    let mut lines = vec![format!(
+        "http {mode}{} {}",
+        request.method,
+        quote(&request.url)
+    )];
+    for (name, value) in &request.headers {
+        lines.push(quote(&format!("{name}:{value}")));
+    }
+    match &request.auth {
+        ExportedAuth::None => {}
+        ExportedAuth::Basic(username, password) => {
+            lines.push(format!("-a {}", quote(&format!("{username}:{password}"))));
+        }
+        ExportedAuth::Digest(username, password) => {
+            lines.push(format!(
+                "-A digest -a {}",
+                quote(&format!("{username}:{password}"))
+            ));
+        }
+    }
+    match &request.body {
+        ExportedBody::None => {}
+        ExportedBody::Urlencoded(pairs) => {
+            for (name, value) in pairs {
+                lines.push(quote(&format!("{name}={value}")));
+            }
+        }
+        ExportedBody::Multipart(pairs, files) => {
+            for (name, value) in pairs {
+                lines.push(quote(&format!("{name}={value}")));
+            }
+            // HTTPie always announces the name of the file in disk.
+            for file in files {
+                let mut field = format!("{}@{}", file.name, file.path);
+                if let Some(content_type) = &file.content_type {
+                    field.push_str(&format!(";type={content_type}"));
+                }
+                lines.push(quote(&field));
+            }
+        }
+        ExportedBody::Raw(content) => lines.push(format!("--raw {}", quote(content))),
+        ExportedBody::File(path) => lines.push(quote(&format!("@{path}"))),
+    }
+    join_lines(lines)
+}
+
+pub fn wget(request: &ExportedRequest) -> Result<String, CarteroError> {
+    let mut lines = vec![format!("wget --method={}", request.method)];
+    for (name, value) in &request.headers {
+        lines.push(format!("--header={}", quote(&format!("{name}: {value}"))));
+    }
+    match &request.auth {
+        ExportedAuth::None => {}
+        // Without this flag, wget waits for a challenge before sending basic credentials.
+        ExportedAuth::Basic(username, password) => lines.push(format!(
+            "--auth-no-challenge --user={} --password={}",
+            quote(username),
+            quote(password)
+        )),
+        ExportedAuth::Digest(username, password) => lines.push(format!(
+            "--user={} --password={}",
+            quote(username),
+            quote(password)
+        )),
+    }
+    match &request.body {
+        ExportedBody::None => {}
+        ExportedBody::Urlencoded(pairs) => {
+            if request.header("Content-Type").is_none() {
+                lines.push(format!(
+                    "--header={}",
+                    quote("Content-Type: application/x-www-form-urlencoded")
+                ));
+            }
+            lines.push(format!("--body-data={}", quote(&encode_pairs(pairs))));
+        }
+        ExportedBody::Multipart(..) => return Err(CarteroError::UnsupportedExport),
+        ExportedBody::Raw(content) => lines.push(format!("--body-data={}", quote(content))),
+        ExportedBody::File(path) => lines.push(format!("--body-file={}", quote(path))),
+    }
+    lines.push(format!("-O - {}", quote(&request.url)));
+    Ok(join_lines(lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{curl, httpie, quote};
+    use crate::export::ExportedRequest;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("hello world"), "'hello world'");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+    }
+
+    #[test]
+    fn test_simple_get() {
+        let request = ExportedRequest {
+            method: "GET".into(),
+            url: "https://example.com".into(),
+            headers: vec![("Accept".into(), "text/html".into())],
+            ..Default::default()
+        };
+        assert_eq!(
+            curl(&request),
+            "curl 'https://example.com' \\\n  -H 'Accept: text/html'"
+        );
+        assert_eq!(
+            httpie(&request),
+            "http GET 'https://example.com' \\\n  'Accept:text/html'"
+        );
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Code snippets that perform the request using a popular HTTP library of a language.
+
+use crate::error::CarteroError;
+
+use super::{ExportedAuth, ExportedBody, ExportedRequest};
+
+/// Writes a string literal that is valid in Python and JavaScript.
+fn literal(text: &str) -> String {
+    serde_json::Value::from(text).to_string()
+}
+
+/// Writes a Rust string literal.
+fn rust_literal(text: &str) -> String {
+    format!("{text:?}")
+}
+
+/// Writes the method as a `reqwest` value, which only has constants for the standard ones.
+fn rust_method(method: &str) -> String {
+    match method {
+        "GET" | "POST" | "PUT" | "DELETE" | "HEAD" | "OPTIONS" | "CONNECT" | "PATCH" | "TRACE" => {
+            format!("reqwest::Method::{method}")
+        }
+        _ => format!("reqwest::Method::from_bytes(b{})?", rust_literal(method)),
+    }
+}
+
+fn pair_list(pairs: &[(String, String)], quote: fn(&str) -> String, wrap: (&str, &str)) -> String {
+    let items: Vec<String> = pairs
+        .iter()
+        .map(|(name, value)| format!("{}{}, {}{}", wrap.0, quote(name), quote(value), wrap.1))
+        .collect();
+    items.join(", ")
+}
+
+/// Snippet for the blocking client of the `reqwest` crate.
+pub fn rust(request: &ExportedRequest) -> Result<String, CarteroError> {
+    let mut code = String::new();
+    code.push_str("fn main() -> Result<(), Box<dyn std::error::Error>> {\n");
+    code.push_str("    let client = reqwest::blocking::Client::new();\n");
+    code.push_str("    let response = client\n");
+    code.push_str(&format!(
+        "        .request({}, {})\n",
+        rust_method(&request.method),
+        rust_literal(&request.url)
+    ));
+    for (name, value) in &request.headers {
+        code.push_str(&format!(
+            "        .header({}, {})\n",
+            rust_literal(name),
+            rust_literal(value)
+        ));
+    }
+    match &request.auth {
+        ExportedAuth::None => {}
+        ExportedAuth::Basic(username, password) => code.push_str(&format!(
+            "        .basic_auth({}, Some({}))\n",
+            rust_literal(username),
+            rust_literal(password)
+        )),
+        ExportedAuth::Digest(..) => return Err(CarteroError::UnsupportedExport),
+    }
+    match &request.body {
+        ExportedBody::None => {}
+        ExportedBody::Urlencoded(pairs) => code.push_str(&format!(
+            "        .form(&[{}])\n",
+            pair_list(pairs, rust_literal, ("(", ")"))
+        )),
+        ExportedBody::Multipart(pairs, files) => {
+            code.push_str("        .multipart(\n");
+            code.push_str("            reqwest::blocking::multipart::Form::new()");
+            for (name, value) in pairs {
+                code.push_str(&format!(
+                    "\n                .text({}, {})",
+                    rust_literal(name),
+                    rust_literal(value)
+                ));
+            }
+            for file in files {
+                let mut part = format!(
+                    "reqwest::blocking::multipart::Part::file({})?",
+                    rust_literal(&file.path)
+                );
+                if let Some(filename) = &file.filename {
+                    part.push_str(&format!(".file_name({})", rust_literal(filename)));
+                }
+                if let Some(content_type) = &file.content_type {
+                    part.push_str(&format!(".mime_str({})?", rust_literal(content_type)));
+                }
+                code.push_str(&format!(
+                    "\n                .part({}, {part})",
+                    rust_literal(&file.name)
+                ));
+            }
+            code.push_str(",\n        )\n");
+        }
+        ExportedBody::Raw(content) => {
+            code.push_str(&format!("        .body({})\n", rust_literal(content)))
+        }
+        ExportedBody::File(path) => code.push_str(&format!(
+            "        .body(std::fs::read({})?)\n",
+            rust_literal(path)
+        )),
+    }
+    code.push_str("        .send()?;\n");
+    code.push_str("    println!(\"{}\", response.text()?);\n");
+    code.push_str("    Ok(())\n");
+    code.push('}');
+    Ok(code)
+}
+
+/// Snippet for the `requests` package.
+pub fn python(request: &ExportedRequest) -> String {
+    let mut code = String::from("import requests\n\nresponse = requests.request(\n");
+    code.push_str(&format!("    {},\n", literal(&request.method)));
+    code.push_str(&format!("    {},\n", literal(&request.url)));
+    if !request.headers.is_empty() {
+        code.push_str("    headers={\n");
+        for (name, value) in &request.headers {
+            code.push_str(&format!("        {}: {},\n", literal(name), literal(value)));
+        }
+        code.push_str("    },\n");
+    }
+    match &request.auth {
+        ExportedAuth::None => {}
+        ExportedAuth::Basic(username, password) => code.push_str(&format!(
+            "    auth=({}, {}),\n",
+            literal(username),
+            literal(password)
+        )),
+        ExportedAuth::Digest(username, password) => code.push_str(&format!(
+            "    auth=requests.auth.HTTPDigestAuth({}, {}),\n",
+            literal(username),
+            literal(password)
+        )),
+    }
+    match &request.body {
+        ExportedBody::None => {}
+        ExportedBody::Urlencoded(pairs) => code.push_str(&format!(
+            "    data=[{}],\n",
+            pair_list(pairs, literal, ("(", ")"))
+        )),
+        ExportedBody::Multipart(pairs, files) => {
+            let mut parts: Vec<String> = pairs
+                .iter()
+                .map(|(name, value)| format!("({}, (None, {}))", literal(name), literal(value)))
+                .collect();
+            parts.extend(files.iter().map(|file| {
+                let content_type = match &file.content_type {
+                    Some(content_type) => format!(", {}", literal(content_type)),
+                    None => String::new(),
+                };
+                format!(
+                    "({}, ({}, open({}, \"rb\"){content_type}))",
+                    literal(&file.name),
+                    literal(&file.filename()),
+                    literal(&file.path)
+                )
+            }));
+            code.push_str(&format!("    files=[{}],\n", parts.join(", ")));
+        }
+        ExportedBody::Raw(content) => code.push_str(&format!("    data={},\n", literal(content))),
+        ExportedBody::File(path) => {
+            code.push_str(&format!("    data=open({}, \"rb\"),\n", literal(path)))
+        }
+    }
+    code.push_str(")\nprint(response.text)");
+    code
+}
+
+/// Snippet for the `fetch` API, available in browsers and modern runtimes.
+pub fn javascript(request: &ExportedRequest) -> Result<String, CarteroError> {
+    let mut code = String::new();
+    if let ExportedBody::File(_) = &request.body {
+        code.push_str("import { openAsBlob } from \"node:fs\";\n\n");
+    }
+    if let ExportedBody::Multipart(pairs, files) = &request.body {
+        // Reading files requires a runtime with access to the disk, such as Node.js.
+        if !files.is_empty() {
+            code.push_str("import { openAsBlob } from \"node:fs\";\n\n");
+        }
+        code.push_str("const body = new FormData();\n");
+        for (name, value) in pairs {
+            code.push_str(&format!(
+                "body.append({}, {});\n",
+                literal(name),
+                literal(value)
+            ));
+        }
+        for file in files {
+            let options = match &file.content_type {
+                Some(content_type) => format!(", {{ type: {} }}", literal(content_type)),
+                None => String::new(),
+            };
+            code.push_str(&format!(
+                "body.append({}, await openAsBlob({}{options}), {});\n",
+                literal(&file.name),
+                literal(&file.path),
+                literal(&file.filename())
+            ));
+        }
+        code.push('\n');
+    }
+    code.push_str(&format!(
+        "const response = await fetch({}, {{\n",
+        literal(&request.url)
+    ));
+    code.push_str(&format!("  method: {},\n", literal(&request.method)));
+    let authorization = match &request.auth {
+        ExportedAuth::None => None,
+        ExportedAuth::Basic(username, password) => Some(format!(
+            "\"Basic \" + btoa({})",
+            literal(&format!("{username}:{password}"))
+        )),
+        ExportedAuth::Digest(..) => return Err(CarteroError::UnsupportedExport),
+    };
+    if !request.headers.is_empty() || authorization.is_some() {
+        code.push_str("  headers: {\n");
+        for (name, value) in &request.headers {
+            code.push_str(&format!("    {}: {},\n", literal(name), literal(value)));
+        }
+        if let Some(authorization) = authorization {
+            code.push_str(&format!("    \"Authorization\": {authorization},\n"));
+        }
+        code.push_str("  },\n");
+    }
+    match &request.body {
+        ExportedBody::None => {}
+        ExportedBody::Urlencoded(pairs) => code.push_str(&format!(
+            "  body: new URLSearchParams([{}]),\n",
+            pair_list(pairs, literal, ("[", "]"))
+        )),
+        ExportedBody::Multipart(..) => code.push_str("  body,\n"),
+        ExportedBody::Raw(content) => code.push_str(&format!("  body: {},\n", literal(content))),
+        ExportedBody::File(path) => {
+            code.push_str(&format!("  body: await openAsBlob({}),\n", literal(path)))
+        }
+    }
+    code.push_str("});\nconsole.log(await response.text());");
+    Ok(code)
+}
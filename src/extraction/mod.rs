@@ -0,0 +1,246 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Extraction of values from a response into variables, so that a request can
+//! use something that an earlier request got back, such as a session token.
+
+mod xpath;
+
+use regex::Regex;
+use thiserror::Error;
+
+use crate::assertions::{json_to_text, JsonBody};
+use crate::entities::{Extraction, ExtractionSource, ResponseData};
+
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum ExtractionError {
+    #[error("The body is not valid JSON")]
+    NotJson,
+
+    #[error("The body is not valid XML")]
+    NotXml,
+
+    #[error("Invalid path: {0}")]
+    InvalidPath(String),
+
+    #[error("Invalid regular expression")]
+    InvalidRegex,
+
+    #[error("No value was found")]
+    NotFound,
+}
+
+/// The outcome of applying a single extraction rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractionResult {
+    pub extraction: Extraction,
+    pub value: Result<String, ExtractionError>,
+}
+
+/// Turns a JSONPath such as `$.items[0]['name']` into a JSON pointer.
+///
+/// Only paths that select a single value are supported. Anything that does
+/// not start with `$` is already taken as a JSON pointer.
+fn json_pointer(path: &str) -> Result<String, ExtractionError> {
+    let invalid = || ExtractionError::InvalidPath(path.to_string());
+    let Some(mut rest) = path.strip_prefix('$') else {
+        return Ok(path.to_string());
+    };
+    let mut pointer = String::new();
+    while !rest.is_empty() {
+        let token = if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            let name = &after[..end];
+            if name.is_empty() || name == "*" {
+                return Err(invalid());
+            }
+            rest = &after[end..];
+            name
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or_else(invalid)?;
+            let inner = after[..end].trim();
+            rest = &after[end + 1..];
+            let quoted = inner
+                .strip_prefix('\'')
+                .and_then(|i| i.strip_suffix('\''))
+                .or_else(|| inner.strip_prefix('"').and_then(|i| i.strip_suffix('"')));
+            match quoted {
+                Some(name) => name,
+                None if !inner.is_empty() && inner.chars().all(|c| c.is_ascii_digit()) => inner,
+                None => return Err(invalid()),
+            }
+        } else {
+            return Err(invalid());
+        };
+        pointer.push('/');
+        pointer.push_str(&token.replace('~', "~0").replace('/', "~1"));
+    }
+    Ok(pointer)
+}
+
+fn extract_with(
+    source: &ExtractionSource,
+    response: &ResponseData,
+    json: &mut JsonBody,
+) -> Result<String, ExtractionError> {
+    match source {
+        ExtractionSource::Json(path) => {
+            let pointer = json_pointer(path)?;
+            let body = json.value().ok_or(ExtractionError::NotJson)?;
+            body.pointer(&pointer)
+                .map(json_to_text)
+                .ok_or(ExtractionError::NotFound)
+        }
+        ExtractionSource::XPath(path) => {
+            xpath::evaluate(&response.body_str(), path).map(|value| value.trim().to_string())
+        }
+        ExtractionSource::Header(name) => response
+            .headers
+            .iter()
+            .find(|header| header.name.eq_ignore_ascii_case(name))
+            .map(|header| header.value.clone())
+            .ok_or(ExtractionError::NotFound),
+        ExtractionSource::Regex(pattern) => {
+            let regex = Regex::new(pattern).map_err(|_| ExtractionError::InvalidRegex)?;
+            let body = response.body_str();
+            let captures = regex.captures(&body).ok_or(ExtractionError::NotFound)?;
+            captures
+                .get(1)
+                .or_else(|| captures.get(0))
+                .map(|found| found.as_str().to_string())
+                .ok_or(ExtractionError::NotFound)
+        }
+    }
+}
+
+/// Takes the value of a single extraction rule out of the given response.
+pub fn extract(
+    extraction: &Extraction,
+    response: &ResponseData,
+) -> Result<String, ExtractionError> {
+    extract_with(&extraction.source, response, &mut JsonBody::new(response))
+}
+
+/// Applies every extraction rule to the given response, keeping the same order.
+pub fn extract_all(extractions: &[Extraction], response: &ResponseData) -> Vec<ExtractionResult> {
+    let mut json = JsonBody::new(response);
+    extractions
+        .iter()
+        .map(|extraction| ExtractionResult {
+            extraction: extraction.clone(),
+            value: extract_with(&extraction.source, response, &mut json),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract, extract_all, json_pointer, ExtractionError};
+    use crate::entities::{
+        Extraction, ExtractionSource, KeyValueTable, ResponseData, VariableScope,
+    };
+
+    fn response(content_type: &str, body: &str) -> ResponseData {
+        ResponseData {
+            status_code: 200,
+            duration: 120,
+            size: body.len(),
+            headers: KeyValueTable::new(&[
+                ("Content-Type", content_type).into(),
+                ("X-Request-Id", "42").into(),
+            ]),
+            body: Vec::from(body),
+        }
+    }
+
+    fn rule(source: ExtractionSource) -> Extraction {
+        Extraction {
+            variable: "VALUE".into(),
+            source,
+            scope: VariableScope::Environment,
+        }
+    }
+
+    #[test]
+    fn test_json_path_to_pointer() {
+        assert_eq!(json_pointer("/data/token").unwrap(), "/data/token");
+        assert_eq!(json_pointer("$").unwrap(), "");
+        assert_eq!(
+            json_pointer("$.data.items[2].id").unwrap(),
+            "/data/items/2/id"
+        );
+        assert_eq!(json_pointer("$['a/b'][\"c~d\"]").unwrap(), "/a~1b/c~0d");
+        assert!(json_pointer("$.items[*].id").is_err());
+        assert!(json_pointer("$..id").is_err());
+        assert!(json_pointer("$[-1]").is_err());
+    }
+
+    #[test]
+    fn test_extract_json() {
+        let res = response(
+            "application/json",
+            r#"{"token": "s3cr3t", "user": {"id": 7, "roles": ["admin"]}}"#,
+        );
+        let results = extract_all(
+            &[
+                rule(ExtractionSource::Json("/token".into())),
+                rule(ExtractionSource::Json("$.user.id".into())),
+                rule(ExtractionSource::Json("$.user.roles[0]".into())),
+                rule(ExtractionSource::Json("$.user.roles".into())),
+                rule(ExtractionSource::Json("/missing".into())),
+            ],
+            &res,
+        );
+        let values: Vec<_> = results.into_iter().map(|r| r.value).collect();
+        assert_eq!(
+            values,
+            vec![
+                Ok("s3cr3t".into()),
+                Ok("7".into()),
+                Ok("admin".into()),
+                Ok(r#"["admin"]"#.into()),
+                Err(ExtractionError::NotFound),
+            ]
+        );
+
+        let html = response("text/html", "<html></html>");
+        let json = rule(ExtractionSource::Json("/token".into()));
+        assert_eq!(extract(&json, &html), Err(ExtractionError::NotJson));
+    }
+
+    #[test]
+    fn test_extract_header_regex_and_xpath() {
+        let res = response(
+            "application/xml",
+            "<session>\n  <id>abc-123</id>\n</session>",
+        );
+        let header = rule(ExtractionSource::Header("x-request-id".into()));
+        assert_eq!(extract(&header, &res).unwrap(), "42");
+        let missing = rule(ExtractionSource::Header("ETag".into()));
+        assert_eq!(extract(&missing, &res), Err(ExtractionError::NotFound));
+
+        let group = rule(ExtractionSource::Regex("<id>([a-z]+)-".into()));
+        assert_eq!(extract(&group, &res).unwrap(), "abc");
+        let whole = rule(ExtractionSource::Regex("[0-9]+".into()));
+        assert_eq!(extract(&whole, &res).unwrap(), "123");
+        let invalid = rule(ExtractionSource::Regex("(".into()));
+        assert_eq!(extract(&invalid, &res), Err(ExtractionError::InvalidRegex));
+
+        let xpath = rule(ExtractionSource::XPath("/session".into()));
+        assert_eq!(extract(&xpath, &res).unwrap(), "abc-123");
+    }
+}
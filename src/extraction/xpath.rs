@@ -0,0 +1,231 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Evaluation of XPath 1.0 expressions against XML responses.
+//!
+//! The namespace prefixes declared in the document can be used in the
+//! expression, so `//dc:title` works without any further setup. As in any
+//! XPath 1.0 engine, names without a prefix only match elements outside of any
+//! namespace; elements in a default namespace need `*[local-name()='title']`.
+
+use std::collections::HashSet;
+
+use regex::Regex;
+use sxd_document::dom::{Document, Element};
+use sxd_xpath::{Context, Factory, Value};
+
+use super::ExtractionError;
+
+/// How deep elements may be nested. The text of an element is collected
+/// recursively and parsing gets slow as documents get deeper, so deeper
+/// documents are rejected before they are parsed.
+const MAX_DEPTH: usize = 256;
+
+/// Finds where the tag at the start of the text ends, right after its `>`. The
+/// `>` may also appear inside the values of the attributes, which are skipped.
+fn tag_end(tag: &str) -> usize {
+    let mut quote = None;
+    for (index, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if open == c => quote = None,
+            (None, '>') => return index + 1,
+            _ => {}
+        }
+    }
+    tag.len()
+}
+
+/// Tells whether the elements of the document are nested deeper than
+/// [`MAX_DEPTH`]. This only looks at the tags; the parser finds any other error.
+fn is_too_deep(xml: &str) -> bool {
+    let mut depth = 0usize;
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->").map(|end| end + 3)
+        } else if rest.starts_with("<![CDATA[") {
+            rest.find("]]>").map(|end| end + 3)
+        } else if rest.starts_with("<?") {
+            rest.find("?>").map(|end| end + 2)
+        } else {
+            let end = tag_end(rest);
+            if rest.starts_with("</") {
+                depth = depth.saturating_sub(1);
+            } else if !rest.starts_with("<!") && !rest[..end].ends_with("/>") {
+                depth += 1;
+                if depth > MAX_DEPTH {
+                    return true;
+                }
+            }
+            Some(end)
+        };
+        match end {
+            Some(end) => rest = &rest[end..],
+            None => break,
+        }
+    }
+    false
+}
+
+/// Lists the namespace prefixes used by the names of the expression.
+fn used_prefixes(path: &str) -> HashSet<&str> {
+    let literals = Regex::new(r#"'[^']*'|"[^"]*""#).unwrap();
+    let names = Regex::new(r"(?:^|[^\w.-])([A-Za-z_][\w.-]*):[A-Za-z_*]").unwrap();
+    let mut prefixes = HashSet::new();
+    for part in literals.split(path) {
+        prefixes.extend(
+            names
+                .captures_iter(part)
+                .map(|c| c.get(1).unwrap().as_str()),
+        );
+    }
+    prefixes
+}
+
+/// Registers the namespace prefixes declared in the document into the context
+/// and returns them.
+fn register_namespaces<'d>(document: &Document<'d>, context: &mut Context<'d>) -> HashSet<&'d str> {
+    let mut prefixes = HashSet::new();
+    let mut pending: Vec<Element> = document
+        .root()
+        .children()
+        .into_iter()
+        .filter_map(|child| child.element())
+        .collect();
+    while let Some(element) = pending.pop() {
+        for namespace in element.namespaces_in_scope() {
+            context.set_namespace(namespace.prefix(), namespace.uri());
+            prefixes.insert(namespace.prefix());
+        }
+        pending.extend(
+            element
+                .children()
+                .into_iter()
+                .filter_map(|child| child.element()),
+        );
+    }
+    prefixes
+}
+
+/// Evaluates the expression against the document. When it selects nodes, the
+/// text of the first one in document order is returned.
+pub fn evaluate(xml: &str, path: &str) -> Result<String, ExtractionError> {
+    let invalid = || ExtractionError::InvalidPath(path.to_string());
+    if is_too_deep(xml) {
+        return Err(ExtractionError::NotXml);
+    }
+    let package = sxd_document::parser::parse(xml).map_err(|_| ExtractionError::NotXml)?;
+    let document = package.as_document();
+    let mut context = Context::new();
+    let declared = register_namespaces(&document, &mut context);
+    // Names with a prefix that the document does not declare cannot be evaluated.
+    if !used_prefixes(path).is_subset(&declared) {
+        return Err(invalid());
+    }
+
+    let xpath = Factory::new()
+        .build(path)
+        .map_err(|_| invalid())?
+        .ok_or_else(invalid)?;
+    match xpath
+        .evaluate(&context, document.root())
+        .map_err(|_| invalid())?
+    {
+        Value::Nodeset(nodes) => nodes
+            .document_order_first()
+            .map(|node| node.string_value())
+            .ok_or(ExtractionError::NotFound),
+        value => Ok(value.into_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate, MAX_DEPTH};
+    use crate::extraction::ExtractionError;
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- A list of books -->
+<catalog xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <book id="1" lang="en">
+    <dc:title>Rust &amp; GTK</dc:title>
+    <price>10</price>
+  </book>
+  <book id="2">
+    <dc:title><![CDATA[<Cartero>]]></dc:title>
+    <price>20</price>
+  </book>
+  <token>abc</token>
+</catalog>"#;
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(evaluate(FEED, "/catalog/token").unwrap(), "abc");
+        assert_eq!(evaluate(FEED, "/catalog/token/text()").unwrap(), "abc");
+        assert_eq!(evaluate(FEED, "//book[2]/price").unwrap(), "20");
+        assert_eq!(evaluate(FEED, "//book[last()]/@id").unwrap(), "2");
+        assert_eq!(evaluate(FEED, "//book[@lang]/@id").unwrap(), "1");
+        assert_eq!(
+            evaluate(FEED, "//book[@id='2']/dc:title").unwrap(),
+            "<Cartero>"
+        );
+        assert_eq!(evaluate(FEED, "//dc:title").unwrap(), "Rust & GTK");
+        assert_eq!(evaluate(FEED, "//book[price='20']/@id").unwrap(), "2");
+        assert_eq!(evaluate(FEED, "/*/book/./@id").unwrap(), "1");
+        assert_eq!(evaluate(FEED, "count(//book)").unwrap(), "2");
+        assert_eq!(
+            evaluate(FEED, "child::catalog/child::token").unwrap(),
+            "abc"
+        );
+        assert_eq!(evaluate(FEED, "concat('a:b', //token)").unwrap(), "a:babc");
+        assert_eq!(evaluate(FEED, "sum(//price) > 25").unwrap(), "true");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            evaluate(FEED, "/catalog/missing"),
+            Err(ExtractionError::NotFound)
+        );
+        assert!(matches!(
+            evaluate(FEED, "/catalog/book["),
+            Err(ExtractionError::InvalidPath(_))
+        ));
+        for path in ["//atom:title", "child::atom:*", "//dc:title | //a:b"] {
+            assert!(matches!(
+                evaluate(FEED, path),
+                Err(ExtractionError::InvalidPath(_))
+            ));
+        }
+        assert_eq!(evaluate("<a><b></a>", "/a"), Err(ExtractionError::NotXml));
+        assert_eq!(evaluate(r#"{"a": 1}"#, "/a"), Err(ExtractionError::NotXml));
+
+        let nested = |depth| format!("{}{}", "<a>".repeat(depth), "</a>".repeat(depth));
+        assert_eq!(evaluate(&nested(MAX_DEPTH), "//a[1]"), Ok(String::new()));
+        let siblings = format!(
+            "<a>{}</a>",
+            "<b x='>'/>1 > 0<!-- <c> --><![CDATA[<d>]]>".repeat(500)
+        );
+        assert_eq!(evaluate(&siblings, "count(//b)"), Ok("500".into()));
+        assert_eq!(
+            evaluate(&nested(100_000), "//a"),
+            Err(ExtractionError::NotXml)
+        );
+    }
+}
@@ -1,12 +1,15 @@
 use std::collections::HashMap;
 
-use gtk::gio;
-use gtk::prelude::FileExtManual;
+#[cfg(feature = "gui")]
+use gtk::{gio, prelude::FileExtManual};
 use serde::{Deserialize, Serialize};
 
 use crate::client::RequestError;
 use crate::entities::{
-    EndpointData, KeyValue, KeyValueTable, RawEncoding, RequestMethod, RequestPayload,
+    ApiKeyLocation, Assertion, ClientOptions, CollectionMetadata, EndpointData, Environment,
+    Extraction, ExtractionSource, HttpVersion, ItemData, KeyValue, KeyValueTable, MultipartFile,
+    OAuth2Config, OAuth2Grant, ProxyMode, ProxySettings, RawEncoding, RequestAuth, RequestMethod,
+    RequestPayload, VariableScope, WebSocketData, WebSocketMessageKind,
 };
 use crate::error::CarteroError;
 
@@ -140,24 +143,95 @@ pub enum FilePayloadRawFormat {
     Json,
     #[serde(rename = "xml")]
     Xml,
+    #[serde(rename = "text")]
+    Text,
+    #[serde(rename = "html")]
+    Html,
+    #[serde(rename = "yaml")]
+    Yaml,
+    #[serde(rename = "javascript")]
+    JavaScript,
+    #[serde(rename = "csv")]
+    Csv,
+    /// The content type is stored in the `content-type` field of the body.
+    #[serde(rename = "custom")]
+    Custom,
 }
 
-impl From<RawEncoding> for FilePayloadRawFormat {
-    fn from(value: RawEncoding) -> Self {
+impl From<&RawEncoding> for FilePayloadRawFormat {
+    fn from(value: &RawEncoding) -> Self {
         match value {
             RawEncoding::Json => Self::Json,
             RawEncoding::OctetStream => Self::OctetStream,
             RawEncoding::Xml => Self::Xml,
+            RawEncoding::Text => Self::Text,
+            RawEncoding::Html => Self::Html,
+            RawEncoding::Yaml => Self::Yaml,
+            RawEncoding::JavaScript => Self::JavaScript,
+            RawEncoding::Csv => Self::Csv,
+            RawEncoding::Custom(_) => Self::Custom,
         }
     }
 }
 
-impl From<FilePayloadRawFormat> for RawEncoding {
-    fn from(value: FilePayloadRawFormat) -> Self {
-        match value {
-            FilePayloadRawFormat::Json => Self::Json,
-            FilePayloadRawFormat::OctetStream => Self::OctetStream,
-            FilePayloadRawFormat::Xml => Self::Xml,
+impl FilePayloadRawFormat {
+    fn into_encoding(self, content_type: Option<String>) -> RawEncoding {
+        match self {
+            FilePayloadRawFormat::Json => RawEncoding::Json,
+            FilePayloadRawFormat::OctetStream => RawEncoding::OctetStream,
+            FilePayloadRawFormat::Xml => RawEncoding::Xml,
+            FilePayloadRawFormat::Text => RawEncoding::Text,
+            FilePayloadRawFormat::Html => RawEncoding::Html,
+            FilePayloadRawFormat::Yaml => RawEncoding::Yaml,
+            FilePayloadRawFormat::JavaScript => RawEncoding::JavaScript,
+            FilePayloadRawFormat::Csv => RawEncoding::Csv,
+            FilePayloadRawFormat::Custom => RawEncoding::Custom(content_type.unwrap_or_default()),
+        }
+    }
+}
+
+fn default_active() -> bool {
+    true
+}
+
+fn is_active(active: &bool) -> bool {
+    *active
+}
+
+/// A file uploaded by a multipart body, stored in the `files` list of the body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FileMultipartFile {
+    name: String,
+    path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    filename: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    #[serde(default = "default_active", skip_serializing_if = "is_active")]
+    active: bool,
+}
+
+impl From<MultipartFile> for FileMultipartFile {
+    fn from(value: MultipartFile) -> Self {
+        Self {
+            name: value.name,
+            path: value.path,
+            filename: value.filename,
+            content_type: value.content_type,
+            active: value.active,
+        }
+    }
+}
+
+impl From<FileMultipartFile> for MultipartFile {
+    fn from(value: FileMultipartFile) -> Self {
+        Self {
+            name: value.name,
+            path: value.path,
+            filename: value.filename,
+            content_type: value.content_type,
+            active: value.active,
         }
     }
 }
@@ -174,12 +248,30 @@ pub enum FilePayload {
     #[serde(rename = "multipart")]
     Multipart {
         variables: Option<KeyValuedFileTable>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        files: Vec<FileMultipartFile>,
     },
     #[serde(rename = "raw")]
     Raw {
         format: Option<FilePayloadRawFormat>,
+        #[serde(
+            default,
+            rename = "content-type",
+            skip_serializing_if = "Option::is_none"
+        )]
+        content_type: Option<String>,
         body: String,
     },
+    #[serde(rename = "file")]
+    File { path: String },
+    #[serde(rename = "graphql", rename_all = "kebab-case")]
+    GraphQL {
+        query: String,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        variables: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        operation_name: Option<String>,
+    },
 }
 
 #[derive(Serialize, Deserialize)]
@@ -196,13 +288,28 @@ impl From<RequestPayload> for FilePayload {
             RequestPayload::Urlencoded(payload) => Self::UrlEncoded {
                 variables: Some(payload.into()),
             },
-            RequestPayload::Multipart { params } => Self::Multipart {
+            RequestPayload::Multipart { params, files } => Self::Multipart {
                 variables: Some(params.into()),
+                files: files.into_iter().map(FileMultipartFile::from).collect(),
             },
             RequestPayload::Raw { encoding, content } => Self::Raw {
-                format: Some(encoding.into()),
+                format: Some((&encoding).into()),
+                content_type: match encoding {
+                    RawEncoding::Custom(content_type) => Some(content_type),
+                    _ => None,
+                },
                 body: String::from_utf8_lossy(&content.clone()).to_string(),
             },
+            RequestPayload::File { path } => Self::File { path },
+            RequestPayload::GraphQL {
+                query,
+                variables,
+                operation_name,
+            } => Self::GraphQL {
+                query,
+                variables,
+                operation_name,
+            },
         }
     }
 }
@@ -211,16 +318,31 @@ impl From<FilePayload> for RequestPayload {
     fn from(value: FilePayload) -> Self {
         match value {
             FilePayload::None => Self::None,
-            FilePayload::Multipart { variables } => Self::Multipart {
+            FilePayload::Multipart { variables, files } => Self::Multipart {
                 params: variables.unwrap_or_default().into(),
+                files: files.into_iter().map(MultipartFile::from).collect(),
             },
             FilePayload::UrlEncoded { variables } => {
                 Self::Urlencoded(variables.unwrap_or_default().into())
             }
-            FilePayload::Raw { format, body } => Self::Raw {
-                encoding: format.unwrap_or_default().into(),
+            FilePayload::Raw {
+                format,
+                content_type,
+                body,
+            } => Self::Raw {
+                encoding: format.unwrap_or_default().into_encoding(content_type),
                 content: Vec::from(body.clone()),
             },
+            FilePayload::File { path } => Self::File { path },
+            FilePayload::GraphQL {
+                query,
+                variables,
+                operation_name,
+            } => Self::GraphQL {
+                query,
+                variables,
+                operation_name,
+            },
         }
     }
 }
@@ -243,6 +365,496 @@ impl From<Body> for RequestPayload {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum FileApiKeyLocation {
+    #[default]
+    Header,
+    Query,
+}
+
+impl From<ApiKeyLocation> for FileApiKeyLocation {
+    fn from(value: ApiKeyLocation) -> Self {
+        match value {
+            ApiKeyLocation::Header => Self::Header,
+            ApiKeyLocation::Query => Self::Query,
+        }
+    }
+}
+
+impl From<FileApiKeyLocation> for ApiKeyLocation {
+    fn from(value: FileApiKeyLocation) -> Self {
+        match value {
+            FileApiKeyLocation::Header => Self::Header,
+            FileApiKeyLocation::Query => Self::Query,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum FileOAuth2Grant {
+    #[default]
+    ClientCredentials,
+    AuthorizationCode,
+}
+
+impl From<OAuth2Grant> for FileOAuth2Grant {
+    fn from(value: OAuth2Grant) -> Self {
+        match value {
+            OAuth2Grant::ClientCredentials => Self::ClientCredentials,
+            OAuth2Grant::AuthorizationCode => Self::AuthorizationCode,
+        }
+    }
+}
+
+impl From<FileOAuth2Grant> for OAuth2Grant {
+    fn from(value: FileOAuth2Grant) -> Self {
+        match value {
+            FileOAuth2Grant::ClientCredentials => Self::ClientCredentials,
+            FileOAuth2Grant::AuthorizationCode => Self::AuthorizationCode,
+        }
+    }
+}
+
+/// The OAuth 2.0 settings of the `[auth]` section. Tokens are never stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct FileOAuth2 {
+    #[serde(default)]
+    grant: FileOAuth2Grant,
+    token_url: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    authorization_url: String,
+    client_id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    client_secret: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    scope: String,
+}
+
+impl From<FileOAuth2> for OAuth2Config {
+    fn from(value: FileOAuth2) -> Self {
+        Self {
+            grant: value.grant.into(),
+            token_url: value.token_url,
+            authorization_url: value.authorization_url,
+            client_id: value.client_id,
+            client_secret: value.client_secret,
+            scope: value.scope,
+        }
+    }
+}
+
+impl From<OAuth2Config> for FileOAuth2 {
+    fn from(value: OAuth2Config) -> Self {
+        Self {
+            grant: value.grant.into(),
+            token_url: value.token_url,
+            authorization_url: value.authorization_url,
+            client_id: value.client_id,
+            client_secret: value.client_secret,
+            scope: value.scope,
+        }
+    }
+}
+
+/// The representation of the `[auth]` section of a request file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum FileAuth {
+    Basic {
+        username: String,
+        #[serde(default)]
+        password: String,
+    },
+    Bearer {
+        token: String,
+    },
+    ApiKey {
+        name: String,
+        value: String,
+        #[serde(default)]
+        location: FileApiKeyLocation,
+    },
+    Digest {
+        username: String,
+        #[serde(default)]
+        password: String,
+    },
+    #[serde(rename = "oauth2")]
+    OAuth2(FileOAuth2),
+}
+
+impl From<FileAuth> for RequestAuth {
+    fn from(value: FileAuth) -> Self {
+        match value {
+            FileAuth::Basic { username, password } => Self::Basic { username, password },
+            FileAuth::Bearer { token } => Self::Bearer { token },
+            FileAuth::ApiKey {
+                name,
+                value,
+                location,
+            } => Self::ApiKey {
+                name,
+                value,
+                location: location.into(),
+            },
+            FileAuth::Digest { username, password } => Self::Digest { username, password },
+            FileAuth::OAuth2(config) => Self::OAuth2(config.into()),
+        }
+    }
+}
+
+impl FileAuth {
+    fn from_auth(value: RequestAuth) -> Option<Self> {
+        match value {
+            RequestAuth::None => None,
+            RequestAuth::Basic { username, password } => Some(Self::Basic { username, password }),
+            RequestAuth::Bearer { token } => Some(Self::Bearer { token }),
+            RequestAuth::ApiKey {
+                name,
+                value,
+                location,
+            } => Some(Self::ApiKey {
+                name,
+                value,
+                location: location.into(),
+            }),
+            RequestAuth::Digest { username, password } => Some(Self::Digest { username, password }),
+            RequestAuth::OAuth2(config) => Some(Self::OAuth2(config.into())),
+        }
+    }
+}
+
+/// The representation of an assertion in the `[[tests]]` section of a request file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum FileAssertion {
+    Status {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        equals: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        min: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max: Option<u32>,
+    },
+    Header {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        equals: Option<String>,
+    },
+    Json {
+        pointer: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        equals: Option<toml::Value>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        matches: Option<String>,
+    },
+    BodyContains {
+        value: String,
+    },
+    Duration {
+        max: u64,
+    },
+}
+
+impl TryFrom<FileAssertion> for Assertion {
+    type Error = CarteroError;
+
+    fn try_from(value: FileAssertion) -> Result<Self, Self::Error> {
+        let assertion = match value {
+            FileAssertion::Status {
+                equals: Some(code),
+                min: None,
+                max: None,
+            } => Assertion::StatusEquals(code),
+            FileAssertion::Status {
+                equals: None,
+                min,
+                max,
+            } if min.is_some() || max.is_some() => Assertion::StatusInRange {
+                min: min.unwrap_or(100),
+                max: max.unwrap_or(599),
+            },
+            FileAssertion::Status { .. } => return Err(CarteroError::InvalidAssertion),
+            FileAssertion::Header { name, equals } => match equals {
+                Some(value) => Assertion::HeaderEquals { name, value },
+                None => Assertion::HeaderPresent(name),
+            },
+            FileAssertion::Json {
+                pointer,
+                equals,
+                matches,
+            } => match (equals, matches) {
+                (None, None) => Assertion::JsonExists(pointer),
+                (Some(value), None) => Assertion::JsonEquals {
+                    pointer,
+                    value: serde_json::to_value(value)
+                        .map_err(|_| CarteroError::InvalidAssertion)?,
+                },
+                (None, Some(pattern)) => Assertion::JsonMatches { pointer, pattern },
+                (Some(_), Some(_)) => return Err(CarteroError::InvalidAssertion),
+            },
+            FileAssertion::BodyContains { value } => Assertion::BodyContains(value),
+            FileAssertion::Duration { max } => Assertion::MaxDuration(max.into()),
+        };
+        Ok(assertion)
+    }
+}
+
+impl From<Assertion> for FileAssertion {
+    fn from(value: Assertion) -> Self {
+        match value {
+            Assertion::StatusEquals(code) => Self::Status {
+                equals: Some(code),
+                min: None,
+                max: None,
+            },
+            Assertion::StatusInRange { min, max } => Self::Status {
+                equals: None,
+                min: Some(min),
+                max: Some(max),
+            },
+            Assertion::HeaderPresent(name) => Self::Header { name, equals: None },
+            Assertion::HeaderEquals { name, value } => Self::Header {
+                name,
+                equals: Some(value),
+            },
+            Assertion::JsonExists(pointer) => Self::Json {
+                pointer,
+                equals: None,
+                matches: None,
+            },
+            Assertion::JsonEquals { pointer, value } => {
+                // TOML has no null, so fall back to the textual representation.
+                let equals = toml::Value::try_from(&value)
+                    .unwrap_or_else(|_| toml::Value::String(value.to_string()));
+                Self::Json {
+                    pointer,
+                    equals: Some(equals),
+                    matches: None,
+                }
+            }
+            Assertion::JsonMatches { pointer, pattern } => Self::Json {
+                pointer,
+                equals: None,
+                matches: Some(pattern),
+            },
+            Assertion::BodyContains(value) => Self::BodyContains { value },
+            Assertion::MaxDuration(max) => Self::Duration {
+                max: u64::try_from(max).unwrap_or(u64::MAX),
+            },
+        }
+    }
+}
+
+/// Where an entry of the `[[extract]]` section takes its value from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum FileExtractionSource {
+    Json { path: String },
+    Xpath { path: String },
+    Header { name: String },
+    Regex { pattern: String },
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FileVariableScope {
+    #[default]
+    Environment,
+    Collection,
+}
+
+fn is_default_scope(scope: &FileVariableScope) -> bool {
+    *scope == FileVariableScope::default()
+}
+
+/// The representation of a rule in the `[[extract]]` section of a request file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileExtraction {
+    variable: String,
+    #[serde(flatten)]
+    source: FileExtractionSource,
+    #[serde(default, skip_serializing_if = "is_default_scope")]
+    scope: FileVariableScope,
+}
+
+impl From<FileExtraction> for Extraction {
+    fn from(value: FileExtraction) -> Self {
+        let source = match value.source {
+            FileExtractionSource::Json { path } => ExtractionSource::Json(path),
+            FileExtractionSource::Xpath { path } => ExtractionSource::XPath(path),
+            FileExtractionSource::Header { name } => ExtractionSource::Header(name),
+            FileExtractionSource::Regex { pattern } => ExtractionSource::Regex(pattern),
+        };
+        let scope = match value.scope {
+            FileVariableScope::Environment => VariableScope::Environment,
+            FileVariableScope::Collection => VariableScope::Collection,
+        };
+        Extraction {
+            variable: value.variable,
+            source,
+            scope,
+        }
+    }
+}
+
+impl From<Extraction> for FileExtraction {
+    fn from(value: Extraction) -> Self {
+        let source = match value.source {
+            ExtractionSource::Json(path) => FileExtractionSource::Json { path },
+            ExtractionSource::XPath(path) => FileExtractionSource::Xpath { path },
+            ExtractionSource::Header(name) => FileExtractionSource::Header { name },
+            ExtractionSource::Regex(pattern) => FileExtractionSource::Regex { pattern },
+        };
+        let scope = match value.scope {
+            VariableScope::Environment => FileVariableScope::Environment,
+            VariableScope::Collection => FileVariableScope::Collection,
+        };
+        FileExtraction {
+            variable: value.variable,
+            source,
+            scope,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy)]
+enum FileHttpVersion {
+    #[serde(rename = "auto")]
+    Auto,
+    #[serde(rename = "1.1")]
+    Http11,
+    #[serde(rename = "2")]
+    Http2,
+}
+
+impl From<FileHttpVersion> for HttpVersion {
+    fn from(value: FileHttpVersion) -> Self {
+        match value {
+            FileHttpVersion::Auto => HttpVersion::Auto,
+            FileHttpVersion::Http11 => HttpVersion::Http11,
+            FileHttpVersion::Http2 => HttpVersion::Http2,
+        }
+    }
+}
+
+impl From<HttpVersion> for FileHttpVersion {
+    fn from(value: HttpVersion) -> Self {
+        match value {
+            HttpVersion::Auto => FileHttpVersion::Auto,
+            HttpVersion::Http11 => FileHttpVersion::Http11,
+            HttpVersion::Http2 => FileHttpVersion::Http2,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum FileProxyMode {
+    System,
+    Direct,
+    Manual,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct FileProxySettings {
+    mode: FileProxyMode,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    url: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    username: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    password: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    no_proxy: Vec<String>,
+}
+
+impl From<FileProxySettings> for ProxySettings {
+    fn from(value: FileProxySettings) -> Self {
+        let mode = match value.mode {
+            FileProxyMode::System => ProxyMode::System,
+            FileProxyMode::Direct => ProxyMode::Direct,
+            FileProxyMode::Manual => ProxyMode::Manual,
+        };
+        ProxySettings {
+            mode,
+            url: value.url,
+            username: value.username,
+            password: value.password,
+            no_proxy: value.no_proxy,
+        }
+    }
+}
+
+impl From<ProxySettings> for FileProxySettings {
+    fn from(value: ProxySettings) -> Self {
+        let mode = match value.mode {
+            ProxyMode::System => FileProxyMode::System,
+            ProxyMode::Direct => FileProxyMode::Direct,
+            ProxyMode::Manual => FileProxyMode::Manual,
+        };
+        FileProxySettings {
+            mode,
+            url: value.url,
+            username: value.username,
+            password: value.password,
+            no_proxy: value.no_proxy,
+        }
+    }
+}
+
+/// Client settings that override the global preferences for a single request.
+#[derive(Deserialize, Serialize, Default)]
+#[serde(rename_all = "kebab-case")]
+struct FileClientOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    connect_timeout: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    follow_redirects: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_redirects: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verify_tls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    http_version: Option<FileHttpVersion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proxy: Option<FileProxySettings>,
+}
+
+impl From<FileClientOptions> for ClientOptions {
+    fn from(value: FileClientOptions) -> Self {
+        ClientOptions {
+            connect_timeout: value.connect_timeout,
+            timeout: value.timeout,
+            follow_redirects: value.follow_redirects,
+            max_redirects: value.max_redirects,
+            verify_tls: value.verify_tls,
+            http_version: value.http_version.map(HttpVersion::from),
+            proxy: value.proxy.map(ProxySettings::from),
+        }
+    }
+}
+
+impl From<ClientOptions> for FileClientOptions {
+    fn from(value: ClientOptions) -> Self {
+        FileClientOptions {
+            connect_timeout: value.connect_timeout,
+            timeout: value.timeout,
+            follow_redirects: value.follow_redirects,
+            max_redirects: value.max_redirects,
+            verify_tls: value.verify_tls,
+            http_version: value.http_version.map(FileHttpVersion::from),
+            proxy: value.proxy.map(FileProxySettings::from),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 struct RequestFile {
     version: usize,
@@ -251,6 +863,14 @@ struct RequestFile {
     body: Option<Body>,
     headers: Option<KeyValuedFileTable>,
     variables: Option<KeyValuedFileTable>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auth: Option<FileAuth>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tests: Option<Vec<FileAssertion>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extract: Option<Vec<FileExtraction>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<FileClientOptions>,
 }
 
 impl TryFrom<RequestFile> for EndpointData {
@@ -266,6 +886,20 @@ impl TryFrom<RequestFile> for EndpointData {
         let body = value.body.map(RequestPayload::from).unwrap_or_default();
         let headers = value.headers.unwrap_or_default().into();
         let variables = value.variables.unwrap_or_default().into();
+        let auth = value.auth.map(RequestAuth::from).unwrap_or_default();
+        let tests = value
+            .tests
+            .unwrap_or_default()
+            .into_iter()
+            .map(Assertion::try_from)
+            .collect::<Result<Vec<Assertion>, CarteroError>>()?;
+        let extractions = value
+            .extract
+            .unwrap_or_default()
+            .into_iter()
+            .map(Extraction::from)
+            .collect();
+        let options = value.options.map(ClientOptions::from).unwrap_or_default();
 
         let request = EndpointData {
             url: value.url.clone(),
@@ -273,6 +907,10 @@ impl TryFrom<RequestFile> for EndpointData {
             body,
             variables,
             headers,
+            auth,
+            tests,
+            extractions,
+            options,
         };
         Ok(request)
     }
@@ -280,27 +918,170 @@ impl TryFrom<RequestFile> for EndpointData {
 
 impl From<EndpointData> for RequestFile {
     fn from(value: EndpointData) -> RequestFile {
-        let method: &str = value.method.into();
+        let method = String::from(value.method);
         let body = match value.body {
             RequestPayload::None => None,
             otherwise => Some(otherwise.into()),
         };
         let headers = value.headers.into();
         let variables = value.variables.into();
+        let auth = FileAuth::from_auth(value.auth);
+        let tests = if value.tests.is_empty() {
+            None
+        } else {
+            Some(value.tests.into_iter().map(FileAssertion::from).collect())
+        };
+        let extract = if value.extractions.is_empty() {
+            None
+        } else {
+            Some(
+                value
+                    .extractions
+                    .into_iter()
+                    .map(FileExtraction::from)
+                    .collect(),
+            )
+        };
+        let options = if value.options.is_empty() {
+            None
+        } else {
+            Some(value.options.into())
+        };
         RequestFile {
             version: 1,
             url: value.url.clone(),
-            method: method.to_owned(),
+            method,
             body,
             headers: Some(headers),
             variables: Some(variables),
+            auth,
+            tests,
+            extract,
+            options,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Default)]
+#[serde(rename_all = "lowercase")]
+enum FileWebSocketMessageFormat {
+    #[default]
+    Text,
+    Binary,
+}
+
+#[derive(Deserialize, Serialize)]
+struct FileWebSocketMessage {
+    #[serde(default)]
+    format: FileWebSocketMessageFormat,
+    content: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct WebSocketFile {
+    version: usize,
+    kind: String,
+    url: String,
+    headers: Option<KeyValuedFileTable>,
+    variables: Option<KeyValuedFileTable>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<FileWebSocketMessage>,
+}
+
+const WEBSOCKET_KIND: &str = "websocket";
+
+impl TryFrom<WebSocketFile> for WebSocketData {
+    type Error = CarteroError;
+
+    fn try_from(value: WebSocketFile) -> Result<WebSocketData, Self::Error> {
+        if value.version != 1 {
+            return Err(CarteroError::OutdatedSchema);
+        }
+        let (message, message_kind) = match value.message {
+            Some(message) => {
+                let kind = match message.format {
+                    FileWebSocketMessageFormat::Text => WebSocketMessageKind::Text,
+                    FileWebSocketMessageFormat::Binary => WebSocketMessageKind::Binary,
+                };
+                (message.content, kind)
+            }
+            None => (String::default(), WebSocketMessageKind::default()),
+        };
+        Ok(WebSocketData {
+            url: value.url,
+            headers: value.headers.unwrap_or_default().into(),
+            variables: value.variables.unwrap_or_default().into(),
+            message,
+            message_kind,
+        })
+    }
+}
+
+impl From<WebSocketData> for WebSocketFile {
+    fn from(value: WebSocketData) -> WebSocketFile {
+        let message = if value.message.is_empty() {
+            None
+        } else {
+            let format = match value.message_kind {
+                WebSocketMessageKind::Text => FileWebSocketMessageFormat::Text,
+                WebSocketMessageKind::Binary => FileWebSocketMessageFormat::Binary,
+            };
+            Some(FileWebSocketMessage {
+                format,
+                content: value.message,
+            })
+        };
+        WebSocketFile {
+            version: 1,
+            kind: WEBSOCKET_KIND.to_string(),
+            url: value.url,
+            headers: Some(value.headers.into()),
+            variables: Some(value.variables.into()),
+            message,
+        }
+    }
+}
+
+/// Only used to peek at the kind of item stored in a file before parsing it.
+/// Files without a kind are HTTP requests, as they were written before other
+/// kinds of items existed.
+#[derive(Deserialize)]
+struct ItemKind {
+    kind: Option<String>,
+}
+
+pub fn parse_item(file: &str) -> Result<ItemData, CarteroError> {
+    let kind = toml::from_str::<ItemKind>(file)?.kind;
+    match kind.as_deref() {
+        None => {
+            let contents = toml::from_str::<RequestFile>(file)?;
+            Ok(ItemData::Endpoint(Box::new(EndpointData::try_from(
+                contents,
+            )?)))
+        }
+        Some(WEBSOCKET_KIND) => {
+            let contents = toml::from_str::<WebSocketFile>(file)?;
+            Ok(ItemData::WebSocket(WebSocketData::try_from(contents)?))
+        }
+        Some(_) => Err(CarteroError::UnsupportedItemKind),
+    }
+}
+
+pub fn store_item(item: &ItemData) -> Result<String, CarteroError> {
+    match item {
+        ItemData::Endpoint(endpoint) => store_toml(endpoint),
+        ItemData::WebSocket(websocket) => {
+            let file = WebSocketFile::from(websocket.clone());
+            toml::to_string(&file).map_err(|e| e.into())
         }
     }
 }
 
 pub fn parse_toml(file: &str) -> Result<EndpointData, CarteroError> {
-    let contents = toml::from_str::<RequestFile>(file)?;
-    EndpointData::try_from(contents)
+    match parse_item(file)? {
+        ItemData::Endpoint(endpoint) => Ok(*endpoint),
+        _ => Err(CarteroError::NotAnEndpoint),
+    }
 }
 
 pub fn store_toml(endpoint: &EndpointData) -> Result<String, CarteroError> {
@@ -308,6 +1089,90 @@ pub fn store_toml(endpoint: &EndpointData) -> Result<String, CarteroError> {
     toml::to_string(&file).map_err(|e| e.into())
 }
 
+#[derive(Deserialize, Serialize)]
+struct CollectionFile {
+    version: usize,
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    variables: Option<KeyValuedFileTable>,
+}
+
+impl TryFrom<CollectionFile> for CollectionMetadata {
+    type Error = CarteroError;
+
+    fn try_from(value: CollectionFile) -> Result<CollectionMetadata, Self::Error> {
+        if value.version != 1 {
+            return Err(CarteroError::OutdatedSchema);
+        }
+        Ok(CollectionMetadata {
+            name: value.name,
+            variables: value.variables.map(KeyValueTable::from).unwrap_or_default(),
+        })
+    }
+}
+
+impl From<CollectionMetadata> for CollectionFile {
+    fn from(value: CollectionMetadata) -> CollectionFile {
+        let variables = if value.variables.is_empty() {
+            None
+        } else {
+            Some(value.variables.into())
+        };
+        CollectionFile {
+            version: 1,
+            name: value.name,
+            variables,
+        }
+    }
+}
+
+pub fn parse_collection_toml(file: &str) -> Result<CollectionMetadata, CarteroError> {
+    let contents = toml::from_str::<CollectionFile>(file)?;
+    CollectionMetadata::try_from(contents)
+}
+
+pub fn store_collection_toml(metadata: &CollectionMetadata) -> Result<String, CarteroError> {
+    let file = CollectionFile::from(metadata.clone());
+    toml::to_string(&file).map_err(|e| e.into())
+}
+
+#[derive(Deserialize, Serialize)]
+struct EnvironmentFile {
+    version: usize,
+    variables: Option<KeyValuedFileTable>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proxy: Option<FileProxySettings>,
+}
+
+/// Parses the contents of an environment file.
+///
+/// The name of the environment is not stored in the file but derived from the
+/// file name, so it has to be provided by the caller.
+pub fn parse_environment_toml(name: &str, file: &str) -> Result<Environment, CarteroError> {
+    let contents = toml::from_str::<EnvironmentFile>(file)?;
+    if contents.version != 1 {
+        return Err(CarteroError::OutdatedSchema);
+    }
+    Ok(Environment {
+        name: name.to_string(),
+        variables: contents
+            .variables
+            .map(KeyValueTable::from)
+            .unwrap_or_default(),
+        proxy: contents.proxy.map(ProxySettings::from),
+    })
+}
+
+pub fn store_environment_toml(environment: &Environment) -> Result<String, CarteroError> {
+    let file = EnvironmentFile {
+        version: 1,
+        variables: Some(environment.variables.clone().into()),
+        proxy: environment.proxy.clone().map(FileProxySettings::from),
+    };
+    toml::to_string(&file).map_err(|e| e.into())
+}
+
+#[cfg(feature = "gui")]
 pub async fn read_file(file: &gio::File) -> Result<String, CarteroError> {
     file.load_contents_future()
         .await
@@ -318,6 +1183,7 @@ pub async fn read_file(file: &gio::File) -> Result<String, CarteroError> {
         })
 }
 
+#[cfg(feature = "gui")]
 pub async fn write_file(file: &gio::File, contents: &str) -> Result<(), CarteroError> {
     file.replace_contents_future(contents.to_string(), None, true, gio::FileCreateFlags::NONE)
         .await
@@ -335,8 +1201,13 @@ mod tests {
 
     use crate::{
         entities::{
-            EndpointData, KeyValue, KeyValueTable, RawEncoding, RequestMethod, RequestPayload,
+            ApiKeyLocation, Assertion, ClientOptions, CollectionMetadata, EndpointData,
+            Environment, Extraction, ExtractionSource, HttpVersion, ItemData, KeyValue,
+            KeyValueTable, MultipartFile, OAuth2Config, OAuth2Grant, ProxyMode, ProxySettings,
+            RawEncoding, RequestAuth, RequestMethod, RequestPayload, VariableScope, WebSocketData,
+            WebSocketMessageKind,
         },
+        error::CarteroError,
         file::KeyValueDetail,
     };
 
@@ -677,11 +1548,30 @@ body = 'hello'
         let toml = "
 version = 1
 url = 'https://www.google.com'
-method = 'THROW'
+method = 'THROW ERROR'
 ";
         assert!(super::parse_toml(toml).is_err());
     }
 
+    #[test]
+    pub fn test_custom_method() {
+        let toml = "
+version = 1
+url = 'https://dav.example.com/files/'
+method = 'PROPFIND'
+";
+        let endpoint = super::parse_toml(toml).unwrap();
+        assert_eq!(endpoint.method, RequestMethod::Custom("PROPFIND".into()));
+
+        let endpoint = EndpointData {
+            method: RequestMethod::Custom("purge".into()),
+            ..endpoint
+        };
+        let stored = super::store_toml(&endpoint).unwrap();
+        assert!(stored.contains("method = \"purge\""));
+        assert_eq!(super::parse_toml(&stored).unwrap(), endpoint);
+    }
+
     #[test]
     pub fn test_empty_url() {
         let toml = "
@@ -724,6 +1614,151 @@ Accept = 'text/html'
         assert_eq!(endpoint.body, RequestPayload::None);
     }
 
+    #[test]
+    pub fn test_multipart_files() {
+        let toml = "
+version = 1
+url = 'https://www.example.com/upload'
+method = 'POST'
+
+[body]
+type = 'multipart'
+
+[body.variables]
+title = 'Holidays'
+
+[[body.files]]
+name = 'photo'
+path = 'photos/beach.jpg'
+filename = 'beach.jpg'
+content-type = 'image/jpeg'
+
+[[body.files]]
+name = 'notes'
+path = 'notes.txt'
+active = false
+";
+        let endpoint = super::parse_toml(toml).unwrap();
+        let expected = RequestPayload::Multipart {
+            params: KeyValueTable::new(&[("title", "Holidays").into()]),
+            files: vec![
+                MultipartFile {
+                    name: "photo".into(),
+                    path: "photos/beach.jpg".into(),
+                    filename: Some("beach.jpg".into()),
+                    content_type: Some("image/jpeg".into()),
+                    active: true,
+                },
+                MultipartFile {
+                    name: "notes".into(),
+                    path: "notes.txt".into(),
+                    active: false,
+                    ..Default::default()
+                },
+            ],
+        };
+        assert_eq!(endpoint.body, expected);
+
+        let stored = super::store_toml(&endpoint).unwrap();
+        assert_eq!(super::parse_toml(&stored).unwrap().body, expected);
+    }
+
+    #[test]
+    pub fn test_raw_formats_roundtrip() {
+        let encodings = [
+            RawEncoding::Text,
+            RawEncoding::Html,
+            RawEncoding::Yaml,
+            RawEncoding::JavaScript,
+            RawEncoding::Csv,
+            RawEncoding::Custom("application/vnd.api+json".into()),
+        ];
+        for encoding in encodings {
+            let endpoint = EndpointData {
+                url: "https://www.example.com".into(),
+                method: RequestMethod::Post,
+                body: RequestPayload::Raw {
+                    encoding: encoding.clone(),
+                    content: Vec::from("a,b\n1,2"),
+                },
+                ..Default::default()
+            };
+            let stored = super::store_toml(&endpoint).unwrap();
+            assert_eq!(super::parse_toml(&stored).unwrap().body, endpoint.body);
+        }
+
+        let toml = "
+version = 1
+url = 'https://www.example.com'
+method = 'POST'
+
+[body]
+type = 'raw'
+format = 'custom'
+content-type = 'text/x-ini'
+body = 'key = value'
+";
+        let endpoint = super::parse_toml(toml).unwrap();
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::Raw {
+                encoding: RawEncoding::Custom("text/x-ini".into()),
+                content: Vec::from("key = value"),
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_graphql_body() {
+        let toml = "
+version = 1
+url = 'https://www.example.com/graphql'
+method = 'POST'
+
+[body]
+type = 'graphql'
+query = '''
+query User($id: ID!) {
+  user(id: $id) { name }
+}
+'''
+variables = '{\"id\": 1}'
+operation-name = 'User'
+";
+        let endpoint = super::parse_toml(toml).unwrap();
+        let expected = RequestPayload::GraphQL {
+            query: "query User($id: ID!) {\n  user(id: $id) { name }\n}\n".into(),
+            variables: "{\"id\": 1}".into(),
+            operation_name: Some("User".into()),
+        };
+        assert_eq!(endpoint.body, expected);
+
+        let stored = super::store_toml(&endpoint).unwrap();
+        assert_eq!(super::parse_toml(&stored).unwrap().body, expected);
+    }
+
+    #[test]
+    pub fn test_file_body() {
+        let toml = "
+version = 1
+url = 'https://www.example.com/upload'
+method = 'PUT'
+
+[body]
+type = 'file'
+path = 'assets/logo.png'
+";
+        let endpoint = super::parse_toml(toml).unwrap();
+        let expected = RequestPayload::File {
+            path: "assets/logo.png".into(),
+        };
+        assert_eq!(endpoint.body, expected);
+
+        let stored = super::store_toml(&endpoint).unwrap();
+        assert!(stored.contains("type = \"file\""));
+        assert_eq!(super::parse_toml(&stored).unwrap().body, expected);
+    }
+
     #[test]
     pub fn test_multiple_headers_serialization() {
         let headers = vec![
@@ -739,6 +1774,7 @@ Accept = 'text/html'
             headers,
             variables: KeyValueTable::default(),
             body,
+            ..Default::default()
         };
 
         let content = super::store_toml(&r).unwrap();
@@ -769,6 +1805,7 @@ Accept = 'text/html'
             headers,
             variables: KeyValueTable::default(),
             body,
+            ..Default::default()
         };
 
         let content = super::store_toml(&r).unwrap();
@@ -817,6 +1854,7 @@ body = 'hello'
             headers,
             variables: KeyValueTable::default(),
             body,
+            ..Default::default()
         };
 
         let content = super::store_toml(&r).unwrap();
@@ -874,6 +1912,7 @@ body = 'hello'
             headers,
             variables,
             body,
+            ..Default::default()
         };
 
         let content = super::store_toml(&r).unwrap();
@@ -900,4 +1939,452 @@ body = 'hello'
             parsed.variables
         );
     }
+
+    #[test]
+    pub fn test_environment_roundtrip_keeps_secrets() {
+        let environment = Environment {
+            name: "production".into(),
+            variables: KeyValueTable::new(&[
+                ("API_ROOT", "api.example.com").into(),
+                KeyValue {
+                    name: "TOKEN".into(),
+                    value: "s3cr3t".into(),
+                    active: true,
+                    secret: true,
+                },
+            ]),
+            proxy: None,
+        };
+
+        let content = super::store_environment_toml(&environment).unwrap();
+        let parsed = super::parse_environment_toml("production", &content).unwrap();
+        assert_eq!(environment, parsed);
+    }
+
+    #[test]
+    pub fn test_environment_requires_version() {
+        let toml = "version = 2\n[variables]\nAPI_ROOT = 'localhost'\n";
+        assert!(super::parse_environment_toml("dev", toml).is_err());
+
+        let toml = "version = 1\n";
+        let parsed = super::parse_environment_toml("dev", toml).unwrap();
+        assert!(parsed.variables.is_empty());
+    }
+
+    #[test]
+    pub fn test_environment_proxy() {
+        let toml = r#"
+version = 1
+
+[proxy]
+mode = "manual"
+url = "http://proxy.corp.example.com:3128"
+username = "{{PROXY_USER}}"
+no-proxy = ["localhost", ".corp.example.com"]
+"#;
+        let parsed = super::parse_environment_toml("office", toml).unwrap();
+        let expected = ProxySettings {
+            mode: ProxyMode::Manual,
+            url: "http://proxy.corp.example.com:3128".into(),
+            username: "{{PROXY_USER}}".into(),
+            password: String::new(),
+            no_proxy: vec!["localhost".into(), ".corp.example.com".into()],
+        };
+        assert_eq!(parsed.proxy, Some(expected));
+
+        let stored = super::store_environment_toml(&parsed).unwrap();
+        assert!(!stored.contains("password"));
+        assert_eq!(
+            super::parse_environment_toml("office", &stored).unwrap(),
+            parsed
+        );
+
+        let stored = super::store_environment_toml(&Environment::default()).unwrap();
+        assert!(!stored.contains("proxy"));
+    }
+
+    #[test]
+    pub fn test_parse_tests_section() {
+        let toml = r#"
+version = 1
+url = "https://www.example.com/users/1"
+method = "GET"
+
+[[tests]]
+type = "status"
+equals = 200
+
+[[tests]]
+type = "status"
+min = 200
+max = 299
+
+[[tests]]
+type = "header"
+name = "Content-Type"
+equals = "application/json"
+
+[[tests]]
+type = "json"
+pointer = "/id"
+equals = 1
+
+[[tests]]
+type = "json"
+pointer = "/name"
+matches = "^[A-Z]"
+
+[[tests]]
+type = "body-contains"
+value = "name"
+
+[[tests]]
+type = "duration"
+max = 500
+"#;
+        let endpoint = super::parse_toml(toml).unwrap();
+        assert_eq!(
+            endpoint.tests,
+            vec![
+                Assertion::StatusEquals(200),
+                Assertion::StatusInRange { min: 200, max: 299 },
+                Assertion::HeaderEquals {
+                    name: "Content-Type".into(),
+                    value: "application/json".into(),
+                },
+                Assertion::JsonEquals {
+                    pointer: "/id".into(),
+                    value: serde_json::json!(1),
+                },
+                Assertion::JsonMatches {
+                    pointer: "/name".into(),
+                    pattern: "^[A-Z]".into(),
+                },
+                Assertion::BodyContains("name".into()),
+                Assertion::MaxDuration(500),
+            ]
+        );
+
+        let stored = super::store_toml(&endpoint).unwrap();
+        let parsed = super::parse_toml(&stored).unwrap();
+        assert_eq!(endpoint.tests, parsed.tests);
+    }
+
+    #[test]
+    pub fn test_invalid_tests_section() {
+        let toml = r#"
+version = 1
+url = "https://www.example.com"
+method = "GET"
+
+[[tests]]
+type = "status"
+"#;
+        assert!(super::parse_toml(toml).is_err());
+    }
+
+    #[test]
+    pub fn test_parse_extract_section() {
+        let toml = r#"
+version = 1
+url = "https://www.example.com/login"
+method = "POST"
+
+[[extract]]
+variable = "TOKEN"
+type = "json"
+path = "$.data.token"
+
+[[extract]]
+variable = "SESSION"
+type = "header"
+name = "X-Session"
+scope = "collection"
+
+[[extract]]
+variable = "ID"
+type = "xpath"
+path = "/user/@id"
+
+[[extract]]
+variable = "CSRF"
+type = "regex"
+pattern = "name=\"csrf\" value=\"([^\"]+)\""
+"#;
+        let endpoint = super::parse_toml(toml).unwrap();
+        let rule = |variable: &str, source, scope| Extraction {
+            variable: variable.into(),
+            source,
+            scope,
+        };
+        assert_eq!(
+            endpoint.extractions,
+            vec![
+                rule(
+                    "TOKEN",
+                    ExtractionSource::Json("$.data.token".into()),
+                    VariableScope::Environment
+                ),
+                rule(
+                    "SESSION",
+                    ExtractionSource::Header("X-Session".into()),
+                    VariableScope::Collection
+                ),
+                rule(
+                    "ID",
+                    ExtractionSource::XPath("/user/@id".into()),
+                    VariableScope::Environment
+                ),
+                rule(
+                    "CSRF",
+                    ExtractionSource::Regex(r#"name="csrf" value="([^"]+)""#.into()),
+                    VariableScope::Environment
+                ),
+            ]
+        );
+
+        let stored = super::store_toml(&endpoint).unwrap();
+        assert!(!stored.contains("scope = \"environment\""));
+        let parsed = super::parse_toml(&stored).unwrap();
+        assert_eq!(endpoint.extractions, parsed.extractions);
+    }
+
+    #[test]
+    pub fn test_collection_variables() {
+        let metadata = super::parse_collection_toml("version = 1\nname = \"API\"\n").unwrap();
+        assert!(metadata.variables.is_empty());
+        assert!(!super::store_collection_toml(&metadata)
+            .unwrap()
+            .contains("variables"));
+
+        let metadata = CollectionMetadata {
+            name: "API".into(),
+            variables: KeyValueTable::new(&[("USER_ID", "7").into()]),
+        };
+        let stored = super::store_collection_toml(&metadata).unwrap();
+        assert_eq!(super::parse_collection_toml(&stored).unwrap(), metadata);
+    }
+
+    #[test]
+    pub fn test_client_options() {
+        let toml = r#"
+version = 1
+url = "https://staging.example.com"
+method = "GET"
+
+[options]
+connect-timeout = 5
+timeout = 30
+follow-redirects = true
+max-redirects = 3
+verify-tls = false
+http-version = "1.1"
+
+[options.proxy]
+mode = "direct"
+"#;
+        let endpoint = super::parse_toml(toml).unwrap();
+        let expected = ClientOptions {
+            connect_timeout: Some(5),
+            timeout: Some(30),
+            follow_redirects: Some(true),
+            max_redirects: Some(3),
+            verify_tls: Some(false),
+            http_version: Some(HttpVersion::Http11),
+            proxy: Some(ProxySettings {
+                mode: ProxyMode::Direct,
+                ..Default::default()
+            }),
+        };
+        assert_eq!(endpoint.options, expected);
+
+        let stored = super::store_toml(&endpoint).unwrap();
+        assert!(stored.contains("http-version = \"1.1\""));
+        assert_eq!(super::parse_toml(&stored).unwrap().options, expected);
+
+        let endpoint = EndpointData {
+            url: "https://www.example.com".into(),
+            options: ClientOptions {
+                verify_tls: Some(false),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let stored = super::store_toml(&endpoint).unwrap();
+        assert!(stored.contains("verify-tls = false"));
+        assert!(!stored.contains("timeout"));
+
+        let stored = super::store_toml(&EndpointData::default()).unwrap();
+        assert!(!stored.contains("options"));
+    }
+
+    #[test]
+    pub fn test_auth_section_roundtrip() {
+        let endpoint = EndpointData {
+            url: "https://www.example.com".into(),
+            auth: RequestAuth::ApiKey {
+                name: "X-Api-Key".into(),
+                value: "{{API_KEY}}".into(),
+                location: ApiKeyLocation::Query,
+            },
+            ..Default::default()
+        };
+        let stored = super::store_toml(&endpoint).unwrap();
+        assert!(stored.contains("[auth]"));
+        assert!(stored.contains("type = \"api-key\""));
+        assert!(stored.contains("location = \"query\""));
+        let parsed = super::parse_toml(&stored).unwrap();
+        assert_eq!(parsed.auth, endpoint.auth);
+
+        let endpoint = EndpointData {
+            url: "https://www.example.com".into(),
+            ..Default::default()
+        };
+        let stored = super::store_toml(&endpoint).unwrap();
+        assert!(!stored.contains("auth"));
+    }
+
+    #[test]
+    pub fn test_oauth2_auth_section() {
+        let toml = "
+version = 1
+url = \"https://www.example.com\"
+method = \"GET\"
+
+[auth]
+type = \"oauth2\"
+grant = \"authorization-code\"
+token-url = \"https://auth.example.com/token\"
+authorization-url = \"https://auth.example.com/authorize\"
+client-id = \"{{CLIENT_ID}}\"
+";
+        let parsed = super::parse_toml(toml).unwrap();
+        let expected = RequestAuth::OAuth2(OAuth2Config {
+            grant: OAuth2Grant::AuthorizationCode,
+            token_url: "https://auth.example.com/token".into(),
+            authorization_url: "https://auth.example.com/authorize".into(),
+            client_id: "{{CLIENT_ID}}".into(),
+            ..Default::default()
+        });
+        assert_eq!(parsed.auth, expected);
+
+        let stored = super::store_toml(&parsed).unwrap();
+        assert!(!stored.contains("client-secret"));
+        assert_eq!(super::parse_toml(&stored).unwrap().auth, expected);
+    }
+
+    #[test]
+    pub fn test_parse_basic_auth_without_password() {
+        let toml = "
+version = 1
+url = \"https://www.example.com\"
+method = \"GET\"
+
+[auth]
+type = \"basic\"
+username = \"admin\"
+";
+        let parsed = super::parse_toml(toml).unwrap();
+        assert_eq!(
+            parsed.auth,
+            RequestAuth::Basic {
+                username: "admin".into(),
+                password: "".into(),
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_no_tests_section_is_not_stored() {
+        let endpoint = EndpointData {
+            url: "https://www.example.com".into(),
+            ..Default::default()
+        };
+        let stored = super::store_toml(&endpoint).unwrap();
+        assert!(!stored.contains("tests"));
+    }
+
+    #[test]
+    pub fn test_websocket_file() {
+        let toml = r#"
+version = 1
+kind = "websocket"
+url = "wss://echo.example.com/{{CHANNEL}}"
+
+[headers]
+Authorization = "Bearer {{TOKEN}}"
+
+[variables]
+CHANNEL = "news"
+
+[message]
+format = "binary"
+content = "aGVsbG8="
+"#;
+        let ItemData::WebSocket(websocket) = super::parse_item(toml).unwrap() else {
+            panic!("expected a websocket");
+        };
+        assert_eq!(websocket.url, "wss://echo.example.com/{{CHANNEL}}");
+        assert_eq!(
+            websocket.headers.header("Authorization"),
+            Some(vec!["Bearer {{TOKEN}}"])
+        );
+        assert_eq!(websocket.variables.len(), 1);
+        assert_eq!(websocket.message, "aGVsbG8=");
+        assert_eq!(websocket.message_kind, WebSocketMessageKind::Binary);
+
+        let stored = super::store_item(&ItemData::WebSocket(websocket.clone())).unwrap();
+        assert!(stored.contains("kind = \"websocket\""));
+        assert_eq!(
+            super::parse_item(&stored).unwrap(),
+            ItemData::WebSocket(websocket)
+        );
+    }
+
+    #[test]
+    pub fn test_websocket_file_without_message() {
+        let websocket = WebSocketData {
+            url: "ws://localhost:8080".into(),
+            ..Default::default()
+        };
+        let stored = super::store_item(&ItemData::WebSocket(websocket.clone())).unwrap();
+        assert!(!stored.contains("message"));
+        assert_eq!(
+            super::parse_item(&stored).unwrap(),
+            ItemData::WebSocket(websocket)
+        );
+    }
+
+    #[test]
+    pub fn test_item_kinds() {
+        let toml = r#"
+version = 1
+url = "https://www.example.com"
+method = "GET"
+"#;
+        assert!(matches!(
+            super::parse_item(toml).unwrap(),
+            ItemData::Endpoint(_)
+        ));
+
+        let toml = r#"
+version = 1
+kind = "websocket"
+url = "ws://localhost"
+"#;
+        assert!(matches!(
+            super::parse_toml(toml),
+            Err(CarteroError::NotAnEndpoint)
+        ));
+
+        let toml = r#"
+version = 1
+kind = "carrier-pigeon"
+url = "ws://localhost"
+"#;
+        assert!(matches!(
+            super::parse_item(toml),
+            Err(CarteroError::UnsupportedItemKind)
+        ));
+    }
 }
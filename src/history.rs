@@ -0,0 +1,521 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! The record of the requests sent from the application.
+//!
+//! Every exchange is stored as a line of JSON in a file, so that recording a
+//! request only has to append to it. The oldest entries are dropped once the
+//! history grows past its limit.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::client::{BoundRequest, RequestBody};
+use crate::entities::{
+    EndpointData, KeyValue, KeyValueTable, RawEncoding, RequestMethod, RequestPayload, ResponseData,
+};
+use crate::error::CarteroError;
+
+/// The name of the file that holds the history, inside the data directory.
+pub const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+/// Headers that carry credentials, which are never recorded.
+const CREDENTIAL_HEADERS: [&str; 3] = ["Authorization", "Proxy-Authorization", "Cookie"];
+
+/// Headers of the response that set cookies, which are never recorded either.
+const COOKIE_HEADERS: [&str; 2] = ["Set-Cookie", "Set-Cookie2"];
+
+/// Bodies larger than this are never recorded, so that the history stays small.
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+fn is_any_of(name: &str, headers: &[&str]) -> bool {
+    headers
+        .iter()
+        .any(|header| header.eq_ignore_ascii_case(name))
+}
+
+/// Removes every parameter with the given name from the query of the URL.
+fn remove_query_pair(url: &str, name: &str) -> String {
+    let Ok(mut parsed) = url::Url::parse(url) else {
+        // Better to lose the whole query than to keep the credentials.
+        return url
+            .split_once('?')
+            .map_or(url, |(base, _)| base)
+            .to_string();
+    };
+    let pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .into_owned()
+        .filter(|(key, _)| key != name)
+        .collect();
+    if pairs.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    parsed.into()
+}
+
+/// A request that was sent, together with the response it got.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub timestamp: SystemTime,
+
+    /// The request as it was sent, without its credentials: the authentication
+    /// and cookie headers are dropped, as well as the query parameter set by the
+    /// authentication, and so are the digest or OAuth 2.0 settings.
+    pub request: BoundRequest,
+
+    /// The response that was received, without the headers that set cookies.
+    pub response: ResponseData,
+
+    /// Whether the body of the response was recorded.
+    pub has_body: bool,
+}
+
+impl HistoryEntry {
+    /// Records an exchange. The bodies of the request and of the response are
+    /// only kept when `keep_body` is set and they are not larger than 1 MiB.
+    /// Files sent in the body of the request are never kept.
+    pub fn new(
+        request: &BoundRequest,
+        response: &ResponseData,
+        timestamp: SystemTime,
+        keep_body: bool,
+    ) -> Self {
+        let headers = request
+            .headers
+            .iter()
+            .filter(|(name, _)| {
+                let is_auth_header = request
+                    .auth_header
+                    .as_ref()
+                    .is_some_and(|header| header.eq_ignore_ascii_case(name));
+                !is_auth_header && !is_any_of(name, &CREDENTIAL_HEADERS)
+            })
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        let url = match &request.auth_query {
+            Some(name) => remove_query_pair(&request.url, name),
+            None => request.url.clone(),
+        };
+        let keeps = |body: &[u8]| keep_body && body.len() <= MAX_BODY_SIZE;
+        let request = BoundRequest {
+            url,
+            method: request.method.clone(),
+            headers,
+            body: request
+                .body
+                .as_ref()
+                .and_then(RequestBody::as_bytes)
+                .filter(|body| keeps(body))
+                .map(|body| RequestBody::from(body.to_vec())),
+            ..Default::default()
+        };
+
+        let mut response = response.clone();
+        response.headers = response
+            .headers
+            .iter()
+            .filter(|header| !is_any_of(&header.name, &COOKIE_HEADERS))
+            .cloned()
+            .collect();
+        let has_body = keeps(&response.body);
+        if !has_body {
+            response.body.clear();
+        }
+        Self {
+            timestamp,
+            request,
+            response,
+            has_body,
+        }
+    }
+
+    /// Builds a request that sends again the recorded one. Since the request was
+    /// already bound, the body is sent as raw content and there are no variables.
+    pub fn to_endpoint(&self) -> EndpointData {
+        let mut headers: Vec<(&String, &String)> = self.request.headers.iter().collect();
+        headers.sort();
+        let (content_type, headers): (Vec<_>, Vec<_>) = headers
+            .into_iter()
+            .partition(|(name, _)| name.eq_ignore_ascii_case("content-type"));
+        let mut headers: KeyValueTable = headers
+            .into_iter()
+            .map(|(name, value)| KeyValue::from((name.clone(), value.clone())))
+            .collect();
+        let content_type = content_type.first().map(|(_, value)| value.as_str());
+
+        let body = self.request.body.as_ref().and_then(RequestBody::as_bytes);
+        let body = match (body, content_type) {
+            (Some(content), content_type) => {
+                let content_type = content_type.unwrap_or_default();
+                let encoding = RawEncoding::from_content_type(content_type);
+                // Keep the parameters of the content type, such as the boundary of a multipart body.
+                if !content_type.is_empty() && encoding.content_type() != content_type {
+                    headers.push(("Content-Type", content_type).into());
+                }
+                RequestPayload::Raw {
+                    encoding,
+                    content: content.to_vec(),
+                }
+            }
+            (None, Some(content_type)) => {
+                headers.push(("Content-Type", content_type).into());
+                RequestPayload::None
+            }
+            (None, None) => RequestPayload::None,
+        };
+        EndpointData {
+            url: self.request.url.clone(),
+            method: self.request.method.clone(),
+            headers,
+            body,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct HistoryLine {
+    timestamp: u64,
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    status: u32,
+    duration: u64,
+    size: usize,
+    response_headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    response_body: Option<String>,
+}
+
+impl From<&HistoryEntry> for HistoryLine {
+    fn from(value: &HistoryEntry) -> Self {
+        let timestamp = value
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let mut headers: Vec<(String, String)> = value
+            .request
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        headers.sort();
+        let response_headers = value
+            .response
+            .headers
+            .iter()
+            .map(|header| (header.name.clone(), header.value.clone()))
+            .collect();
+        HistoryLine {
+            timestamp,
+            method: value.request.method.as_str().to_string(),
+            url: value.request.url.clone(),
+            headers,
+            body: value
+                .request
+                .body
+                .as_ref()
+                .and_then(RequestBody::as_bytes)
+                .map(base64::encode),
+            status: value.response.status_code,
+            duration: value.response.duration as u64,
+            size: value.response.size,
+            response_headers,
+            response_body: value.has_body.then(|| base64::encode(&value.response.body)),
+        }
+    }
+}
+
+impl TryFrom<HistoryLine> for HistoryEntry {
+    type Error = ();
+
+    fn try_from(value: HistoryLine) -> Result<Self, Self::Error> {
+        let method = RequestMethod::try_from(value.method.as_str())?;
+        let body = match value.body {
+            Some(body) => Some(RequestBody::from(base64::decode(body).map_err(|_| ())?)),
+            None => None,
+        };
+        let response_body = match &value.response_body {
+            Some(body) => base64::decode(body).map_err(|_| ())?,
+            None => Vec::new(),
+        };
+        let request = BoundRequest {
+            url: value.url,
+            method,
+            headers: value.headers.into_iter().collect(),
+            body,
+            ..Default::default()
+        };
+        let response = ResponseData {
+            status_code: value.status,
+            duration: value.duration.into(),
+            size: value.size,
+            headers: value
+                .response_headers
+                .into_iter()
+                .map(KeyValue::from)
+                .collect(),
+            body: response_body,
+        };
+        Ok(HistoryEntry {
+            timestamp: UNIX_EPOCH + Duration::from_millis(value.timestamp),
+            request,
+            response,
+            has_body: value.response_body.is_some(),
+        })
+    }
+}
+
+/// The file where the history is kept.
+pub struct HistoryStore {
+    path: PathBuf,
+    limit: usize,
+}
+
+impl HistoryStore {
+    /// Opens the history kept in the given directory, which is created when the
+    /// first entry is recorded. At most `limit` entries are kept.
+    pub fn new(directory: &Path, limit: usize) -> Self {
+        Self {
+            path: directory.join(HISTORY_FILE_NAME),
+            limit,
+        }
+    }
+
+    /// Reads every entry, the most recent first. Lines that cannot be understood
+    /// are skipped, so that a damaged file does not hide the rest of the history.
+    pub fn load(&self) -> Result<Vec<HistoryEntry>, CarteroError> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+        let mut entries: Vec<HistoryEntry> = contents
+            .lines()
+            .filter_map(|line| serde_json::from_str::<HistoryLine>(line).ok())
+            .filter_map(|line| HistoryEntry::try_from(line).ok())
+            .collect();
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// Records a new entry, dropping the oldest ones if the limit is exceeded.
+    pub fn append(&self, entry: &HistoryEntry) -> Result<(), CarteroError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let line = serde_json::to_string(&HistoryLine::from(entry))
+            .map_err(|_| CarteroError::InvalidHistoryEntry)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{line}")?;
+        drop(file);
+
+        let contents = fs::read_to_string(&self.path)?;
+        let lines: Vec<&str> = contents.lines().collect();
+        if lines.len() > self.limit {
+            let kept = &lines[lines.len() - self.limit..];
+            fs::write(&self.path, kept.join("\n") + "\n")?;
+        }
+        Ok(())
+    }
+
+    /// Forgets every entry.
+    pub fn clear(&self) -> Result<(), CarteroError> {
+        match fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{HistoryEntry, HistoryStore};
+    use crate::client::{BoundRequest, RequestBody};
+    use crate::entities::{
+        KeyValueTable, RawEncoding, RequestMethod, RequestPayload, ResponseData,
+    };
+
+    fn request(url: &str) -> BoundRequest {
+        BoundRequest {
+            url: url.into(),
+            method: RequestMethod::Post,
+            headers: HashMap::from([
+                ("Content-Type".to_string(), "application/json".to_string()),
+                ("Accept".to_string(), "application/json".to_string()),
+                ("authorization".to_string(), "Bearer secret".to_string()),
+                ("Cookie".to_string(), "session=secret".to_string()),
+                ("X-Api-Key".to_string(), "secret".to_string()),
+            ]),
+            body: Some(RequestBody::from(b"{\"name\":\"cartero\"}".to_vec())),
+            digest_credentials: Some(("admin".into(), "secret".into())),
+            auth_header: Some("X-Api-Key".into()),
+            ..Default::default()
+        }
+    }
+
+    fn response() -> ResponseData {
+        ResponseData {
+            status_code: 201,
+            duration: 42,
+            size: 2,
+            headers: KeyValueTable::new(&[
+                ("Content-Type", "application/json").into(),
+                ("set-cookie", "session=secret").into(),
+            ]),
+            body: b"{}".to_vec(),
+        }
+    }
+
+    fn entry(url: &str) -> HistoryEntry {
+        HistoryEntry::new(
+            &request(url),
+            &response(),
+            UNIX_EPOCH + Duration::from_millis(1_700_000_000_000),
+            true,
+        )
+    }
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("cartero-history-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_entry_drops_credentials() {
+        let entry = entry("https://www.example.com");
+        assert_eq!(entry.request.digest_credentials, None);
+        let mut headers: Vec<&String> = entry.request.headers.keys().collect();
+        headers.sort();
+        assert_eq!(headers, ["Accept", "Content-Type"]);
+        assert_eq!(entry.response.headers.len(), 1);
+        assert_eq!(entry.response.headers[0].name, "Content-Type");
+
+        let mut request = request("https://www.example.com/users?page=2&api_key=secret#top");
+        request.auth_query = Some("api_key".into());
+        let entry = HistoryEntry::new(&request, &response(), UNIX_EPOCH, true);
+        assert_eq!(
+            entry.request.url,
+            "https://www.example.com/users?page=2#top"
+        );
+        request.url = "https://www.example.com/?api_key=secret".into();
+        let entry = HistoryEntry::new(&request, &response(), UNIX_EPOCH, true);
+        assert_eq!(entry.request.url, "https://www.example.com/");
+    }
+
+    #[test]
+    fn test_entry_bodies() {
+        let entry = HistoryEntry::new(&request("https://a.com"), &response(), UNIX_EPOCH, false);
+        assert_eq!(entry.request.body, None);
+        assert!(entry.response.body.is_empty());
+        assert!(!entry.has_body);
+
+        let mut request = request("https://a.com");
+        request.body = Some(RequestBody::from(vec![b'a'; super::MAX_BODY_SIZE + 1]));
+        let mut response = response();
+        response.body = vec![b'a'; super::MAX_BODY_SIZE + 1];
+        let entry = HistoryEntry::new(&request, &response, UNIX_EPOCH, true);
+        assert_eq!(entry.request.body, None);
+        assert!(entry.response.body.is_empty());
+        assert!(!entry.has_body);
+    }
+
+    #[test]
+    fn test_store_roundtrip_and_limit() {
+        let dir = temp_dir("roundtrip");
+        let store = HistoryStore::new(&dir, 2);
+        assert!(store.load().unwrap().is_empty());
+
+        store.append(&entry("https://www.example.com/1")).unwrap();
+        store.append(&entry("https://www.example.com/2")).unwrap();
+        store.append(&entry("https://www.example.com/3")).unwrap();
+        let entries = store.load().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].request.url, "https://www.example.com/3");
+        assert_eq!(entries[1], entry("https://www.example.com/2"));
+
+        store.clear().unwrap();
+        assert!(store.load().unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_store_without_body() {
+        let dir = temp_dir("nobody");
+        let store = HistoryStore::new(&dir, 10);
+        let mut entry = entry("https://www.example.com");
+        entry.response.body.clear();
+        entry.has_body = false;
+        store.append(&entry).unwrap();
+        std::fs::write(
+            dir.join(super::HISTORY_FILE_NAME),
+            std::fs::read_to_string(dir.join(super::HISTORY_FILE_NAME)).unwrap() + "garbage\n",
+        )
+        .unwrap();
+        assert_eq!(store.load().unwrap(), vec![entry]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_to_endpoint() {
+        let endpoint = entry("https://www.example.com/users").to_endpoint();
+        assert_eq!(endpoint.url, "https://www.example.com/users");
+        assert_eq!(endpoint.method, RequestMethod::Post);
+        assert_eq!(
+            endpoint.headers.header("accept"),
+            Some(vec!["application/json"])
+        );
+        assert_eq!(endpoint.headers.header("content-type"), None);
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::Raw {
+                encoding: RawEncoding::Json,
+                content: b"{\"name\":\"cartero\"}".to_vec(),
+            }
+        );
+
+        let mut multipart = entry("https://www.example.com/upload");
+        multipart.request.headers.insert(
+            "Content-Type".into(),
+            "multipart/form-data; boundary=xyz".into(),
+        );
+        let endpoint = multipart.to_endpoint();
+        assert_eq!(
+            endpoint.headers.header("content-type"),
+            Some(vec!["multipart/form-data; boundary=xyz"])
+        );
+    }
+}
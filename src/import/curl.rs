@@ -0,0 +1,665 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Parser for curl command lines, such as the ones found in API documentation.
+
+use crate::entities::{
+    EndpointData, KeyValue, KeyValueTable, MultipartFile, RawEncoding, RequestAuth, RequestMethod,
+    RequestPayload,
+};
+
+use super::ImportError;
+
+/// Splits a command line into words following the quoting rules of a POSIX shell.
+///
+/// Single quotes, double quotes, backslash escapes and line continuations are
+/// supported. Other shell features such as variable expansion are not.
+fn split_words(command: &str) -> Result<Vec<String>, ImportError> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err(ImportError::UnterminatedQuote),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('\n') => {}
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err(ImportError::UnterminatedQuote),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err(ImportError::UnterminatedQuote),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                // A line continuation, the word may keep going on the next line.
+                Some('\n') => {}
+                Some('\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                }
+                Some(c) => {
+                    in_word = true;
+                    current.push(c);
+                }
+                None => {}
+            },
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
+
+/// Options that do not take a value and do not change the request.
+const IGNORED_FLAGS: &[&str] = &[
+    "-s",
+    "--silent",
+    "-S",
+    "--show-error",
+    "-L",
+    "--location",
+    "-k",
+    "--insecure",
+    "-v",
+    "--verbose",
+    "-i",
+    "--include",
+    "-f",
+    "--fail",
+    "-g",
+    "--globoff",
+    "-N",
+    "--no-buffer",
+    "-#",
+    "--progress-bar",
+    "--compressed",
+    "--http1.0",
+    "--http1.1",
+    "--http2",
+];
+
+/// Options that take a value but do not change the request.
+const IGNORED_OPTIONS: &[&str] = &[
+    "-o",
+    "--output",
+    "-m",
+    "--max-time",
+    "--connect-timeout",
+    "--retry",
+    "-w",
+    "--write-out",
+    "-c",
+    "--cookie-jar",
+    "--cacert",
+    "--cert",
+    "--key",
+    "--resolve",
+];
+
+/// Options that take a value.
+const VALUED_OPTIONS: &[&str] = &[
+    "-X",
+    "--request",
+    "-H",
+    "--header",
+    "-d",
+    "--data",
+    "--data-raw",
+    "--data-ascii",
+    "--data-binary",
+    "--data-urlencode",
+    "-F",
+    "--form",
+    "--form-string",
+    "-u",
+    "--user",
+    "-A",
+    "--user-agent",
+    "-e",
+    "--referer",
+    "-b",
+    "--cookie",
+    "--url",
+];
+
+enum BodyPart {
+    Data(String),
+    UrlEncoded(String),
+}
+
+#[derive(Default)]
+struct CurlCommand {
+    method: Option<String>,
+    url: Option<String>,
+    headers: Vec<KeyValue>,
+    data: Vec<BodyPart>,
+    form: Vec<KeyValue>,
+    files: Vec<MultipartFile>,
+    /// A file sent verbatim as the body with `--data-binary @file`.
+    data_file: Option<String>,
+    user: Option<String>,
+    digest: bool,
+    get: bool,
+    head: bool,
+}
+
+/// Expands grouped short flags, such as `-sSL`, and short options with an attached
+/// value, such as `-XPOST`, into separate words.
+fn expand_short_options(words: Vec<String>) -> Vec<String> {
+    let mut expanded = Vec::new();
+    for word in words {
+        let is_grouped = word.len() > 2 && word.starts_with('-') && !word.starts_with("--");
+        if !is_grouped {
+            expanded.push(word);
+            continue;
+        }
+        let mut rest = &word[1..];
+        while let Some(flag) = rest.chars().next() {
+            let option = format!("-{flag}");
+            rest = &rest[flag.len_utf8()..];
+            let takes_value = VALUED_OPTIONS.contains(&option.as_str())
+                || IGNORED_OPTIONS.contains(&option.as_str());
+            expanded.push(option);
+            if takes_value {
+                if !rest.is_empty() {
+                    expanded.push(rest.to_string());
+                }
+                break;
+            }
+        }
+    }
+    expanded
+}
+
+fn parse_header(header: &str) -> KeyValue {
+    match header.split_once(':') {
+        Some((name, value)) => (name.trim(), value.trim()).into(),
+        None => (header.trim(), "").into(),
+    }
+}
+
+/// Parses the value of a `-F name=@path;type=...;filename=...` option.
+fn parse_form_file(name: &str, value: &str) -> MultipartFile {
+    let (path, attributes) = match value.strip_prefix('"') {
+        Some(quoted) => {
+            let (path, rest) = quoted.split_once('"').unwrap_or((quoted, ""));
+            (path.to_string(), rest)
+        }
+        None => match value.split_once(';') {
+            Some((path, rest)) => (path.to_string(), rest),
+            None => (value.to_string(), ""),
+        },
+    };
+    let mut file = MultipartFile {
+        name: name.to_string(),
+        path,
+        ..Default::default()
+    };
+    for attribute in attributes.split(';') {
+        match attribute.trim().split_once('=') {
+            Some(("type", content_type)) => file.content_type = Some(content_type.to_string()),
+            Some(("filename", filename)) => file.filename = Some(filename.to_string()),
+            _ => {}
+        }
+    }
+    file
+}
+
+fn parse_command(words: Vec<String>) -> Result<CurlCommand, ImportError> {
+    let mut words = words.into_iter();
+    match words.next().as_deref() {
+        Some("curl") => {}
+        _ => return Err(ImportError::NotCurl),
+    }
+
+    let mut command = CurlCommand::default();
+    let words = expand_short_options(words.collect());
+    let mut words = words.into_iter();
+    while let Some(word) = words.next() {
+        let option = word.as_str();
+        if !option.starts_with('-') || option == "-" {
+            command.url = Some(word);
+            continue;
+        }
+        if IGNORED_FLAGS.contains(&option) {
+            continue;
+        }
+        match option {
+            "-G" | "--get" => {
+                command.get = true;
+                continue;
+            }
+            "-I" | "--head" => {
+                command.head = true;
+                continue;
+            }
+            "--digest" => {
+                command.digest = true;
+                continue;
+            }
+            "--basic" => {
+                command.digest = false;
+                continue;
+            }
+            _ => {}
+        }
+        if !VALUED_OPTIONS.contains(&option) && !IGNORED_OPTIONS.contains(&option) {
+            return Err(ImportError::UnsupportedOption(word));
+        }
+
+        let value = words
+            .next()
+            .ok_or_else(|| ImportError::MissingValue(word.clone()))?;
+        match option {
+            "-X" | "--request" => command.method = Some(value),
+            "-H" | "--header" => command.headers.push(parse_header(&value)),
+            "--data-binary" if value.starts_with('@') => {
+                if command.data_file.is_some() {
+                    return Err(ImportError::UnsupportedOption(format!("{word} @file")));
+                }
+                command.data_file = Some(value[1..].to_string());
+            }
+            "-d" | "--data" | "--data-raw" | "--data-ascii" | "--data-binary" => {
+                if value.starts_with('@') && option != "--data-raw" {
+                    return Err(ImportError::UnsupportedOption(format!("{word} @file")));
+                }
+                command.data.push(BodyPart::Data(value));
+            }
+            "--data-urlencode" => {
+                if value.contains('@') && !value.contains('=') {
+                    return Err(ImportError::UnsupportedOption(format!("{word} @file")));
+                }
+                command.data.push(BodyPart::UrlEncoded(value));
+            }
+            "-F" | "--form" | "--form-string" => {
+                let (name, content) = value.split_once('=').unwrap_or((&value, ""));
+                if option == "--form-string" {
+                    command.form.push((name, content).into());
+                } else if let Some(file) = content.strip_prefix('@') {
+                    command.files.push(parse_form_file(name, file));
+                } else if content.starts_with('<') {
+                    return Err(ImportError::UnsupportedOption(format!("{word} <file")));
+                } else {
+                    command.form.push((name, content).into());
+                }
+            }
+            "-u" | "--user" => command.user = Some(value),
+            "-A" | "--user-agent" => command.headers.push(("User-Agent", value.as_str()).into()),
+            "-e" | "--referer" => command.headers.push(("Referer", value.as_str()).into()),
+            "-b" | "--cookie" => command.headers.push(("Cookie", value.as_str()).into()),
+            "--url" => command.url = Some(value),
+            _ => {}
+        }
+    }
+    // The contents of the file cannot be mixed with other data.
+    if command.data_file.is_some() && !command.data.is_empty() {
+        return Err(ImportError::UnsupportedOption("--data-binary @file".into()));
+    }
+    Ok(command)
+}
+
+fn header_value<'a>(headers: &'a [KeyValue], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .rev()
+        .find(|header| header.name.eq_ignore_ascii_case(name))
+        .map(|header| header.value.as_str())
+}
+
+/// Converts the data given with `-d` and `--data-urlencode` into pairs.
+fn data_as_pairs(data: &[BodyPart]) -> KeyValueTable {
+    let mut table = KeyValueTable::default();
+    for part in data {
+        match part {
+            BodyPart::Data(data) => {
+                for (name, value) in url::form_urlencoded::parse(data.as_bytes()) {
+                    table.push((name.as_ref(), value.as_ref()).into());
+                }
+            }
+            BodyPart::UrlEncoded(data) => {
+                let (name, value) = data.split_once('=').unwrap_or(("", data));
+                table.push((name, value).into());
+            }
+        }
+    }
+    table
+}
+
+fn data_as_raw(data: &[BodyPart]) -> String {
+    let parts: Vec<String> = data
+        .iter()
+        .map(|part| match part {
+            BodyPart::Data(data) => data.clone(),
+            BodyPart::UrlEncoded(data) => match data.split_once('=') {
+                Some((name, value)) => format!(
+                    "{name}={}",
+                    url::form_urlencoded::byte_serialize(value.as_bytes()).collect::<String>()
+                ),
+                None => url::form_urlencoded::byte_serialize(data.as_bytes()).collect(),
+            },
+        })
+        .collect();
+    parts.join("&")
+}
+
+fn build_payload(command: &CurlCommand) -> RequestPayload {
+    if !command.form.is_empty() || !command.files.is_empty() {
+        return RequestPayload::Multipart {
+            params: KeyValueTable::new(&command.form),
+            files: command.files.clone(),
+        };
+    }
+    if let Some(path) = &command.data_file {
+        if !command.get {
+            return RequestPayload::File { path: path.clone() };
+        }
+    }
+    if command.data.is_empty() || command.get {
+        return RequestPayload::None;
+    }
+
+    let explicit_type = header_value(&command.headers, "Content-Type");
+    let content_type = explicit_type
+        .unwrap_or("application/x-www-form-urlencoded")
+        .to_lowercase();
+    if content_type.starts_with("application/x-www-form-urlencoded") {
+        let raw = data_as_raw(&command.data);
+        let looks_like_form = raw.split('&').all(|pair| pair.contains('='));
+        if looks_like_form {
+            return RequestPayload::Urlencoded(data_as_pairs(&command.data));
+        }
+    }
+
+    let encoding = match explicit_type {
+        Some(content_type) => RawEncoding::from_content_type(content_type),
+        None => RawEncoding::OctetStream,
+    };
+    RequestPayload::Raw {
+        encoding,
+        content: data_as_raw(&command.data).into_bytes(),
+    }
+}
+
+/// Appends the data to the query string, which is what `curl -G` does.
+fn append_query(url: &str, query: &str) -> String {
+    if query.is_empty() {
+        url.to_string()
+    } else if url.contains('?') {
+        format!("{url}&{query}")
+    } else {
+        format!("{url}?{query}")
+    }
+}
+
+fn build_auth(command: &CurlCommand) -> RequestAuth {
+    let Some(user) = &command.user else {
+        return RequestAuth::None;
+    };
+    let (username, password) = user.split_once(':').unwrap_or((user, ""));
+    let (username, password) = (username.to_string(), password.to_string());
+    if command.digest {
+        RequestAuth::Digest { username, password }
+    } else {
+        RequestAuth::Basic { username, password }
+    }
+}
+
+/// Parses a curl command line into an endpoint.
+///
+/// The headers that only exist to describe the body, such as `Content-Type` for
+/// URL encoded forms, are kept, since Cartero lets user headers override them.
+pub fn parse_curl(command: &str) -> Result<EndpointData, ImportError> {
+    let command = command.trim();
+    let command = command.strip_prefix("$ ").unwrap_or(command);
+    let command = parse_command(split_words(command)?)?;
+
+    let url = command.url.clone().ok_or(ImportError::MissingUrl)?;
+    let url = if command.get {
+        append_query(&url, &data_as_raw(&command.data))
+    } else {
+        url
+    };
+
+    let method = match &command.method {
+        Some(method) => RequestMethod::try_from(method.as_str())
+            .map_err(|_| ImportError::UnsupportedOption(format!("-X {method}")))?,
+        None if command.head => RequestMethod::Head,
+        None if command.get => RequestMethod::Get,
+        None if !command.data.is_empty()
+            || !command.form.is_empty()
+            || !command.files.is_empty()
+            || command.data_file.is_some() =>
+        {
+            RequestMethod::Post
+        }
+        None => RequestMethod::Get,
+    };
+
+    Ok(EndpointData {
+        url,
+        method,
+        body: build_payload(&command),
+        headers: KeyValueTable::new(&command.headers),
+        auth: build_auth(&command),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_curl, split_words};
+    use crate::entities::{
+        KeyValueTable, MultipartFile, RawEncoding, RequestAuth, RequestMethod, RequestPayload,
+    };
+    use crate::import::ImportError;
+
+    #[test]
+    fn test_split_words_quoting() {
+        let words = split_words("curl 'a b' \"c \\\"d\\\"\" e\\ f \\\n  g").unwrap();
+        assert_eq!(words, vec!["curl", "a b", "c \"d\"", "e f", "g"]);
+        assert_eq!(
+            split_words("curl 'unterminated"),
+            Err(ImportError::UnterminatedQuote)
+        );
+    }
+
+    #[test]
+    fn test_parse_simple_get() {
+        let endpoint = parse_curl("curl https://api.example.com/users").unwrap();
+        assert_eq!(endpoint.url, "https://api.example.com/users");
+        assert_eq!(endpoint.method, RequestMethod::Get);
+        assert_eq!(endpoint.body, RequestPayload::None);
+    }
+
+    #[test]
+    fn test_parse_json_post_with_continuations() {
+        let command = r#"curl -X POST \
+  --url https://api.example.com/users \
+  -H 'Content-Type: application/json' \
+  -H "Accept: application/json" \
+  -d '{"name": "John"}'"#;
+        let endpoint = parse_curl(command).unwrap();
+        assert_eq!(endpoint.method, RequestMethod::Post);
+        assert_eq!(endpoint.url, "https://api.example.com/users");
+        assert_eq!(
+            endpoint.headers,
+            KeyValueTable::new(&[
+                ("Content-Type", "application/json").into(),
+                ("Accept", "application/json").into(),
+            ])
+        );
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::Raw {
+                encoding: RawEncoding::Json,
+                content: Vec::from(r#"{"name": "John"}"#),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_form_data() {
+        let endpoint =
+            parse_curl("curl https://example.com -d 'a=1&b=2' --data-urlencode 'q=hello world'")
+                .unwrap();
+        assert_eq!(endpoint.method, RequestMethod::Post);
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::Urlencoded(KeyValueTable::new(&[
+                ("a", "1").into(),
+                ("b", "2").into(),
+                ("q", "hello world").into(),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_multipart_and_user() {
+        let endpoint =
+            parse_curl("curl -sSL -u admin:secret -F name=cartero -F 'version=0.2' example.com")
+                .unwrap();
+        assert_eq!(endpoint.method, RequestMethod::Post);
+        assert_eq!(
+            endpoint.auth,
+            RequestAuth::Basic {
+                username: "admin".into(),
+                password: "secret".into(),
+            }
+        );
+        assert_eq!(endpoint.headers.header("authorization"), None);
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::Multipart {
+                params: KeyValueTable::new(&[
+                    ("name", "cartero").into(),
+                    ("version", "0.2").into()
+                ]),
+                files: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_form_files() {
+        let endpoint = parse_curl(
+            "curl -F 'photo=@\"my;photo.jpg\";type=image/jpeg' -F doc=@cv.pdf example.com",
+        )
+        .unwrap();
+        let RequestPayload::Multipart { files, .. } = endpoint.body else {
+            panic!("Expected a multipart body");
+        };
+        assert_eq!(
+            files,
+            vec![
+                MultipartFile {
+                    name: "photo".into(),
+                    path: "my;photo.jpg".into(),
+                    content_type: Some("image/jpeg".into()),
+                    ..Default::default()
+                },
+                MultipartFile {
+                    name: "doc".into(),
+                    path: "cv.pdf".into(),
+                    ..Default::default()
+                },
+            ]
+        );
+        assert!(parse_curl("curl -F 'text=<notes.txt' example.com").is_err());
+    }
+
+    #[test]
+    fn test_parse_data_binary_file() {
+        let endpoint = parse_curl("curl --data-binary @photos/cat.jpg example.com").unwrap();
+        assert_eq!(endpoint.method, RequestMethod::Post);
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::File {
+                path: "photos/cat.jpg".into()
+            }
+        );
+        assert!(parse_curl("curl --data-binary @cat.jpg -d a=b example.com").is_err());
+    }
+
+    #[test]
+    fn test_parse_digest_user() {
+        let endpoint = parse_curl("curl --digest --user admin https://example.com").unwrap();
+        assert_eq!(
+            endpoint.auth,
+            RequestAuth::Digest {
+                username: "admin".into(),
+                password: "".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_attached_method_and_get() {
+        let endpoint = parse_curl("curl -XDELETE https://example.com/1").unwrap();
+        assert_eq!(endpoint.method, RequestMethod::Delete);
+
+        let endpoint = parse_curl("curl -G https://example.com/search -d q=rust").unwrap();
+        assert_eq!(endpoint.method, RequestMethod::Get);
+        assert_eq!(endpoint.url, "https://example.com/search?q=rust");
+        assert_eq!(endpoint.body, RequestPayload::None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_curl("wget example.com"), Err(ImportError::NotCurl));
+        assert_eq!(
+            parse_curl("curl -H"),
+            Err(ImportError::MissingValue("-H".into()))
+        );
+        assert_eq!(parse_curl("curl -s"), Err(ImportError::MissingUrl));
+        assert!(matches!(
+            parse_curl("curl --frobnicate example.com"),
+            Err(ImportError::UnsupportedOption(_))
+        ));
+        assert!(parse_curl("curl -d @body.json example.com").is_err());
+    }
+}
@@ -0,0 +1,330 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Importer for HTTP Archive (HAR) files, such as the ones exported by the
+//! network tools of web browsers.
+
+use serde::Deserialize;
+
+use crate::entities::{
+    EndpointData, KeyValue, KeyValueTable, RawEncoding, RequestMethod, RequestPayload,
+};
+
+use super::{ImportError, ImportedCollection, ImportedItem};
+
+/// Headers that are computed when the request is sent and should not be copied.
+const SKIPPED_HEADERS: [&str; 3] = ["content-length", "host", "connection"];
+
+#[derive(Deserialize)]
+struct HarNameValue {
+    name: String,
+    #[serde(default)]
+    value: String,
+    #[serde(default, rename = "fileName")]
+    file_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct HarPostData {
+    #[serde(default, rename = "mimeType")]
+    mime_type: String,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    params: Vec<HarNameValue>,
+}
+
+#[derive(Deserialize)]
+struct HarRequest {
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: Vec<HarNameValue>,
+    #[serde(default, rename = "postData")]
+    post_data: Option<HarPostData>,
+}
+
+#[derive(Deserialize)]
+struct HarEntry {
+    request: HarRequest,
+}
+
+#[derive(Deserialize)]
+struct HarPage {
+    #[serde(default)]
+    title: String,
+}
+
+#[derive(Deserialize)]
+struct HarLog {
+    #[serde(default)]
+    pages: Vec<HarPage>,
+    entries: Vec<HarEntry>,
+}
+
+#[derive(Deserialize)]
+struct HarFile {
+    log: HarLog,
+}
+
+fn parse_body(
+    post_data: &Option<HarPostData>,
+    name: &str,
+    warnings: &mut Vec<String>,
+) -> RequestPayload {
+    let Some(post_data) = post_data else {
+        return RequestPayload::None;
+    };
+    let mime_type = post_data.mime_type.to_lowercase();
+
+    if mime_type.starts_with("multipart/form-data") {
+        let mut params = KeyValueTable::default();
+        for param in &post_data.params {
+            if param.file_name.is_some() {
+                warnings.push(format!("{name}: the file field {} was skipped", param.name));
+                continue;
+            }
+            params.push((param.name.as_str(), param.value.as_str()).into());
+        }
+        return RequestPayload::Multipart {
+            params,
+            files: Vec::new(),
+        };
+    }
+    if mime_type.starts_with("application/x-www-form-urlencoded") {
+        let params: KeyValueTable = if post_data.params.is_empty() {
+            let text = post_data.text.as_deref().unwrap_or("");
+            url::form_urlencoded::parse(text.as_bytes())
+                .map(|(name, value)| KeyValue::from((name.as_ref(), value.as_ref())))
+                .collect()
+        } else {
+            // Browsers store the parameters without decoding them.
+            post_data
+                .params
+                .iter()
+                .map(|param| {
+                    let decode = |text: &str| {
+                        url::form_urlencoded::parse(format!("x={text}").as_bytes())
+                            .next()
+                            .map(|(_, value)| value.to_string())
+                            .unwrap_or_default()
+                    };
+                    KeyValue::from((decode(&param.name), decode(&param.value)))
+                })
+                .collect()
+        };
+        return RequestPayload::Urlencoded(params);
+    }
+
+    match &post_data.text {
+        Some(text) if !text.is_empty() => RequestPayload::Raw {
+            encoding: RawEncoding::from_content_type(&mime_type),
+            content: Vec::from(text.as_str()),
+        },
+        _ => RequestPayload::None,
+    }
+}
+
+/// Names the request after the method and the path of the URL.
+fn entry_name(method: &str, url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(url) => format!(
+            "{method} {}{}",
+            url.host_str().unwrap_or_default(),
+            url.path()
+        ),
+        Err(_) => format!("{method} {url}"),
+    }
+}
+
+/// Parses a HAR file, turning every entry into a request.
+pub fn parse_har(contents: &str) -> Result<ImportedCollection, ImportError> {
+    let file: HarFile = serde_json::from_str(contents)?;
+    let mut warnings = Vec::new();
+    let mut items = Vec::new();
+
+    for entry in &file.log.entries {
+        let request = &entry.request;
+        let name = entry_name(&request.method, &request.url);
+        let method = match RequestMethod::try_from(request.method.as_str()) {
+            Ok(method) => method,
+            Err(_) => {
+                warnings.push(format!(
+                    "{name}: the method {} is not supported",
+                    request.method
+                ));
+                continue;
+            }
+        };
+        let headers: KeyValueTable = request
+            .headers
+            .iter()
+            // HTTP/2 pseudo-headers such as :authority are part of the URL.
+            .filter(|header| !header.name.starts_with(':'))
+            .filter(|header| !SKIPPED_HEADERS.contains(&header.name.to_lowercase().as_str()))
+            .map(|header| KeyValue::from((header.name.as_str(), header.value.as_str())))
+            .collect();
+        let body = parse_body(&request.post_data, &name, &mut warnings);
+
+        items.push(ImportedItem::Request {
+            name,
+            endpoint: Box::new(EndpointData {
+                url: request.url.clone(),
+                method,
+                headers,
+                body,
+                ..Default::default()
+            }),
+        });
+    }
+
+    let name = file
+        .log
+        .pages
+        .first()
+        .map(|page| page.title.clone())
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| "HAR archive".into());
+    Ok(ImportedCollection {
+        name,
+        items,
+        environments: Vec::new(),
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_har;
+    use crate::entities::{KeyValueTable, RawEncoding, RequestMethod, RequestPayload};
+    use crate::import::{ImportError, ImportedItem};
+
+    const ARCHIVE: &str = r#"{
+        "log": {
+            "version": "1.2",
+            "creator": {"name": "Firefox", "version": "128"},
+            "pages": [{"title": "https://example.com/"}],
+            "entries": [
+                {
+                    "request": {
+                        "method": "GET",
+                        "url": "https://example.com/api/users?page=1",
+                        "headers": [
+                            {"name": ":authority", "value": "example.com"},
+                            {"name": "Host", "value": "example.com"},
+                            {"name": "Accept", "value": "application/json"}
+                        ]
+                    },
+                    "response": {"status": 200}
+                },
+                {
+                    "request": {
+                        "method": "POST",
+                        "url": "https://example.com/api/users",
+                        "headers": [{"name": "Content-Length", "value": "15"}],
+                        "postData": {"mimeType": "application/json", "text": "{\"name\":\"john\"}"}
+                    }
+                },
+                {
+                    "request": {
+                        "method": "POST",
+                        "url": "https://example.com/login",
+                        "postData": {
+                            "mimeType": "application/x-www-form-urlencoded",
+                            "params": [{"name": "user", "value": "john%20doe"}]
+                        }
+                    }
+                },
+                {
+                    "request": {
+                        "method": "POST",
+                        "url": "https://example.com/upload",
+                        "postData": {
+                            "mimeType": "multipart/form-data; boundary=x",
+                            "params": [
+                                {"name": "title", "value": "Cat"},
+                                {"name": "photo", "fileName": "cat.png", "contentType": "image/png"}
+                            ]
+                        }
+                    }
+                }
+            ]
+        }
+    }"#;
+
+    fn endpoint(item: &ImportedItem) -> &crate::entities::EndpointData {
+        match item {
+            ImportedItem::Request { endpoint, .. } => endpoint,
+            _ => panic!("Expected a request"),
+        }
+    }
+
+    #[test]
+    fn test_parse_har_entries() {
+        let collection = parse_har(ARCHIVE).unwrap();
+        assert_eq!(collection.name, "https://example.com/");
+        assert_eq!(collection.items.len(), 4);
+
+        let ImportedItem::Request { name, endpoint } = &collection.items[0] else {
+            panic!("Expected a request");
+        };
+        assert_eq!(name, "GET example.com/api/users");
+        assert_eq!(endpoint.url, "https://example.com/api/users?page=1");
+        assert_eq!(
+            endpoint.headers,
+            KeyValueTable::new(&[("Accept", "application/json").into()])
+        );
+    }
+
+    #[test]
+    fn test_parse_har_bodies() {
+        let collection = parse_har(ARCHIVE).unwrap();
+        let json = endpoint(&collection.items[1]);
+        assert_eq!(json.method, RequestMethod::Post);
+        assert!(json.headers.is_empty());
+        assert_eq!(
+            json.body,
+            RequestPayload::Raw {
+                encoding: RawEncoding::Json,
+                content: Vec::from(r#"{"name":"john"}"#),
+            }
+        );
+        assert_eq!(
+            endpoint(&collection.items[2]).body,
+            RequestPayload::Urlencoded(KeyValueTable::new(&[("user", "john doe").into()]))
+        );
+        assert_eq!(
+            endpoint(&collection.items[3]).body,
+            RequestPayload::Multipart {
+                params: KeyValueTable::new(&[("title", "Cat").into()]),
+                files: vec![],
+            }
+        );
+        assert_eq!(
+            collection.warnings,
+            vec!["POST example.com/upload: the file field photo was skipped"]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_har() {
+        assert!(matches!(
+            parse_har(r#"{"entries": []}"#),
+            Err(ImportError::InvalidDocument(_))
+        ));
+    }
+}
@@ -0,0 +1,237 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Conversion of requests written in other formats into Cartero endpoints.
+
+pub mod curl;
+pub mod har;
+pub mod openapi;
+pub mod postman;
+
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::collection::Collection;
+use crate::entities::{EndpointData, Environment};
+use crate::error::CarteroError;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ImportError {
+    #[error("The text is not a curl command")]
+    NotCurl,
+
+    #[error("Unterminated quote in the command")]
+    UnterminatedQuote,
+
+    #[error("Option {0} requires a value")]
+    MissingValue(String),
+
+    #[error("Unsupported option: {0}")]
+    UnsupportedOption(String),
+
+    #[error("The command does not contain an URL")]
+    MissingUrl,
+
+    #[error("The file is not valid: {0}")]
+    InvalidDocument(String),
+
+    #[error("The file is not in a supported format")]
+    UnsupportedFormat,
+}
+
+impl From<serde_json::Error> for ImportError {
+    fn from(value: serde_json::Error) -> Self {
+        ImportError::InvalidDocument(value.to_string())
+    }
+}
+
+/// A request or a folder read from a file written by another application.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportedItem {
+    Folder {
+        name: String,
+        items: Vec<ImportedItem>,
+    },
+    Request {
+        name: String,
+        endpoint: Box<EndpointData>,
+    },
+}
+
+/// A tree of requests read from another application, ready to be written as a collection.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ImportedCollection {
+    pub name: String,
+    pub items: Vec<ImportedItem>,
+    pub environments: Vec<Environment>,
+
+    /// Human readable descriptions of the features that could not be imported.
+    pub warnings: Vec<String>,
+}
+
+/// Turns a name coming from an external file into a valid collection item name.
+fn item_name(name: &str, fallback: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' => '-',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+    let name = name.trim_start_matches('.').trim();
+    if name.is_empty() {
+        fallback.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Appends a counter to the name until nothing in the folder uses it.
+fn unique_name(folder: &Path, name: &str, suffix: &str) -> String {
+    let mut candidate = name.to_string();
+    let mut counter = 2;
+    while folder.join(format!("{candidate}{suffix}")).exists() {
+        candidate = format!("{name} ({counter})");
+        counter += 1;
+    }
+    candidate
+}
+
+impl ImportedCollection {
+    /// Writes the imported items into the given directory.
+    ///
+    /// The directory becomes a new collection unless it already is one, in which
+    /// case the items are added to it. Items never replace existing files; when
+    /// a name is taken, a counter is appended to the new one.
+    pub fn write(&self, root: &Path) -> Result<Collection, CarteroError> {
+        let mut collection = match Collection::open(root) {
+            Ok(collection) => collection,
+            Err(CarteroError::NotACollection) | Err(CarteroError::FileError(_)) => {
+                Collection::create(root, &self.name)?
+            }
+            Err(e) => return Err(e),
+        };
+        let root = collection.root().to_path_buf();
+        write_items(&mut collection, &root, &self.items)?;
+
+        for environment in &self.environments {
+            let name = item_name(&environment.name, "Imported");
+            let name = unique_name(&root, &name, crate::collection::ENVIRONMENT_SUFFIX);
+            let environment = Environment {
+                name,
+                variables: environment.variables.clone(),
+                proxy: environment.proxy.clone(),
+            };
+            collection.save_environment(&environment)?;
+        }
+        Ok(collection)
+    }
+}
+
+fn write_items(
+    collection: &mut Collection,
+    folder: &Path,
+    items: &[ImportedItem],
+) -> Result<(), CarteroError> {
+    let extension = format!(".{}", crate::collection::REQUEST_EXTENSION);
+    for item in items {
+        match item {
+            ImportedItem::Folder { name, items } => {
+                let name = unique_name(folder, &item_name(name, "Untitled folder"), "");
+                let path = collection.create_folder(folder, &name)?;
+                write_items(collection, &path, items)?;
+            }
+            ImportedItem::Request { name, endpoint } => {
+                let name = unique_name(folder, &item_name(name, "Untitled request"), &extension);
+                collection.add_request(folder, &name, endpoint)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Counts the requests in a tree of imported items.
+pub fn count_requests(items: &[ImportedItem]) -> usize {
+    items
+        .iter()
+        .map(|item| match item {
+            ImportedItem::Folder { items, .. } => count_requests(items),
+            ImportedItem::Request { .. } => 1,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{item_name, ImportedCollection, ImportedItem};
+    use crate::entities::{EndpointData, Environment, KeyValueTable};
+
+    #[test]
+    fn test_item_name() {
+        assert_eq!(item_name("Users / Create", "x"), "Users - Create");
+        assert_eq!(item_name("  .hidden ", "x"), "hidden");
+        assert_eq!(item_name("", "Untitled"), "Untitled");
+    }
+
+    #[test]
+    fn test_write_collection() {
+        let dir = std::env::temp_dir().join(format!("cartero-import-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let request = |name: &str| ImportedItem::Request {
+            name: name.into(),
+            endpoint: Box::new(EndpointData {
+                url: "https://example.com".into(),
+                ..Default::default()
+            }),
+        };
+        let imported = ImportedCollection {
+            name: "Imported".into(),
+            items: vec![
+                ImportedItem::Folder {
+                    name: "Users".into(),
+                    items: vec![request("List"), request("List")],
+                },
+                request("Health"),
+            ],
+            environments: vec![Environment {
+                name: "dev".into(),
+                variables: KeyValueTable::new(&[("BASE", "https://example.com").into()]),
+                proxy: None,
+            }],
+            warnings: vec![],
+        };
+
+        let collection = imported.write(&dir).unwrap();
+        assert_eq!(collection.name(), "Imported");
+        assert_eq!(collection.requests().len(), 3);
+        assert!(dir.join("Users").join("List (2).cartero").is_file());
+        assert_eq!(collection.environments(), ["dev"]);
+
+        // Importing again into the same collection keeps the existing items.
+        let collection = imported.write(&dir).unwrap();
+        assert_eq!(collection.requests().len(), 6);
+        assert_eq!(collection.environments(), ["dev", "dev (2)"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,770 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Importer for OpenAPI 3 and Swagger 2 specifications.
+//!
+//! Every operation in the specification becomes a request. Operations are grouped
+//! into folders using their first tag, and the server URL is stored as the
+//! `baseUrl` variable of an environment, so that it can be changed in one place.
+
+use std::cell::RefCell;
+
+use serde_json::{Map, Value};
+
+use crate::entities::{
+    EndpointData, Environment, KeyValue, KeyValueTable, RawEncoding, RequestMethod, RequestPayload,
+};
+
+use super::{ImportError, ImportedCollection, ImportedItem};
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// How deep a schema is expanded when generating an example, to stop recursive schemas.
+const MAX_SCHEMA_DEPTH: usize = 8;
+
+fn parse_document(contents: &str) -> Result<Value, ImportError> {
+    if contents.trim_start().starts_with('{') {
+        Ok(serde_json::from_str(contents)?)
+    } else {
+        serde_yaml::from_str(contents).map_err(|e| ImportError::InvalidDocument(e.to_string()))
+    }
+}
+
+struct Spec<'a> {
+    document: &'a Value,
+    swagger: bool,
+    warnings: RefCell<Vec<String>>,
+}
+
+impl<'a> Spec<'a> {
+    /// Follows a local `$ref`, returning the value itself when it is not a reference.
+    fn resolve(&self, value: &'a Value) -> &'a Value {
+        let mut value = value;
+        // References may point to other references, but never too many times.
+        for _ in 0..MAX_SCHEMA_DEPTH {
+            let Some(reference) = value.get("$ref").and_then(Value::as_str) else {
+                break;
+            };
+            match reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.document.pointer(pointer))
+            {
+                Some(target) => value = target,
+                None => break,
+            }
+        }
+        value
+    }
+
+    fn base_url(&self) -> String {
+        if self.swagger {
+            let host = self.document.get("host").and_then(Value::as_str);
+            let base_path = self
+                .document
+                .get("basePath")
+                .and_then(Value::as_str)
+                .unwrap_or("");
+            let scheme = self
+                .document
+                .pointer("/schemes/0")
+                .and_then(Value::as_str)
+                .unwrap_or("https");
+            return match host {
+                Some(host) => format!("{scheme}://{host}{base_path}"),
+                None => base_path.to_string(),
+            };
+        }
+
+        let Some(server) = self.document.pointer("/servers/0") else {
+            return String::new();
+        };
+        let mut url = server
+            .get("url")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string();
+        if let Some(Value::Object(variables)) = server.get("variables") {
+            for (name, variable) in variables {
+                let default = variable.get("default").map(value_to_string);
+                url = url.replace(&format!("{{{name}}}"), &default.unwrap_or_default());
+            }
+        }
+        url.trim_end_matches('/').to_string()
+    }
+
+    /// Builds an example value out of a schema.
+    fn example(&self, schema: &'a Value, depth: usize) -> Value {
+        let schema = self.resolve(schema);
+        if depth > MAX_SCHEMA_DEPTH {
+            return Value::Null;
+        }
+        if let Some(example) = schema.get("example") {
+            return example.clone();
+        }
+        if let Some(default) = schema.get("default") {
+            return default.clone();
+        }
+        if let Some(value) = schema.pointer("/enum/0") {
+            return value.clone();
+        }
+        if let Some(Value::Array(schemas)) = schema.get("allOf") {
+            let mut merged = Map::new();
+            for schema in schemas {
+                if let Value::Object(object) = self.example(schema, depth + 1) {
+                    merged.extend(object);
+                }
+            }
+            return Value::Object(merged);
+        }
+        for key in ["oneOf", "anyOf"] {
+            if let Some(schema) = schema.get(key).and_then(|schemas| schemas.get(0)) {
+                return self.example(schema, depth + 1);
+            }
+        }
+
+        let kind = match schema.get("type") {
+            Some(Value::String(kind)) => kind.as_str(),
+            // OpenAPI 3.1 allows a list of types, such as ["string", "null"].
+            Some(Value::Array(kinds)) => kinds
+                .iter()
+                .filter_map(Value::as_str)
+                .find(|kind| *kind != "null")
+                .unwrap_or("null"),
+            _ if schema.get("properties").is_some() => "object",
+            _ => "",
+        };
+        match kind {
+            "object" => {
+                let mut object = Map::new();
+                if let Some(Value::Object(properties)) = schema.get("properties") {
+                    for (name, property) in properties {
+                        object.insert(name.clone(), self.example(property, depth + 1));
+                    }
+                }
+                Value::Object(object)
+            }
+            "array" => match schema.get("items") {
+                Some(items) => Value::Array(vec![self.example(items, depth + 1)]),
+                None => Value::Array(vec![]),
+            },
+            "string" => {
+                let format = schema.get("format").and_then(Value::as_str).unwrap_or("");
+                Value::from(match format {
+                    "date" => "2024-01-01",
+                    "date-time" => "2024-01-01T00:00:00Z",
+                    "email" => "user@example.com",
+                    "uuid" => "00000000-0000-0000-0000-000000000000",
+                    "uri" | "url" => "https://example.com",
+                    _ => "string",
+                })
+            }
+            "integer" => Value::from(0),
+            "number" => Value::from(0.0),
+            "boolean" => Value::from(true),
+            _ => Value::Null,
+        }
+    }
+
+    /// The example value for a parameter, which is empty when there is nothing to suggest.
+    fn parameter_example(&self, parameter: &'a Value) -> String {
+        if let Some(example) = parameter.get("example") {
+            return value_to_string(example);
+        }
+        if let Some(example) = parameter
+            .get("examples")
+            .and_then(Value::as_object)
+            .and_then(|examples| examples.values().next())
+            .and_then(|example| self.resolve(example).get("value"))
+        {
+            return value_to_string(example);
+        }
+        // Swagger 2 describes the type of the parameter in the parameter itself.
+        let schema = parameter.get("schema").unwrap_or(parameter);
+        let schema = self.resolve(schema);
+        for key in ["example", "default"] {
+            if let Some(value) = schema.get(key) {
+                return value_to_string(value);
+            }
+        }
+        match schema.pointer("/enum/0") {
+            Some(value) => value_to_string(value),
+            None => String::new(),
+        }
+    }
+
+    /// Merges the parameters of the path with the parameters of the operation.
+    ///
+    /// Parameters defined in the operation replace the ones with the same name and
+    /// location defined in the path.
+    fn parameters(&self, path_item: &'a Value, operation: &'a Value) -> Vec<&'a Value> {
+        let mut parameters: Vec<&Value> = Vec::new();
+        let lists = [path_item.get("parameters"), operation.get("parameters")];
+        for parameter in lists
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_array)
+            .flatten()
+        {
+            let parameter = self.resolve(parameter);
+            let key = (parameter.get("name"), parameter.get("in"));
+            parameters.retain(|other| (other.get("name"), other.get("in")) != key);
+            parameters.push(parameter);
+        }
+        parameters
+    }
+
+    fn body(&self, operation: &'a Value, parameters: &[&'a Value], name: &str) -> RequestPayload {
+        if self.swagger {
+            return self.swagger_body(operation, parameters, name);
+        }
+        let Some(request_body) = operation.get("requestBody") else {
+            return RequestPayload::None;
+        };
+        let request_body = self.resolve(request_body);
+        let Some(Value::Object(content)) = request_body.get("content") else {
+            return RequestPayload::None;
+        };
+
+        let find = |predicate: fn(&str) -> bool| {
+            content
+                .iter()
+                .find(|(mime, _)| predicate(mime.as_str()))
+                .map(|(_, media)| media)
+        };
+        if let Some(media) = find(|mime| mime.contains("json")) {
+            let example = self.media_example(media);
+            return RequestPayload::Raw {
+                encoding: RawEncoding::Json,
+                content: serde_json::to_vec_pretty(&example).unwrap_or_default(),
+            };
+        }
+        if let Some(media) = find(|mime| mime == "application/x-www-form-urlencoded") {
+            let params = self.form_fields(media.get("schema"), name);
+            return RequestPayload::Urlencoded(params);
+        }
+        if let Some(media) = find(|mime| mime == "multipart/form-data") {
+            let params = self.form_fields(media.get("schema"), name);
+            return RequestPayload::Multipart {
+                params,
+                files: Vec::new(),
+            };
+        }
+        if let Some(media) = find(|mime| mime.contains("xml")) {
+            let content = match self.media_example(media) {
+                Value::String(xml) => xml,
+                _ => String::new(),
+            };
+            return RequestPayload::Raw {
+                encoding: RawEncoding::Xml,
+                content: Vec::from(content),
+            };
+        }
+        if let Some(mime) = content.keys().next() {
+            self.warn(name, &format!("{mime} bodies are not supported"));
+        }
+        RequestPayload::None
+    }
+
+    fn media_example(&self, media: &'a Value) -> Value {
+        if let Some(example) = media.get("example") {
+            return example.clone();
+        }
+        if let Some(example) = media
+            .get("examples")
+            .and_then(Value::as_object)
+            .and_then(|examples| examples.values().next())
+            .and_then(|example| self.resolve(example).get("value"))
+        {
+            return example.clone();
+        }
+        match media.get("schema") {
+            Some(schema) => self.example(schema, 0),
+            None => Value::Null,
+        }
+    }
+
+    fn form_fields(&self, schema: Option<&'a Value>, name: &str) -> KeyValueTable {
+        let mut fields = KeyValueTable::default();
+        let Some(schema) = schema else {
+            return fields;
+        };
+        let schema = self.resolve(schema);
+        if let Some(Value::Object(properties)) = schema.get("properties") {
+            for (field, property) in properties {
+                let property = self.resolve(property);
+                if property.get("format").and_then(Value::as_str) == Some("binary") {
+                    self.warn(name, &format!("the file field {field} was skipped"));
+                    continue;
+                }
+                let value = value_to_string(&self.example(property, 0));
+                fields.push((field.as_str(), value.as_str()).into());
+            }
+        }
+        fields
+    }
+
+    fn swagger_body(
+        &self,
+        operation: &'a Value,
+        parameters: &[&'a Value],
+        name: &str,
+    ) -> RequestPayload {
+        let body = parameters
+            .iter()
+            .find(|parameter| parameter.get("in").and_then(Value::as_str) == Some("body"));
+        if let Some(schema) = body.and_then(|body| body.get("schema")) {
+            let example = self.example(schema, 0);
+            return RequestPayload::Raw {
+                encoding: RawEncoding::Json,
+                content: serde_json::to_vec_pretty(&example).unwrap_or_default(),
+            };
+        }
+
+        let mut fields = KeyValueTable::default();
+        let mut multipart = false;
+        for parameter in parameters
+            .iter()
+            .filter(|parameter| parameter.get("in").and_then(Value::as_str) == Some("formData"))
+        {
+            let field = parameter.get("name").and_then(Value::as_str).unwrap_or("");
+            if parameter.get("type").and_then(Value::as_str) == Some("file") {
+                self.warn(name, &format!("the file field {field} was skipped"));
+                multipart = true;
+                continue;
+            }
+            let value = self.parameter_example(parameter);
+            fields.push((field, value.as_str()).into());
+        }
+        let consumes = operation
+            .get("consumes")
+            .or_else(|| self.document.get("consumes"))
+            .and_then(Value::as_array);
+        if consumes.is_some_and(|consumes| {
+            consumes
+                .iter()
+                .any(|mime| mime.as_str() == Some("multipart/form-data"))
+        }) {
+            multipart = true;
+        }
+
+        if fields.is_empty() && !multipart {
+            RequestPayload::None
+        } else if multipart {
+            RequestPayload::Multipart {
+                params: fields,
+                files: Vec::new(),
+            }
+        } else {
+            RequestPayload::Urlencoded(fields)
+        }
+    }
+
+    fn warn(&self, name: &str, message: &str) {
+        self.warnings
+            .borrow_mut()
+            .push(format!("{name}: {message}"));
+    }
+
+    fn operation(
+        &self,
+        path: &str,
+        path_item: &'a Value,
+        method: RequestMethod,
+        operation: &'a Value,
+    ) -> (String, EndpointData) {
+        let method_name = method.as_str();
+        let name = operation
+            .get("operationId")
+            .or_else(|| operation.get("summary"))
+            .and_then(Value::as_str)
+            .map(String::from)
+            .unwrap_or_else(|| format!("{method_name} {path}"));
+
+        let mut url = format!("{{{{baseUrl}}}}{path}");
+        let mut variables = KeyValueTable::default();
+        let mut headers = KeyValueTable::default();
+        let mut query = Vec::new();
+        let parameters = self.parameters(path_item, operation);
+        for parameter in &parameters {
+            let Some(parameter_name) = parameter.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+            let required = parameter.get("required").and_then(Value::as_bool) == Some(true);
+            let example = self.parameter_example(parameter);
+            match parameter.get("in").and_then(Value::as_str) {
+                Some("path") => {
+                    url = url.replace(
+                        &format!("{{{parameter_name}}}"),
+                        &format!("{{{{{parameter_name}}}}}"),
+                    );
+                    variables.push((parameter_name, example.as_str()).into());
+                }
+                Some("query") => query.push((parameter_name.to_string(), example)),
+                Some("header") if required => {
+                    headers.push((parameter_name, example.as_str()).into());
+                }
+                Some("cookie") if required => {
+                    self.warn(&name, &format!("the cookie {parameter_name} was skipped"));
+                }
+                _ => {}
+            }
+        }
+        if !query.is_empty() {
+            let query: Vec<String> = query
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect();
+            url = format!("{url}?{}", query.join("&"));
+        }
+
+        let security = operation
+            .get("security")
+            .or_else(|| self.document.get("security"))
+            .and_then(Value::as_array);
+        if security.is_some_and(|security| !security.is_empty()) {
+            self.warn(&name, "authentication is not supported");
+        }
+
+        let body = self.body(operation, &parameters, &name);
+        let endpoint = EndpointData {
+            url,
+            method,
+            headers,
+            variables,
+            body,
+            ..Default::default()
+        };
+        (name, endpoint)
+    }
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Parses an OpenAPI 3 or Swagger 2 specification, written either in JSON or YAML.
+pub fn parse_openapi(contents: &str) -> Result<ImportedCollection, ImportError> {
+    let document = parse_document(contents)?;
+    let swagger = match (document.get("openapi"), document.get("swagger")) {
+        (Some(Value::String(version)), _) if version.starts_with("3.") => false,
+        (_, Some(Value::String(version))) if version.starts_with("2.") => true,
+        _ => return Err(ImportError::UnsupportedFormat),
+    };
+    let spec = Spec {
+        document: &document,
+        swagger,
+        warnings: RefCell::new(Vec::new()),
+    };
+
+    let title = document
+        .pointer("/info/title")
+        .and_then(Value::as_str)
+        .unwrap_or("OpenAPI")
+        .to_string();
+
+    // Operations are grouped in folders named after their first tag.
+    let mut root = Vec::new();
+    let mut folders: Vec<(String, Vec<ImportedItem>)> = Vec::new();
+    if let Some(Value::Object(paths)) = document.get("paths") {
+        for (path, path_item) in paths {
+            let path_item = spec.resolve(path_item);
+            for method_name in METHODS {
+                let Some(operation) = path_item.get(method_name) else {
+                    continue;
+                };
+                let method = RequestMethod::try_from(method_name).unwrap_or_default();
+                let (name, endpoint) = spec.operation(path, path_item, method, operation);
+                let item = ImportedItem::Request {
+                    name,
+                    endpoint: Box::new(endpoint),
+                };
+                match operation.pointer("/tags/0").and_then(Value::as_str) {
+                    Some(tag) => match folders.iter_mut().find(|(name, _)| name == tag) {
+                        Some((_, items)) => items.push(item),
+                        None => folders.push((tag.to_string(), vec![item])),
+                    },
+                    None => root.push(item),
+                }
+            }
+        }
+    }
+    let mut items: Vec<ImportedItem> = folders
+        .into_iter()
+        .map(|(name, items)| ImportedItem::Folder { name, items })
+        .collect();
+    items.extend(root);
+
+    let environment = Environment {
+        name: title.clone(),
+        variables: KeyValueTable::new(&[KeyValue::from(("baseUrl", spec.base_url().as_str()))]),
+        proxy: None,
+    };
+    let warnings = spec.warnings.into_inner();
+    Ok(ImportedCollection {
+        name: title,
+        items,
+        environments: vec![environment],
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_openapi;
+    use crate::entities::{KeyValueTable, RawEncoding, RequestMethod, RequestPayload};
+    use crate::import::{ImportError, ImportedItem};
+
+    const PETSTORE: &str = r##"
+openapi: 3.0.3
+info:
+  title: Petstore
+  version: 1.0.0
+servers:
+  - url: https://{region}.example.com/v1/
+    variables:
+      region:
+        default: eu
+paths:
+  /pets:
+    get:
+      operationId: listPets
+      tags: [pets]
+      parameters:
+        - name: limit
+          in: query
+          schema:
+            type: integer
+            example: 10
+        - name: X-Request-Id
+          in: header
+          required: true
+          example: abc
+        - name: X-Optional
+          in: header
+    post:
+      operationId: createPet
+      tags: [pets]
+      security:
+        - api_key: []
+      requestBody:
+        content:
+          application/json:
+            schema:
+              $ref: '#/components/schemas/Pet'
+  /pets/{petId}:
+    parameters:
+      - name: petId
+        in: path
+        required: true
+        schema:
+          type: string
+          example: "42"
+    delete:
+      summary: Delete a pet
+      tags: [pets]
+  /health:
+    get:
+      responses: {}
+  /login:
+    post:
+      operationId: login
+      requestBody:
+        content:
+          multipart/form-data:
+            schema:
+              type: object
+              properties:
+                user:
+                  type: string
+                avatar:
+                  type: string
+                  format: binary
+components:
+  schemas:
+    Pet:
+      type: object
+      properties:
+        name:
+          type: string
+          example: Rex
+        tags:
+          type: array
+          items:
+            type: string
+        owner:
+          $ref: '#/components/schemas/Owner'
+    Owner:
+      type: object
+      properties:
+        email:
+          type: string
+          format: email
+        pets:
+          type: array
+          items:
+            $ref: '#/components/schemas/Pet'
+"##;
+
+    fn find<'a>(
+        items: &'a [ImportedItem],
+        name: &str,
+    ) -> Option<&'a crate::entities::EndpointData> {
+        items.iter().find_map(|item| match item {
+            ImportedItem::Request {
+                name: item_name,
+                endpoint,
+            } if item_name == name => Some(endpoint.as_ref()),
+            ImportedItem::Folder { items, .. } => find(items, name),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_parse_openapi_structure() {
+        let collection = parse_openapi(PETSTORE).unwrap();
+        assert_eq!(collection.name, "Petstore");
+        assert_eq!(
+            collection.environments[0].variables,
+            KeyValueTable::new(&[("baseUrl", "https://eu.example.com/v1").into()])
+        );
+        let ImportedItem::Folder { name, items } = &collection.items[0] else {
+            panic!("Expected a folder");
+        };
+        assert_eq!(name, "pets");
+        assert_eq!(items.len(), 3);
+        assert_eq!(collection.items.len(), 3);
+        assert!(find(&collection.items, "GET /health").is_some());
+    }
+
+    #[test]
+    fn test_parse_openapi_parameters() {
+        let collection = parse_openapi(PETSTORE).unwrap();
+        let list = find(&collection.items, "listPets").unwrap();
+        assert_eq!(list.method, RequestMethod::Get);
+        assert_eq!(list.url, "{{baseUrl}}/pets?limit=10");
+        assert_eq!(
+            list.headers,
+            KeyValueTable::new(&[("X-Request-Id", "abc").into()])
+        );
+
+        let delete = find(&collection.items, "Delete a pet").unwrap();
+        assert_eq!(delete.method, RequestMethod::Delete);
+        assert_eq!(delete.url, "{{baseUrl}}/pets/{{petId}}");
+        assert_eq!(
+            delete.variables,
+            KeyValueTable::new(&[("petId", "42").into()])
+        );
+    }
+
+    #[test]
+    fn test_parse_openapi_bodies() {
+        let collection = parse_openapi(PETSTORE).unwrap();
+        let create = find(&collection.items, "createPet").unwrap();
+        let RequestPayload::Raw { encoding, content } = &create.body else {
+            panic!("Expected a raw body");
+        };
+        assert_eq!(*encoding, RawEncoding::Json);
+        let body: serde_json::Value = serde_json::from_slice(content).unwrap();
+        assert_eq!(body["name"], "Rex");
+        assert_eq!(body["tags"][0], "string");
+        assert_eq!(body["owner"]["email"], "user@example.com");
+
+        let login = find(&collection.items, "login").unwrap();
+        assert_eq!(
+            login.body,
+            RequestPayload::Multipart {
+                params: KeyValueTable::new(&[("user", "string").into()]),
+                files: vec![],
+            }
+        );
+        assert_eq!(
+            collection.warnings,
+            vec![
+                "login: the file field avatar was skipped",
+                "createPet: authentication is not supported",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_swagger() {
+        let spec = r#"{
+            "swagger": "2.0",
+            "info": {"title": "Legacy", "version": "1"},
+            "host": "legacy.example.com",
+            "basePath": "/api",
+            "schemes": ["http"],
+            "paths": {
+                "/items/{id}": {
+                    "put": {
+                        "operationId": "updateItem",
+                        "parameters": [
+                            {"name": "id", "in": "path", "required": true, "type": "integer", "default": 1},
+                            {"name": "body", "in": "body", "schema": {"type": "object", "properties": {"done": {"type": "boolean"}}}}
+                        ]
+                    }
+                },
+                "/items": {
+                    "post": {
+                        "operationId": "addItem",
+                        "parameters": [
+                            {"name": "title", "in": "formData", "type": "string"}
+                        ]
+                    }
+                }
+            }
+        }"#;
+        let collection = parse_openapi(spec).unwrap();
+        assert_eq!(
+            collection.environments[0].variables,
+            KeyValueTable::new(&[("baseUrl", "http://legacy.example.com/api").into()])
+        );
+        let update = find(&collection.items, "updateItem").unwrap();
+        assert_eq!(update.url, "{{baseUrl}}/items/{{id}}");
+        assert_eq!(update.variables, KeyValueTable::new(&[("id", "1").into()]));
+        assert_eq!(
+            update.body,
+            RequestPayload::Raw {
+                encoding: RawEncoding::Json,
+                content: Vec::from("{\n  \"done\": true\n}"),
+            }
+        );
+        let add = find(&collection.items, "addItem").unwrap();
+        assert_eq!(
+            add.body,
+            RequestPayload::Urlencoded(KeyValueTable::new(&[("title", "").into()]))
+        );
+    }
+
+    #[test]
+    fn test_rejects_unknown_documents() {
+        assert_eq!(
+            parse_openapi("openapi: 2.5.0\ninfo: {}"),
+            Err(ImportError::UnsupportedFormat)
+        );
+        assert!(matches!(
+            parse_openapi("openapi: [unterminated"),
+            Err(ImportError::InvalidDocument(_))
+        ));
+    }
+}
@@ -0,0 +1,817 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Importer for the collections and environments exported by Postman.
+//!
+//! Only the version 2.1 of the collection format is officially supported, although
+//! the version 2.0 is similar enough to be read as well.
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::entities::{
+    ApiKeyLocation, EndpointData, Environment, KeyValue, KeyValueTable, MultipartFile,
+    OAuth2Config, OAuth2Grant, RawEncoding, RequestAuth, RequestMethod, RequestPayload,
+};
+
+use super::{ImportError, ImportedCollection, ImportedItem};
+
+#[derive(Deserialize)]
+struct PostmanInfo {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    schema: String,
+}
+
+#[derive(Deserialize, Default)]
+struct PostmanKeyValue {
+    #[serde(default)]
+    key: String,
+    #[serde(default)]
+    value: Value,
+    #[serde(default)]
+    disabled: bool,
+    #[serde(default)]
+    enabled: Option<bool>,
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+    #[serde(default)]
+    src: Option<Value>,
+    #[serde(default, rename = "contentType")]
+    content_type: Option<String>,
+}
+
+impl PostmanKeyValue {
+    fn value_string(&self) -> String {
+        match &self.value {
+            Value::Null => String::new(),
+            Value::String(value) => value.clone(),
+            value => value.to_string(),
+        }
+    }
+
+    fn active(&self) -> bool {
+        !self.disabled && self.enabled.unwrap_or(true)
+    }
+}
+
+impl From<&PostmanKeyValue> for KeyValue {
+    fn from(value: &PostmanKeyValue) -> Self {
+        KeyValue {
+            name: value.key.clone(),
+            value: value.value_string(),
+            active: value.active(),
+            secret: value.kind.as_deref() == Some("secret"),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct PostmanBody {
+    #[serde(default)]
+    mode: Option<String>,
+    #[serde(default)]
+    raw: Option<String>,
+    #[serde(default)]
+    urlencoded: Vec<PostmanKeyValue>,
+    #[serde(default)]
+    formdata: Vec<PostmanKeyValue>,
+    #[serde(default)]
+    file: Option<Value>,
+    #[serde(default)]
+    graphql: Option<Value>,
+    #[serde(default)]
+    options: Option<Value>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+#[derive(Deserialize)]
+struct PostmanRequest {
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    url: Option<Value>,
+    #[serde(default)]
+    header: Option<Value>,
+    #[serde(default)]
+    body: Option<PostmanBody>,
+    #[serde(default)]
+    auth: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct PostmanEvent {
+    #[serde(default)]
+    listen: String,
+    #[serde(default)]
+    disabled: bool,
+}
+
+#[derive(Deserialize)]
+struct PostmanItem {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    item: Option<Vec<PostmanItem>>,
+    #[serde(default)]
+    request: Option<Value>,
+    #[serde(default)]
+    event: Vec<PostmanEvent>,
+    #[serde(default)]
+    auth: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct PostmanCollection {
+    info: PostmanInfo,
+    #[serde(default)]
+    item: Vec<PostmanItem>,
+    #[serde(default)]
+    variable: Vec<PostmanKeyValue>,
+    #[serde(default)]
+    event: Vec<PostmanEvent>,
+    #[serde(default)]
+    auth: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct PostmanEnvironment {
+    #[serde(default)]
+    name: String,
+    values: Vec<PostmanKeyValue>,
+}
+
+/// Collects the warnings, prefixing them with the location of the item in the tree.
+///
+/// It also tracks the authentication inherited from the parent folders, since Cartero
+/// stores the authentication on every request.
+struct Context {
+    path: Vec<String>,
+    warnings: Vec<String>,
+    auth: RequestAuth,
+}
+
+/// Reads an attribute of an authentication scheme. Collections v2.1 store them as a
+/// list of key-value pairs, while collections v2.0 store them as an object.
+fn auth_attribute(auth: &Value, kind: &str, key: &str) -> String {
+    let value = match auth.get(kind) {
+        Some(Value::Array(attributes)) => attributes
+            .iter()
+            .find(|attribute| attribute.get("key").and_then(Value::as_str) == Some(key))
+            .and_then(|attribute| attribute.get("value")),
+        Some(Value::Object(attributes)) => attributes.get(key),
+        _ => None,
+    };
+    match value {
+        Some(Value::String(value)) => value.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(value) => value.to_string(),
+    }
+}
+
+impl Context {
+    fn warn(&mut self, message: &str) {
+        if self.path.is_empty() {
+            self.warnings.push(message.to_string());
+        } else {
+            self.warnings
+                .push(format!("{}: {message}", self.path.join(" / ")));
+        }
+    }
+
+    fn check_events(&mut self, events: &[PostmanEvent]) {
+        for event in events.iter().filter(|event| !event.disabled) {
+            match event.listen.as_str() {
+                "prerequest" => self.warn("pre-request scripts are not supported"),
+                "test" => self.warn("test scripts are not supported"),
+                other => self.warn(&format!("{other} scripts are not supported")),
+            }
+        }
+    }
+
+    /// Returns the authentication declared by an item, or None if it inherits the
+    /// authentication of its parent.
+    fn parse_auth(&mut self, auth: &Option<Value>) -> Option<RequestAuth> {
+        let auth = auth.as_ref()?;
+        let kind = auth.get("type").and_then(Value::as_str)?;
+        let attribute = |key: &str| auth_attribute(auth, kind, key);
+        match kind {
+            "inherit" => None,
+            "noauth" => Some(RequestAuth::None),
+            "basic" => Some(RequestAuth::Basic {
+                username: attribute("username"),
+                password: attribute("password"),
+            }),
+            "digest" => Some(RequestAuth::Digest {
+                username: attribute("username"),
+                password: attribute("password"),
+            }),
+            "bearer" => Some(RequestAuth::Bearer {
+                token: attribute("token"),
+            }),
+            "apikey" => Some(RequestAuth::ApiKey {
+                name: attribute("key"),
+                value: attribute("value"),
+                location: match attribute("in").as_str() {
+                    "query" => ApiKeyLocation::Query,
+                    _ => ApiKeyLocation::Header,
+                },
+            }),
+            "oauth2" => {
+                let grant = match attribute("grant_type").as_str() {
+                    "" | "client_credentials" => OAuth2Grant::ClientCredentials,
+                    "authorization_code" | "authorization_code_with_pkce" => {
+                        OAuth2Grant::AuthorizationCode
+                    }
+                    grant => {
+                        self.warn(&format!("the OAuth 2.0 grant {grant} is not supported"));
+                        return Some(RequestAuth::None);
+                    }
+                };
+                Some(RequestAuth::OAuth2(OAuth2Config {
+                    grant,
+                    token_url: attribute("accessTokenUrl"),
+                    authorization_url: attribute("authUrl"),
+                    client_id: attribute("clientId"),
+                    client_secret: attribute("clientSecret"),
+                    scope: attribute("scope"),
+                }))
+            }
+            kind => {
+                self.warn(&format!("{kind} authentication is not supported"));
+                Some(RequestAuth::None)
+            }
+        }
+    }
+}
+
+fn parse_url(url: &Option<Value>) -> String {
+    match url {
+        Some(Value::String(url)) => url.clone(),
+        Some(Value::Object(url)) => {
+            if let Some(raw) = url.get("raw").and_then(Value::as_str) {
+                return raw.to_string();
+            }
+            let join = |key: &str, separator: &str| match url.get(key) {
+                Some(Value::Array(parts)) => parts
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<&str>>()
+                    .join(separator),
+                Some(Value::String(part)) => part.clone(),
+                _ => String::new(),
+            };
+            let mut result = String::new();
+            if let Some(protocol) = url.get("protocol").and_then(Value::as_str) {
+                result.push_str(&format!("{protocol}://"));
+            }
+            result.push_str(&join("host", "."));
+            let path = join("path", "/");
+            if !path.is_empty() {
+                result.push('/');
+                result.push_str(&path);
+            }
+            result
+        }
+        _ => String::new(),
+    }
+}
+
+fn parse_headers(headers: &Option<Value>) -> Result<KeyValueTable, ImportError> {
+    match headers {
+        Some(Value::Array(_)) => {
+            let headers: Vec<PostmanKeyValue> =
+                serde_json::from_value(headers.clone().unwrap_or_default())?;
+            Ok(headers.iter().map(KeyValue::from).collect())
+        }
+        // Older exports store the headers as a single block of text.
+        Some(Value::String(block)) => Ok(block
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim(), value.trim()).into())
+            .collect()),
+        _ => Ok(KeyValueTable::default()),
+    }
+}
+
+fn raw_encoding(body: &PostmanBody, headers: &KeyValueTable) -> RawEncoding {
+    let language = body
+        .options
+        .as_ref()
+        .and_then(|options| options.pointer("/raw/language"))
+        .and_then(Value::as_str);
+    let content_type = headers
+        .header("Content-Type")
+        .and_then(|values| values.first().cloned());
+    match (language, content_type) {
+        (Some("json"), _) => RawEncoding::Json,
+        (Some("xml"), _) => RawEncoding::Xml,
+        (Some("html"), _) => RawEncoding::Html,
+        (Some("javascript"), _) => RawEncoding::JavaScript,
+        (_, Some(content_type)) => RawEncoding::from_content_type(content_type),
+        (Some("text"), None) => RawEncoding::Text,
+        _ => RawEncoding::OctetStream,
+    }
+}
+
+fn parse_body(
+    body: &Option<PostmanBody>,
+    headers: &KeyValueTable,
+    context: &mut Context,
+) -> RequestPayload {
+    let Some(body) = body else {
+        return RequestPayload::None;
+    };
+    if body.disabled {
+        return RequestPayload::None;
+    }
+    match body.mode.as_deref() {
+        None => RequestPayload::None,
+        Some("raw") => match &body.raw {
+            Some(raw) if !raw.is_empty() => RequestPayload::Raw {
+                encoding: raw_encoding(body, headers),
+                content: Vec::from(raw.as_str()),
+            },
+            _ => RequestPayload::None,
+        },
+        Some("urlencoded") => {
+            RequestPayload::Urlencoded(body.urlencoded.iter().map(KeyValue::from).collect())
+        }
+        Some("formdata") => {
+            let mut params = KeyValueTable::default();
+            let mut files = Vec::new();
+            for field in &body.formdata {
+                if field.kind.as_deref() != Some("file") && field.src.is_none() {
+                    params.push(KeyValue::from(field));
+                    continue;
+                }
+                // A file field may hold a single path or a list of them.
+                let paths: Vec<&str> = match &field.src {
+                    Some(Value::String(path)) => vec![path],
+                    Some(Value::Array(paths)) => paths.iter().filter_map(Value::as_str).collect(),
+                    _ => vec![],
+                };
+                if paths.is_empty() {
+                    context.warn(&format!("the file field {} has no file", field.key));
+                }
+                files.extend(paths.into_iter().map(|path| MultipartFile {
+                    name: field.key.clone(),
+                    path: path.to_string(),
+                    content_type: field.content_type.clone(),
+                    active: field.active(),
+                    ..Default::default()
+                }));
+            }
+            RequestPayload::Multipart { params, files }
+        }
+        Some("file") => match body.file.as_ref().and_then(|file| file["src"].as_str()) {
+            Some(path) if !path.is_empty() => RequestPayload::File {
+                path: path.to_string(),
+            },
+            _ => {
+                context.warn("the file body has no file");
+                RequestPayload::None
+            }
+        },
+        Some("graphql") => {
+            let graphql = body.graphql.clone().unwrap_or_default();
+            RequestPayload::GraphQL {
+                query: graphql["query"].as_str().unwrap_or_default().to_string(),
+                variables: graphql["variables"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                operation_name: None,
+            }
+        }
+        Some(mode) => {
+            context.warn(&format!("{mode} bodies are not supported"));
+            RequestPayload::None
+        }
+    }
+}
+
+fn parse_request(request: &Value, context: &mut Context) -> Result<EndpointData, ImportError> {
+    // A request can also be written as a plain URL.
+    let request: PostmanRequest = match request {
+        Value::String(url) => PostmanRequest {
+            method: None,
+            url: Some(Value::String(url.clone())),
+            header: None,
+            body: None,
+            auth: None,
+        },
+        request => serde_json::from_value(request.clone())?,
+    };
+
+    let auth = context
+        .parse_auth(&request.auth)
+        .unwrap_or_else(|| context.auth.clone());
+    let method = request.method.as_deref().unwrap_or("GET");
+    let method = match RequestMethod::try_from(method) {
+        Ok(method) => method,
+        Err(_) => {
+            context.warn(&format!("the method {method} is not supported, using GET"));
+            RequestMethod::Get
+        }
+    };
+    let headers = parse_headers(&request.header)?;
+    let body = parse_body(&request.body, &headers, context);
+    let url = parse_url(&request.url);
+    if url.contains("{{$") {
+        context.warn("dynamic variables such as {{$guid}} are not supported");
+    }
+
+    Ok(EndpointData {
+        url,
+        method,
+        headers,
+        body,
+        auth,
+        ..Default::default()
+    })
+}
+
+fn parse_items(
+    items: &[PostmanItem],
+    context: &mut Context,
+) -> Result<Vec<ImportedItem>, ImportError> {
+    let mut result = Vec::new();
+    for item in items {
+        context.path.push(item.name.clone());
+        context.check_events(&item.event);
+        let inherited = context.auth.clone();
+        if let Some(auth) = context.parse_auth(&item.auth) {
+            context.auth = auth;
+        }
+        if let Some(children) = &item.item {
+            result.push(ImportedItem::Folder {
+                name: item.name.clone(),
+                items: parse_items(children, context)?,
+            });
+        } else if let Some(request) = &item.request {
+            result.push(ImportedItem::Request {
+                name: item.name.clone(),
+                endpoint: Box::new(parse_request(request, context)?),
+            });
+        }
+        context.auth = inherited;
+        context.path.pop();
+    }
+    Ok(result)
+}
+
+fn parse_collection(value: Value) -> Result<ImportedCollection, ImportError> {
+    let collection: PostmanCollection = serde_json::from_value(value)?;
+    let schema = &collection.info.schema;
+    if !schema.contains("/v2.1.") && !schema.contains("/v2.0.") {
+        return Err(ImportError::UnsupportedFormat);
+    }
+
+    let mut context = Context {
+        path: Vec::new(),
+        warnings: Vec::new(),
+        auth: RequestAuth::None,
+    };
+    context.check_events(&collection.event);
+    if let Some(auth) = context.parse_auth(&collection.auth) {
+        context.auth = auth;
+    }
+    let items = parse_items(&collection.item, &mut context)?;
+
+    // Cartero has no collection variables, so they become an environment.
+    let mut environments = Vec::new();
+    if !collection.variable.is_empty() {
+        environments.push(Environment {
+            name: collection.info.name.clone(),
+            variables: collection.variable.iter().map(KeyValue::from).collect(),
+            proxy: None,
+        });
+        context.warn("collection variables were imported as an environment");
+    }
+
+    Ok(ImportedCollection {
+        name: collection.info.name,
+        items,
+        environments,
+        warnings: context.warnings,
+    })
+}
+
+fn parse_environment(value: Value) -> Result<ImportedCollection, ImportError> {
+    let environment: PostmanEnvironment = serde_json::from_value(value)?;
+    Ok(ImportedCollection {
+        name: environment.name.clone(),
+        environments: vec![Environment {
+            name: environment.name,
+            variables: environment.values.iter().map(KeyValue::from).collect(),
+            proxy: None,
+        }],
+        ..Default::default()
+    })
+}
+
+/// Parses a Postman collection or environment export.
+///
+/// Environments are returned as a collection without items, so that both kinds
+/// of files can be handled in the same way.
+pub fn parse_postman(contents: &str) -> Result<ImportedCollection, ImportError> {
+    let value: Value = serde_json::from_str(contents)?;
+    if value.get("info").is_some() {
+        parse_collection(value)
+    } else if value.get("values").is_some() {
+        parse_environment(value)
+    } else {
+        Err(ImportError::UnsupportedFormat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_postman;
+    use crate::entities::{
+        KeyValue, KeyValueTable, MultipartFile, RawEncoding, RequestAuth, RequestMethod,
+        RequestPayload,
+    };
+    use crate::import::{ImportError, ImportedItem};
+
+    const COLLECTION: &str = r#"{
+        "info": {
+            "name": "Sample API",
+            "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"
+        },
+        "item": [
+            {
+                "name": "Users",
+                "item": [
+                    {
+                        "name": "Create user",
+                        "event": [{"listen": "test", "script": {"exec": ["pm.test()"]}}],
+                        "request": {
+                            "method": "POST",
+                            "header": [
+                                {"key": "Content-Type", "value": "application/json"},
+                                {"key": "X-Debug", "value": "1", "disabled": true}
+                            ],
+                            "body": {
+                                "mode": "raw",
+                                "raw": "{\"name\": \"{{name}}\"}",
+                                "options": {"raw": {"language": "json"}}
+                            },
+                            "url": {
+                                "raw": "{{baseUrl}}/users",
+                                "host": ["{{baseUrl}}"],
+                                "path": ["users"]
+                            }
+                        }
+                    }
+                ]
+            },
+            {
+                "name": "Login",
+                "request": {
+                    "method": "POST",
+                    "auth": {
+                        "type": "bearer",
+                        "bearer": [{"key": "token", "value": "{{token}}", "type": "string"}]
+                    },
+                    "body": {
+                        "mode": "formdata",
+                        "formdata": [
+                            {"key": "user", "value": "john", "type": "text"},
+                            {"key": "avatar", "type": "file", "src": "/tmp/a.png"}
+                        ]
+                    },
+                    "url": "{{baseUrl}}/login"
+                }
+            },
+            {
+                "name": "Search",
+                "request": {
+                    "method": "GET",
+                    "auth": {"type": "noauth"},
+                    "body": {
+                        "mode": "urlencoded",
+                        "urlencoded": [{"key": "q", "value": "rust"}]
+                    },
+                    "url": "{{baseUrl}}/search"
+                }
+            }
+        ],
+        "variable": [{"key": "baseUrl", "value": "https://example.com"}],
+        "auth": {
+            "type": "basic",
+            "basic": {"username": "admin", "password": "{{password}}"}
+        }
+    }"#;
+
+    #[test]
+    fn test_parse_collection_tree() {
+        let collection = parse_postman(COLLECTION).unwrap();
+        assert_eq!(collection.name, "Sample API");
+        assert_eq!(collection.items.len(), 3);
+
+        let ImportedItem::Folder { name, items } = &collection.items[0] else {
+            panic!("Expected a folder");
+        };
+        assert_eq!(name, "Users");
+        let ImportedItem::Request { name, endpoint } = &items[0] else {
+            panic!("Expected a request");
+        };
+        assert_eq!(name, "Create user");
+        assert_eq!(endpoint.method, RequestMethod::Post);
+        assert_eq!(endpoint.url, "{{baseUrl}}/users");
+        assert_eq!(endpoint.headers.len(), 2);
+        assert!(!endpoint.headers[1].active);
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::Raw {
+                encoding: RawEncoding::Json,
+                content: Vec::from(r#"{"name": "{{name}}"}"#),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_collection_bodies() {
+        let collection = parse_postman(COLLECTION).unwrap();
+        let ImportedItem::Request { endpoint, .. } = &collection.items[1] else {
+            panic!("Expected a request");
+        };
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::Multipart {
+                params: KeyValueTable::new(&[("user", "john").into()]),
+                files: vec![MultipartFile {
+                    name: "avatar".into(),
+                    path: "/tmp/a.png".into(),
+                    ..Default::default()
+                }],
+            }
+        );
+        let ImportedItem::Request { endpoint, .. } = &collection.items[2] else {
+            panic!("Expected a request");
+        };
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::Urlencoded(KeyValueTable::new(&[("q", "rust").into()]))
+        );
+    }
+
+    #[test]
+    fn test_parse_file_and_graphql_bodies() {
+        let contents = r#"{
+            "info": {
+                "name": "Uploads",
+                "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"
+            },
+            "item": [
+                {
+                    "name": "Upload",
+                    "request": {
+                        "method": "PUT",
+                        "body": {"mode": "file", "file": {"src": "/tmp/logo.png"}},
+                        "url": "https://example.com/logo"
+                    }
+                },
+                {
+                    "name": "Query",
+                    "request": {
+                        "method": "POST",
+                        "body": {
+                            "mode": "graphql",
+                            "graphql": {"query": "{ me { id } }", "variables": "{}"}
+                        },
+                        "url": "https://example.com/graphql"
+                    }
+                }
+            ]
+        }"#;
+        let collection = parse_postman(contents).unwrap();
+        let ImportedItem::Request { endpoint, .. } = &collection.items[0] else {
+            panic!("Expected a request");
+        };
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::File {
+                path: "/tmp/logo.png".into()
+            }
+        );
+        let ImportedItem::Request { endpoint, .. } = &collection.items[1] else {
+            panic!("Expected a request");
+        };
+        assert_eq!(
+            endpoint.body,
+            RequestPayload::GraphQL {
+                query: "{ me { id } }".into(),
+                variables: "{}".into(),
+                operation_name: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_inherited_auth() {
+        let collection = parse_postman(COLLECTION).unwrap();
+        let ImportedItem::Folder { items, .. } = &collection.items[0] else {
+            panic!("Expected a folder");
+        };
+        let ImportedItem::Request { endpoint, .. } = &items[0] else {
+            panic!("Expected a request");
+        };
+        assert_eq!(
+            endpoint.auth,
+            RequestAuth::Basic {
+                username: "admin".into(),
+                password: "{{password}}".into(),
+            }
+        );
+        let ImportedItem::Request { endpoint, .. } = &collection.items[1] else {
+            panic!("Expected a request");
+        };
+        assert_eq!(
+            endpoint.auth,
+            RequestAuth::Bearer {
+                token: "{{token}}".into(),
+            }
+        );
+        let ImportedItem::Request { endpoint, .. } = &collection.items[2] else {
+            panic!("Expected a request");
+        };
+        assert_eq!(endpoint.auth, RequestAuth::None);
+    }
+
+    #[test]
+    fn test_reports_unsupported_features() {
+        let collection = parse_postman(COLLECTION).unwrap();
+        assert_eq!(
+            collection.warnings,
+            vec![
+                "Users / Create user: test scripts are not supported",
+                "collection variables were imported as an environment",
+            ]
+        );
+        assert_eq!(collection.environments.len(), 1);
+        assert_eq!(
+            collection.environments[0].variables,
+            KeyValueTable::new(&[("baseUrl", "https://example.com").into()])
+        );
+    }
+
+    #[test]
+    fn test_parse_environment() {
+        let contents = r#"{
+            "name": "Production",
+            "values": [
+                {"key": "baseUrl", "value": "https://api.example.com", "enabled": true},
+                {"key": "token", "value": "s3cret", "type": "secret", "enabled": true},
+                {"key": "old", "value": "x", "enabled": false}
+            ],
+            "_postman_variable_scope": "environment"
+        }"#;
+        let collection = parse_postman(contents).unwrap();
+        assert!(collection.items.is_empty());
+        let environment = &collection.environments[0];
+        assert_eq!(environment.name, "Production");
+        assert_eq!(
+            environment.variables[1],
+            KeyValue {
+                name: "token".into(),
+                value: "s3cret".into(),
+                active: true,
+                secret: true,
+            }
+        );
+        assert!(!environment.variables[2].active);
+    }
+
+    #[test]
+    fn test_rejects_unknown_files() {
+        assert_eq!(
+            parse_postman(r#"{"openapi": "3.0.0"}"#),
+            Err(ImportError::UnsupportedFormat)
+        );
+        let old = r#"{"info": {"name": "x", "schema": "https://schema.getpostman.com/json/collection/v1.0.0/collection.json"}}"#;
+        assert_eq!(parse_postman(old), Err(ImportError::UnsupportedFormat));
+        assert!(matches!(
+            parse_postman("{ not json"),
+            Err(ImportError::InvalidDocument(_))
+        ));
+    }
+}
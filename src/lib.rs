@@ -0,0 +1,34 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The parts of Cartero that do not depend on GTK.
+//!
+//! This includes the file formats, the entities and the HTTP client, so that
+//! they can be shared between the graphical application and the command line
+//! runner.
+
+pub mod assertions;
+pub mod client;
+pub mod collection;
+pub mod entities;
+pub mod error;
+pub mod export;
+pub mod extraction;
+pub mod file;
+pub mod history;
+pub mod import;
+pub mod runner;
@@ -18,13 +18,9 @@
 #![windows_subsystem = "windows"]
 
 mod app;
-mod client;
-mod error;
-mod file;
 mod widgets;
 #[rustfmt::skip]
 mod config;
-mod entities;
 mod objects;
 mod utils;
 mod win;
@@ -35,6 +31,11 @@ use gettextrs::LocaleCategory;
 use gtk::gio;
 use gtk::prelude::*;
 
+use cartero::{
+    assertions, client, collection, entities, error, export, extraction, file, history, import,
+    runner,
+};
+
 use self::app::CarteroApplication;
 use self::config::{APP_ID, GETTEXT_PACKAGE};
 
@@ -0,0 +1,81 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use glib::Object;
+use gtk::gio;
+use gtk::prelude::*;
+
+use crate::collection::CollectionNode;
+
+mod imp {
+    use std::cell::RefCell;
+
+    use glib::Properties;
+    use gtk::gio;
+    use gtk::glib;
+    use gtk::glib::prelude::*;
+    use gtk::glib::subclass::prelude::*;
+
+    #[derive(Default, Debug, Properties)]
+    #[properties(wrapper_type = super::CollectionItem)]
+    pub struct CollectionItem {
+        #[property(get, set)]
+        name: RefCell<String>,
+        #[property(get, set)]
+        path: RefCell<String>,
+        #[property(get, set)]
+        folder: RefCell<bool>,
+        #[property(get, set, nullable)]
+        children: RefCell<Option<gio::ListStore>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for CollectionItem {
+        const NAME: &'static str = "CarteroCollectionItem";
+        type Type = super::CollectionItem;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for CollectionItem {}
+}
+
+glib::wrapper! {
+    pub struct CollectionItem(ObjectSubclass<imp::CollectionItem>);
+}
+
+impl CollectionItem {
+    pub fn file(&self) -> gio::File {
+        gio::File::for_path(self.path())
+    }
+}
+
+impl From<&CollectionNode> for CollectionItem {
+    fn from(value: &CollectionNode) -> Self {
+        let item: Self = Object::builder()
+            .property("name", value.name())
+            .property("path", value.path().display().to_string())
+            .property("folder", value.is_folder())
+            .build();
+        if let CollectionNode::Folder { children, .. } = value {
+            let store = gio::ListStore::with_type(CollectionItem::static_type());
+            let items: Vec<CollectionItem> = children.iter().map(CollectionItem::from).collect();
+            store.extend_from_slice(&items);
+            item.set_children(Some(store));
+        }
+        item
+    }
+}
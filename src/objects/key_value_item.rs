@@ -126,6 +126,17 @@ impl From<KeyValue> for KeyValueItem {
     }
 }
 
+impl From<KeyValueItem> for KeyValue {
+    fn from(value: KeyValueItem) -> Self {
+        Self {
+            name: value.header_name().clone(),
+            value: value.header_value().clone(),
+            active: value.active(),
+            secret: value.secret(),
+        }
+    }
+}
+
 impl From<(&str, &str)> for KeyValueItem {
     fn from(value: (&str, &str)) -> Self {
         let header = Self::new();
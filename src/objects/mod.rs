@@ -15,6 +15,8 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+mod collection_item;
 mod key_value_item;
 
+pub use collection_item::CollectionItem;
 pub use key_value_item::KeyValueItem;
@@ -0,0 +1,658 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Sends a sequence of requests, such as every request in a folder of a
+//! collection, several times in a row.
+//!
+//! The runner does not send anything by itself, so that both the graphical
+//! application and the command line can drive it with their own HTTP client:
+//! they ask for the next step, send the request that [`Runner::prepare`] gives
+//! back and report the outcome with [`Runner::complete`].
+//!
+//! Values extracted from a response are kept in memory for the rest of the run,
+//! where they take precedence over the variables of the environment and of the
+//! data file.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::assertions::{evaluate_all, json_to_text, AssertionResult};
+use crate::client::BoundRequest;
+use crate::collection::Collection;
+use crate::entities::{
+    Assertion, Environment, Extraction, ItemData, KeyValue, KeyValueTable, ResponseData,
+};
+use crate::error::CarteroError;
+use crate::extraction::{extract_all, ExtractionResult};
+
+/// How long a run waits for a response before it gives up on the request.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Server-Sent Events streams may never end, so a run only listens to them for
+/// this long before it goes on with the next request.
+pub const STREAM_DURATION: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RunnerError {
+    #[error("Data files must be CSV or JSON files")]
+    UnsupportedDataFile,
+
+    #[error("Invalid CSV data at line {0}")]
+    InvalidCsv(usize),
+
+    #[error("JSON data must be an array of objects")]
+    InvalidJson,
+}
+
+/// A request that is part of a run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunnerItem {
+    /// The name shown in the results.
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl RunnerItem {
+    pub fn new(name: &str, path: &Path) -> Self {
+        Self {
+            name: name.to_string(),
+            path: path.to_path_buf(),
+        }
+    }
+}
+
+/// Lists the HTTP requests placed in the given folder of the collection, in the
+/// order of the collection tree. WebSocket connections are left out.
+///
+/// Requests are named after their path in the collection.
+pub fn collection_items(
+    collection: &Collection,
+    folder: &Path,
+) -> Result<Vec<RunnerItem>, CarteroError> {
+    let folder = folder.canonicalize()?;
+    let mut items = Vec::new();
+    for path in collection.requests() {
+        if !path.starts_with(&folder) {
+            continue;
+        }
+        let contents = std::fs::read_to_string(&path)?;
+        // Files that cannot be read are kept, so that the run reports the error.
+        if let Ok(ItemData::WebSocket(_)) = crate::file::parse_item(&contents) {
+            continue;
+        }
+        let relative = path.strip_prefix(collection.root()).unwrap_or(&path);
+        let name = relative.with_extension("");
+        items.push(RunnerItem::new(&name.to_string_lossy(), &path));
+    }
+    Ok(items)
+}
+
+/// Settings of a run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunnerOptions {
+    /// How many times every request is sent. When it is not set, the requests
+    /// are sent once for every row of the data file, or just once without it.
+    pub iterations: Option<u32>,
+
+    /// The time to wait between two requests.
+    pub delay: Duration,
+
+    /// Whether the run ends after the first request that fails.
+    pub stop_on_failure: bool,
+
+    /// The rows of the data file. Every iteration gets the variables of one
+    /// row, and the last row is used again when there are more iterations.
+    pub data: Vec<KeyValueTable>,
+}
+
+impl RunnerOptions {
+    pub fn iterations(&self) -> u32 {
+        self.iterations.unwrap_or(self.data.len() as u32).max(1)
+    }
+}
+
+/// A request of the run that is about to be sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunStep {
+    /// The iteration this step belongs to, starting at 1.
+    pub iteration: u32,
+
+    /// The time to wait before sending the request.
+    pub delay: Duration,
+
+    index: usize,
+}
+
+/// The outcome of a single request of the run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunResult {
+    pub iteration: u32,
+    pub item: RunnerItem,
+    pub status_code: Option<u32>,
+    pub duration: Option<u128>,
+    pub tests: Vec<AssertionResult>,
+    pub extractions: Vec<ExtractionResult>,
+
+    /// Why the request could not be sent, if it could not.
+    pub error: Option<String>,
+
+    /// Requests with tests pass if every test passes. Any other request passes
+    /// if it gets a response that is not a 4xx or 5xx error.
+    pub passed: bool,
+}
+
+/// The totals of a run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunSummary {
+    pub passed: usize,
+    pub failed: usize,
+
+    /// Requests that were not sent because the run stopped early.
+    pub skipped: usize,
+
+    /// The time spent waiting for responses, in milliseconds.
+    pub duration: u128,
+}
+
+pub struct Runner {
+    items: Vec<RunnerItem>,
+    options: RunnerOptions,
+    position: usize,
+    stopped: bool,
+    extracted: KeyValueTable,
+    pending: Option<(Vec<Assertion>, Vec<Extraction>)>,
+    results: Vec<RunResult>,
+}
+
+impl Runner {
+    pub fn new(items: Vec<RunnerItem>, options: RunnerOptions) -> Self {
+        Self {
+            items,
+            options,
+            position: 0,
+            stopped: false,
+            extracted: KeyValueTable::default(),
+            pending: None,
+            results: Vec::new(),
+        }
+    }
+
+    /// The number of requests that a complete run sends.
+    pub fn total_steps(&self) -> usize {
+        self.items.len() * self.options.iterations() as usize
+    }
+
+    /// Moves to the next request, or returns `None` when the run is over.
+    pub fn next_step(&mut self) -> Option<RunStep> {
+        if self.stopped || self.position >= self.total_steps() {
+            return None;
+        }
+        let index = self.position;
+        self.position += 1;
+        Some(RunStep {
+            iteration: (index / self.items.len()) as u32 + 1,
+            delay: if index == 0 {
+                Duration::ZERO
+            } else {
+                self.options.delay
+            },
+            index,
+        })
+    }
+
+    /// Ends the run, so that no more steps are given.
+    pub fn stop(&mut self) {
+        self.stopped = true;
+    }
+
+    pub fn item(&self, step: &RunStep) -> &RunnerItem {
+        &self.items[step.index % self.items.len()]
+    }
+
+    /// The given environment with the variables of the data file and the values
+    /// extracted so far on top.
+    fn environment_for(
+        &self,
+        step: &RunStep,
+        environment: Option<&Environment>,
+    ) -> Option<Environment> {
+        let row = self
+            .options
+            .data
+            .get(step.iteration as usize - 1)
+            .or(self.options.data.last());
+        if row.is_none() && self.extracted.is_empty() {
+            return environment.cloned();
+        }
+        let mut environment = environment.cloned().unwrap_or_default();
+        for variable in row
+            .iter()
+            .flat_map(|row| row.iter())
+            .chain(self.extracted.iter())
+        {
+            environment.variables.set(&variable.name, &variable.value);
+        }
+        Some(environment)
+    }
+
+    /// Reads the request of the step and binds it to the given environment.
+    pub fn prepare(
+        &mut self,
+        step: &RunStep,
+        environment: Option<&Environment>,
+    ) -> Result<BoundRequest, CarteroError> {
+        self.pending = None;
+        let path = self.item(step).path.clone();
+        let contents = std::fs::read_to_string(&path)?;
+        let endpoint = crate::file::parse_toml(&contents)?;
+        self.pending = Some((endpoint.tests.clone(), endpoint.extractions.clone()));
+        let environment = self.environment_for(step, environment);
+        BoundRequest::bind_relative_to(endpoint, environment.as_ref(), path.parent())
+    }
+
+    /// Records the outcome of the request of the step, evaluating its tests and
+    /// applying its extraction rules.
+    pub fn complete(
+        &mut self,
+        step: &RunStep,
+        outcome: Result<&ResponseData, &CarteroError>,
+    ) -> &RunResult {
+        let (tests, extractions) = self.pending.take().unwrap_or_default();
+        let item = self.item(step).clone();
+        let result = match outcome {
+            Ok(response) => {
+                let tests = evaluate_all(&tests, response);
+                let extractions = extract_all(&extractions, response);
+                for extraction in &extractions {
+                    if let Ok(value) = &extraction.value {
+                        self.extracted.set(&extraction.extraction.variable, value);
+                    }
+                }
+                let passed = if tests.is_empty() {
+                    response.status_code < 400
+                } else {
+                    tests.iter().all(|test| test.passed)
+                };
+                RunResult {
+                    iteration: step.iteration,
+                    item,
+                    status_code: Some(response.status_code),
+                    duration: Some(response.duration),
+                    tests,
+                    extractions,
+                    error: None,
+                    passed,
+                }
+            }
+            Err(error) => RunResult {
+                iteration: step.iteration,
+                item,
+                status_code: None,
+                duration: None,
+                tests: Vec::new(),
+                extractions: Vec::new(),
+                error: Some(error.to_string()),
+                passed: false,
+            },
+        };
+        if !result.passed && self.options.stop_on_failure {
+            self.stopped = true;
+        }
+        self.results.push(result);
+        self.results.last().unwrap()
+    }
+
+    pub fn results(&self) -> &[RunResult] {
+        &self.results
+    }
+
+    pub fn summary(&self) -> RunSummary {
+        let passed = self.results.iter().filter(|r| r.passed).count();
+        RunSummary {
+            passed,
+            failed: self.results.len() - passed,
+            skipped: self.total_steps() - self.results.len(),
+            duration: self.results.iter().filter_map(|r| r.duration).sum(),
+        }
+    }
+}
+
+/// Splits CSV text into records, keeping the line where every record starts.
+fn csv_records(contents: &str) -> Result<Vec<(usize, Vec<String>)>, RunnerError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut blank = true;
+    let mut line = 1;
+    let mut start = 1;
+
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => {
+                quoted = true;
+                blank = false;
+            }
+            ',' => {
+                record.push(std::mem::take(&mut field));
+                blank = false;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                if !blank {
+                    record.push(std::mem::take(&mut field));
+                    records.push((start, std::mem::take(&mut record)));
+                }
+                blank = true;
+                line += 1;
+                start = line;
+            }
+            _ => {
+                field.push(c);
+                blank = false;
+            }
+        }
+    }
+    if quoted {
+        return Err(RunnerError::InvalidCsv(start));
+    }
+    if !blank {
+        record.push(field);
+        records.push((start, record));
+    }
+    Ok(records)
+}
+
+/// Parses CSV data whose first line holds the names of the variables.
+pub fn parse_csv_data(contents: &str) -> Result<Vec<KeyValueTable>, RunnerError> {
+    let mut records = csv_records(contents)?.into_iter();
+    let Some((_, names)) = records.next() else {
+        return Ok(Vec::new());
+    };
+    records
+        .map(|(line, values)| {
+            if values.len() != names.len() {
+                return Err(RunnerError::InvalidCsv(line));
+            }
+            let row: Vec<KeyValue> = names
+                .iter()
+                .zip(values)
+                .map(|(name, value)| (name.trim().to_string(), value).into())
+                .collect();
+            Ok(KeyValueTable::new(&row))
+        })
+        .collect()
+}
+
+/// Parses JSON data made of an array of objects, one for every row.
+pub fn parse_json_data(contents: &str) -> Result<Vec<KeyValueTable>, RunnerError> {
+    let Ok(Value::Array(rows)) = serde_json::from_str::<Value>(contents) else {
+        return Err(RunnerError::InvalidJson);
+    };
+    rows.iter()
+        .map(|row| match row {
+            Value::Object(object) => {
+                let row: Vec<KeyValue> = object
+                    .iter()
+                    .map(|(name, value)| (name.clone(), json_to_text(value)).into())
+                    .collect();
+                Ok(KeyValueTable::new(&row))
+            }
+            _ => Err(RunnerError::InvalidJson),
+        })
+        .collect()
+}
+
+/// Reads a data file, whose format is given by its extension.
+pub fn load_data_file(path: &Path) -> Result<Vec<KeyValueTable>, CarteroError> {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    let rows = match extension.as_deref() {
+        Some("csv") => parse_csv_data(&std::fs::read_to_string(path)?)?,
+        Some("json") => parse_json_data(&std::fs::read_to_string(path)?)?,
+        _ => return Err(RunnerError::UnsupportedDataFile.into()),
+    };
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use super::{parse_csv_data, parse_json_data, Runner, RunnerError, RunnerItem, RunnerOptions};
+    use crate::entities::{Environment, KeyValueTable, ResponseData};
+    use crate::error::CarteroError;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("cartero-runner-test-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn response(status_code: u32, body: &str) -> ResponseData {
+        ResponseData {
+            status_code,
+            duration: 100,
+            size: body.len(),
+            headers: KeyValueTable::default(),
+            body: Vec::from(body),
+        }
+    }
+
+    fn values(table: &KeyValueTable) -> Vec<(&str, &str)> {
+        table
+            .iter()
+            .map(|kv| (kv.name.as_str(), kv.value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_csv_data() {
+        let csv = "user, password\r\nalice,\"se,cret\"\n\n\"bob\",\"say \"\"hi\"\"\nthere\"\n";
+        let rows = parse_csv_data(csv).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            values(&rows[0]),
+            vec![("user", "alice"), ("password", "se,cret")]
+        );
+        assert_eq!(
+            values(&rows[1]),
+            vec![("user", "bob"), ("password", "say \"hi\"\nthere")]
+        );
+
+        assert_eq!(parse_csv_data(""), Ok(Vec::new()));
+        assert_eq!(
+            parse_csv_data("a,b\n1,2\n3\n"),
+            Err(RunnerError::InvalidCsv(3))
+        );
+        assert_eq!(
+            parse_csv_data("a,b\n\"1,2\n"),
+            Err(RunnerError::InvalidCsv(2))
+        );
+    }
+
+    #[test]
+    fn test_parse_json_data() {
+        let rows = parse_json_data(r#"[{"id": 1, "name": "one"}, {"id": 2}]"#).unwrap();
+        assert_eq!(values(&rows[0]), vec![("id", "1"), ("name", "one")]);
+        assert_eq!(values(&rows[1]), vec![("id", "2")]);
+        assert_eq!(
+            parse_json_data(r#"{"id": 1}"#),
+            Err(RunnerError::InvalidJson)
+        );
+        assert_eq!(parse_json_data("[1, 2]"), Err(RunnerError::InvalidJson));
+    }
+
+    #[test]
+    fn test_runner_binds_data_and_extracted_values() {
+        let dir = temp_dir("bind");
+        let login = dir.join("login.cartero");
+        fs::write(
+            &login,
+            r#"
+version = 1
+url = "{{BASE}}/login?user={{USER}}"
+method = "POST"
+
+[[extract]]
+variable = "TOKEN"
+type = "json"
+path = "/token"
+"#,
+        )
+        .unwrap();
+        let profile = dir.join("profile.cartero");
+        fs::write(
+            &profile,
+            r#"
+version = 1
+url = "{{BASE}}/profile?token={{TOKEN}}"
+method = "GET"
+"#,
+        )
+        .unwrap();
+
+        let options = RunnerOptions {
+            delay: Duration::from_millis(250),
+            data: vec![
+                KeyValueTable::new(&[("USER", "alice").into()]),
+                KeyValueTable::new(&[("USER", "bob").into()]),
+            ],
+            ..Default::default()
+        };
+        let items = vec![
+            RunnerItem::new("login", &login),
+            RunnerItem::new("profile", &profile),
+        ];
+        let mut runner = Runner::new(items, options);
+        assert_eq!(runner.total_steps(), 4);
+        let environment = Environment {
+            name: "dev".into(),
+            variables: KeyValueTable::new(&[("BASE", "https://example.com").into()]),
+            proxy: None,
+        };
+
+        let mut urls = Vec::new();
+        let mut delays = Vec::new();
+        let mut iterations = Vec::new();
+        while let Some(step) = runner.next_step() {
+            delays.push(step.delay.as_millis());
+            iterations.push(step.iteration);
+            let request = runner.prepare(&step, Some(&environment)).unwrap();
+            urls.push(request.url.clone());
+            let token = format!(r#"{{"token": "t{}"}}"#, step.iteration);
+            runner.complete(&step, Ok(&response(200, &token)));
+        }
+        assert_eq!(
+            urls,
+            vec![
+                "https://example.com/login?user=alice",
+                "https://example.com/profile?token=t1",
+                "https://example.com/login?user=bob",
+                "https://example.com/profile?token=t2",
+            ]
+        );
+        assert_eq!(delays, vec![0, 250, 250, 250]);
+        assert_eq!(iterations, vec![1, 1, 2, 2]);
+        assert_eq!(runner.summary().passed, 4);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_runner_stops_on_failure() {
+        let dir = temp_dir("stop");
+        let ok = dir.join("ok.cartero");
+        fs::write(
+            &ok,
+            "version = 1\nurl = \"https://example.com\"\nmethod = \"GET\"\n",
+        )
+        .unwrap();
+        let checked = dir.join("checked.cartero");
+        fs::write(
+            &checked,
+            r#"
+version = 1
+url = "https://example.com"
+method = "GET"
+
+[[tests]]
+type = "status"
+equals = 404
+"#,
+        )
+        .unwrap();
+        let items = vec![
+            RunnerItem::new("checked", &checked),
+            RunnerItem::new("ok", &ok),
+            RunnerItem::new("missing", &dir.join("missing.cartero")),
+        ];
+
+        // Tests decide the outcome, so an expected 404 passes.
+        let mut runner = Runner::new(items.clone(), RunnerOptions::default());
+        let step = runner.next_step().unwrap();
+        runner.prepare(&step, None).unwrap();
+        assert!(runner.complete(&step, Ok(&response(404, ""))).passed);
+        let step = runner.next_step().unwrap();
+        runner.prepare(&step, None).unwrap();
+        assert!(!runner.complete(&step, Ok(&response(500, ""))).passed);
+        let step = runner.next_step().unwrap();
+        let error = runner.prepare(&step, None).unwrap_err();
+        let result = runner.complete(&step, Err(&error));
+        assert!(result.error.is_some());
+        assert!(runner.next_step().is_none());
+
+        let options = RunnerOptions {
+            iterations: Some(2),
+            stop_on_failure: true,
+            ..Default::default()
+        };
+        let mut runner = Runner::new(items, options);
+        let step = runner.next_step().unwrap();
+        runner.prepare(&step, None).unwrap();
+        runner.complete(&step, Ok(&response(200, "")));
+        assert!(runner.next_step().is_none());
+        let summary = runner.summary();
+        assert_eq!((summary.passed, summary.failed, summary.skipped), (0, 1, 5));
+        assert!(matches!(error, CarteroError::FileError(_)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,252 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use adw::prelude::ComboRowExt;
+use glib::object::ObjectExt;
+use gtk::prelude::EditableExt;
+use gtk::subclass::prelude::*;
+
+use crate::entities::{ApiKeyLocation, OAuth2Config, OAuth2Grant, RequestAuth};
+
+mod imp {
+    use std::sync::OnceLock;
+
+    use adw::prelude::*;
+    use adw::subclass::prelude::*;
+    use adw::{ComboRow, EntryRow, PasswordEntryRow};
+    use glib::subclass::{InitializingObject, Signal};
+    use gtk::template_callbacks;
+    use gtk::{CompositeTemplate, Stack};
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/auth_pane.ui")]
+    pub struct AuthPane {
+        #[template_child]
+        pub(super) combo: TemplateChild<ComboRow>,
+
+        #[template_child]
+        stack: TemplateChild<Stack>,
+
+        #[template_child]
+        pub(super) basic_username: TemplateChild<EntryRow>,
+
+        #[template_child]
+        pub(super) basic_password: TemplateChild<PasswordEntryRow>,
+
+        #[template_child]
+        pub(super) bearer_token: TemplateChild<PasswordEntryRow>,
+
+        #[template_child]
+        pub(super) api_key_name: TemplateChild<EntryRow>,
+
+        #[template_child]
+        pub(super) api_key_value: TemplateChild<PasswordEntryRow>,
+
+        #[template_child]
+        pub(super) api_key_location: TemplateChild<ComboRow>,
+
+        #[template_child]
+        pub(super) digest_username: TemplateChild<EntryRow>,
+
+        #[template_child]
+        pub(super) digest_password: TemplateChild<PasswordEntryRow>,
+
+        #[template_child]
+        pub(super) oauth2_grant: TemplateChild<ComboRow>,
+
+        #[template_child]
+        pub(super) oauth2_token_url: TemplateChild<EntryRow>,
+
+        #[template_child]
+        pub(super) oauth2_authorization_url: TemplateChild<EntryRow>,
+
+        #[template_child]
+        pub(super) oauth2_client_id: TemplateChild<EntryRow>,
+
+        #[template_child]
+        pub(super) oauth2_client_secret: TemplateChild<PasswordEntryRow>,
+
+        #[template_child]
+        pub(super) oauth2_scope: TemplateChild<EntryRow>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for AuthPane {
+        const NAME: &'static str = "CarteroAuthPane";
+        type Type = super::AuthPane;
+        type ParentType = gtk::Box;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for AuthPane {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.on_selection_changed();
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("changed").build()])
+        }
+    }
+
+    impl WidgetImpl for AuthPane {}
+
+    impl BoxImpl for AuthPane {}
+
+    #[template_callbacks]
+    impl AuthPane {
+        #[template_callback]
+        fn on_selection_changed(&self) {
+            let page = match self.combo.selected() {
+                1 => "basic",
+                2 => "bearer",
+                3 => "api-key",
+                4 => "digest",
+                5 => "oauth2",
+                _ => "none",
+            };
+            self.stack.set_visible_child_name(page);
+            self.obj().emit_by_name::<()>("changed", &[]);
+        }
+
+        #[template_callback]
+        fn on_grant_changed(&self) {
+            // Only the authorization code grant sends the user to the browser.
+            let authorization_code = self.oauth2_grant.selected() == 1;
+            self.oauth2_authorization_url
+                .set_visible(authorization_code);
+            self.obj().emit_by_name::<()>("changed", &[]);
+        }
+
+        #[template_callback]
+        fn on_field_changed(&self) {
+            self.obj().emit_by_name::<()>("changed", &[]);
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct AuthPane(ObjectSubclass<imp::AuthPane>)
+        @extends gtk::Widget, gtk::Box,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl AuthPane {
+    pub fn connect_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "changed",
+            true,
+            glib::closure_local!(|ref pane| {
+                f(pane);
+            }),
+        )
+    }
+
+    pub fn auth(&self) -> RequestAuth {
+        let imp = self.imp();
+        match imp.combo.selected() {
+            1 => RequestAuth::Basic {
+                username: imp.basic_username.text().into(),
+                password: imp.basic_password.text().into(),
+            },
+            2 => RequestAuth::Bearer {
+                token: imp.bearer_token.text().into(),
+            },
+            3 => RequestAuth::ApiKey {
+                name: imp.api_key_name.text().into(),
+                value: imp.api_key_value.text().into(),
+                location: match imp.api_key_location.selected() {
+                    1 => ApiKeyLocation::Query,
+                    _ => ApiKeyLocation::Header,
+                },
+            },
+            4 => RequestAuth::Digest {
+                username: imp.digest_username.text().into(),
+                password: imp.digest_password.text().into(),
+            },
+            5 => RequestAuth::OAuth2(OAuth2Config {
+                grant: match imp.oauth2_grant.selected() {
+                    1 => OAuth2Grant::AuthorizationCode,
+                    _ => OAuth2Grant::ClientCredentials,
+                },
+                token_url: imp.oauth2_token_url.text().into(),
+                authorization_url: imp.oauth2_authorization_url.text().into(),
+                client_id: imp.oauth2_client_id.text().into(),
+                client_secret: imp.oauth2_client_secret.text().into(),
+                scope: imp.oauth2_scope.text().into(),
+            }),
+            _ => RequestAuth::None,
+        }
+    }
+
+    pub fn set_auth(&self, auth: &RequestAuth) {
+        let imp = self.imp();
+        let selected = match auth {
+            RequestAuth::None => 0,
+            RequestAuth::Basic { username, password } => {
+                imp.basic_username.set_text(username);
+                imp.basic_password.set_text(password);
+                1
+            }
+            RequestAuth::Bearer { token } => {
+                imp.bearer_token.set_text(token);
+                2
+            }
+            RequestAuth::ApiKey {
+                name,
+                value,
+                location,
+            } => {
+                imp.api_key_name.set_text(name);
+                imp.api_key_value.set_text(value);
+                imp.api_key_location.set_selected(match location {
+                    ApiKeyLocation::Header => 0,
+                    ApiKeyLocation::Query => 1,
+                });
+                3
+            }
+            RequestAuth::Digest { username, password } => {
+                imp.digest_username.set_text(username);
+                imp.digest_password.set_text(password);
+                4
+            }
+            RequestAuth::OAuth2(config) => {
+                imp.oauth2_grant.set_selected(match config.grant {
+                    OAuth2Grant::ClientCredentials => 0,
+                    OAuth2Grant::AuthorizationCode => 1,
+                });
+                imp.oauth2_token_url.set_text(&config.token_url);
+                imp.oauth2_authorization_url
+                    .set_text(&config.authorization_url);
+                imp.oauth2_client_id.set_text(&config.client_id);
+                imp.oauth2_client_secret.set_text(&config.client_secret);
+                imp.oauth2_scope.set_text(&config.scope);
+                5
+            }
+        };
+        imp.combo.set_selected(selected);
+    }
+}
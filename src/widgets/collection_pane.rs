@@ -0,0 +1,739 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::path::{Path, PathBuf};
+
+use glib::subclass::types::ObjectSubclassIsExt;
+use glib::Object;
+use gtk::gio;
+use gtk::prelude::*;
+
+use crate::collection::Collection;
+use crate::entities::Environment;
+use crate::error::CarteroError;
+use crate::extraction::ExtractionResult;
+
+mod imp {
+    use std::cell::{OnceCell, RefCell};
+    use std::path::{Path, PathBuf};
+    use std::sync::OnceLock;
+
+    use adw::prelude::*;
+    use adw::subclass::prelude::*;
+    use gettextrs::gettext;
+    use glib::subclass::{InitializingObject, Signal};
+    use gtk::{gdk, gio, CompositeTemplate};
+
+    use crate::collection::Collection;
+    use crate::error::CarteroError;
+    use crate::objects::CollectionItem;
+    use crate::widgets::RunnerDialog;
+    use crate::win::CarteroWindow;
+
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/es/danirod/Cartero/collection_pane.ui")]
+    pub struct CollectionPane {
+        #[template_child]
+        stack: TemplateChild<gtk::Stack>,
+
+        #[template_child]
+        title: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        list_view: TemplateChild<gtk::ListView>,
+
+        pub(super) collection: RefCell<Option<Collection>>,
+
+        root_store: OnceCell<gio::ListStore>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for CollectionPane {
+        const NAME: &'static str = "CarteroCollectionPane";
+        type Type = super::CollectionPane;
+        type ParentType = adw::Bin;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+
+            klass.install_action_async(
+                "collection.new-request",
+                Some(glib::VariantTy::STRING),
+                |pane, _, target| async move {
+                    let imp = pane.imp();
+                    if let Err(e) = imp.new_request(target).await {
+                        imp.report_error(e);
+                    }
+                },
+            );
+            klass.install_action_async(
+                "collection.new-folder",
+                Some(glib::VariantTy::STRING),
+                |pane, _, target| async move {
+                    let imp = pane.imp();
+                    if let Err(e) = imp.new_folder(target).await {
+                        imp.report_error(e);
+                    }
+                },
+            );
+            klass.install_action_async(
+                "collection.rename",
+                Some(glib::VariantTy::STRING),
+                |pane, _, target| async move {
+                    let imp = pane.imp();
+                    if let Err(e) = imp.rename_item(target).await {
+                        imp.report_error(e);
+                    }
+                },
+            );
+            klass.install_action_async(
+                "collection.move",
+                Some(glib::VariantTy::STRING),
+                |pane, _, target| async move {
+                    let imp = pane.imp();
+                    if let Err(e) = imp.move_item(target).await {
+                        imp.report_error(e);
+                    }
+                },
+            );
+            klass.install_action_async(
+                "collection.delete",
+                Some(glib::VariantTy::STRING),
+                |pane, _, target| async move {
+                    let imp = pane.imp();
+                    if let Err(e) = imp.delete_item(target).await {
+                        imp.report_error(e);
+                    }
+                },
+            );
+            klass.install_action_async(
+                "collection.run",
+                Some(glib::VariantTy::STRING),
+                |pane, _, target| async move {
+                    let imp = pane.imp();
+                    if let Err(e) = imp.run_requests(target) {
+                        imp.report_error(e);
+                    }
+                },
+            );
+            klass.install_action_async(
+                "collection.new-environment",
+                None,
+                |pane, _, _| async move {
+                    let imp = pane.imp();
+                    if let Err(e) = imp.new_environment().await {
+                        imp.report_error(e);
+                    }
+                },
+            );
+            klass.install_action("collection.refresh", None, |pane, _, _| {
+                if let Err(e) = pane.refresh() {
+                    pane.imp().report_error(e);
+                }
+            });
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for CollectionPane {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("item-activated")
+                        .param_types([gio::File::static_type()])
+                        .build(),
+                    Signal::builder("item-moved")
+                        .param_types([gio::File::static_type(), gio::File::static_type()])
+                        .build(),
+                    Signal::builder("item-deleted")
+                        .param_types([gio::File::static_type()])
+                        .build(),
+                    Signal::builder("refreshed").build(),
+                ]
+            })
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let store = gio::ListStore::with_type(CollectionItem::static_type());
+            let tree = gtk::TreeListModel::new(store.clone(), false, false, |item| {
+                item.downcast_ref::<CollectionItem>()
+                    .and_then(CollectionItem::children)
+                    .map(|children| children.upcast::<gio::ListModel>())
+            });
+            let selection = gtk::SingleSelection::new(Some(tree));
+            self.list_view.set_model(Some(&selection));
+            self.list_view.set_factory(Some(&self.build_factory()));
+            self.root_store.set(store).unwrap();
+
+            self.list_view.connect_activate(
+                glib::clone!(@weak self as pane => move |_, position| {
+                    pane.activate_row(position);
+                }),
+            );
+
+            self.sync_state();
+        }
+    }
+
+    impl WidgetImpl for CollectionPane {}
+
+    impl BinImpl for CollectionPane {}
+
+    impl CollectionPane {
+        fn build_factory(&self) -> gtk::SignalListItemFactory {
+            let factory = gtk::SignalListItemFactory::new();
+            factory.connect_setup(glib::clone!(@weak self as pane => move |_, item| {
+                let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+                let icon = gtk::Image::new();
+                let label = gtk::Label::builder()
+                    .xalign(0.0)
+                    .ellipsize(gtk::pango::EllipsizeMode::End)
+                    .build();
+                let content = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+                content.append(&icon);
+                content.append(&label);
+                let expander = gtk::TreeExpander::new();
+                expander.set_child(Some(&content));
+
+                let gesture = gtk::GestureClick::builder()
+                    .button(gdk::BUTTON_SECONDARY)
+                    .build();
+                gesture.connect_pressed(
+                    glib::clone!(@weak pane, @weak expander => move |_, _, x, y| {
+                        if let Some(entry) = expander.item().and_downcast::<CollectionItem>() {
+                            pane.show_context_menu(&expander, &entry, x, y);
+                        }
+                    }),
+                );
+                expander.add_controller(gesture);
+                item.set_child(Some(&expander));
+            }));
+            factory.connect_bind(|_, item| {
+                let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+                let Some(row) = item.item().and_downcast::<gtk::TreeListRow>() else {
+                    return;
+                };
+                let Some(entry) = row.item().and_downcast::<CollectionItem>() else {
+                    return;
+                };
+                let expander = item.child().and_downcast::<gtk::TreeExpander>().unwrap();
+                expander.set_list_row(Some(&row));
+
+                let content = expander.child().unwrap();
+                let icon = content.first_child().and_downcast::<gtk::Image>().unwrap();
+                let label = content.last_child().and_downcast::<gtk::Label>().unwrap();
+                let icon_name = if entry.folder() {
+                    "folder-symbolic"
+                } else {
+                    "text-x-generic-symbolic"
+                };
+                icon.set_icon_name(Some(icon_name));
+                label.set_label(&entry.name());
+                expander.set_tooltip_text(Some(&entry.path()));
+            });
+            factory
+        }
+
+        fn show_context_menu(
+            &self,
+            parent: &gtk::TreeExpander,
+            entry: &CollectionItem,
+            x: f64,
+            y: f64,
+        ) {
+            let target = entry.path().to_variant();
+            let menu_item = |label: String, action: &str| {
+                let item = gio::MenuItem::new(Some(&label), None);
+                item.set_action_and_target_value(Some(action), Some(&target));
+                item
+            };
+
+            let menu = gio::Menu::new();
+            if entry.folder() {
+                let create = gio::Menu::new();
+                create.append_item(&menu_item(gettext("New request"), "collection.new-request"));
+                create.append_item(&menu_item(gettext("New folder"), "collection.new-folder"));
+                menu.append_section(None, &create);
+                let run = gio::Menu::new();
+                run.append_item(&menu_item(gettext("Run..."), "collection.run"));
+                menu.append_section(None, &run);
+            }
+            let edit = gio::Menu::new();
+            edit.append_item(&menu_item(gettext("Rename..."), "collection.rename"));
+            edit.append_item(&menu_item(gettext("Move to..."), "collection.move"));
+            edit.append_item(&menu_item(gettext("Delete"), "collection.delete"));
+            menu.append_section(None, &edit);
+
+            let popover = gtk::PopoverMenu::from_model(Some(&menu));
+            popover.set_parent(parent);
+            popover.set_has_arrow(false);
+            popover.set_halign(gtk::Align::Start);
+            popover.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+            popover.connect_closed(|popover| {
+                let popover = popover.clone();
+                glib::idle_add_local_once(move || popover.unparent());
+            });
+            popover.popup();
+        }
+
+        fn activate_row(&self, position: u32) {
+            let Some(model) = self.list_view.model() else {
+                return;
+            };
+            let Some(row) = model.item(position).and_downcast::<gtk::TreeListRow>() else {
+                return;
+            };
+            let Some(entry) = row.item().and_downcast::<CollectionItem>() else {
+                return;
+            };
+            if entry.folder() {
+                row.set_expanded(!row.is_expanded());
+            } else {
+                self.obj()
+                    .emit_by_name::<()>("item-activated", &[&entry.file()]);
+            }
+        }
+
+        pub(super) fn sync_state(&self) {
+            let store = self.root_store.get().unwrap();
+            let collection = self.collection.borrow();
+            match collection.as_ref() {
+                Some(collection) => {
+                    let items: Vec<CollectionItem> = collection
+                        .children()
+                        .iter()
+                        .map(CollectionItem::from)
+                        .collect();
+                    store.remove_all();
+                    store.extend_from_slice(&items);
+                    self.title.set_label(collection.name());
+                    self.title
+                        .set_tooltip_text(Some(&collection.root().display().to_string()));
+                    self.stack.set_visible_child_name("tree");
+                }
+                None => {
+                    store.remove_all();
+                    self.title.set_label(&gettext("Collection"));
+                    self.title.set_tooltip_text(None);
+                    self.stack.set_visible_child_name("empty");
+                }
+            }
+            drop(collection);
+            self.obj().emit_by_name::<()>("refreshed", &[]);
+        }
+
+        fn report_error(&self, error: CarteroError) {
+            if let Some(window) = self.obj().root().and_downcast::<CarteroWindow>() {
+                window.toast_error(error);
+            }
+        }
+
+        /// Converts the target of a collection action into a path.
+        ///
+        /// Actions triggered from the collection menu have an empty target, which
+        /// means that they apply to the root of the collection.
+        fn target_path(&self, target: Option<glib::Variant>) -> Option<PathBuf> {
+            let target = target.and_then(|t| t.get::<String>()).unwrap_or_default();
+            if target.is_empty() {
+                let collection = self.collection.borrow();
+                collection.as_ref().map(|c| c.root().to_path_buf())
+            } else {
+                Some(PathBuf::from(target))
+            }
+        }
+
+        /// The folder where new items should be placed when the target is a request.
+        fn target_folder(&self, target: Option<glib::Variant>) -> Option<PathBuf> {
+            let path = self.target_path(target)?;
+            if path.is_dir() {
+                Some(path)
+            } else {
+                path.parent().map(Path::to_path_buf)
+            }
+        }
+
+        async fn prompt(&self, heading: &str, accept: &str, initial: &str) -> Option<String> {
+            let entry = gtk::Entry::builder()
+                .text(initial)
+                .activates_default(true)
+                .build();
+            let dialog = adw::AlertDialog::builder()
+                .heading(heading)
+                .extra_child(&entry)
+                .close_response("cancel")
+                .default_response("accept")
+                .build();
+            dialog.add_response("cancel", &gettext("_Cancel"));
+            dialog.add_response("accept", accept);
+            dialog.set_response_appearance("accept", adw::ResponseAppearance::Suggested);
+
+            let response = dialog.choose_future(&*self.obj()).await;
+            if response == "accept" {
+                Some(entry.text().to_string())
+            } else {
+                None
+            }
+        }
+
+        async fn new_request(&self, target: Option<glib::Variant>) -> Result<(), CarteroError> {
+            let Some(folder) = self.target_folder(target) else {
+                return Ok(());
+            };
+            let heading = gettext("New request");
+            let Some(name) = self.prompt(&heading, &gettext("_Create"), "").await else {
+                return Ok(());
+            };
+            let path = {
+                let mut collection = self.collection.borrow_mut();
+                let collection = collection.as_mut().ok_or(CarteroError::NotACollection)?;
+                collection.create_request(&folder, &name)?
+            };
+            self.sync_state();
+            let file = gio::File::for_path(path);
+            self.obj().emit_by_name::<()>("item-activated", &[&file]);
+            Ok(())
+        }
+
+        async fn new_folder(&self, target: Option<glib::Variant>) -> Result<(), CarteroError> {
+            let Some(parent) = self.target_folder(target) else {
+                return Ok(());
+            };
+            let heading = gettext("New folder");
+            let Some(name) = self.prompt(&heading, &gettext("_Create"), "").await else {
+                return Ok(());
+            };
+            {
+                let mut collection = self.collection.borrow_mut();
+                let collection = collection.as_mut().ok_or(CarteroError::NotACollection)?;
+                collection.create_folder(&parent, &name)?;
+            }
+            self.sync_state();
+            Ok(())
+        }
+
+        async fn new_environment(&self) -> Result<(), CarteroError> {
+            let heading = gettext("New environment");
+            let Some(name) = self.prompt(&heading, &gettext("_Create"), "").await else {
+                return Ok(());
+            };
+            let environment = self.obj().create_environment(&name)?;
+            let _ = self
+                .obj()
+                .activate_action("win.edit-environment", Some(&environment.name.to_variant()));
+            Ok(())
+        }
+
+        /// Opens the runner with the requests placed inside the target folder.
+        fn run_requests(&self, target: Option<glib::Variant>) -> Result<(), CarteroError> {
+            let Some(window) = self.obj().root().and_downcast::<CarteroWindow>() else {
+                return Ok(());
+            };
+            let Some(folder) = self.target_path(target) else {
+                return Ok(());
+            };
+            let (name, items) = {
+                let collection = self.collection.borrow();
+                let collection = collection.as_ref().ok_or(CarteroError::NotACollection)?;
+                let name = if folder == collection.root() {
+                    collection.name().to_string()
+                } else {
+                    folder
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default()
+                };
+                (name, crate::runner::collection_items(collection, &folder)?)
+            };
+            if items.is_empty() {
+                window.toast_message(&gettext("There are no requests to run"));
+                return Ok(());
+            }
+            let dialog = RunnerDialog::new(&window, &name, items);
+            dialog.present(&window);
+            Ok(())
+        }
+
+        async fn rename_item(&self, target: Option<glib::Variant>) -> Result<(), CarteroError> {
+            let Some(path) = self.target_path(target) else {
+                return Ok(());
+            };
+            let current = if path.is_dir() {
+                path.file_name()
+            } else {
+                path.file_stem()
+            };
+            let current = current
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let heading = gettext("Rename");
+            let Some(name) = self.prompt(&heading, &gettext("_Rename"), &current).await else {
+                return Ok(());
+            };
+            let new_path = {
+                let mut collection = self.collection.borrow_mut();
+                let collection = collection.as_mut().ok_or(CarteroError::NotACollection)?;
+                collection.rename(&path, &name)?
+            };
+            self.sync_state();
+            self.emit_moved(&path, &new_path);
+            Ok(())
+        }
+
+        async fn move_item(&self, target: Option<glib::Variant>) -> Result<(), CarteroError> {
+            let Some(path) = self.target_path(target) else {
+                return Ok(());
+            };
+            let Some(root) = self.obj().collection_root() else {
+                return Ok(());
+            };
+            let current = path
+                .parent()
+                .and_then(|parent| parent.strip_prefix(&root).ok())
+                .map(|parent| parent.display().to_string())
+                .unwrap_or_default();
+            let heading = gettext("Move to folder");
+            let Some(folder) = self.prompt(&heading, &gettext("_Move"), &current).await else {
+                return Ok(());
+            };
+            let folder = folder.trim().trim_matches('/');
+            let folder = if folder.is_empty() {
+                root
+            } else {
+                root.join(folder)
+            };
+            let new_path = {
+                let mut collection = self.collection.borrow_mut();
+                let collection = collection.as_mut().ok_or(CarteroError::NotACollection)?;
+                collection.move_to(&path, &folder)?
+            };
+            self.sync_state();
+            self.emit_moved(&path, &new_path);
+            Ok(())
+        }
+
+        async fn delete_item(&self, target: Option<glib::Variant>) -> Result<(), CarteroError> {
+            let Some(path) = self.target_path(target) else {
+                return Ok(());
+            };
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let dialog = adw::AlertDialog::builder()
+                .heading(gettext("Delete item?"))
+                .body(
+                    gettext("“{}” will be permanently deleted from the disk.").replace("{}", &name),
+                )
+                .close_response("cancel")
+                .default_response("cancel")
+                .build();
+            dialog.add_response("cancel", &gettext("_Cancel"));
+            dialog.add_response("delete", &gettext("_Delete"));
+            dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+            if dialog.choose_future(&*self.obj()).await != "delete" {
+                return Ok(());
+            }
+
+            {
+                let mut collection = self.collection.borrow_mut();
+                let collection = collection.as_mut().ok_or(CarteroError::NotACollection)?;
+                collection.delete(&path)?;
+            }
+            self.sync_state();
+            let file = gio::File::for_path(path);
+            self.obj().emit_by_name::<()>("item-deleted", &[&file]);
+            Ok(())
+        }
+
+        fn emit_moved(&self, from: &Path, to: &Path) {
+            let from = gio::File::for_path(from);
+            let to = gio::File::for_path(to);
+            self.obj().emit_by_name::<()>("item-moved", &[&from, &to]);
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct CollectionPane(ObjectSubclass<imp::CollectionPane>)
+        @extends gtk::Widget, adw::Bin,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl Default for CollectionPane {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+impl CollectionPane {
+    pub fn connect_item_activated<F: Fn(&Self, &gio::File) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "item-activated",
+            true,
+            glib::closure_local!(|ref pane, ref file| {
+                f(pane, file);
+            }),
+        )
+    }
+
+    pub fn connect_item_moved<F: Fn(&Self, &gio::File, &gio::File) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "item-moved",
+            true,
+            glib::closure_local!(|ref pane, ref from, ref to| {
+                f(pane, from, to);
+            }),
+        )
+    }
+
+    pub fn connect_item_deleted<F: Fn(&Self, &gio::File) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "item-deleted",
+            true,
+            glib::closure_local!(|ref pane, ref file| {
+                f(pane, file);
+            }),
+        )
+    }
+
+    /// Connects to the signal emitted every time the collection is reloaded, opened or closed.
+    pub fn connect_refreshed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "refreshed",
+            true,
+            glib::closure_local!(|ref pane| {
+                f(pane);
+            }),
+        )
+    }
+
+    /// Opens the collection placed in the given directory.
+    pub fn open_collection(&self, path: &Path) -> Result<(), CarteroError> {
+        let collection = Collection::open(path)?;
+        let imp = self.imp();
+        imp.collection.replace(Some(collection));
+        imp.sync_state();
+        Ok(())
+    }
+
+    /// Turns the given directory into a collection and opens it.
+    pub fn create_collection(&self, path: &Path, name: &str) -> Result<(), CarteroError> {
+        let collection = Collection::create(path, name)?;
+        let imp = self.imp();
+        imp.collection.replace(Some(collection));
+        imp.sync_state();
+        Ok(())
+    }
+
+    pub fn close_collection(&self) {
+        let imp = self.imp();
+        imp.collection.replace(None);
+        imp.sync_state();
+    }
+
+    pub fn collection_root(&self) -> Option<PathBuf> {
+        let collection = self.imp().collection.borrow();
+        collection.as_ref().map(|c| c.root().to_path_buf())
+    }
+
+    /// Re-reads the collection from the disk, to pick up external changes.
+    pub fn refresh(&self) -> Result<(), CarteroError> {
+        let imp = self.imp();
+        {
+            let mut collection = imp.collection.borrow_mut();
+            if let Some(collection) = collection.as_mut() {
+                collection.refresh()?;
+            }
+        }
+        imp.sync_state();
+        Ok(())
+    }
+
+    /// The names of the environments defined in the open collection.
+    pub fn environments(&self) -> Vec<String> {
+        let collection = self.imp().collection.borrow();
+        collection
+            .as_ref()
+            .map(|c| c.environments().to_vec())
+            .unwrap_or_default()
+    }
+
+    pub fn load_environment(&self, name: &str) -> Result<Environment, CarteroError> {
+        let collection = self.imp().collection.borrow();
+        let collection = collection.as_ref().ok_or(CarteroError::NotACollection)?;
+        collection.load_environment(name)
+    }
+
+    pub fn save_environment(&self, environment: &Environment) -> Result<(), CarteroError> {
+        let imp = self.imp();
+        {
+            let mut collection = imp.collection.borrow_mut();
+            let collection = collection.as_mut().ok_or(CarteroError::NotACollection)?;
+            collection.save_environment(environment)?;
+        }
+        imp.sync_state();
+        Ok(())
+    }
+
+    /// Adds the variables of the open collection to the given environment.
+    pub fn scoped_environment(&self, environment: Option<Environment>) -> Option<Environment> {
+        let collection = self.imp().collection.borrow();
+        match collection.as_ref() {
+            Some(collection) => collection.scoped_environment(environment),
+            None => environment,
+        }
+    }
+
+    /// Stores the values extracted from a response in the open collection.
+    ///
+    /// The tree is not rebuilt, since only variables change.
+    pub fn store_extracted(
+        &self,
+        environment: Option<&str>,
+        results: &[ExtractionResult],
+    ) -> Result<(), CarteroError> {
+        let mut collection = self.imp().collection.borrow_mut();
+        let collection = collection.as_mut().ok_or(CarteroError::NotACollection)?;
+        collection.store_extracted(environment, results)
+    }
+
+    pub fn create_environment(&self, name: &str) -> Result<Environment, CarteroError> {
+        let imp = self.imp();
+        let environment = {
+            let mut collection = imp.collection.borrow_mut();
+            let collection = collection.as_mut().ok_or(CarteroError::NotACollection)?;
+            collection.create_environment(name)?
+        };
+        imp.sync_state();
+        Ok(environment)
+    }
+}
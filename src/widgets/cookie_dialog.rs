@@ -0,0 +1,198 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use adw::prelude::*;
+use gettextrs::gettext;
+use glib::subclass::types::ObjectSubclassIsExt;
+use glib::Object;
+
+use crate::client::{Cookie, CookieJar};
+
+mod imp {
+    use std::cell::RefCell;
+    use std::sync::OnceLock;
+
+    use adw::prelude::*;
+    use adw::subclass::prelude::*;
+    use glib::subclass::{InitializingObject, Signal};
+    use gtk::CompositeTemplate;
+
+    use crate::client::Cookie;
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/cookie_dialog.ui")]
+    pub struct CookieDialog {
+        #[template_child]
+        pub(super) stack: TemplateChild<gtk::Stack>,
+
+        #[template_child]
+        pub(super) cookie_list: TemplateChild<gtk::ListBox>,
+
+        #[template_child]
+        pub(super) clear_button: TemplateChild<gtk::Button>,
+
+        /// Every cookie that is still in the dialog, with the row that edits its value.
+        pub(super) rows: RefCell<Vec<(Cookie, adw::EntryRow)>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for CookieDialog {
+        const NAME: &'static str = "CarteroCookieDialog";
+        type Type = super::CookieDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for CookieDialog {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("saved").build()])
+        }
+    }
+
+    impl WidgetImpl for CookieDialog {}
+
+    impl AdwDialogImpl for CookieDialog {}
+
+    #[gtk::template_callbacks]
+    impl CookieDialog {
+        #[template_callback]
+        fn on_cancel_clicked(&self) {
+            self.obj().close();
+        }
+
+        #[template_callback]
+        fn on_save_clicked(&self) {
+            let obj = self.obj();
+            obj.emit_by_name::<()>("saved", &[]);
+            obj.close();
+        }
+
+        #[template_callback]
+        fn on_clear_clicked(&self) {
+            self.rows.borrow_mut().clear();
+            self.cookie_list.remove_all();
+            self.update_visibility();
+        }
+
+        pub(super) fn remove_row(&self, row: &adw::EntryRow) {
+            self.rows.borrow_mut().retain(|(_, r)| r != row);
+            self.cookie_list.remove(row);
+            self.update_visibility();
+        }
+
+        pub(super) fn update_visibility(&self) {
+            let empty = self.rows.borrow().is_empty();
+            self.stack
+                .set_visible_child_name(if empty { "empty" } else { "cookies" });
+            self.clear_button.set_sensitive(!empty);
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct CookieDialog(ObjectSubclass<imp::CookieDialog>)
+        @extends gtk::Widget, adw::Dialog,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+/// Describes where a cookie is sent and until when.
+fn cookie_subtitle(cookie: &Cookie) -> String {
+    let domain = if cookie.host_only {
+        cookie.domain.clone()
+    } else {
+        format!(".{}", cookie.domain)
+    };
+    let expires = match cookie.expires {
+        Some(expires) => {
+            let seconds = expires
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            glib::DateTime::from_unix_local(seconds as i64)
+                .and_then(|time| time.format("%x %X"))
+                .map(String::from)
+                .unwrap_or_default()
+        }
+        None => gettext("end of session"),
+    };
+    gettext("{domain}{path}, until {expires}")
+        .replace("{domain}", &domain)
+        .replace("{path}", &cookie.path)
+        .replace("{expires}", &expires)
+}
+
+impl CookieDialog {
+    pub fn new(jar: &CookieJar) -> Self {
+        let dialog: Self = Object::builder().build();
+        let imp = dialog.imp();
+        for cookie in jar.cookies() {
+            let row = adw::EntryRow::builder()
+                .title(format!("{} · {}", cookie.name, cookie_subtitle(cookie)))
+                .text(&cookie.value)
+                .build();
+            let delete = gtk::Button::builder()
+                .icon_name("user-trash-symbolic")
+                .tooltip_text(gettext("Remove cookie"))
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            delete.connect_clicked(glib::clone!(@weak dialog, @weak row => move |_| {
+                dialog.imp().remove_row(&row);
+            }));
+            row.add_suffix(&delete);
+            imp.cookie_list.append(&row);
+            imp.rows.borrow_mut().push((cookie.clone(), row));
+        }
+        imp.update_visibility();
+        dialog
+    }
+
+    /// Builds the jar using the current contents of the dialog.
+    pub fn jar(&self) -> CookieJar {
+        let now = SystemTime::now();
+        let mut jar = CookieJar::default();
+        for (cookie, row) in self.imp().rows.borrow().iter() {
+            let cookie = Cookie {
+                value: row.text().to_string(),
+                ..cookie.clone()
+            };
+            jar.insert(cookie, now);
+        }
+        jar
+    }
+
+    /// Connects to the signal emitted when the user accepts the changes.
+    pub fn connect_saved<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "saved",
+            true,
+            glib::closure_local!(|ref dialog| {
+                f(dialog);
+            }),
+        )
+    }
+}
@@ -15,15 +15,26 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use glib::{subclass::types::ObjectSubclassIsExt, Object};
-use gtk::glib;
+use std::time::SystemTime;
 
-use crate::{entities::EndpointData, error::CarteroError};
+use glib::{subclass::types::ObjectSubclassIsExt, Object};
+use gtk::{glib, prelude::*};
+
+use crate::{
+    app::CarteroApplication,
+    client::{BoundRequest, CookieJar, GraphQLSchema, TokenCache},
+    entities::{EndpointData, Environment, ProxyMode, ProxySettings, ResponseData},
+    error::CarteroError,
+    export::{ExportFormat, ExportOptions},
+    extraction::ExtractionResult,
+    win::CarteroWindow,
+};
 
 mod imp {
-    use std::cell::RefCell;
+    use std::cell::{Cell, RefCell};
+    use std::future::Future;
     use std::sync::{Arc, Mutex};
-    use std::time::Instant;
+    use std::time::{Instant, SystemTime};
 
     use adw::subclass::breakpoint_bin::BreakpointBinImpl;
     use glib::subclass::InitializingObject;
@@ -34,11 +45,18 @@ mod imp {
     use url::Url;
 
     use crate::app::CarteroApplication;
-    use crate::client::{BoundRequest, RequestError};
-    use crate::entities::{EndpointData, KeyValue};
+    use crate::client::{
+        cancellable, introspection_endpoint, BoundRequest, GraphQLSchema, RequestError,
+    };
+    use crate::entities::{
+        Assertion, ClientOptions, EndpointData, Environment, Extraction, KeyValue, ResponseData,
+    };
     use crate::error::CarteroError;
     use crate::objects::KeyValueItem;
-    use crate::widgets::{ItemPane, KeyValuePane, MethodDropdown, PayloadTab, ResponsePanel};
+    use crate::widgets::{
+        AuthPane, ItemPane, KeyValuePane, MethodDropdown, PayloadTab, ResponsePanel,
+    };
+    use crate::win::CarteroWindow;
 
     #[derive(CompositeTemplate, Properties, Default)]
     #[template(resource = "/es/danirod/Cartero/endpoint_pane.ui")]
@@ -65,6 +83,9 @@ mod imp {
         #[template_child]
         pub payload_pane: TemplateChild<PayloadTab>,
 
+        #[template_child]
+        pub auth_pane: TemplateChild<AuthPane>,
+
         #[template_child]
         pub response: TemplateChild<ResponsePanel>,
 
@@ -75,6 +96,21 @@ mod imp {
         pub item_pane: RefCell<Option<ItemPane>>,
 
         variable_changing: Arc<Mutex<bool>>,
+
+        /// Tests are not editable from the pane, but have to survive a save.
+        tests: RefCell<Vec<Assertion>>,
+
+        /// Extraction rules are not editable from the pane either.
+        pub extractions: RefCell<Vec<Extraction>>,
+
+        /// Client options of the request file, which are not editable either.
+        options: RefCell<ClientOptions>,
+
+        /// The last request that was sent and its response, kept to export them.
+        pub last_exchange: RefCell<Option<(BoundRequest, ResponseData, SystemTime)>>,
+
+        /// Whether a request is in flight, so that another one cannot be sent.
+        pub requesting: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -191,6 +227,8 @@ mod imp {
                 .connect_changed(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
             self.payload_pane
                 .connect_changed(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
+            self.auth_pane
+                .connect_changed(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
             self.header_pane
                 .connect_changed(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
             self.variable_pane
@@ -215,9 +253,10 @@ mod imp {
         /// For a request to be formed, an URL has to be set. You cannot submit a request if
         /// you haven't introduced an URL into the corresponding entry field. Every other field
         /// can be blank.
-        fn update_send_button_sensitivity(&self) {
+        pub(super) fn update_send_button_sensitivity(&self) {
             let empty = self.request_url.buffer().text().is_empty();
-            self.send_button.set_sensitive(!empty);
+            self.send_button
+                .set_sensitive(!empty && !self.requesting.get());
         }
 
         #[template_callback]
@@ -248,6 +287,10 @@ mod imp {
             self.header_pane.set_entries(&headers);
             self.variable_pane.set_entries(&variables);
             self.payload_pane.set_payload(&endpoint.body);
+            self.auth_pane.set_auth(&endpoint.auth);
+            self.tests.replace(endpoint.tests.clone());
+            self.extractions.replace(endpoint.extractions.clone());
+            self.options.replace(endpoint.options.clone());
         }
 
         /// Takes the current state of the pane and extracts it into an Endpoint value.
@@ -256,7 +299,7 @@ mod imp {
             let variable_list = self.variable_pane.get_entries();
 
             let url = String::from(self.request_url.buffer().text());
-            let method = self.request_method.request_method();
+            let method = self.request_method.request_method()?;
 
             let headers = header_list
                 .iter()
@@ -278,33 +321,170 @@ mod imp {
                 .collect();
 
             let body = self.payload_pane.payload();
+            let auth = self.auth_pane.auth();
+            let tests = self.tests.borrow().clone();
+            let extractions = self.extractions.borrow().clone();
+            let options = self.options.borrow().clone();
             Ok(EndpointData {
                 url,
                 method,
                 headers,
                 variables,
                 body,
+                auth,
+                tests,
+                extractions,
+                options,
             })
         }
 
         /// Executes an HTTP request based on the current contents of the pane.
-        pub(super) async fn perform_request(&self) -> Result<(), CarteroError> {
-            let request = self.extract_endpoint()?;
-            let request = BoundRequest::try_from(request)?;
-            let request_obj = isahc::Request::try_from(request)?;
+        ///
+        /// The request is aborted if the `cancel` future completes before it finishes.
+        pub(super) async fn perform_request(
+            &self,
+            environment: Option<&Environment>,
+            cancel: impl Future<Output = ()>,
+        ) -> Result<(), CarteroError> {
+            let endpoint = self.extract_endpoint()?;
+            let outcome = cancellable(self.send_endpoint(endpoint, environment), cancel).await;
+            // Cancelling drops the request even if it was in the middle of a stream.
+            self.response.finish_stream();
+            let (request, response, started) = outcome?;
+            self.response.assign_from_response(&response);
+            let results = crate::assertions::evaluate_all(&self.tests.borrow(), &response);
+            self.response.assign_test_results(&results);
+            self.last_exchange
+                .replace(Some((request, response, started)));
+            Ok(())
+        }
+
+        /// Asks the server for its GraphQL schema, using the settings of the pane.
+        pub(super) async fn fetch_graphql_schema(
+            &self,
+            environment: Option<&Environment>,
+        ) -> Result<GraphQLSchema, CarteroError> {
+            let endpoint = introspection_endpoint(&self.extract_endpoint()?);
+            let (_, response, _) = self.send_endpoint(endpoint, environment).await?;
+            Ok(GraphQLSchema::parse(&response.body)?)
+        }
 
+        /// Binds the endpoint, completes it with the settings of the application
+        /// and sends it.
+        async fn send_endpoint(
+            &self,
+            endpoint: EndpointData,
+            environment: Option<&Environment>,
+        ) -> Result<(BoundRequest, ResponseData, SystemTime), CarteroError> {
+            let base_dir = self
+                .obj()
+                .item_pane()
+                .and_then(|pane| pane.file())
+                .and_then(|file| file.parent())
+                .and_then(|folder| folder.path());
+            let mut request =
+                BoundRequest::bind_relative_to(endpoint, environment, base_dir.as_deref())?;
+            let window = self.obj().root().and_downcast::<CarteroWindow>();
+            super::prepare_request(&mut request, window.as_ref()).await?;
+            let request_obj = isahc::Request::try_from(request.clone())?;
+
+            let started = SystemTime::now();
             let start = Instant::now();
             let mut response_obj = request_obj
                 .send_async()
                 .await
                 .map_err(RequestError::NetworkError)?;
-            let response = crate::client::extract_isahc_response(&mut response_obj, &start).await?;
-            self.response.assign_from_response(&response);
-            Ok(())
+            // Cookies belong to the server that answered, after any redirection.
+            let url = crate::client::effective_url(&response_obj, &request.url);
+            let response = if crate::client::is_isahc_event_stream(&response_obj) {
+                let stop = self.response.start_stream();
+                let panel = self.response.clone();
+                let outcome =
+                    crate::client::stream_isahc_events(&mut response_obj, &start, stop, |event| {
+                        panel.append_event(&event)
+                    })
+                    .await;
+                self.response.finish_stream();
+                outcome?
+            } else {
+                crate::client::extract_isahc_response(&mut response_obj, &start).await?
+            };
+            super::store_cookies(window.as_ref(), &url, &response)?;
+            Ok((request, response, started))
         }
     }
 }
 
+/// Completes a bound request with the settings of the application before it is
+/// sent: the client options that neither the request nor the environment set,
+/// the proxy of the desktop, the authorization and the cookies of the jar.
+pub(crate) async fn prepare_request(
+    request: &mut BoundRequest,
+    window: Option<&CarteroWindow>,
+) -> Result<(), CarteroError> {
+    request.options = request
+        .options
+        .or(&CarteroApplication::get().client_options());
+    if let Some(proxy) = request.options.proxy.as_mut() {
+        if proxy.mode == ProxyMode::System {
+            if let Some(system) = resolve_system_proxy(&request.url).await {
+                *proxy = system;
+            }
+        }
+    }
+    let parent = window.map(|window| window.clone().upcast::<gtk::Window>());
+    request
+        .authorize(TokenCache::shared(), move |url| {
+            gtk::UriLauncher::new(url).launch(parent.as_ref(), gtk::gio::Cancellable::NONE, |_| {});
+        })
+        .await?;
+    if let Some(path) = window.and_then(|window| window.cookie_jar_path()) {
+        CookieJar::load(&path, SystemTime::now())?.apply(request, SystemTime::now());
+    }
+    Ok(())
+}
+
+/// Keeps the cookies set by the response in the jar, unless cookies are disabled.
+pub(crate) fn store_cookies(
+    window: Option<&CarteroWindow>,
+    url: &str,
+    response: &ResponseData,
+) -> Result<(), CarteroError> {
+    if let Some(path) = window.and_then(|window| window.cookie_jar_path()) {
+        // The jar is read again, in case another request changed it meanwhile.
+        let now = SystemTime::now();
+        let mut jar = CookieJar::load(&path, now)?;
+        jar.store_response(url, &response.headers, now);
+        jar.save(&path)?;
+    }
+    Ok(())
+}
+
+/// Asks the desktop which proxy reaches the given URL, since its settings are
+/// not visible to the HTTP client. Returns `None` if the desktop cannot tell,
+/// in which case the client falls back to the proxy environment variables.
+async fn resolve_system_proxy(url: &str) -> Option<ProxySettings> {
+    let resolver = gtk::gio::ProxyResolver::default();
+    let proxies = resolver.lookup_future(url).await.ok()?;
+    let proxy = proxies.first()?;
+    if proxy.starts_with("direct://") {
+        return Some(ProxySettings {
+            mode: ProxyMode::Direct,
+            ..Default::default()
+        });
+    }
+    // GIO uses socks:// for proxies of an unknown SOCKS version.
+    let url = match proxy.strip_prefix("socks://") {
+        Some(address) => format!("socks5://{address}"),
+        None => proxy.to_string(),
+    };
+    Some(ProxySettings {
+        mode: ProxyMode::Manual,
+        url,
+        ..Default::default()
+    })
+}
+
 glib::wrapper! {
     pub struct EndpointPane(ObjectSubclass<imp::EndpointPane>)
         @extends gtk::Widget, gtk::Box;
@@ -330,16 +510,87 @@ impl EndpointPane {
         imp.extract_endpoint()
     }
 
+    /// Writes the current contents of the pane as a command or a code snippet.
+    pub fn export_request(
+        &self,
+        format: ExportFormat,
+        environment: Option<&Environment>,
+        options: &ExportOptions,
+    ) -> Result<String, CarteroError> {
+        let endpoint = self.extract_endpoint()?;
+        crate::export::export_endpoint(&endpoint, environment, format, options)
+    }
+
+    /// The last request sent from this pane, with its response and the time it was sent.
+    pub fn last_exchange(&self) -> Option<(BoundRequest, ResponseData, SystemTime)> {
+        self.imp().last_exchange.borrow().clone()
+    }
+
+    /// Shows an exchange that was recorded elsewhere as if it had been sent from this pane.
+    pub fn show_exchange(
+        &self,
+        request: BoundRequest,
+        response: ResponseData,
+        started: SystemTime,
+    ) {
+        let imp = self.imp();
+        imp.response.assign_from_response(&response);
+        imp.last_exchange
+            .replace(Some((request, response, started)));
+    }
+
+    /// Applies the extraction rules of the request to the last response that
+    /// it got, returning nothing if it was not sent yet.
+    pub fn extract_variables(&self) -> Vec<ExtractionResult> {
+        let imp = self.imp();
+        let exchange = imp.last_exchange.borrow();
+        match exchange.as_ref() {
+            Some((_, response, _)) => {
+                crate::extraction::extract_all(&imp.extractions.borrow(), response)
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Writes the last request sent from this pane and its response as a HAR archive.
+    pub fn export_har(&self) -> Result<String, CarteroError> {
+        let imp = self.imp();
+        let exchange = imp.last_exchange.borrow();
+        let Some((request, response, started)) = exchange.as_ref() else {
+            return Err(CarteroError::NoResponse);
+        };
+        crate::export::har::export_har(request, response, *started)
+    }
+
     /// Executes an HTTP request based on the current contents of the pane.
-    ///
-    /// TODO: Should actually the EndpointPane do the requests? This method
-    /// will probably change once collections are correctly implemented,
-    /// since the EndpointPane would be probably bound to an Endpoint object.
-    pub async fn perform_request(&self) -> Result<(), CarteroError> {
+    pub async fn perform_request(
+        &self,
+        environment: Option<&Environment>,
+    ) -> Result<(), CarteroError> {
         let imp = self.imp();
-        imp.response.set_spinning(true);
-        let outcome = imp.perform_request().await;
+        if imp.requesting.replace(true) {
+            return Ok(());
+        }
+        imp.update_send_button_sensitivity();
+        let cancel = imp.response.start_request();
+        let outcome = imp.perform_request(environment, cancel).await;
         imp.response.set_spinning(false);
+        imp.requesting.set(false);
+        imp.update_send_button_sensitivity();
         outcome
     }
+
+    /// Aborts the request that is being sent from this pane, if any.
+    pub fn cancel_request(&self) {
+        self.imp().response.cancel();
+    }
+
+    /// Fetches the GraphQL schema of the server the pane points to.
+    pub async fn fetch_graphql_schema(
+        &self,
+        environment: Option<&Environment>,
+    ) -> Result<GraphQLSchema, CarteroError> {
+        let imp = self.imp();
+        imp.fetch_graphql_schema(environment).await
+    }
 }
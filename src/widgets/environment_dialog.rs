@@ -0,0 +1,141 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use adw::prelude::*;
+use glib::subclass::types::ObjectSubclassIsExt;
+use glib::Object;
+
+use crate::entities::{Environment, KeyValue};
+use crate::objects::KeyValueItem;
+
+mod imp {
+    use std::cell::RefCell;
+    use std::sync::OnceLock;
+
+    use adw::prelude::*;
+    use adw::subclass::prelude::*;
+    use glib::subclass::{InitializingObject, Signal};
+    use gtk::CompositeTemplate;
+
+    use crate::widgets::{KeyValuePane, ProxyGroup};
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/environment_dialog.ui")]
+    pub struct EnvironmentDialog {
+        #[template_child]
+        pub(super) variable_pane: TemplateChild<KeyValuePane>,
+
+        #[template_child]
+        pub(super) proxy_group: TemplateChild<ProxyGroup>,
+
+        pub(super) name: RefCell<String>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for EnvironmentDialog {
+        const NAME: &'static str = "CarteroEnvironmentDialog";
+        type Type = super::EnvironmentDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for EnvironmentDialog {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("saved").build()])
+        }
+    }
+
+    impl WidgetImpl for EnvironmentDialog {}
+
+    impl AdwDialogImpl for EnvironmentDialog {}
+
+    #[gtk::template_callbacks]
+    impl EnvironmentDialog {
+        #[template_callback]
+        fn on_cancel_clicked(&self) {
+            self.obj().close();
+        }
+
+        #[template_callback]
+        fn on_save_clicked(&self) {
+            let obj = self.obj();
+            obj.emit_by_name::<()>("saved", &[]);
+            obj.close();
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct EnvironmentDialog(ObjectSubclass<imp::EnvironmentDialog>)
+        @extends gtk::Widget, adw::Dialog,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl EnvironmentDialog {
+    pub fn new(environment: &Environment) -> Self {
+        let dialog: Self = Object::builder()
+            .property("title", &environment.name)
+            .build();
+        let imp = dialog.imp();
+        imp.name.replace(environment.name.clone());
+        let variables: Vec<KeyValueItem> = environment
+            .variables
+            .iter()
+            .map(|item| KeyValueItem::from(item.clone()))
+            .collect();
+        imp.variable_pane.set_entries(&variables);
+        imp.proxy_group.set_inheritable(true);
+        imp.proxy_group.set_proxy(environment.proxy.as_ref());
+        dialog
+    }
+
+    /// Builds the environment using the current contents of the dialog.
+    pub fn environment(&self) -> Environment {
+        let imp = self.imp();
+        let variables = imp
+            .variable_pane
+            .get_entries()
+            .into_iter()
+            .map(KeyValue::from)
+            .collect();
+        Environment {
+            name: imp.name.borrow().clone(),
+            variables,
+            proxy: imp.proxy_group.proxy(),
+        }
+    }
+
+    /// Connects to the signal emitted when the user accepts the changes.
+    pub fn connect_saved<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "saved",
+            true,
+            glib::closure_local!(|ref dialog| {
+                f(dialog);
+            }),
+        )
+    }
+}
@@ -168,3 +168,196 @@ pub async fn save_file(win: &CarteroWindow) -> Result<gio::File, CarteroError> {
 
     Ok(file)
 }
+
+pub async fn open_folder(win: &CarteroWindow) -> Result<gio::File, CarteroError> {
+    let dialog = FileDialog::builder()
+        .accept_label(gettext("Select"))
+        .title(gettext("Select collection folder"))
+        .modal(true)
+        .build();
+
+    let app = CarteroApplication::get();
+    let settings = app.settings();
+    if let Some(dir) = settings.get::<Option<String>>("last-open-dir") {
+        let path = PathBuf::from(&dir);
+        let file = gtk::gio::File::for_path(path);
+        dialog.set_initial_folder(Some(&file));
+    }
+
+    let folder = dialog.select_folder_future(Some(win)).await.map_err(|e| {
+        if let Some(file_error) = e.kind::<DialogError>() {
+            match file_error {
+                DialogError::Dismissed => CarteroError::NoFilePicked,
+                _ => CarteroError::FileDialogError,
+            }
+        } else {
+            CarteroError::FileDialogError
+        }
+    })?;
+
+    if let Some(parent) = folder.parent() {
+        if let Some(location) = parent.path() {
+            let string = location.to_str().ok_or(CarteroError::FileDialogError)?;
+            settings
+                .set("last-open-dir", Some(string))
+                .map_err(|_| CarteroError::FileDialogError)?;
+        }
+    }
+
+    Ok(folder)
+}
+
+/// Picks a file written by another application in order to import its contents.
+///
+/// The patterns are used to build the default filter of the dialog, although any
+/// file can still be picked.
+pub async fn open_import_file(
+    win: &CarteroWindow,
+    title: &str,
+    filter_name: &str,
+    patterns: &[&str],
+) -> Result<gio::File, CarteroError> {
+    open_foreign_file(win, &gettext("Import"), title, filter_name, patterns).await
+}
+
+/// Picks the CSV or JSON file whose rows are bound to the variables of a run.
+pub async fn open_data_file(win: &CarteroWindow) -> Result<gio::File, CarteroError> {
+    open_foreign_file(
+        win,
+        &gettext("Select"),
+        &gettext("Select data file"),
+        &gettext("Data files (CSV, JSON)"),
+        &["*.csv", "*.json"],
+    )
+    .await
+}
+
+async fn open_foreign_file(
+    win: &CarteroWindow,
+    accept_label: &str,
+    title: &str,
+    filter_name: &str,
+    patterns: &[&str],
+) -> Result<gio::File, CarteroError> {
+    let filters = ListStore::with_type(FileFilter::static_type());
+    let filter = FileFilter::new();
+    for pattern in patterns {
+        filter.add_pattern(pattern);
+    }
+    filter.set_name(Some(filter_name));
+    filters.append(&filter);
+    let any = FileFilter::new();
+    any.add_pattern("*");
+    any.set_name(Some(&gettext("All files")));
+    filters.append(&any);
+
+    let dialog = FileDialog::builder()
+        .accept_label(accept_label)
+        .title(title)
+        .filters(&filters)
+        .default_filter(&filter)
+        .modal(true)
+        .build();
+
+    let app = CarteroApplication::get();
+    let settings = app.settings();
+    if let Some(dir) = settings.get::<Option<String>>("last-open-dir") {
+        let path = PathBuf::from(&dir);
+        let file = gtk::gio::File::for_path(path);
+        dialog.set_initial_folder(Some(&file));
+    }
+
+    let file = dialog.open_future(Some(win)).await.map_err(|e| {
+        if let Some(file_error) = e.kind::<DialogError>() {
+            match file_error {
+                DialogError::Dismissed => CarteroError::NoFilePicked,
+                _ => CarteroError::FileDialogError,
+            }
+        } else {
+            CarteroError::FileDialogError
+        }
+    })?;
+
+    if let Some(folder) = file.parent() {
+        if let Some(location) = folder.path() {
+            let string = location.to_str().ok_or(CarteroError::FileDialogError)?;
+            settings
+                .set("last-open-dir", Some(string))
+                .map_err(|_| CarteroError::FileDialogError)?;
+        }
+    }
+
+    Ok(file)
+}
+
+/// Picks the location of a file that will be written in a format other than Cartero's.
+pub async fn save_export_file(
+    win: &CarteroWindow,
+    title: &str,
+    initial_name: &str,
+) -> Result<gio::File, CarteroError> {
+    let dialog = FileDialog::builder()
+        .accept_label(gettext("Export"))
+        .title(title)
+        .modal(true)
+        .initial_name(initial_name)
+        .build();
+
+    let app = CarteroApplication::get();
+    let settings = app.settings();
+    if let Some(dir) = settings.get::<Option<String>>("last-save-dir") {
+        let path = PathBuf::from(&dir);
+        let file = gtk::gio::File::for_path(path);
+        dialog.set_initial_folder(Some(&file));
+    }
+
+    let file = dialog.save_future(Some(win)).await.map_err(|e| {
+        if let Some(file_error) = e.kind::<DialogError>() {
+            match file_error {
+                DialogError::Dismissed => CarteroError::NoFilePicked,
+                _ => CarteroError::FileDialogError,
+            }
+        } else {
+            CarteroError::FileDialogError
+        }
+    })?;
+
+    if let Some(folder) = file.parent() {
+        if let Some(location) = folder.path() {
+            let string = location.to_str().ok_or(CarteroError::FileDialogError)?;
+            settings
+                .set("last-save-dir", Some(string))
+                .map_err(|_| CarteroError::FileDialogError)?;
+        }
+    }
+
+    Ok(file)
+}
+
+/// Picks any file in the system, such as a file to be attached to a request body.
+pub async fn open_attachment(win: &CarteroWindow) -> Result<gio::File, CarteroError> {
+    let dialog = FileDialog::builder()
+        .accept_label(gettext("Select"))
+        .title(gettext("Select file"))
+        .modal(true)
+        .build();
+
+    let app = CarteroApplication::get();
+    let settings = app.settings();
+    if let Some(dir) = settings.get::<Option<String>>("last-open-dir") {
+        let path = PathBuf::from(&dir);
+        let file = gtk::gio::File::for_path(path);
+        dialog.set_initial_folder(Some(&file));
+    }
+
+    dialog.open_future(Some(win)).await.map_err(|e| {
+        if let Some(file_error) = e.kind::<DialogError>() {
+            match file_error {
+                DialogError::Dismissed => CarteroError::NoFilePicked,
+                _ => CarteroError::FileDialogError,
+            }
+        } else {
+            CarteroError::FileDialogError
+        }
+    })
+}
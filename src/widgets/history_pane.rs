@@ -0,0 +1,187 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+use glib::subclass::types::ObjectSubclassIsExt;
+use glib::Object;
+
+use crate::history::HistoryEntry;
+
+mod imp {
+    use std::cell::RefCell;
+    use std::time::UNIX_EPOCH;
+
+    use adw::prelude::*;
+    use adw::subclass::prelude::*;
+    use glib::subclass::InitializingObject;
+    use gtk::CompositeTemplate;
+
+    use crate::app::CarteroApplication;
+    use crate::history::HistoryEntry;
+    use crate::widgets::response_panel::format_bytes;
+    use crate::win::CarteroWindow;
+
+    #[derive(CompositeTemplate, Default)]
+    #[template(resource = "/es/danirod/Cartero/history_pane.ui")]
+    pub struct HistoryPane {
+        #[template_child]
+        stack: TemplateChild<gtk::Stack>,
+
+        #[template_child]
+        entry_list: TemplateChild<gtk::ListBox>,
+
+        #[template_child]
+        clear_button: TemplateChild<gtk::Button>,
+
+        /// The entries shown in the list, in the same order as the rows.
+        pub(super) entries: RefCell<Vec<HistoryEntry>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for HistoryPane {
+        const NAME: &'static str = "CarteroHistoryPane";
+        type Type = super::HistoryPane;
+        type ParentType = adw::Bin;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for HistoryPane {}
+
+    impl WidgetImpl for HistoryPane {}
+
+    impl BinImpl for HistoryPane {}
+
+    #[gtk::template_callbacks]
+    impl HistoryPane {
+        #[template_callback]
+        fn on_row_activated(&self, row: &gtk::ListBoxRow) {
+            let Ok(index) = usize::try_from(row.index()) else {
+                return;
+            };
+            let Some(entry) = self.entries.borrow().get(index).cloned() else {
+                return;
+            };
+            if let Some(window) = self.obj().root().and_downcast::<CarteroWindow>() {
+                window.open_history_entry(&entry);
+            }
+        }
+
+        #[template_callback]
+        fn on_clear_clicked(&self) {
+            if let Err(e) = CarteroApplication::get().history().clear() {
+                if let Some(window) = self.obj().root().and_downcast::<CarteroWindow>() {
+                    window.toast_error(e);
+                }
+                return;
+            }
+            self.entries.borrow_mut().clear();
+            self.entry_list.remove_all();
+            self.update_visibility();
+        }
+
+        pub(super) fn build_row(entry: &HistoryEntry) -> adw::ActionRow {
+            let title = format!("{} {}", entry.request.method.as_str(), entry.request.url);
+            let seconds = entry
+                .timestamp
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let time = glib::DateTime::from_unix_local(seconds as i64)
+                .and_then(|time| time.format("%x %X"))
+                .map(String::from)
+                .unwrap_or_default();
+            let subtitle = format!(
+                "{} · {} ms · {} · {}",
+                entry.response.status_code,
+                entry.response.duration,
+                format_bytes(entry.response.size),
+                time
+            );
+            let row = adw::ActionRow::builder()
+                .title(title)
+                .subtitle(subtitle)
+                .title_lines(1)
+                .subtitle_lines(1)
+                .use_markup(false)
+                .activatable(true)
+                .build();
+            row.set_tooltip_text(Some(&entry.request.url));
+            row
+        }
+
+        pub(super) fn update_visibility(&self) {
+            let empty = self.entries.borrow().is_empty();
+            self.stack
+                .set_visible_child_name(if empty { "empty" } else { "entries" });
+            self.clear_button.set_sensitive(!empty);
+        }
+
+        pub(super) fn set_entries(&self, entries: Vec<HistoryEntry>) {
+            self.entry_list.remove_all();
+            for entry in &entries {
+                self.entry_list.append(&Self::build_row(entry));
+            }
+            self.entries.replace(entries);
+            self.update_visibility();
+        }
+
+        pub(super) fn prepend(&self, entry: HistoryEntry, limit: usize) {
+            self.entry_list.prepend(&Self::build_row(&entry));
+            let mut entries = self.entries.borrow_mut();
+            entries.insert(0, entry);
+            while entries.len() > limit {
+                entries.pop();
+                if let Some(row) = self.entry_list.row_at_index(entries.len() as i32) {
+                    self.entry_list.remove(&row);
+                }
+            }
+            drop(entries);
+            self.update_visibility();
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct HistoryPane(ObjectSubclass<imp::HistoryPane>)
+        @extends gtk::Widget, adw::Bin,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl Default for HistoryPane {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+impl HistoryPane {
+    /// Fills the list with the given entries, the most recent first.
+    pub fn set_entries(&self, entries: Vec<HistoryEntry>) {
+        self.imp().set_entries(entries);
+    }
+
+    /// Adds an entry that was just recorded on top of the list, dropping the
+    /// oldest rows so that the list does not show more than `limit` entries.
+    pub fn prepend(&self, entry: HistoryEntry, limit: usize) {
+        self.imp().prepend(entry, limit);
+    }
+}
@@ -20,9 +20,10 @@ use gettextrs::gettext;
 use glib::Object;
 use gtk::{gio, ClosureExpression};
 
+use crate::entities::{EndpointData, ItemData, WebSocketData};
 use crate::error::CarteroError;
 
-use super::EndpointPane;
+use super::{EndpointPane, WebSocketPane};
 
 mod imp {
     use std::cell::RefCell;
@@ -65,27 +66,72 @@ glib::wrapper! {
 }
 
 impl ItemPane {
-    pub async fn new_for_endpoint(file: Option<&gio::File>) -> Result<Self, CarteroError> {
+    /// Opens the given file in a pane of the kind of item it contains, or creates
+    /// a pane for a new HTTP request if no file is given.
+    pub async fn new_for_file(file: Option<&gio::File>) -> Result<Self, CarteroError> {
+        let Some(path) = file else {
+            return Ok(Self::new_for_endpoint(None, &EndpointData::default()));
+        };
+        let contents = crate::file::read_file(path).await?;
+        let pane = match crate::file::parse_item(&contents)? {
+            ItemData::Endpoint(endpoint) => Self::new_for_endpoint(file, &endpoint),
+            ItemData::WebSocket(websocket) => Self::new_for_websocket(file, &websocket),
+        };
+        Ok(pane)
+    }
+
+    pub fn new_for_endpoint(file: Option<&gio::File>, endpoint: &EndpointData) -> Self {
         let pane: Self = Object::builder().property("file", file).build();
 
         let child_pane = EndpointPane::default();
         pane.set_child(Some(&child_pane));
+        child_pane.assign_endpoint(endpoint);
+        child_pane.set_item_pane(Some(&pane));
 
-        if let Some(path) = file {
-            let contents = crate::file::read_file(path).await?;
-            let endpoint = crate::file::parse_toml(&contents)?;
-            child_pane.assign_endpoint(&endpoint);
-        }
+        pane
+    }
+
+    pub fn new_for_websocket(file: Option<&gio::File>, websocket: &WebSocketData) -> Self {
+        let pane: Self = Object::builder().property("file", file).build();
 
+        let child_pane = WebSocketPane::default();
+        pane.set_child(Some(&child_pane));
+        child_pane.assign_websocket(websocket);
         child_pane.set_item_pane(Some(&pane));
 
-        Ok(pane)
+        pane
     }
 
     pub fn endpoint(&self) -> Option<EndpointPane> {
         self.child().and_downcast::<EndpointPane>()
     }
 
+    pub fn websocket(&self) -> Option<WebSocketPane> {
+        self.child().and_downcast::<WebSocketPane>()
+    }
+
+    /// Aborts whatever the pane is waiting for, such as a request that is being
+    /// sent or an open WebSocket connection.
+    pub fn cancel(&self) {
+        if let Some(endpoint) = self.endpoint() {
+            endpoint.cancel_request();
+        }
+        if let Some(websocket) = self.websocket() {
+            websocket.disconnect();
+        }
+    }
+
+    /// Takes the current contents of the pane, whatever the kind of item it holds.
+    pub fn extract_item(&self) -> Result<Option<ItemData>, CarteroError> {
+        if let Some(endpoint) = self.endpoint() {
+            let endpoint = endpoint.extract_endpoint()?;
+            return Ok(Some(ItemData::Endpoint(Box::new(endpoint))));
+        }
+        Ok(self
+            .websocket()
+            .map(|websocket| ItemData::WebSocket(websocket.extract_websocket())))
+    }
+
     pub fn window_title_binding(&self) -> ClosureExpression {
         ClosureExpression::new::<String>(
             [
@@ -17,6 +17,7 @@
 
 use glib::{object::ObjectExt, subclass::types::ObjectSubclassIsExt};
 
+use crate::client::RequestError;
 use crate::entities::RequestMethod;
 
 mod imp {
@@ -27,8 +28,12 @@ mod imp {
         object::{Cast, ObjectExt},
         subclass::{InitializingObject, Signal},
     };
-    use gtk::{prelude::ListModelExt, CompositeTemplate, StringObject, TemplateChild};
+    use gtk::{
+        prelude::{EditableExt, ListModelExt, WidgetExt},
+        CompositeTemplate, StringObject, TemplateChild,
+    };
 
+    use crate::client::RequestError;
     use crate::entities::RequestMethod;
 
     #[derive(Default, CompositeTemplate)]
@@ -39,6 +44,9 @@ mod imp {
 
         #[template_child]
         verbs_string_list: TemplateChild<gtk::StringList>,
+
+        #[template_child]
+        custom_entry: TemplateChild<gtk::Entry>,
     }
 
     #[glib::object_subclass]
@@ -72,10 +80,40 @@ mod imp {
     impl MethodDropdown {
         #[template_callback]
         fn on_selection_changed(&self) {
+            let custom = self.is_custom_selected();
+            self.custom_entry.set_visible(custom);
+            if custom {
+                self.custom_entry.grab_focus();
+            }
             self.obj().emit_by_name::<()>("changed", &[]);
         }
 
-        pub(super) fn request_method(&self) -> RequestMethod {
+        #[template_callback]
+        fn on_custom_changed(&self) {
+            let text = self.custom_entry.text();
+            if text.is_empty() || RequestMethod::try_from(text.as_str()).is_ok() {
+                self.custom_entry.remove_css_class("error");
+            } else {
+                self.custom_entry.add_css_class("error");
+            }
+            self.obj().emit_by_name::<()>("changed", &[]);
+        }
+
+        /// The last item of the list lets the user type any other method.
+        fn custom_position(&self) -> u32 {
+            self.verbs_string_list.n_items() - 1
+        }
+
+        fn is_custom_selected(&self) -> bool {
+            self.dropdown.selected() == self.custom_position()
+        }
+
+        pub(super) fn request_method(&self) -> Result<RequestMethod, RequestError> {
+            if self.is_custom_selected() {
+                let text = self.custom_entry.text();
+                return RequestMethod::try_from(text.trim())
+                    .map_err(|_| RequestError::InvalidHttpVerb);
+            }
             let method = self
                 .dropdown
                 .selected_item()
@@ -86,12 +124,17 @@ mod imp {
             // Note: we should probably be safe from unwrapping here, since it would
             // be impossible to have a method that is not an acceptable value without
             // completely hacking and wrecking the user interface.
-            RequestMethod::try_from(method.as_str()).unwrap()
+            Ok(RequestMethod::try_from(method.as_str()).unwrap())
         }
 
         pub(super) fn set_request_method(&self, rm: RequestMethod) {
+            if let RequestMethod::Custom(name) = rm {
+                self.custom_entry.set_text(&name);
+                self.dropdown.set_selected(self.custom_position());
+                return;
+            }
             let verb_to_find = String::from(rm);
-            let element_count = self.dropdown.model().unwrap().n_items();
+            let element_count = self.custom_position();
             let target_position = (0..element_count).find(|i| {
                 if let Some(verb) = self.verbs_string_list.string(*i) {
                     if verb == verb_to_find {
@@ -128,7 +171,8 @@ impl MethodDropdown {
         self.imp().set_request_method(rm)
     }
 
-    pub fn request_method(&self) -> RequestMethod {
+    /// The selected method. Fails if the custom method is not a valid one.
+    pub fn request_method(&self) -> Result<RequestMethod, RequestError> {
         self.imp().request_method()
     }
 }
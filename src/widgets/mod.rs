@@ -15,24 +15,44 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+mod auth_pane;
+mod collection_pane;
+mod cookie_dialog;
 mod endpoint_pane;
+mod environment_dialog;
 mod file_dialogs;
+mod history_pane;
 mod item_pane;
 mod key_value_pane;
 mod key_value_row;
 mod method_dropdown;
+mod multipart_file_row;
+mod preferences_dialog;
+mod proxy_group;
 mod request_body;
 mod response_headers;
 mod response_panel;
+mod runner_dialog;
 mod save_dialog;
+mod websocket_pane;
 
+pub use auth_pane::AuthPane;
+pub use collection_pane::CollectionPane;
+pub use cookie_dialog::CookieDialog;
 pub use endpoint_pane::EndpointPane;
+pub use environment_dialog::EnvironmentDialog;
 pub use file_dialogs::*;
+pub use history_pane::HistoryPane;
 pub use item_pane::ItemPane;
 pub use key_value_pane::KeyValuePane;
 pub use key_value_row::KeyValueRow;
 pub use method_dropdown::MethodDropdown;
+pub use multipart_file_row::MultipartFileRow;
+pub use preferences_dialog::PreferencesDialog;
+pub use proxy_group::ProxyGroup;
 pub use request_body::*;
 pub use response_headers::ResponseHeaders;
 pub use response_panel::ResponsePanel;
+pub use runner_dialog::RunnerDialog;
 pub use save_dialog::SaveDialog;
+pub use websocket_pane::WebSocketPane;
@@ -0,0 +1,204 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+use adw::prelude::*;
+use glib::object::ObjectExt;
+use gtk::subclass::prelude::*;
+
+use crate::entities::MultipartFile;
+
+mod imp {
+    use std::cell::RefCell;
+    use std::sync::OnceLock;
+
+    use adw::prelude::*;
+    use adw::subclass::prelude::*;
+    use adw::{ActionRow, EntryRow};
+    use glib::subclass::{InitializingObject, Signal};
+    use gtk::gio::prelude::FileExt;
+    use gtk::{template_callbacks, CheckButton, CompositeTemplate};
+
+    use crate::error::CarteroError;
+    use crate::widgets::open_attachment;
+    use crate::win::CarteroWindow;
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/multipart_file_row.ui")]
+    pub struct MultipartFileRow {
+        #[template_child]
+        pub(super) active: TemplateChild<CheckButton>,
+
+        #[template_child]
+        pub(super) field_name: TemplateChild<EntryRow>,
+
+        #[template_child]
+        pub(super) path_row: TemplateChild<ActionRow>,
+
+        #[template_child]
+        pub(super) filename: TemplateChild<EntryRow>,
+
+        #[template_child]
+        pub(super) content_type: TemplateChild<EntryRow>,
+
+        pub(super) path: RefCell<String>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MultipartFileRow {
+        const NAME: &'static str = "CarteroMultipartFileRow";
+        type Type = super::MultipartFileRow;
+        type ParentType = adw::ExpanderRow;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MultipartFileRow {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("changed").build(),
+                    Signal::builder("delete").build(),
+                ]
+            })
+        }
+    }
+
+    impl WidgetImpl for MultipartFileRow {}
+
+    impl ListBoxRowImpl for MultipartFileRow {}
+
+    impl PreferencesRowImpl for MultipartFileRow {}
+
+    impl ExpanderRowImpl for MultipartFileRow {}
+
+    #[template_callbacks]
+    impl MultipartFileRow {
+        #[template_callback]
+        fn on_field_changed(&self) {
+            self.update_title();
+            self.obj().emit_by_name::<()>("changed", &[]);
+        }
+
+        #[template_callback]
+        fn on_delete_clicked(&self) {
+            self.obj().emit_by_name::<()>("delete", &[]);
+        }
+
+        #[template_callback]
+        fn on_choose_clicked(&self) {
+            let Some(window) = self.obj().root().and_downcast::<CarteroWindow>() else {
+                return;
+            };
+            glib::spawn_future_local(glib::clone!(@weak self as row => async move {
+                match open_attachment(&window).await {
+                    Ok(file) => {
+                        if let Some(path) = file.path() {
+                            row.set_path(&path.to_string_lossy());
+                            row.obj().emit_by_name::<()>("changed", &[]);
+                        }
+                    }
+                    Err(CarteroError::NoFilePicked) => {}
+                    Err(e) => window.toast_error(e),
+                }
+            }));
+        }
+
+        pub(super) fn set_path(&self, path: &str) {
+            self.path.replace(path.to_string());
+            self.path_row.set_subtitle(path);
+            self.update_title();
+        }
+
+        fn update_title(&self) {
+            let name = self.field_name.text();
+            self.obj().set_title(&glib::markup_escape_text(&name));
+            self.obj()
+                .set_subtitle(&glib::markup_escape_text(&self.path.borrow()));
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct MultipartFileRow(ObjectSubclass<imp::MultipartFileRow>)
+        @extends gtk::Widget, gtk::ListBoxRow, adw::PreferencesRow, adw::ExpanderRow,
+        @implements gtk::Accessible, gtk::Buildable, gtk::Actionable;
+}
+
+impl Default for MultipartFileRow {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl MultipartFileRow {
+    pub fn connect_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "changed",
+            true,
+            glib::closure_local!(|ref row| {
+                f(row);
+            }),
+        )
+    }
+
+    pub fn connect_delete<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "delete",
+            true,
+            glib::closure_local!(|ref row| {
+                f(row);
+            }),
+        )
+    }
+
+    pub fn file(&self) -> MultipartFile {
+        let imp = self.imp();
+        let optional = |text: glib::GString| {
+            let text = text.to_string();
+            if text.is_empty() {
+                None
+            } else {
+                Some(text)
+            }
+        };
+        MultipartFile {
+            name: imp.field_name.text().into(),
+            path: imp.path.borrow().clone(),
+            filename: optional(imp.filename.text()),
+            content_type: optional(imp.content_type.text()),
+            active: imp.active.is_active(),
+        }
+    }
+
+    pub fn set_file(&self, file: &MultipartFile) {
+        let imp = self.imp();
+        imp.field_name.set_text(&file.name);
+        imp.set_path(&file.path);
+        imp.filename
+            .set_text(file.filename.as_deref().unwrap_or_default());
+        imp.content_type
+            .set_text(file.content_type.as_deref().unwrap_or_default());
+        imp.active.set_active(file.active);
+    }
+}
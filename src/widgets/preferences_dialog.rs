@@ -0,0 +1,184 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+use glib::Object;
+use gtk::glib;
+
+mod imp {
+    use adw::prelude::*;
+    use adw::subclass::prelude::*;
+    use glib::subclass::InitializingObject;
+    use gtk::gio::Settings;
+    use gtk::CompositeTemplate;
+
+    use crate::app::CarteroApplication;
+    use crate::widgets::ProxyGroup;
+
+    /// The values of the http-version key, in the order shown in the combo row.
+    const HTTP_VERSIONS: [&str; 3] = ["auto", "1.1", "2"];
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/preferences_dialog.ui")]
+    pub struct PreferencesDialog {
+        #[template_child]
+        connect_timeout: TemplateChild<adw::SpinRow>,
+
+        #[template_child]
+        request_timeout: TemplateChild<adw::SpinRow>,
+
+        #[template_child]
+        follow_redirects: TemplateChild<adw::SwitchRow>,
+
+        #[template_child]
+        max_redirects: TemplateChild<adw::SpinRow>,
+
+        #[template_child]
+        cookies_enabled: TemplateChild<adw::SwitchRow>,
+
+        #[template_child]
+        verify_tls: TemplateChild<adw::SwitchRow>,
+
+        #[template_child]
+        http_version: TemplateChild<adw::ComboRow>,
+
+        #[template_child]
+        proxy_group: TemplateChild<ProxyGroup>,
+
+        #[template_child]
+        history_enabled: TemplateChild<adw::SwitchRow>,
+
+        #[template_child]
+        history_save_bodies: TemplateChild<adw::SwitchRow>,
+
+        #[template_child]
+        history_limit: TemplateChild<adw::SpinRow>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PreferencesDialog {
+        const NAME: &'static str = "CarteroPreferencesDialog";
+        type Type = super::PreferencesDialog;
+        type ParentType = adw::PreferencesDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for PreferencesDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.bind_settings();
+        }
+    }
+
+    impl WidgetImpl for PreferencesDialog {}
+
+    impl AdwDialogImpl for PreferencesDialog {}
+
+    impl PreferencesDialogImpl for PreferencesDialog {}
+
+    impl PreferencesDialog {
+        /// Binds an unsigned integer key to the value of a spin row, which is a double.
+        fn bind_uint(settings: &Settings, key: &str, row: &adw::SpinRow) {
+            settings
+                .bind(key, row, "value")
+                .mapping(|variant, _| {
+                    let value = variant.get::<u32>()?;
+                    Some(f64::from(value).to_value())
+                })
+                .set_mapping(|value, _| {
+                    let value = value.get::<f64>().ok()?;
+                    Some((value.round() as u32).to_variant())
+                })
+                .build();
+        }
+
+        fn bind_settings(&self) {
+            let app = CarteroApplication::get();
+            let settings = app.settings();
+
+            Self::bind_uint(settings, "connect-timeout", &self.connect_timeout);
+            Self::bind_uint(settings, "request-timeout", &self.request_timeout);
+            Self::bind_uint(settings, "max-redirects", &self.max_redirects);
+            settings
+                .bind("follow-redirects", &*self.follow_redirects, "active")
+                .build();
+            settings
+                .bind("follow-redirects", &*self.max_redirects, "sensitive")
+                .flags(gtk::gio::SettingsBindFlags::GET)
+                .build();
+            settings
+                .bind("cookies-enabled", &*self.cookies_enabled, "active")
+                .build();
+            settings
+                .bind("verify-tls", &*self.verify_tls, "active")
+                .build();
+            settings
+                .bind("http-version", &*self.http_version, "selected")
+                .mapping(|variant, _| {
+                    let version = variant.get::<String>()?;
+                    let position = HTTP_VERSIONS.iter().position(|v| *v == version)?;
+                    Some((position as u32).to_value())
+                })
+                .set_mapping(|value, _| {
+                    let position = value.get::<u32>().ok()?;
+                    let version = HTTP_VERSIONS.get(position as usize)?;
+                    Some(version.to_variant())
+                })
+                .build();
+
+            Self::bind_uint(settings, "history-limit", &self.history_limit);
+            settings
+                .bind("history-enabled", &*self.history_enabled, "active")
+                .build();
+            settings
+                .bind("history-save-bodies", &*self.history_save_bodies, "active")
+                .build();
+            settings
+                .bind("history-enabled", &*self.history_save_bodies, "sensitive")
+                .flags(gtk::gio::SettingsBindFlags::GET)
+                .build();
+
+            self.proxy_group.set_proxy(Some(&app.proxy_settings()));
+            self.proxy_group
+                .connect_changed(glib::clone!(@weak self as dialog => move |group| {
+                    let Some(proxy) = group.proxy() else {
+                        return;
+                    };
+                    if let Err(e) = CarteroApplication::get().set_proxy_settings(&proxy) {
+                        dialog.obj().add_toast(adw::Toast::new(&e.to_string()));
+                    }
+                }));
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct PreferencesDialog(ObjectSubclass<imp::PreferencesDialog>)
+        @extends gtk::Widget, adw::Dialog, adw::PreferencesDialog,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl Default for PreferencesDialog {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
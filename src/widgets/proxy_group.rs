@@ -0,0 +1,212 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+use adw::prelude::*;
+use gettextrs::gettext;
+use glib::subclass::types::ObjectSubclassIsExt;
+use glib::Object;
+
+use crate::entities::{ProxyMode, ProxySettings};
+
+mod imp {
+    use std::cell::Cell;
+    use std::sync::OnceLock;
+
+    use adw::prelude::*;
+    use adw::subclass::prelude::*;
+    use glib::subclass::{InitializingObject, Signal};
+    use gtk::CompositeTemplate;
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/proxy_group.ui")]
+    pub struct ProxyGroup {
+        #[template_child]
+        pub(super) mode: TemplateChild<adw::ComboRow>,
+
+        #[template_child]
+        pub(super) url: TemplateChild<adw::EntryRow>,
+
+        #[template_child]
+        pub(super) username: TemplateChild<adw::EntryRow>,
+
+        #[template_child]
+        pub(super) password: TemplateChild<adw::PasswordEntryRow>,
+
+        #[template_child]
+        pub(super) no_proxy: TemplateChild<adw::EntryRow>,
+
+        /// Whether the first mode means using the proxy of the preferences.
+        pub(super) inheritable: Cell<bool>,
+
+        /// Set while the rows are filled, so that no change is notified.
+        pub(super) loading: Cell<bool>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ProxyGroup {
+        const NAME: &'static str = "CarteroProxyGroup";
+        type Type = super::ProxyGroup;
+        type ParentType = adw::PreferencesGroup;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for ProxyGroup {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("changed").build()])
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().set_inheritable(false);
+        }
+    }
+
+    impl WidgetImpl for ProxyGroup {}
+
+    impl PreferencesGroupImpl for ProxyGroup {}
+
+    #[gtk::template_callbacks]
+    impl ProxyGroup {
+        #[template_callback]
+        fn on_mode_changed(&self) {
+            self.obj().update_sensitivity();
+            self.on_changed();
+        }
+
+        #[template_callback]
+        fn on_changed(&self) {
+            if !self.loading.get() {
+                self.obj().emit_by_name::<()>("changed", &[]);
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct ProxyGroup(ObjectSubclass<imp::ProxyGroup>)
+        @extends gtk::Widget, adw::PreferencesGroup,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl Default for ProxyGroup {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+const MODES: [ProxyMode; 3] = [ProxyMode::System, ProxyMode::Direct, ProxyMode::Manual];
+
+impl ProxyGroup {
+    /// Allows leaving the proxy unset, so that the one in the preferences is used.
+    pub fn set_inheritable(&self, inheritable: bool) {
+        let imp = self.imp();
+        imp.inheritable.set(inheritable);
+        let mut labels = vec![];
+        if inheritable {
+            labels.push(gettext("Same as preferences"));
+        }
+        labels.push(gettext("System proxy"));
+        labels.push(gettext("No proxy"));
+        labels.push(gettext("Manual proxy"));
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        imp.loading.set(true);
+        imp.mode.set_model(Some(&gtk::StringList::new(&labels)));
+        imp.loading.set(false);
+        self.update_sensitivity();
+    }
+
+    fn selected_mode(&self) -> Option<ProxyMode> {
+        let imp = self.imp();
+        let mut position = imp.mode.selected() as usize;
+        if imp.inheritable.get() {
+            if position == 0 {
+                return None;
+            }
+            position -= 1;
+        }
+        MODES.get(position).copied()
+    }
+
+    fn update_sensitivity(&self) {
+        let imp = self.imp();
+        let manual = self.selected_mode() == Some(ProxyMode::Manual);
+        imp.url.set_sensitive(manual);
+        imp.username.set_sensitive(manual);
+        imp.password.set_sensitive(manual);
+        imp.no_proxy.set_sensitive(manual);
+    }
+
+    /// Fills the rows with the given settings. `None` is only meaningful when
+    /// the group is inheritable.
+    pub fn set_proxy(&self, proxy: Option<&ProxySettings>) {
+        let imp = self.imp();
+        imp.loading.set(true);
+        let offset = u32::from(imp.inheritable.get());
+        let position = match proxy {
+            Some(proxy) => MODES.iter().position(|m| *m == proxy.mode).unwrap_or(0) as u32 + offset,
+            None => 0,
+        };
+        imp.mode.set_selected(position);
+        let proxy = proxy.cloned().unwrap_or_default();
+        imp.url.set_text(&proxy.url);
+        imp.username.set_text(&proxy.username);
+        imp.password.set_text(&proxy.password);
+        imp.no_proxy.set_text(&proxy.no_proxy.join(", "));
+        imp.loading.set(false);
+        self.update_sensitivity();
+    }
+
+    /// Builds the proxy settings using the current contents of the rows.
+    pub fn proxy(&self) -> Option<ProxySettings> {
+        let imp = self.imp();
+        let mode = self.selected_mode()?;
+        let no_proxy = imp
+            .no_proxy
+            .text()
+            .split(',')
+            .map(str::trim)
+            .filter(|host| !host.is_empty())
+            .map(String::from)
+            .collect();
+        Some(ProxySettings {
+            mode,
+            url: imp.url.text().trim().to_string(),
+            username: imp.username.text().to_string(),
+            password: imp.password.text().to_string(),
+            no_proxy,
+        })
+    }
+
+    /// Connects to the signal emitted when the user changes any setting.
+    pub fn connect_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "changed",
+            true,
+            glib::closure_local!(|ref group| {
+                f(group);
+            }),
+        )
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+use glib::{object::ObjectExt, subclass::types::ObjectSubclassIsExt};
+use gtk::prelude::EditableExt;
+
+use crate::entities::RequestPayload;
+
+use super::{BasePayloadPane, BasePayloadPaneExt};
+
+mod imp {
+    use std::path::{Path, PathBuf};
+    use std::sync::OnceLock;
+
+    use adw::prelude::*;
+    use adw::subclass::bin::BinImpl;
+    use adw::{ActionRow, EntryRow};
+    use gettextrs::gettext;
+    use glib::subclass::{InitializingObject, Signal};
+    use gtk::subclass::prelude::*;
+    use gtk::{template_callbacks, CompositeTemplate};
+
+    use crate::client::{guess_content_type, resolve_path};
+    use crate::error::CarteroError;
+    use crate::widgets::{open_attachment, BasePayloadPane, BasePayloadPaneImpl, ItemPane};
+    use crate::win::CarteroWindow;
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/binary_payload_pane.ui")]
+    pub struct BinaryPayloadPane {
+        #[template_child]
+        pub(super) path: TemplateChild<EntryRow>,
+
+        #[template_child]
+        size: TemplateChild<ActionRow>,
+
+        #[template_child]
+        content_type: TemplateChild<ActionRow>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for BinaryPayloadPane {
+        const NAME: &'static str = "CarteroBinaryPayloadPane";
+        type Type = super::BinaryPayloadPane;
+        type ParentType = BasePayloadPane;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for BinaryPayloadPane {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("changed").build()])
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            // The request file may not be known until the pane is placed in a tab.
+            self.obj()
+                .connect_map(glib::clone!(@weak self as pane => move |_| {
+                    pane.update_file_info();
+                }));
+        }
+    }
+
+    impl WidgetImpl for BinaryPayloadPane {}
+
+    impl BinImpl for BinaryPayloadPane {}
+
+    impl BasePayloadPaneImpl for BinaryPayloadPane {}
+
+    #[template_callbacks]
+    impl BinaryPayloadPane {
+        #[template_callback]
+        fn on_path_changed(&self) {
+            self.update_file_info();
+            self.obj().emit_by_name::<()>("changed", &[]);
+        }
+
+        #[template_callback]
+        fn on_choose_clicked(&self) {
+            let Some(window) =
+                gtk::prelude::WidgetExt::root(&*self.obj()).and_downcast::<CarteroWindow>()
+            else {
+                return;
+            };
+            glib::spawn_future_local(glib::clone!(@weak self as pane => async move {
+                match open_attachment(&window).await {
+                    Ok(file) => {
+                        if let Some(path) = file.path() {
+                            // Files next to the request are stored relative to it.
+                            let path = match pane.base_dir() {
+                                Some(base_dir) => path
+                                    .strip_prefix(&base_dir)
+                                    .map(Path::to_path_buf)
+                                    .unwrap_or(path),
+                                None => path,
+                            };
+                            pane.path.set_text(&path.to_string_lossy());
+                        }
+                    }
+                    Err(CarteroError::NoFilePicked) => {}
+                    Err(e) => window.toast_error(e),
+                }
+            }));
+        }
+
+        /// The directory that contains the request file, if the request has been saved.
+        fn base_dir(&self) -> Option<PathBuf> {
+            let item_pane = self.obj().ancestor(ItemPane::static_type());
+            let file = item_pane.and_downcast::<ItemPane>()?.file()?;
+            file.parent()?.path()
+        }
+
+        fn update_file_info(&self) {
+            let text = self.path.text();
+            if text.is_empty() {
+                self.size.set_subtitle("");
+                self.content_type.set_subtitle("");
+                return;
+            }
+            let path = resolve_path(self.base_dir().as_deref(), &text);
+            let size = match std::fs::metadata(&path) {
+                Ok(metadata) => glib::format_size(metadata.len()).to_string(),
+                Err(_) => gettext("The file cannot be read"),
+            };
+            self.size.set_subtitle(&size);
+            self.content_type.set_subtitle(guess_content_type(&path));
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct BinaryPayloadPane(ObjectSubclass<imp::BinaryPayloadPane>)
+        @extends gtk::Widget, adw::Bin, BasePayloadPane,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl BinaryPayloadPane {
+    pub fn connect_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "changed",
+            true,
+            glib::closure_local!(|ref pane| {
+                f(pane);
+            }),
+        )
+    }
+}
+
+impl BasePayloadPaneExt for BinaryPayloadPane {
+    fn payload(&self) -> RequestPayload {
+        let imp = self.imp();
+        RequestPayload::File {
+            path: imp.path.text().into(),
+        }
+    }
+
+    fn set_payload(&self, payload: &RequestPayload) {
+        let imp = self.imp();
+        if let RequestPayload::File { path } = payload {
+            imp.path.set_text(path);
+        }
+    }
+}
@@ -22,17 +22,19 @@ use crate::entities::RequestPayload;
 use super::{BasePayloadPane, BasePayloadPaneExt};
 
 mod imp {
+    use adw::prelude::*;
     use glib::subclass::{InitializingObject, Signal};
     use glib::Properties;
     use gtk::subclass::prelude::*;
-    use gtk::{prelude::*, CompositeTemplate};
+    use gtk::CompositeTemplate;
     use std::cell::RefCell;
     use std::sync::OnceLock;
 
     use crate::entities::KeyValue;
     use crate::entities::KeyValueTable;
+    use crate::entities::MultipartFile;
     use crate::objects::KeyValueItem;
-    use crate::widgets::{BasePayloadPane, BasePayloadPaneImpl, KeyValuePane};
+    use crate::widgets::{BasePayloadPane, BasePayloadPaneImpl, KeyValuePane, MultipartFileRow};
 
     #[derive(Default, Properties, CompositeTemplate)]
     #[properties(wrapper_type = super::FormdataPayloadPane)]
@@ -41,6 +43,11 @@ mod imp {
         #[template_child]
         data: TemplateChild<KeyValuePane>,
 
+        #[template_child]
+        files: TemplateChild<adw::PreferencesGroup>,
+
+        file_rows: RefCell<Vec<MultipartFileRow>>,
+
         #[property(get, set)]
         boundary: RefCell<String>,
     }
@@ -53,6 +60,7 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
+            klass.bind_template_callbacks();
         }
 
         fn instance_init(obj: &InitializingObject<Self>) {
@@ -71,8 +79,7 @@ mod imp {
             self.parent_constructed();
             self.data.assert_always_placeholder();
 
-            let boundary = formdata::generate_boundary();
-            let boundary = String::from_utf8_lossy(&boundary).to_string();
+            let boundary = crate::client::generate_boundary();
             self.boundary.set(boundary);
 
             self.data
@@ -86,7 +93,48 @@ mod imp {
 
     impl BasePayloadPaneImpl for FormdataPayloadPane {}
 
+    #[gtk::template_callbacks]
     impl FormdataPayloadPane {
+        #[template_callback]
+        fn on_add_file(&self) {
+            let row = self.add_file_row(&MultipartFile::default());
+            row.set_expanded(true);
+            self.obj().emit_by_name::<()>("changed", &[]);
+        }
+
+        fn add_file_row(&self, file: &MultipartFile) -> MultipartFileRow {
+            let row = MultipartFileRow::default();
+            row.set_file(file);
+            row.connect_changed(glib::clone!(@weak self as pane => move |_| {
+                pane.obj().emit_by_name::<()>("changed", &[]);
+            }));
+            row.connect_delete(glib::clone!(@weak self as pane => move |row| {
+                pane.files.remove(row);
+                pane.file_rows.borrow_mut().retain(|r| r != row);
+                pane.obj().emit_by_name::<()>("changed", &[]);
+            }));
+            self.files.add(&row);
+            self.file_rows.borrow_mut().push(row.clone());
+            row
+        }
+
+        pub(super) fn get_files(&self) -> Vec<MultipartFile> {
+            self.file_rows
+                .borrow()
+                .iter()
+                .map(MultipartFileRow::file)
+                .collect()
+        }
+
+        pub(super) fn set_files(&self, files: &[MultipartFile]) {
+            for row in self.file_rows.take() {
+                self.files.remove(&row);
+            }
+            for file in files {
+                self.add_file_row(file);
+            }
+        }
+
         pub(super) fn get_table(&self) -> KeyValueTable {
             let entries = self.data.get_entries();
             let key_values: Vec<KeyValue> = entries.into_iter().map(KeyValue::from).collect();
@@ -125,13 +173,18 @@ impl BasePayloadPaneExt for FormdataPayloadPane {
     fn payload(&self) -> RequestPayload {
         let imp = self.imp();
         let table = imp.get_table();
-        RequestPayload::Multipart { params: table }
+        let files = imp.get_files();
+        RequestPayload::Multipart {
+            params: table,
+            files,
+        }
     }
 
     fn set_payload(&self, payload: &RequestPayload) {
         let imp = self.imp();
-        if let RequestPayload::Multipart { params } = payload {
+        if let RequestPayload::Multipart { params, files } = payload {
             imp.set_table(params);
+            imp.set_files(files);
         }
     }
 }
@@ -0,0 +1,230 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+use glib::{object::ObjectExt, subclass::types::ObjectSubclassIsExt};
+use gtk::prelude::{EditableExt, TextBufferExt};
+
+use crate::entities::RequestPayload;
+
+use super::BasePayloadPaneExt;
+
+mod imp {
+    use std::sync::OnceLock;
+
+    use adw::prelude::*;
+    use adw::subclass::bin::BinImpl;
+    use adw::{ActionRow, EntryRow};
+    use gettextrs::gettext;
+    use glib::subclass::{InitializingObject, Signal};
+    use gtk::gio::SettingsBindFlags;
+    use gtk::subclass::prelude::*;
+    use gtk::{template_callbacks, CompositeTemplate};
+    use sourceview5::prelude::*;
+    use sourceview5::{Buffer, CompletionWords, LanguageManager, StyleSchemeManager, View};
+
+    use crate::app::CarteroApplication;
+    use crate::widgets::{BasePayloadPane, BasePayloadPaneImpl, EndpointPane};
+    use crate::win::CarteroWindow;
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/graphql_payload_pane.ui")]
+    pub struct GraphQLPayloadPane {
+        #[template_child]
+        pub(super) operation_name: TemplateChild<EntryRow>,
+
+        #[template_child]
+        schema_row: TemplateChild<ActionRow>,
+
+        #[template_child]
+        fetch_schema: TemplateChild<gtk::Button>,
+
+        #[template_child]
+        query_view: TemplateChild<View>,
+
+        #[template_child]
+        pub(super) query_buffer: TemplateChild<Buffer>,
+
+        #[template_child]
+        variables_view: TemplateChild<View>,
+
+        #[template_child]
+        pub(super) variables_buffer: TemplateChild<Buffer>,
+
+        /// Holds one name of the schema per line, so that it can feed the completion.
+        #[template_child]
+        schema_words: TemplateChild<gtk::TextBuffer>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for GraphQLPayloadPane {
+        const NAME: &'static str = "CarteroGraphQLPayloadPane";
+        type Type = super::GraphQLPayloadPane;
+        type ParentType = BasePayloadPane;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for GraphQLPayloadPane {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("changed").build()])
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let completion = CompletionWords::new(Some(&gettext("Schema")));
+            completion.register(&*self.schema_words);
+            self.query_view.completion().add_provider(&completion);
+
+            if let Some(json) = LanguageManager::default().language("json") {
+                self.variables_buffer.set_language(Some(&json));
+            }
+
+            let app = CarteroApplication::get();
+            let settings = app.settings();
+            for view in [&*self.query_view, &*self.variables_view] {
+                settings
+                    .bind("show-line-numbers", view, "show-line-numbers")
+                    .flags(SettingsBindFlags::GET)
+                    .build();
+            }
+
+            self.update_source_view_style();
+            adw::StyleManager::default().connect_dark_notify(
+                glib::clone!(@weak self as pane => move |_| {
+                    pane.update_source_view_style();
+                }),
+            );
+        }
+    }
+
+    impl WidgetImpl for GraphQLPayloadPane {}
+
+    impl BinImpl for GraphQLPayloadPane {}
+
+    impl BasePayloadPaneImpl for GraphQLPayloadPane {}
+
+    #[template_callbacks]
+    impl GraphQLPayloadPane {
+        #[template_callback]
+        fn on_field_changed(&self) {
+            self.obj().emit_by_name::<()>("changed", &[]);
+        }
+
+        #[template_callback]
+        fn on_fetch_schema(&self) {
+            let Some(window) = self.obj().root().and_downcast::<CarteroWindow>() else {
+                return;
+            };
+            let Some(endpoint) = self
+                .obj()
+                .ancestor(EndpointPane::static_type())
+                .and_downcast::<EndpointPane>()
+            else {
+                return;
+            };
+            glib::spawn_future_local(glib::clone!(@weak self as pane => async move {
+                pane.fetch_schema.set_sensitive(false);
+                let schema = match window.active_environment() {
+                    Ok(environment) => endpoint.fetch_graphql_schema(environment.as_ref()).await,
+                    Err(e) => Err(e),
+                };
+                pane.fetch_schema.set_sensitive(true);
+                match schema {
+                    Ok(schema) => {
+                        let words = schema.completion_words();
+                        pane.schema_words.set_text(&words.join("\n"));
+                        let subtitle = gettext("{} names available for completion")
+                            .replace("{}", &words.len().to_string());
+                        pane.schema_row.set_subtitle(&subtitle);
+                    }
+                    Err(e) => window.toast_error(e),
+                }
+            }));
+        }
+
+        fn update_source_view_style(&self) {
+            let dark_mode = adw::StyleManager::default().is_dark();
+            let color_theme = if dark_mode { "Adwaita-dark" } else { "Adwaita" };
+            let theme = StyleSchemeManager::default().scheme(color_theme);
+            for buffer in [&*self.query_buffer, &*self.variables_buffer] {
+                buffer.set_style_scheme(theme.as_ref());
+                buffer.set_highlight_syntax(theme.is_some());
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct GraphQLPayloadPane(ObjectSubclass<imp::GraphQLPayloadPane>)
+        @extends gtk::Widget, adw::Bin, super::BasePayloadPane,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+impl GraphQLPayloadPane {
+    pub fn connect_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "changed",
+            true,
+            glib::closure_local!(|ref pane| {
+                f(pane);
+            }),
+        )
+    }
+}
+
+impl BasePayloadPaneExt for GraphQLPayloadPane {
+    fn payload(&self) -> RequestPayload {
+        let imp = self.imp();
+        let text = |buffer: &sourceview5::Buffer| {
+            let (start, end) = buffer.bounds();
+            buffer.text(&start, &end, true).to_string()
+        };
+        let operation_name = imp.operation_name.text();
+        RequestPayload::GraphQL {
+            query: text(&imp.query_buffer),
+            variables: text(&imp.variables_buffer),
+            operation_name: if operation_name.is_empty() {
+                None
+            } else {
+                Some(operation_name.into())
+            },
+        }
+    }
+
+    fn set_payload(&self, payload: &RequestPayload) {
+        let imp = self.imp();
+        if let RequestPayload::GraphQL {
+            query,
+            variables,
+            operation_name,
+        } = payload
+        {
+            imp.query_buffer.set_text(query);
+            imp.variables_buffer.set_text(variables);
+            imp.operation_name
+                .set_text(operation_name.as_deref().unwrap_or_default());
+        }
+    }
+}
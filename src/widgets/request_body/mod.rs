@@ -16,13 +16,17 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 mod base;
+mod binary;
 mod formdata;
+mod graphql;
 mod raw;
 mod tab;
 mod urlencoded;
 
 pub use base::*;
+pub use binary::*;
 pub use formdata::*;
+pub use graphql::*;
 pub use raw::*;
 pub use tab::*;
 pub use urlencoded::*;
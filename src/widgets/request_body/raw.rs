@@ -16,6 +16,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use glib::{object::ObjectExt, subclass::types::ObjectSubclassIsExt};
+use gtk::prelude::EditableExt;
 
 use crate::entities::{RawEncoding, RequestPayload};
 
@@ -47,8 +48,14 @@ mod imp {
         #[template_child]
         buffer: TemplateChild<Buffer>,
 
+        #[template_child]
+        content_type_clamp: TemplateChild<adw::Clamp>,
+
+        #[template_child]
+        pub(super) content_type: TemplateChild<adw::EntryRow>,
+
         #[property(get = Self::format, set = Self::set_format, builder(PayloadType::default()))]
-        _format: RefCell<PayloadType>,
+        format: RefCell<PayloadType>,
     }
 
     #[glib::object_subclass]
@@ -83,6 +90,10 @@ mod imp {
                 .connect_changed(glib::clone!(@weak self as pane => move |_| {
                     pane.obj().emit_by_name::<()>("changed", &[]);
                 }));
+            self.content_type
+                .connect_changed(glib::clone!(@weak self as pane => move |_| {
+                    pane.obj().emit_by_name::<()>("changed", &[]);
+                }));
         }
     }
 
@@ -105,30 +116,27 @@ mod imp {
         }
 
         fn format(&self) -> PayloadType {
-            if let Some(language) = self.buffer.language() {
-                if language.name() == "JSON" {
-                    PayloadType::Json
-                } else if language.name() == "XML" {
-                    PayloadType::Xml
-                } else {
-                    PayloadType::Raw
-                }
-            } else {
-                PayloadType::Raw
-            }
+            *self.format.borrow()
         }
 
         fn set_format(&self, format: PayloadType) {
+            self.format.replace(format);
             let manager = LanguageManager::default();
             let language = match format {
                 PayloadType::Json => manager.language("json"),
                 PayloadType::Xml => manager.language("xml"),
+                PayloadType::Html => manager.language("html"),
+                PayloadType::Yaml => manager.language("yaml"),
+                PayloadType::JavaScript => manager.language("js"),
+                PayloadType::Csv => manager.language("csv"),
                 _ => None,
             };
             match language {
                 Some(lang) => self.buffer.set_language(Some(&lang)),
                 None => self.buffer.set_language(None),
             }
+            self.content_type_clamp
+                .set_visible(format == PayloadType::Custom);
         }
 
         fn init_settings(&self) {
@@ -237,8 +245,14 @@ impl BasePayloadPaneExt for RawPayloadPane {
         let imp = self.imp();
         let content = imp.payload();
         let encoding = match self.format() {
-            super::PayloadType::Json => RawEncoding::Json,
-            super::PayloadType::Xml => RawEncoding::Xml,
+            PayloadType::Json => RawEncoding::Json,
+            PayloadType::Xml => RawEncoding::Xml,
+            PayloadType::Text => RawEncoding::Text,
+            PayloadType::Html => RawEncoding::Html,
+            PayloadType::Yaml => RawEncoding::Yaml,
+            PayloadType::JavaScript => RawEncoding::JavaScript,
+            PayloadType::Csv => RawEncoding::Csv,
+            PayloadType::Custom => RawEncoding::Custom(imp.content_type.text().into()),
             _ => RawEncoding::OctetStream,
         };
         RequestPayload::Raw { encoding, content }
@@ -248,12 +262,10 @@ impl BasePayloadPaneExt for RawPayloadPane {
         if let RequestPayload::Raw { encoding, content } = payload {
             let imp = self.imp();
             imp.set_payload(content);
-            let format = match encoding {
-                RawEncoding::Json => PayloadType::Json,
-                RawEncoding::Xml => PayloadType::Xml,
-                _ => PayloadType::Raw,
-            };
-            self.set_format(format);
+            if let RawEncoding::Custom(content_type) = encoding {
+                imp.content_type.set_text(content_type);
+            }
+            self.set_format(PayloadType::from(encoding));
         }
     }
 }
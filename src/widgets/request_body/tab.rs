@@ -22,7 +22,10 @@ use gtk::subclass::prelude::*;
 
 use crate::entities::{RawEncoding, RequestPayload};
 
-use super::{BasePayloadPaneExt, FormdataPayloadPane, RawPayloadPane, UrlencodedPayloadPane};
+use super::{
+    BasePayloadPaneExt, BinaryPayloadPane, FormdataPayloadPane, GraphQLPayloadPane, RawPayloadPane,
+    UrlencodedPayloadPane,
+};
 
 #[derive(Debug, Copy, Clone, Default, PartialEq, Eq, glib::Enum)]
 #[enum_type(name = "CarteroPayloadType")]
@@ -33,7 +36,15 @@ pub enum PayloadType {
     MultipartFormData,
     Json,
     Xml,
+    Yaml,
+    Html,
+    JavaScript,
+    Csv,
+    Text,
     Raw,
+    Custom,
+    File,
+    GraphQL,
 }
 
 impl PayloadType {
@@ -46,12 +57,36 @@ impl PayloadType {
                 PayloadType::MultipartFormData,
                 PayloadType::Json,
                 PayloadType::Xml,
+                PayloadType::Yaml,
+                PayloadType::Html,
+                PayloadType::JavaScript,
+                PayloadType::Csv,
+                PayloadType::Text,
                 PayloadType::Raw,
+                PayloadType::Custom,
+                PayloadType::File,
+                PayloadType::GraphQL,
             ]
         })
     }
 }
 
+impl From<&RawEncoding> for PayloadType {
+    fn from(value: &RawEncoding) -> Self {
+        match value {
+            RawEncoding::Json => PayloadType::Json,
+            RawEncoding::Xml => PayloadType::Xml,
+            RawEncoding::Yaml => PayloadType::Yaml,
+            RawEncoding::Html => PayloadType::Html,
+            RawEncoding::JavaScript => PayloadType::JavaScript,
+            RawEncoding::Csv => PayloadType::Csv,
+            RawEncoding::Text => PayloadType::Text,
+            RawEncoding::OctetStream => PayloadType::Raw,
+            RawEncoding::Custom(_) => PayloadType::Custom,
+        }
+    }
+}
+
 mod imp {
     use std::cell::RefCell;
     use std::sync::OnceLock;
@@ -66,7 +101,9 @@ mod imp {
     use gtk::{CompositeTemplate, Stack};
 
     use crate::widgets::BasePayloadPane;
+    use crate::widgets::BinaryPayloadPane;
     use crate::widgets::FormdataPayloadPane;
+    use crate::widgets::GraphQLPayloadPane;
     use crate::widgets::RawPayloadPane;
     use crate::widgets::UrlencodedPayloadPane;
 
@@ -94,6 +131,12 @@ mod imp {
         #[template_child]
         formdata: TemplateChild<FormdataPayloadPane>,
 
+        #[template_child]
+        binary: TemplateChild<BinaryPayloadPane>,
+
+        #[template_child]
+        graphql: TemplateChild<GraphQLPayloadPane>,
+
         #[property(get = Self::payload_type, set = Self::set_payload_type, builder(PayloadType::default()))]
         _payload_type: RefCell<PayloadType>,
     }
@@ -139,6 +182,16 @@ mod imp {
                 .connect_changed(glib::clone!(@weak self as pane => move |_| {
                     pane.obj().emit_by_name::<()>("changed", &[]);
                 }));
+
+            self.binary
+                .connect_changed(glib::clone!(@weak self as pane => move |_| {
+                    pane.obj().emit_by_name::<()>("changed", &[]);
+                }));
+
+            self.graphql
+                .connect_changed(glib::clone!(@weak self as pane => move |_| {
+                    pane.obj().emit_by_name::<()>("changed", &[]);
+                }));
         }
 
         fn signals() -> &'static [Signal] {
@@ -161,8 +214,16 @@ mod imp {
                 PayloadType::Raw => "raw",
                 PayloadType::Json => "raw",
                 PayloadType::Xml => "raw",
+                PayloadType::Yaml => "raw",
+                PayloadType::Html => "raw",
+                PayloadType::JavaScript => "raw",
+                PayloadType::Csv => "raw",
+                PayloadType::Text => "raw",
+                PayloadType::Custom => "raw",
                 PayloadType::UrlEncoded => "urlencoded",
                 PayloadType::MultipartFormData => "formdata",
+                PayloadType::File => "binary",
+                PayloadType::GraphQL => "graphql",
             };
             self.stack.set_visible_child_name(tab);
 
@@ -189,9 +250,17 @@ mod imp {
                 PayloadType::MultipartFormData => {
                     Some(self.formdata.upcast_ref::<BasePayloadPane>().clone())
                 }
-                PayloadType::Json | PayloadType::Xml | PayloadType::Raw => {
-                    Some(self.raw.upcast_ref::<BasePayloadPane>().clone())
-                }
+                PayloadType::Json
+                | PayloadType::Xml
+                | PayloadType::Yaml
+                | PayloadType::Html
+                | PayloadType::JavaScript
+                | PayloadType::Csv
+                | PayloadType::Text
+                | PayloadType::Raw
+                | PayloadType::Custom => Some(self.raw.upcast_ref::<BasePayloadPane>().clone()),
+                PayloadType::File => Some(self.binary.upcast_ref::<BasePayloadPane>().clone()),
+                PayloadType::GraphQL => Some(self.graphql.upcast_ref::<BasePayloadPane>().clone()),
             }
         }
     }
@@ -218,15 +287,13 @@ impl PayloadTab {
         let payload_type = match payload {
             RequestPayload::None => PayloadType::None,
             RequestPayload::Urlencoded(_) => PayloadType::UrlEncoded,
-            RequestPayload::Multipart { params: _ } => PayloadType::MultipartFormData,
+            RequestPayload::Multipart { .. } => PayloadType::MultipartFormData,
             RequestPayload::Raw {
                 encoding,
                 content: _,
-            } => match encoding {
-                RawEncoding::Json => PayloadType::Json,
-                RawEncoding::Xml => PayloadType::Xml,
-                RawEncoding::OctetStream => PayloadType::Raw,
-            },
+            } => PayloadType::from(encoding),
+            RequestPayload::File { .. } => PayloadType::File,
+            RequestPayload::GraphQL { .. } => PayloadType::GraphQL,
         };
         self.set_payload_type(payload_type);
 
@@ -241,10 +308,26 @@ impl PayloadTab {
                 let widget = widget.and_downcast::<FormdataPayloadPane>().unwrap();
                 widget.set_payload(payload);
             }
-            PayloadType::Json | PayloadType::Xml | PayloadType::Raw => {
+            PayloadType::Json
+            | PayloadType::Xml
+            | PayloadType::Yaml
+            | PayloadType::Html
+            | PayloadType::JavaScript
+            | PayloadType::Csv
+            | PayloadType::Text
+            | PayloadType::Raw
+            | PayloadType::Custom => {
                 let widget = widget.and_downcast::<RawPayloadPane>().unwrap();
                 widget.set_payload(payload);
             }
+            PayloadType::File => {
+                let widget = widget.and_downcast::<BinaryPayloadPane>().unwrap();
+                widget.set_payload(payload);
+            }
+            PayloadType::GraphQL => {
+                let widget = widget.and_downcast::<GraphQLPayloadPane>().unwrap();
+                widget.set_payload(payload);
+            }
         }
     }
 
@@ -260,10 +343,26 @@ impl PayloadTab {
                 let widget = widget.and_downcast::<FormdataPayloadPane>().unwrap();
                 widget.payload()
             }
-            PayloadType::Json | PayloadType::Xml | PayloadType::Raw => {
+            PayloadType::Json
+            | PayloadType::Xml
+            | PayloadType::Yaml
+            | PayloadType::Html
+            | PayloadType::JavaScript
+            | PayloadType::Csv
+            | PayloadType::Text
+            | PayloadType::Raw
+            | PayloadType::Custom => {
                 let widget = widget.and_downcast::<RawPayloadPane>().unwrap();
                 widget.payload()
             }
+            PayloadType::File => {
+                let widget = widget.and_downcast::<BinaryPayloadPane>().unwrap();
+                widget.payload()
+            }
+            PayloadType::GraphQL => {
+                let widget = widget.and_downcast::<GraphQLPayloadPane>().unwrap();
+                widget.payload()
+            }
         }
     }
 }
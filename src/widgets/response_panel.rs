@@ -17,21 +17,24 @@
 
 use std::path::PathBuf;
 
+use adw::prelude::*;
+use gettextrs::gettext;
 use glib::Object;
 use gtk::gio::{ListModel, ListStore};
 use gtk::glib;
 use gtk::prelude::TextViewExt;
-use gtk::prelude::*;
 use serde_json::Value;
 use sourceview5::prelude::BufferExt;
 use sourceview5::LanguageManager;
 
+use crate::assertions::AssertionResult;
+use crate::client::{is_event_stream, pretty_json, SseEvent};
 use crate::entities::ResponseData;
 use crate::objects::KeyValueItem;
 use glib::subclass::types::ObjectSubclassIsExt;
 
 mod imp {
-    use std::cell::RefCell;
+    use std::cell::{Cell, RefCell};
 
     use adw::prelude::*;
     use adw::subclass::bin::BinImpl;
@@ -44,7 +47,7 @@ mod imp {
         subclass::widget::{CompositeTemplateClass, CompositeTemplateInitializingExt, WidgetImpl},
         Box, CompositeTemplate, Label, TemplateChild,
     };
-    use gtk::{Spinner, Stack, WrapMode};
+    use gtk::{Stack, WrapMode};
     use sourceview5::prelude::BufferExt;
     use sourceview5::StyleSchemeManager;
 
@@ -70,12 +73,36 @@ mod imp {
         #[template_child]
         pub response_size: TemplateChild<Label>,
         #[template_child]
-        pub spinner: TemplateChild<Spinner>,
+        pub request_progress: TemplateChild<Box>,
         #[template_child]
         pub metadata_stack: TemplateChild<Stack>,
+        #[template_child]
+        pub tests_label: TemplateChild<Label>,
+        #[template_child]
+        pub test_results: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub notebook: TemplateChild<gtk::Notebook>,
+        #[template_child]
+        pub events_page: TemplateChild<Box>,
+        #[template_child]
+        pub stream_status: TemplateChild<Label>,
+        #[template_child]
+        pub stop_stream: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub events_scroll: TemplateChild<gtk::ScrolledWindow>,
+        #[template_child]
+        pub events: TemplateChild<gtk::ListBox>,
 
         #[property(get = Self::spinning, set = Self::set_spinning)]
         _spinning: RefCell<bool>,
+
+        /// Stops the event stream that is being received, if any.
+        pub stop_sender: RefCell<Option<tokio::sync::oneshot::Sender<()>>>,
+        /// Cancels the request that is being sent, if any.
+        pub cancel_sender: RefCell<Option<tokio::sync::oneshot::Sender<()>>>,
+        pub event_count: Cell<usize>,
+        /// Whether an event stream is being received right now.
+        pub streaming: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -86,6 +113,7 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
+            klass.bind_template_callbacks();
         }
 
         fn instance_init(obj: &InitializingObject<Self>) {
@@ -107,7 +135,27 @@ mod imp {
 
     impl BinImpl for ResponsePanel {}
 
+    #[gtk::template_callbacks]
     impl ResponsePanel {
+        #[template_callback]
+        fn on_stop_stream(&self) {
+            if let Some(sender) = self.stop_sender.take() {
+                let _ = sender.send(());
+            }
+            self.stop_stream.set_sensitive(false);
+        }
+
+        /// Event streams never end on their own, so cancelling one just stops it,
+        /// keeping the events that were already received.
+        #[template_callback]
+        fn on_cancel_request(&self) {
+            if self.stop_sender.borrow().is_some() {
+                self.on_stop_stream();
+            } else if let Some(sender) = self.cancel_sender.take() {
+                let _ = sender.send(());
+            }
+        }
+
         fn init_settings(&self) {
             let app = CarteroApplication::get();
             let settings = app.settings();
@@ -167,14 +215,15 @@ mod imp {
         fn spinning(&self) -> bool {
             self.metadata_stack
                 .visible_child()
-                .is_some_and(|w| w.is::<Spinner>())
+                .is_some_and(|w| &w == self.request_progress.upcast_ref::<gtk::Widget>())
         }
 
         fn set_spinning(&self, spinning: bool) {
             self.stack.set_visible_child_name("response");
             let widget: &gtk::Widget = if spinning {
-                self.spinner.upcast_ref()
+                self.request_progress.upcast_ref()
             } else {
+                self.cancel_sender.take();
                 self.response_meta.upcast_ref()
             };
             self.metadata_stack.set_visible_child(widget);
@@ -195,7 +244,7 @@ impl Default for ResponsePanel {
 }
 
 // TODO: Whether to use SI units or base 2 units?
-fn format_bytes(count: usize) -> String {
+pub(crate) fn format_bytes(count: usize) -> String {
     let units = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
     let mut total = count as f64;
     let mut unit = 0;
@@ -217,15 +266,150 @@ impl ResponsePanel {
         Object::builder().build()
     }
 
-    pub fn start_request(&self) {
+    /// Shows the request as in progress, with a button to cancel it.
+    ///
+    /// The returned future completes when the request has to be cancelled. It
+    /// never completes if the request finishes first.
+    pub fn start_request(&self) -> impl std::future::Future<Output = ()> {
         let imp = self.imp();
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        imp.cancel_sender.replace(Some(sender));
+        self.set_spinning(true);
+        async move {
+            if receiver.await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        }
+    }
 
-        imp.metadata_stack.set_visible_child(&*imp.spinner);
+    /// Cancels the request that is in progress and stops any event stream.
+    pub fn cancel(&self) {
+        let imp = self.imp();
+        if let Some(sender) = imp.stop_sender.take() {
+            let _ = sender.send(());
+        }
+        if let Some(sender) = imp.cancel_sender.take() {
+            let _ = sender.send(());
+        }
+    }
+
+    /// Shows the outcome of the tests of the request, replacing any previous result.
+    pub fn assign_test_results(&self, results: &[AssertionResult]) {
+        let imp = self.imp();
+
+        imp.test_results.remove_all();
+        for result in results {
+            let row = adw::ActionRow::new();
+            row.set_use_markup(false);
+            row.set_title(&result.assertion.to_string());
+            if let Some(details) = &result.details {
+                row.set_subtitle(details);
+            }
+            let (icon_name, css_class) = if result.passed {
+                ("emblem-ok-symbolic", "success")
+            } else {
+                ("dialog-error-symbolic", "error")
+            };
+            let icon = gtk::Image::from_icon_name(icon_name);
+            icon.add_css_class(css_class);
+            row.add_prefix(&icon);
+            imp.test_results.append(&row);
+        }
+
+        let label = if results.is_empty() {
+            gettext("Tests")
+        } else {
+            let passed = results.iter().filter(|r| r.passed).count();
+            gettext("Tests ({}/{})")
+                .replacen("{}", &passed.to_string(), 1)
+                .replacen("{}", &results.len().to_string(), 1)
+        };
+        imp.tests_label.set_label(&label);
+    }
+
+    /// Prepares the panel to show the events of a Server-Sent Events stream.
+    ///
+    /// The returned future completes when the user asks to stop the stream.
+    pub fn start_stream(&self) -> impl std::future::Future<Output = ()> {
+        let imp = self.imp();
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        imp.stop_sender.replace(Some(sender));
+        imp.streaming.set(true);
+        imp.event_count.set(0);
+        imp.events.remove_all();
+        imp.stream_status.set_label(&gettext("Receiving events…"));
+        imp.stop_stream.set_sensitive(true);
+        imp.events_page.set_visible(true);
+        if let Some(page) = imp.notebook.page_num(&*imp.events_page) {
+            imp.notebook.set_current_page(Some(page));
+        }
+        async move {
+            let _ = receiver.await;
+        }
+    }
+
+    /// Adds an event of the stream to the list of events.
+    pub fn append_event(&self, event: &SseEvent) {
+        let imp = self.imp();
+        imp.event_count.set(imp.event_count.get() + 1);
+
+        let time = glib::DateTime::now_local()
+            .and_then(|now| now.format("%H:%M:%S"))
+            .map(String::from)
+            .unwrap_or_default();
+        let mut caption = vec![time];
+        if let Some(name) = &event.event {
+            caption.push(gettext("event: {}").replace("{}", name));
+        }
+        if let Some(id) = &event.id {
+            caption.push(gettext("id: {}").replace("{}", id));
+        }
+
+        let row = gtk::Box::new(gtk::Orientation::Vertical, 3);
+        row.set_margin_top(3);
+        row.set_margin_bottom(3);
+        let caption = gtk::Label::new(Some(caption.join(" · ").as_str()));
+        caption.add_css_class("caption");
+        caption.add_css_class("dim-label");
+        caption.set_xalign(0.0);
+        let data = pretty_json(&event.data).unwrap_or_else(|| event.data.clone());
+        let data = gtk::Label::new(Some(data.as_str()));
+        data.add_css_class("monospace");
+        data.set_xalign(0.0);
+        data.set_wrap(true);
+        data.set_wrap_mode(gtk::pango::WrapMode::WordChar);
+        data.set_selectable(true);
+        row.append(&caption);
+        row.append(&data);
+        imp.events.append(&row);
+
+        let adjustment = imp.events_scroll.vadjustment();
+        glib::idle_add_local_once(move || {
+            adjustment.set_value(adjustment.upper() - adjustment.page_size());
+        });
+    }
+
+    /// Marks the event stream as finished, either by the server or by the user.
+    /// Does nothing when no event stream is being received.
+    pub fn finish_stream(&self) {
+        let imp = self.imp();
+        if !imp.streaming.replace(false) {
+            return;
+        }
+        imp.stop_sender.take();
+        imp.stop_stream.set_sensitive(false);
+        let status = gettext("Stream closed, {} events received")
+            .replace("{}", &imp.event_count.get().to_string());
+        imp.stream_status.set_label(&status);
     }
 
     pub fn assign_from_response(&self, resp: &ResponseData) {
         let imp = self.imp();
 
+        if !is_event_stream(&resp.headers) {
+            imp.events_page.set_visible(false);
+        }
+
         let mut headers = resp.headers.clone();
         headers.sort();
         let headers: Vec<KeyValueItem> = headers
@@ -0,0 +1,395 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::time::{Duration, Instant};
+
+use adw::prelude::*;
+use futures_lite::Future;
+use gettextrs::gettext;
+use glib::subclass::types::ObjectSubclassIsExt;
+use glib::Object;
+use isahc::RequestExt;
+
+use crate::client::{cancellable, timed, BoundRequest, RequestError};
+use crate::entities::ResponseData;
+use crate::error::CarteroError;
+use crate::runner::{
+    RunResult, RunSummary, Runner, RunnerItem, RunnerOptions, REQUEST_TIMEOUT, STREAM_DURATION,
+};
+use crate::win::CarteroWindow;
+
+use super::endpoint_pane::{prepare_request, store_cookies};
+
+mod imp {
+    use std::cell::{Cell, RefCell};
+
+    use adw::prelude::*;
+    use adw::subclass::dialog::AdwDialogImplExt;
+    use adw::subclass::prelude::*;
+    use gettextrs::gettext;
+    use glib::subclass::InitializingObject;
+    use gtk::CompositeTemplate;
+
+    use crate::entities::KeyValueTable;
+    use crate::error::CarteroError;
+    use crate::runner::RunnerItem;
+    use crate::win::CarteroWindow;
+
+    #[derive(Default, CompositeTemplate)]
+    #[template(resource = "/es/danirod/Cartero/runner_dialog.ui")]
+    pub struct RunnerDialog {
+        #[template_child]
+        pub(super) run_button: TemplateChild<gtk::Button>,
+
+        #[template_child]
+        pub(super) settings_group: TemplateChild<adw::PreferencesGroup>,
+
+        #[template_child]
+        pub(super) iterations: TemplateChild<adw::SpinRow>,
+
+        #[template_child]
+        pub(super) delay: TemplateChild<adw::SpinRow>,
+
+        #[template_child]
+        pub(super) stop_on_failure: TemplateChild<adw::SwitchRow>,
+
+        #[template_child]
+        pub(super) data_row: TemplateChild<adw::ActionRow>,
+
+        #[template_child]
+        pub(super) clear_data_button: TemplateChild<gtk::Button>,
+
+        #[template_child]
+        pub(super) results_group: TemplateChild<adw::PreferencesGroup>,
+
+        #[template_child]
+        pub(super) result_list: TemplateChild<gtk::ListBox>,
+
+        /// The window that sends the requests, which provides the environment.
+        pub(super) window: glib::WeakRef<CarteroWindow>,
+
+        pub(super) items: RefCell<Vec<RunnerItem>>,
+
+        /// The rows of the data file, if one was chosen.
+        pub(super) data: RefCell<Vec<KeyValueTable>>,
+
+        pub(super) running: Cell<bool>,
+
+        /// Set when the run has to end before the next request is sent.
+        pub(super) stop_requested: Cell<bool>,
+
+        /// Cancels the request in flight when the run is stopped.
+        pub(super) cancel_sender: RefCell<Option<tokio::sync::oneshot::Sender<()>>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for RunnerDialog {
+        const NAME: &'static str = "CarteroRunnerDialog";
+        type Type = super::RunnerDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for RunnerDialog {}
+
+    impl WidgetImpl for RunnerDialog {}
+
+    impl AdwDialogImpl for RunnerDialog {
+        fn closed(&self) {
+            self.stop();
+            self.parent_closed();
+        }
+    }
+
+    #[gtk::template_callbacks]
+    impl RunnerDialog {
+        #[template_callback]
+        fn on_run_clicked(&self) {
+            if self.running.get() {
+                self.stop();
+                self.run_button.set_sensitive(false);
+                return;
+            }
+            let obj = self.obj().clone();
+            glib::spawn_future_local(async move {
+                obj.imp().run().await;
+            });
+        }
+
+        #[template_callback]
+        fn on_choose_data_clicked(&self) {
+            let Some(window) = self.window.upgrade() else {
+                return;
+            };
+            let obj = self.obj().clone();
+            glib::spawn_future_local(async move {
+                match obj.imp().choose_data_file(&window).await {
+                    Ok(_) | Err(CarteroError::NoFilePicked) => {}
+                    Err(e) => window.toast_error(e),
+                }
+            });
+        }
+
+        #[template_callback]
+        fn on_clear_data_clicked(&self) {
+            self.data.borrow_mut().clear();
+            self.data_row.set_subtitle(&gettext(
+                "CSV or JSON rows bound to variables, one for every iteration",
+            ));
+            self.clear_data_button.set_visible(false);
+        }
+
+        async fn choose_data_file(&self, window: &CarteroWindow) -> Result<(), CarteroError> {
+            let file = crate::widgets::open_data_file(window).await?;
+            let path = file.path().ok_or(CarteroError::FileDialogError)?;
+            let rows = crate::runner::load_data_file(&path)?;
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let subtitle = gettext("{file}, {rows} rows")
+                .replace("{file}", &name)
+                .replace("{rows}", &rows.len().to_string());
+            self.data_row.set_subtitle(&subtitle);
+            self.clear_data_button.set_visible(true);
+            if !rows.is_empty() {
+                self.iterations.set_value(rows.len() as f64);
+            }
+            self.data.replace(rows);
+            Ok(())
+        }
+
+        /// Ends the run, cancelling the request that is being sent.
+        fn stop(&self) {
+            self.stop_requested.set(true);
+            if let Some(sender) = self.cancel_sender.take() {
+                let _ = sender.send(());
+            }
+        }
+
+        pub(super) fn set_running(&self, running: bool) {
+            self.running.set(running);
+            self.stop_requested.set(false);
+            self.settings_group.set_sensitive(!running);
+            self.run_button.set_sensitive(true);
+            if running {
+                self.run_button.set_label(&gettext("_Stop"));
+                self.run_button.remove_css_class("suggested-action");
+                self.run_button.add_css_class("destructive-action");
+            } else {
+                self.run_button.set_label(&gettext("_Run"));
+                self.run_button.remove_css_class("destructive-action");
+                self.run_button.add_css_class("suggested-action");
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct RunnerDialog(ObjectSubclass<imp::RunnerDialog>)
+        @extends gtk::Widget, adw::Dialog,
+        @implements gtk::Accessible, gtk::Buildable;
+}
+
+/// Sends a request of the run with the settings of the application.
+///
+/// The request is aborted if the `cancel` future completes before it finishes
+/// or if it takes too long. Event streams are only listened to for a while.
+async fn send(
+    mut request: BoundRequest,
+    window: &CarteroWindow,
+    cancel: impl Future<Output = ()>,
+) -> Result<ResponseData, CarteroError> {
+    futures_lite::pin!(cancel);
+    cancellable(prepare_request(&mut request, Some(window)), cancel.as_mut()).await?;
+    let request_obj = isahc::Request::try_from(request.clone())?;
+    let exchange = async {
+        let start = Instant::now();
+        let mut response_obj = request_obj
+            .send_async()
+            .await
+            .map_err(RequestError::NetworkError)?;
+        let url = crate::client::effective_url(&response_obj, &request.url);
+        let stop = glib::timeout_future(STREAM_DURATION);
+        let response = crate::client::read_isahc_response(&mut response_obj, &start, stop).await?;
+        store_cookies(Some(window), &url, &response)?;
+        Ok(response)
+    };
+    cancellable(
+        timed(exchange, glib::timeout_future(REQUEST_TIMEOUT)),
+        cancel,
+    )
+    .await
+}
+
+/// Builds the row that shows the outcome of a request in the results table.
+fn result_row(result: &RunResult, show_iteration: bool) -> adw::ActionRow {
+    let mut details = Vec::new();
+    if show_iteration {
+        details.push(gettext("Iteration {}").replace("{}", &result.iteration.to_string()));
+    }
+    if let Some(error) = &result.error {
+        details.push(error.clone());
+    }
+    for test in result.tests.iter().filter(|test| !test.passed) {
+        details.push(match &test.details {
+            Some(reason) => format!("{} ({reason})", test.assertion),
+            None => test.assertion.to_string(),
+        });
+    }
+    for extraction in &result.extractions {
+        if let Err(e) = &extraction.value {
+            details.push(
+                gettext("Cannot extract {}: {}")
+                    .replacen("{}", &extraction.extraction.variable, 1)
+                    .replacen("{}", &e.to_string(), 1),
+            );
+        }
+    }
+
+    let row = adw::ActionRow::builder()
+        .title(&result.item.name)
+        .subtitle(details.join("\n"))
+        .use_markup(false)
+        .build();
+
+    let status = gtk::Label::builder()
+        .label(
+            result
+                .status_code
+                .map(|c| c.to_string())
+                .unwrap_or_default(),
+        )
+        .width_chars(4)
+        .css_classes(["numeric", "dim-label"])
+        .build();
+    let duration = gtk::Label::builder()
+        .label(
+            result
+                .duration
+                .map(|ms| format!("{ms} ms"))
+                .unwrap_or_default(),
+        )
+        .width_chars(9)
+        .xalign(1.0)
+        .css_classes(["numeric", "dim-label"])
+        .build();
+    let (icon, class, tooltip) = match (result.passed, &result.error) {
+        (true, _) => ("emblem-ok-symbolic", "success", gettext("Passed")),
+        (false, None) => ("dialog-warning-symbolic", "warning", gettext("Failed")),
+        (false, Some(_)) => ("dialog-error-symbolic", "error", gettext("Error")),
+    };
+    let outcome = gtk::Image::builder()
+        .icon_name(icon)
+        .css_classes([class])
+        .tooltip_text(tooltip)
+        .build();
+    row.add_suffix(&status);
+    row.add_suffix(&duration);
+    row.add_suffix(&outcome);
+    row
+}
+
+fn summary_text(summary: &RunSummary) -> String {
+    let text = if summary.skipped > 0 {
+        gettext("{passed} passed, {failed} failed, {skipped} skipped")
+    } else {
+        gettext("{passed} passed, {failed} failed")
+    };
+    let text = text
+        .replace("{passed}", &summary.passed.to_string())
+        .replace("{failed}", &summary.failed.to_string())
+        .replace("{skipped}", &summary.skipped.to_string());
+    format!("{text} · {} ms", summary.duration)
+}
+
+impl imp::RunnerDialog {
+    /// Sends every request of the run, adding a row to the table for each one.
+    async fn run(&self) {
+        let Some(window) = self.window.upgrade() else {
+            return;
+        };
+        self.set_running(true);
+        self.result_list.remove_all();
+        self.results_group.set_visible(true);
+        self.results_group.set_description(None);
+
+        let options = RunnerOptions {
+            iterations: Some(self.iterations.value() as u32),
+            delay: Duration::from_millis(self.delay.value() as u64),
+            stop_on_failure: self.stop_on_failure.is_active(),
+            data: self.data.borrow().clone(),
+        };
+        let show_iteration = options.iterations() > 1;
+        let mut runner = Runner::new(self.items.borrow().clone(), options);
+
+        while let Some(step) = runner.next_step() {
+            if !step.delay.is_zero() {
+                glib::timeout_future(step.delay).await;
+            }
+            if self.stop_requested.get() {
+                runner.stop();
+                break;
+            }
+            // The environment is read every time, since a request may change it.
+            let prepared = window
+                .active_environment()
+                .and_then(|environment| runner.prepare(&step, environment.as_ref()));
+            let (sender, receiver) = tokio::sync::oneshot::channel();
+            self.cancel_sender.replace(Some(sender));
+            let cancel = async move {
+                let _ = receiver.await;
+            };
+            let outcome = match prepared {
+                Ok(request) => send(request, &window, cancel).await,
+                Err(e) => Err(e),
+            };
+            self.cancel_sender.take();
+            let result = runner.complete(&step, outcome.as_ref());
+            self.result_list.append(&result_row(result, show_iteration));
+            self.results_group
+                .set_description(Some(summary_text(&runner.summary()).as_str()));
+        }
+
+        self.results_group
+            .set_description(Some(summary_text(&runner.summary()).as_str()));
+        self.set_running(false);
+    }
+}
+
+impl RunnerDialog {
+    /// Creates a dialog that runs the given requests, sending them from the window.
+    pub fn new(window: &CarteroWindow, name: &str, items: Vec<RunnerItem>) -> Self {
+        let dialog: Self = Object::builder().build();
+        let imp = dialog.imp();
+        imp.window.set(Some(window));
+        imp.settings_group.set_title(name);
+        let count = gettext("{} requests").replace("{}", &items.len().to_string());
+        imp.settings_group.set_description(Some(count.as_str()));
+        imp.run_button.set_sensitive(!items.is_empty());
+        imp.items.replace(items);
+        dialog
+    }
+}
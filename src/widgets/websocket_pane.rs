@@ -0,0 +1,440 @@
+// Copyright 2024 the Cartero authors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+use glib::{subclass::types::ObjectSubclassIsExt, Object};
+use gtk::glib;
+
+use crate::entities::WebSocketData;
+
+mod imp {
+    use std::cell::RefCell;
+
+    use adw::prelude::*;
+    use adw::subclass::bin::BinImpl;
+    use gettextrs::gettext;
+    use glib::subclass::InitializingObject;
+    use glib::Properties;
+    use gtk::gio::SettingsBindFlags;
+    use gtk::subclass::prelude::*;
+    use gtk::{template_callbacks, CompositeTemplate};
+    use sourceview5::prelude::*;
+    use sourceview5::{Buffer, LanguageManager, StyleSchemeManager, View};
+
+    use crate::app::CarteroApplication;
+    use crate::client::{BoundWebSocket, WebSocketConnection, WebSocketEvent, WebSocketFrame};
+    use crate::entities::{KeyValue, KeyValueTable, WebSocketData, WebSocketMessageKind};
+    use crate::error::CarteroError;
+    use crate::objects::KeyValueItem;
+    use crate::widgets::{ItemPane, KeyValuePane};
+    use crate::win::CarteroWindow;
+
+    #[derive(CompositeTemplate, Properties, Default)]
+    #[template(resource = "/es/danirod/Cartero/websocket_pane.ui")]
+    #[properties(wrapper_type = super::WebSocketPane)]
+    pub struct WebSocketPane {
+        #[template_child]
+        url: TemplateChild<gtk::Entry>,
+
+        #[template_child]
+        connect_button: TemplateChild<gtk::Button>,
+
+        #[template_child]
+        paned: TemplateChild<gtk::Paned>,
+
+        #[template_child]
+        message_view: TemplateChild<View>,
+
+        #[template_child]
+        message_buffer: TemplateChild<Buffer>,
+
+        #[template_child]
+        message_kind: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        send_button: TemplateChild<gtk::Button>,
+
+        #[template_child]
+        header_pane: TemplateChild<KeyValuePane>,
+
+        #[template_child]
+        variable_pane: TemplateChild<KeyValuePane>,
+
+        #[template_child]
+        status: TemplateChild<gtk::Label>,
+
+        #[template_child]
+        log_scroll: TemplateChild<gtk::ScrolledWindow>,
+
+        #[template_child]
+        log: TemplateChild<gtk::ListBox>,
+
+        #[property(get, set, nullable)]
+        item_pane: RefCell<Option<ItemPane>>,
+
+        /// The open connection, if any. It is closed when dropped.
+        pub(super) connection: RefCell<Option<WebSocketConnection>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for WebSocketPane {
+        const NAME: &'static str = "CarteroWebSocketPane";
+        type Type = super::WebSocketPane;
+        type ParentType = adw::Bin;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            klass.bind_template_callbacks();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for WebSocketPane {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.header_pane.assert_always_placeholder();
+            self.variable_pane.assert_always_placeholder();
+            self.header_pane
+                .connect_changed(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
+            self.variable_pane
+                .connect_changed(glib::clone!(@weak self as pane => move |_| pane.mark_dirty()));
+
+            let app = CarteroApplication::get();
+            let settings = app.settings();
+            settings
+                .bind(
+                    "show-line-numbers",
+                    &*self.message_view,
+                    "show-line-numbers",
+                )
+                .flags(SettingsBindFlags::GET)
+                .build();
+            let initial_position = SettingsExtManual::get(settings, "paned-position");
+            self.paned.set_position(initial_position);
+
+            self.update_message_language();
+            self.update_source_view_style();
+            adw::StyleManager::default().connect_dark_notify(
+                glib::clone!(@weak self as pane => move |_| {
+                    pane.update_source_view_style();
+                }),
+            );
+        }
+
+        fn dispose(&self) {
+            self.connection.take();
+        }
+    }
+
+    impl WidgetImpl for WebSocketPane {}
+
+    impl BinImpl for WebSocketPane {}
+
+    #[template_callbacks]
+    impl WebSocketPane {
+        fn mark_dirty(&self) {
+            if let Some(item_pane) = self.obj().item_pane() {
+                item_pane.set_dirty(true);
+            }
+        }
+
+        fn message_kind(&self) -> WebSocketMessageKind {
+            match self.message_kind.selected() {
+                1 => WebSocketMessageKind::Binary,
+                _ => WebSocketMessageKind::Text,
+            }
+        }
+
+        fn message_text(&self) -> String {
+            let (start, end) = self.message_buffer.bounds();
+            self.message_buffer.text(&start, &end, true).to_string()
+        }
+
+        #[template_callback]
+        fn on_url_changed(&self) {
+            let empty = self.url.buffer().text().is_empty();
+            let connected = self.connection.borrow().is_some();
+            self.connect_button.set_sensitive(connected || !empty);
+            self.mark_dirty();
+        }
+
+        #[template_callback]
+        fn on_message_changed(&self) {
+            self.mark_dirty();
+        }
+
+        #[template_callback]
+        fn on_message_kind_changed(&self) {
+            self.update_message_language();
+            self.mark_dirty();
+        }
+
+        #[template_callback]
+        fn on_clear_log(&self) {
+            self.log.remove_all();
+        }
+
+        #[template_callback]
+        fn on_connect(&self) {
+            if let Some(connection) = self.connection.borrow().as_ref() {
+                connection.close();
+                self.connect_button.set_sensitive(false);
+                self.status.set_label(&gettext("Disconnecting…"));
+                return;
+            }
+            if let Err(e) = self.connect() {
+                if let Some(window) = self.obj().root().and_downcast::<CarteroWindow>() {
+                    window.toast_error(e);
+                }
+            }
+        }
+
+        #[template_callback]
+        fn on_send(&self) {
+            let frame = match WebSocketFrame::from_draft(self.message_kind(), &self.message_text())
+            {
+                Ok(frame) => frame,
+                Err(e) => {
+                    if let Some(window) = self.obj().root().and_downcast::<CarteroWindow>() {
+                        window.toast_error(e.into());
+                    }
+                    return;
+                }
+            };
+            let sent = match self.connection.borrow().as_ref() {
+                Some(connection) => connection.send(frame.clone()),
+                None => false,
+            };
+            if sent {
+                self.append_frame("↑", &frame);
+            }
+        }
+
+        fn connect(&self) -> Result<(), CarteroError> {
+            let environment = match self.obj().root().and_downcast::<CarteroWindow>() {
+                Some(window) => window.active_environment()?,
+                None => None,
+            };
+            let websocket = self.extract_websocket();
+            let bound = BoundWebSocket::bind(&websocket, environment.as_ref())?;
+            let (connection, mut events) = WebSocketConnection::open(&bound)?;
+            self.connection.replace(Some(connection));
+            self.set_connected(false);
+            self.connect_button.set_sensitive(true);
+            self.status.set_label(&gettext("Connecting…"));
+
+            glib::spawn_future_local(glib::clone!(@weak self as pane => async move {
+                while let Some(event) = events.recv().await {
+                    pane.handle_event(event);
+                }
+            }));
+            Ok(())
+        }
+
+        fn handle_event(&self, event: WebSocketEvent) {
+            match event {
+                WebSocketEvent::Opened => {
+                    self.set_connected(true);
+                    self.append_event(&gettext("Connected"));
+                }
+                WebSocketEvent::Received(frame) => self.append_frame("↓", &frame),
+                WebSocketEvent::Closed(reason) => {
+                    let message = match reason {
+                        Some(reason) => gettext("Disconnected: {}").replace("{}", &reason),
+                        None => gettext("Disconnected"),
+                    };
+                    self.append_event(&message);
+                    self.connection.take();
+                    self.set_connected(false);
+                }
+                WebSocketEvent::Failed(error) => {
+                    self.append_event(&gettext("Error: {}").replace("{}", &error));
+                    self.connection.take();
+                    self.set_connected(false);
+                }
+            }
+        }
+
+        fn set_connected(&self, connected: bool) {
+            let open = self.connection.borrow().is_some();
+            self.send_button.set_sensitive(connected);
+            self.url.set_sensitive(!open);
+            self.connect_button
+                .set_sensitive(open || !self.url.text().is_empty());
+            if open {
+                self.connect_button.set_label(&gettext("Disconnect"));
+                self.connect_button.remove_css_class("suggested-action");
+                self.connect_button.add_css_class("destructive-action");
+            } else {
+                self.connect_button.set_label(&gettext("Connect"));
+                self.connect_button.remove_css_class("destructive-action");
+                self.connect_button.add_css_class("suggested-action");
+            }
+            if connected {
+                self.status.set_label(&gettext("Connected"));
+            } else if !open {
+                self.status.set_label(&gettext("Disconnected"));
+            }
+        }
+
+        fn timestamp() -> String {
+            glib::DateTime::now_local()
+                .and_then(|now| now.format("%H:%M:%S"))
+                .map(String::from)
+                .unwrap_or_default()
+        }
+
+        /// Adds a message that was sent or received to the log.
+        fn append_frame(&self, direction: &str, frame: &WebSocketFrame) {
+            let kind = match frame {
+                WebSocketFrame::Text(_) => gettext("text"),
+                WebSocketFrame::Binary(_) => gettext("binary"),
+            };
+            let size = glib::format_size(frame.len() as u64);
+            let caption = format!("{direction} {} · {kind} · {size}", Self::timestamp());
+
+            let row = gtk::Box::new(gtk::Orientation::Vertical, 3);
+            row.set_margin_top(3);
+            row.set_margin_bottom(3);
+            let caption = gtk::Label::new(Some(caption.as_str()));
+            caption.add_css_class("caption");
+            caption.add_css_class("dim-label");
+            caption.set_xalign(0.0);
+            let body = gtk::Label::new(Some(frame.display_text().as_str()));
+            body.add_css_class("monospace");
+            body.set_xalign(0.0);
+            body.set_wrap(true);
+            body.set_wrap_mode(gtk::pango::WrapMode::WordChar);
+            body.set_selectable(true);
+            row.append(&caption);
+            row.append(&body);
+            self.append_row(&row);
+        }
+
+        /// Adds a change in the state of the connection to the log.
+        fn append_event(&self, message: &str) {
+            let label =
+                gtk::Label::new(Some(format!("{} · {message}", Self::timestamp()).as_str()));
+            label.add_css_class("caption-heading");
+            label.add_css_class("dim-label");
+            label.set_xalign(0.0);
+            label.set_wrap(true);
+            label.set_margin_top(3);
+            label.set_margin_bottom(3);
+            self.append_row(&label);
+        }
+
+        fn append_row(&self, row: &impl IsA<gtk::Widget>) {
+            self.log.append(row);
+            let adjustment = self.log_scroll.vadjustment();
+            glib::idle_add_local_once(move || {
+                adjustment.set_value(adjustment.upper() - adjustment.page_size());
+            });
+        }
+
+        fn update_message_language(&self) {
+            let language = match self.message_kind() {
+                WebSocketMessageKind::Text => LanguageManager::default().language("json"),
+                WebSocketMessageKind::Binary => None,
+            };
+            self.message_buffer.set_language(language.as_ref());
+        }
+
+        fn update_source_view_style(&self) {
+            let dark_mode = adw::StyleManager::default().is_dark();
+            let color_theme = if dark_mode { "Adwaita-dark" } else { "Adwaita" };
+            let theme = StyleSchemeManager::default().scheme(color_theme);
+            self.message_buffer.set_style_scheme(theme.as_ref());
+            self.message_buffer.set_highlight_syntax(theme.is_some());
+        }
+
+        fn table_from(pane: &KeyValuePane) -> KeyValueTable {
+            pane.get_entries()
+                .iter()
+                .map(|pair| KeyValue {
+                    name: pair.header_name(),
+                    value: pair.header_value(),
+                    active: pair.active(),
+                    secret: pair.secret(),
+                })
+                .collect()
+        }
+
+        fn items_from(table: &KeyValueTable) -> Vec<KeyValueItem> {
+            table
+                .iter()
+                .map(|item| KeyValueItem::from(item.clone()))
+                .collect()
+        }
+
+        pub(super) fn assign_websocket(&self, websocket: &WebSocketData) {
+            self.url.set_text(&websocket.url);
+            self.header_pane
+                .set_entries(&Self::items_from(&websocket.headers));
+            self.variable_pane
+                .set_entries(&Self::items_from(&websocket.variables));
+            self.message_buffer.set_text(&websocket.message);
+            let selected = match websocket.message_kind {
+                WebSocketMessageKind::Text => 0,
+                WebSocketMessageKind::Binary => 1,
+            };
+            self.message_kind.set_selected(selected);
+        }
+
+        pub(super) fn extract_websocket(&self) -> WebSocketData {
+            WebSocketData {
+                url: self.url.text().into(),
+                headers: Self::table_from(&self.header_pane),
+                variables: Self::table_from(&self.variable_pane),
+                message: self.message_text(),
+                message_kind: self.message_kind(),
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct WebSocketPane(ObjectSubclass<imp::WebSocketPane>)
+        @extends gtk::Widget, adw::Bin;
+}
+
+impl Default for WebSocketPane {
+    fn default() -> Self {
+        Object::builder().build()
+    }
+}
+
+impl WebSocketPane {
+    /// Updates the contents of the widget so that they reflect the connection data.
+    pub fn assign_websocket(&self, websocket: &WebSocketData) {
+        self.imp().assign_websocket(websocket);
+    }
+
+    pub fn extract_websocket(&self) -> WebSocketData {
+        self.imp().extract_websocket()
+    }
+
+    /// Closes the connection, if it is open.
+    pub fn disconnect(&self) {
+        if let Some(connection) = self.imp().connection.borrow().as_ref() {
+            connection.close();
+        }
+    }
+}
@@ -15,19 +15,31 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use crate::{app::CarteroApplication, error::CarteroError};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    app::CarteroApplication, entities::Environment, error::CarteroError, history::HistoryEntry,
+};
 use glib::subclass::types::ObjectSubclassIsExt;
 use glib::Object;
 use gtk::{gio, glib, prelude::SettingsExtManual};
 
 mod imp {
-    use adw::prelude::AlertDialogExtManual;
+    use adw::prelude::{AdwDialogExt, AlertDialogExt, AlertDialogExtManual};
     use adw::AboutWindow;
     use adw::{subclass::prelude::*, TabPage};
+    use std::path::{Path, PathBuf};
+    use std::time::SystemTime;
+
     use gettextrs::gettext;
     use gtk::gio::{self, ActionEntry};
     use gtk::prelude::*;
 
+    use crate::client::{CookieJar, RequestError};
+    use crate::entities::{EndpointData, Environment, WebSocketData};
+    use crate::export::{ExportFormat, ExportOptions};
+    use crate::history::HistoryEntry;
+    use crate::import::{ImportError, ImportedCollection};
     use crate::utils::SingleExpressionWatch;
     use crate::{app::CarteroApplication, error::CarteroError};
     use crate::{config, widgets::*};
@@ -53,6 +65,21 @@ mod imp {
         #[template_child]
         stack: TemplateChild<gtk::Stack>,
 
+        #[template_child]
+        split_view: TemplateChild<adw::OverlaySplitView>,
+
+        #[template_child]
+        collection_pane: TemplateChild<CollectionPane>,
+
+        #[template_child]
+        history_pane: TemplateChild<HistoryPane>,
+
+        #[template_child]
+        environment_selector: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        environment_list: TemplateChild<gtk::StringList>,
+
         window_title_binding: SingleExpressionWatch,
 
         window_subtitle_binding: SingleExpressionWatch,
@@ -73,6 +100,21 @@ mod imp {
 
         #[template_child]
         stack: TemplateChild<gtk::Stack>,
+
+        #[template_child]
+        split_view: TemplateChild<adw::OverlaySplitView>,
+
+        #[template_child]
+        collection_pane: TemplateChild<CollectionPane>,
+
+        #[template_child]
+        history_pane: TemplateChild<HistoryPane>,
+
+        #[template_child]
+        environment_selector: TemplateChild<gtk::DropDown>,
+
+        #[template_child]
+        environment_list: TemplateChild<gtk::StringList>,
     }
 
     #[gtk::template_callbacks]
@@ -80,7 +122,7 @@ mod imp {
         fn update_tab_actions(&self) {
             let has_tabs = self.tabview.n_pages() > 0;
             let obj = self.obj();
-            let actions = vec!["save", "save-as", "close"];
+            let actions = vec!["save", "save-as", "close", "export-request", "export-har"];
             for action in actions {
                 if let Some(action) = obj.lookup_action(action) {
                     action.set_property("enabled", has_tabs);
@@ -125,6 +167,8 @@ mod imp {
             let actions = [
                 "auto-indent",
                 "body-wrap",
+                "export-keep-variables",
+                "export-mask-secrets",
                 "indent-style",
                 "show-line-numbers",
                 "tab-width",
@@ -155,6 +199,14 @@ mod imp {
 
         fn finish_window_close(&self) -> glib::Propagation {
             self.save_window_state();
+            let pages = self.tabview.pages();
+            for i in 0..pages.n_items() {
+                if let Some(page) = pages.item(i).and_downcast::<TabPage>() {
+                    if let Ok(pane) = page.child().downcast::<ItemPane>() {
+                        pane.cancel();
+                    }
+                }
+            }
             glib::Propagation::Proceed
         }
 
@@ -220,23 +272,205 @@ mod imp {
                 }
             }
 
-            match ItemPane::new_for_endpoint(file).await {
-                Ok(pane) => {
-                    self.stack.set_visible_child_name("tabview");
-                    let page = self.tabview.add_page(&pane, None);
-                    pane.window_title_binding()
-                        .bind(&page, "title", Some(&pane));
-                    pane.window_subtitle_binding()
-                        .bind(&page, "tooltip", Some(&pane));
-                    self.tabview.set_selected_page(&page);
-                    self.save_visible_tabs();
-                }
+            match ItemPane::new_for_file(file).await {
+                Ok(pane) => self.add_pane(&pane),
                 Err(e) => {
                     self.obj().toast_error(e);
                 }
             };
         }
 
+        /// Opens a new unsaved tab for a WebSocket connection.
+        fn add_websocket(&self) {
+            let pane = ItemPane::new_for_websocket(None, &WebSocketData::default());
+            self.add_pane(&pane);
+        }
+
+        fn add_pane(&self, pane: &ItemPane) {
+            self.stack.set_visible_child_name("tabview");
+            let page = self.tabview.add_page(pane, None);
+            pane.window_title_binding().bind(&page, "title", Some(pane));
+            pane.window_subtitle_binding()
+                .bind(&page, "tooltip", Some(pane));
+            self.tabview.set_selected_page(&page);
+            self.save_visible_tabs();
+        }
+
+        fn load_history(&self) {
+            match CarteroApplication::get().history().load() {
+                Ok(entries) => self.history_pane.set_entries(entries),
+                Err(e) => self.toast_error(e),
+            }
+        }
+
+        /// Records the last request sent from the pane in the history, unless
+        /// the history is turned off in the preferences.
+        fn record_history(&self, pane: &EndpointPane) -> Result<(), CarteroError> {
+            let app = CarteroApplication::get();
+            let settings = app.settings();
+            if !settings.boolean("history-enabled") {
+                return Ok(());
+            }
+            let Some((request, response, started)) = pane.last_exchange() else {
+                return Ok(());
+            };
+            let keep_body = settings.boolean("history-save-bodies");
+            let entry = HistoryEntry::new(&request, &response, started, keep_body);
+            app.history().append(&entry)?;
+            let limit = settings.uint("history-limit") as usize;
+            self.history_pane.prepend(entry, limit);
+            Ok(())
+        }
+
+        /// Opens a new unsaved tab with a request from the history, showing the
+        /// response that it got back then.
+        pub(super) fn open_history_entry(&self, entry: &HistoryEntry) {
+            let pane = ItemPane::new_for_endpoint(None, &entry.to_endpoint());
+            pane.set_dirty(true);
+            self.add_pane(&pane);
+            if let Some(endpoint) = pane.endpoint() {
+                endpoint.show_exchange(
+                    entry.request.clone(),
+                    entry.response.clone(),
+                    entry.timestamp,
+                );
+            }
+        }
+
+        /// Opens a new unsaved tab for a request that was imported from somewhere else.
+        pub async fn add_imported_endpoint(&self, endpoint: &EndpointData) {
+            let pane = ItemPane::new_for_endpoint(None, endpoint);
+            pane.set_dirty(true);
+            self.add_pane(&pane);
+        }
+
+        /// Copies the request of the current tab to the clipboard in the given format.
+        fn trigger_export_request(&self, format: &str) -> Result<(), CarteroError> {
+            let Ok(format) = ExportFormat::try_from(format) else {
+                return Ok(());
+            };
+            let Some(pane) = self.current_pane().and_then(|pane| pane.endpoint()) else {
+                return Ok(());
+            };
+            let app = CarteroApplication::get();
+            let settings = app.settings();
+            let options = ExportOptions {
+                keep_variables: settings.boolean("export-keep-variables"),
+                mask_secrets: settings.boolean("export-mask-secrets"),
+            };
+            let environment = self.active_environment()?;
+            let output = pane.export_request(format, environment.as_ref(), &options)?;
+            self.obj().clipboard().set_text(&output);
+            let toast = adw::Toast::new(&gettext("Request copied to the clipboard"));
+            self.toaster.add_toast(toast);
+            Ok(())
+        }
+
+        /// Writes the imported items as a collection and opens it in the sidebar.
+        ///
+        /// When the file only contains environments, they are added to the collection
+        /// that is currently open, if any. Otherwise a folder is requested.
+        async fn import_collection(
+            &self,
+            imported: ImportedCollection,
+        ) -> Result<(), CarteroError> {
+            let root = match self.collection_pane.collection_root() {
+                Some(root) if imported.items.is_empty() => root,
+                _ => {
+                    let folder = crate::widgets::open_folder(&self.obj()).await?;
+                    folder.path().ok_or(CarteroError::FileDialogError)?
+                }
+            };
+            imported.write(&root)?;
+            self.open_collection(&root)?;
+
+            let requests = crate::import::count_requests(&imported.items);
+            if imported.warnings.is_empty() {
+                let message = gettext("Imported {} requests").replace("{}", &requests.to_string());
+                self.toaster.add_toast(adw::Toast::new(&message));
+            } else {
+                let dialog = adw::AlertDialog::builder()
+                    .heading(gettext("Some features could not be imported"))
+                    .body(imported.warnings.join("\n"))
+                    .close_response("close")
+                    .build();
+                dialog.add_response("close", &gettext("_Close"));
+                dialog.choose_future(&*self.obj()).await;
+            }
+            Ok(())
+        }
+
+        async fn trigger_import_postman(&self) -> Result<(), CarteroError> {
+            let obj = self.obj();
+            let file = crate::widgets::open_import_file(
+                &obj,
+                &gettext("Import Postman collection or environment"),
+                &gettext("Postman export (.json)"),
+                &["*.json"],
+            )
+            .await?;
+            let contents = crate::file::read_file(&file).await?;
+            let imported = crate::import::postman::parse_postman(&contents)?;
+            self.import_collection(imported).await
+        }
+
+        async fn trigger_import_har(&self) -> Result<(), CarteroError> {
+            let obj = self.obj();
+            let file = crate::widgets::open_import_file(
+                &obj,
+                &gettext("Import HAR archive"),
+                &gettext("HTTP archive (.har)"),
+                &["*.har", "*.json"],
+            )
+            .await?;
+            let contents = crate::file::read_file(&file).await?;
+            let imported = crate::import::har::parse_har(&contents)?;
+            self.import_collection(imported).await
+        }
+
+        async fn trigger_export_har(&self) -> Result<(), CarteroError> {
+            let Some(pane) = self.current_pane().and_then(|pane| pane.endpoint()) else {
+                return Ok(());
+            };
+            let contents = pane.export_har()?;
+            let obj = self.obj();
+            let file = crate::widgets::save_export_file(
+                &obj,
+                &gettext("Export HAR archive"),
+                "request.har",
+            )
+            .await?;
+            crate::file::write_file(&file, &contents).await
+        }
+
+        async fn trigger_import_openapi(&self) -> Result<(), CarteroError> {
+            let obj = self.obj();
+            let file = crate::widgets::open_import_file(
+                &obj,
+                &gettext("Import OpenAPI specification"),
+                &gettext("OpenAPI or Swagger specification"),
+                &["*.json", "*.yaml", "*.yml"],
+            )
+            .await?;
+            let contents = crate::file::read_file(&file).await?;
+            let imported = crate::import::openapi::parse_openapi(&contents)?;
+            self.import_collection(imported).await
+        }
+
+        /// Creates a new tab out of the curl command line found in the clipboard.
+        async fn trigger_paste_curl(&self) -> Result<(), CarteroError> {
+            let clipboard = self.obj().clipboard();
+            let text = clipboard
+                .read_text_future()
+                .await
+                .ok()
+                .flatten()
+                .ok_or(ImportError::NotCurl)?;
+            let endpoint = crate::import::curl::parse_curl(&text)?;
+            self.add_imported_endpoint(&endpoint).await;
+            Ok(())
+        }
+
         async fn trigger_open(&self) -> Result<(), CarteroError> {
             // In order to place the modal, we need a reference to the public type.
             let obj = self.obj();
@@ -249,7 +483,7 @@ mod imp {
         }
 
         async fn save_pane(&self, pane: &ItemPane) -> Result<(), CarteroError> {
-            let Some(endpoint) = pane.endpoint() else {
+            let Some(item) = pane.extract_item()? else {
                 return Ok(());
             };
 
@@ -261,8 +495,7 @@ mod imp {
                 }
             };
 
-            let endpoint = endpoint.extract_endpoint()?;
-            let serialized_payload = crate::file::store_toml(&endpoint)?;
+            let serialized_payload = crate::file::store_item(&item)?;
             crate::file::write_file(&file, &serialized_payload).await?;
             pane.set_file(Some(file.clone()));
             pane.set_dirty(false);
@@ -271,15 +504,14 @@ mod imp {
         }
 
         async fn save_pane_as(&self, pane: &ItemPane) -> Result<(), CarteroError> {
-            let Some(endpoint) = pane.endpoint() else {
+            let Some(item) = pane.extract_item()? else {
                 return Ok(());
             };
 
             let obj = self.obj();
             let file = crate::widgets::save_file(&obj).await?;
 
-            let endpoint = endpoint.extract_endpoint()?;
-            let serialized_payload = crate::file::store_toml(&endpoint)?;
+            let serialized_payload = crate::file::store_item(&item)?;
             crate::file::write_file(&file, &serialized_payload).await?;
             pane.set_file(Some(file.clone()));
             pane.set_dirty(false);
@@ -295,6 +527,7 @@ mod imp {
             if res.is_ok() {
                 self.bind_current_tab(Some(&pane));
                 self.save_visible_tabs();
+                let _ = self.collection_pane.refresh();
             }
             res
         }
@@ -307,15 +540,249 @@ mod imp {
             if res.is_ok() {
                 self.bind_current_tab(Some(&pane));
                 self.save_visible_tabs();
+                let _ = self.collection_pane.refresh();
             }
             res
         }
 
+        /// Opens the collection at the given folder in the sidebar.
+        pub(super) fn open_collection(&self, folder: &Path) -> Result<(), CarteroError> {
+            self.collection_pane.open_collection(folder)?;
+            self.split_view.set_show_sidebar(true);
+            self.save_open_collection();
+            Ok(())
+        }
+
+        fn save_open_collection(&self) {
+            let root = self
+                .collection_pane
+                .collection_root()
+                .map(|root| root.display().to_string());
+            let app = CarteroApplication::get();
+            let settings = app.settings();
+            settings.set("open-collection", root).unwrap();
+        }
+
+        async fn trigger_open_collection(&self) -> Result<(), CarteroError> {
+            let obj = self.obj();
+            let folder = crate::widgets::open_folder(&obj).await?;
+            let Some(path) = folder.path() else {
+                return Err(CarteroError::FileDialogError);
+            };
+            self.open_collection(&path)
+        }
+
+        async fn trigger_new_collection(&self) -> Result<(), CarteroError> {
+            let obj = self.obj();
+            let folder = crate::widgets::open_folder(&obj).await?;
+            let Some(path) = folder.path() else {
+                return Err(CarteroError::FileDialogError);
+            };
+
+            // Picking a folder that is already a collection just opens it.
+            if path.join(crate::collection::MANIFEST_FILE_NAME).exists() {
+                return self.open_collection(&path);
+            }
+
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| gettext("Untitled collection"));
+            self.collection_pane.create_collection(&path, &name)?;
+            self.split_view.set_show_sidebar(true);
+            self.save_open_collection();
+            Ok(())
+        }
+
+        fn close_collection(&self) {
+            self.collection_pane.close_collection();
+            self.save_open_collection();
+        }
+
+        /// Fills the environment selector with the environments of the open collection.
+        ///
+        /// The previously active environment is selected again if it still exists.
+        fn sync_environments(&self) {
+            let app = CarteroApplication::get();
+            let settings = app.settings();
+            let active = settings.get::<Option<String>>("active-environment");
+
+            let environments = self.collection_pane.environments();
+            let mut names = vec![gettext("No environment")];
+            names.extend(environments.iter().cloned());
+            let names: Vec<&str> = names.iter().map(String::as_str).collect();
+            let count = self.environment_list.n_items();
+            self.environment_list.splice(0, count, &names);
+
+            let position = active
+                .and_then(|name| environments.iter().position(|env| *env == name))
+                .map(|pos| pos as u32 + 1)
+                .unwrap_or(0);
+            self.environment_selector.set_selected(position);
+            self.environment_selector
+                .set_sensitive(self.collection_pane.collection_root().is_some());
+        }
+
+        fn save_active_environment(&self) {
+            // Keep the last choice around while there is no collection to pick from.
+            if self.collection_pane.collection_root().is_none() {
+                return;
+            }
+            let name = match self.environment_selector.selected() {
+                0 | gtk::INVALID_LIST_POSITION => None,
+                pos => self.environment_list.string(pos).map(String::from),
+            };
+            let app = CarteroApplication::get();
+            let settings = app.settings();
+            settings.set("active-environment", name).unwrap();
+        }
+
+        /// The name of the environment currently picked in the selector, if any.
+        fn active_environment_name(&self) -> Option<String> {
+            let position = self.environment_selector.selected();
+            if position == 0 || position == gtk::INVALID_LIST_POSITION {
+                return None;
+            }
+            self.environment_list.string(position).map(String::from)
+        }
+
+        /// Loads the environment currently picked in the selector, if any, with
+        /// the variables of the collection added under its own.
+        ///
+        /// The file is read again every time, so that changes made outside the
+        /// application are taken into account.
+        pub(super) fn active_environment(&self) -> Result<Option<Environment>, CarteroError> {
+            let environment = match self.active_environment_name() {
+                Some(name) => Some(self.collection_pane.load_environment(&name)?),
+                None => None,
+            };
+            Ok(self.collection_pane.scoped_environment(environment))
+        }
+
+        /// Stores the values extracted from the last response of the pane in the
+        /// variables of the active environment or of the collection.
+        fn store_extracted(&self, pane: &EndpointPane) -> Result<(), CarteroError> {
+            let results = pane.extract_variables();
+            for result in &results {
+                if let Err(e) = &result.value {
+                    let message = gettext("Cannot extract {}: {}")
+                        .replacen("{}", &result.extraction.variable, 1)
+                        .replacen("{}", &e.to_string(), 1);
+                    self.toaster.add_toast(adw::Toast::new(&message));
+                }
+            }
+            if !results.iter().any(|result| result.value.is_ok()) {
+                return Ok(());
+            }
+            if self.collection_pane.collection_root().is_none() {
+                let message = gettext("Open a collection to keep the extracted variables");
+                self.toaster.add_toast(adw::Toast::new(&message));
+                return Ok(());
+            }
+            let environment = self.active_environment_name();
+            self.collection_pane
+                .store_extracted(environment.as_deref(), &results)
+        }
+
+        fn edit_environment(&self, name: &str) -> Result<(), CarteroError> {
+            let name = if name.is_empty() {
+                match self.environment_selector.selected() {
+                    0 | gtk::INVALID_LIST_POSITION => return Ok(()),
+                    pos => match self.environment_list.string(pos) {
+                        Some(name) => name.to_string(),
+                        None => return Ok(()),
+                    },
+                }
+            } else {
+                name.to_string()
+            };
+
+            let environment = self.collection_pane.load_environment(&name)?;
+            let dialog = EnvironmentDialog::new(&environment);
+            dialog.connect_saved(glib::clone!(@weak self as window => move |dialog| {
+                let environment = dialog.environment();
+                if let Err(e) = window.collection_pane.save_environment(&environment) {
+                    window.toast_error(e);
+                }
+            }));
+            dialog.present(&*self.obj());
+
+            if let Some(position) = (0..self.environment_list.n_items()).skip(1).find(|pos| {
+                self.environment_list
+                    .string(*pos)
+                    .is_some_and(|s| s.as_str() == name)
+            }) {
+                self.environment_selector.set_selected(position);
+            }
+            Ok(())
+        }
+
+        /// The file that keeps the cookies of the open collection, unless cookies
+        /// are turned off in the preferences.
+        pub(super) fn cookie_jar_path(&self) -> Option<PathBuf> {
+            let app = CarteroApplication::get();
+            if !app.settings().boolean("cookies-enabled") {
+                return None;
+            }
+            let root = self.collection_pane.collection_root();
+            Some(app.cookie_jar_path(root.as_deref()))
+        }
+
+        fn edit_cookies(&self) -> Result<(), CarteroError> {
+            let app = CarteroApplication::get();
+            let root = self.collection_pane.collection_root();
+            let path = app.cookie_jar_path(root.as_deref());
+            let jar = CookieJar::load(&path, SystemTime::now())?;
+            let dialog = CookieDialog::new(&jar);
+            dialog.connect_saved(glib::clone!(@weak self as window => move |dialog| {
+                if let Err(e) = dialog.jar().save(&path) {
+                    window.toast_error(e);
+                }
+            }));
+            dialog.present(&*self.obj());
+            Ok(())
+        }
+
+        /// Keeps the tabs pointing to the right file after an item is moved or renamed.
+        fn on_collection_item_moved(&self, from: &gio::File, to: &gio::File) {
+            for page in self.tabview.pages().iter::<TabPage>().flatten() {
+                let pane = page.child().downcast::<ItemPane>().unwrap();
+                let Some(file) = pane.file() else {
+                    continue;
+                };
+                if file.equal(from) {
+                    pane.set_file(Some(to.clone()));
+                } else if let Some(relative) = from.relative_path(&file) {
+                    pane.set_file(Some(to.resolve_relative_path(relative)));
+                }
+            }
+            self.save_visible_tabs();
+        }
+
+        /// Detaches the tabs whose file has been deleted, so that they are not lost.
+        fn on_collection_item_deleted(&self, deleted: &gio::File) {
+            for page in self.tabview.pages().iter::<TabPage>().flatten() {
+                let pane = page.child().downcast::<ItemPane>().unwrap();
+                let Some(file) = pane.file() else {
+                    continue;
+                };
+                if file.equal(deleted) || file.has_prefix(deleted) {
+                    pane.set_file(Option::<gio::File>::None);
+                    pane.set_dirty(true);
+                }
+            }
+            self.save_visible_tabs();
+        }
+
         pub(super) fn toast_error(&self, error: CarteroError) {
             let toast = adw::Toast::new(&error.to_string());
             self.toaster.add_toast(toast);
         }
 
+        pub(super) fn toast_message(&self, message: &str) {
+            self.toaster.add_toast(adw::Toast::new(message));
+        }
+
         fn get_modified_panes(&self) -> Vec<ItemPane> {
             let pages = self.tabview.pages();
             let count = pages.n_items();
@@ -362,6 +829,10 @@ mod imp {
             KeyValueRow::static_type();
             KeyValuePane::static_type();
             EndpointPane::static_type();
+            CollectionPane::static_type();
+            EnvironmentDialog::static_type();
+            ProxyGroup::static_type();
+            HistoryPane::static_type();
             klass.bind_template();
             klass.bind_template_callbacks();
         }
@@ -381,6 +852,7 @@ mod imp {
             }
 
             self.init_settings();
+            self.load_history();
 
             self.tabview.connect_selected_page_notify(
                 glib::clone!(@weak self as window => move |tabview| {
@@ -421,6 +893,9 @@ mod imp {
                     false
                 };
 
+                if !outcome {
+                    item_pane.cancel();
+                }
                 tabview.close_page_finish(tabpage, !outcome);
                 let imp = window.imp();
                 imp.update_tab_actions();
@@ -438,6 +913,36 @@ mod imp {
                 ),
             );
 
+            self.collection_pane
+                .connect_refreshed(glib::clone!(@weak self as window => move |_| {
+                    window.sync_environments();
+                }));
+            self.environment_selector.connect_selected_notify(
+                glib::clone!(@weak self as window => move |_| {
+                    window.save_active_environment();
+                }),
+            );
+            self.sync_environments();
+
+            self.collection_pane.connect_item_activated(
+                glib::clone!(@weak self as window => move |_, file| {
+                    let file = file.clone();
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        window.add_endpoint(Some(&file)).await;
+                    }));
+                }),
+            );
+            self.collection_pane.connect_item_moved(
+                glib::clone!(@weak self as window => move |_, from, to| {
+                    window.on_collection_item_moved(from, to);
+                }),
+            );
+            self.collection_pane.connect_item_deleted(
+                glib::clone!(@weak self as window => move |_, file| {
+                    window.on_collection_item_deleted(file);
+                }),
+            );
+
             let action_new = ActionEntry::builder("new")
                 .activate(glib::clone!(@weak self as window => move |_, _, _| {
                     glib::spawn_future_local(async move {
@@ -446,15 +951,35 @@ mod imp {
                 }))
                 .build();
 
+            let action_new_websocket = ActionEntry::builder("new-websocket")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    window.add_websocket();
+                }))
+                .build();
+
             let action_request = ActionEntry::builder("request")
                 .activate(glib::clone!(@weak self as window => move |_, _, _| {
                     glib::spawn_future_local(glib::clone!(@weak window => async move {
                         if let Some(pane) = window.current_pane().and_then(|e| e.endpoint()) {
-                            pane.set_sensitive(false);
-                            if let Err(e) = pane.perform_request().await {
-                                window.toast_error(e);
+                            let environment = match window.active_environment() {
+                                Ok(environment) => environment,
+                                Err(e) => {
+                                    window.toast_error(e);
+                                    return;
+                                }
+                            };
+                            match pane.perform_request(environment.as_ref()).await {
+                                Ok(_) => {
+                                    if let Err(e) = window.record_history(&pane) {
+                                        window.toast_error(e);
+                                    }
+                                    if let Err(e) = window.store_extracted(&pane) {
+                                        window.toast_error(e);
+                                    }
+                                }
+                                Err(CarteroError::Request(RequestError::Cancelled)) => {}
+                                Err(e) => window.toast_error(e),
                             }
-                            pane.set_sensitive(true);
                         }
                     }));
                 }))
@@ -497,6 +1022,132 @@ mod imp {
                 }))
                 .build();
 
+            let action_paste_curl = ActionEntry::builder("paste-curl")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        if let Err(e) = window.trigger_paste_curl().await {
+                            window.toast_error(e);
+                        }
+                    }));
+                }))
+                .build();
+
+            let action_export_request = ActionEntry::builder("export-request")
+                .parameter_type(Some(glib::VariantTy::STRING))
+                .activate(glib::clone!(@weak self as window => move |_, _, param| {
+                    let format = param.and_then(|p| p.get::<String>()).unwrap_or_default();
+                    if let Err(e) = window.trigger_export_request(&format) {
+                        window.toast_error(e);
+                    }
+                }))
+                .build();
+
+            let action_import_postman = ActionEntry::builder("import-postman")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        if let Err(e) = window.trigger_import_postman().await {
+                            match e {
+                                CarteroError::NoFilePicked => {},
+                                e => window.toast_error(e),
+                            };
+                        }
+                    }));
+                }))
+                .build();
+
+            let action_import_openapi = ActionEntry::builder("import-openapi")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        if let Err(e) = window.trigger_import_openapi().await {
+                            match e {
+                                CarteroError::NoFilePicked => {},
+                                e => window.toast_error(e),
+                            };
+                        }
+                    }));
+                }))
+                .build();
+
+            let action_import_har = ActionEntry::builder("import-har")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        if let Err(e) = window.trigger_import_har().await {
+                            match e {
+                                CarteroError::NoFilePicked => {},
+                                e => window.toast_error(e),
+                            };
+                        }
+                    }));
+                }))
+                .build();
+            let action_export_har = ActionEntry::builder("export-har")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        if let Err(e) = window.trigger_export_har().await {
+                            match e {
+                                CarteroError::NoFilePicked => {},
+                                e => window.toast_error(e),
+                            };
+                        }
+                    }));
+                }))
+                .build();
+
+            let action_open_collection = ActionEntry::builder("open-collection")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        if let Err(e) = window.trigger_open_collection().await {
+                            match e {
+                                CarteroError::NoFilePicked => {},
+                                e => window.toast_error(e),
+                            };
+                        }
+                    }));
+                }))
+                .build();
+            let action_new_collection = ActionEntry::builder("new-collection")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    glib::spawn_future_local(glib::clone!(@weak window => async move {
+                        if let Err(e) = window.trigger_new_collection().await {
+                            match e {
+                                CarteroError::NoFilePicked => {},
+                                e => window.toast_error(e),
+                            };
+                        }
+                    }));
+                }))
+                .build();
+            let action_close_collection = ActionEntry::builder("close-collection")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    window.close_collection();
+                }))
+                .build();
+
+            let action_edit_environment = ActionEntry::builder("edit-environment")
+                .parameter_type(Some(glib::VariantTy::STRING))
+                .activate(glib::clone!(@weak self as window => move |_, _, param| {
+                    let name = param.and_then(|p| p.get::<String>()).unwrap_or_default();
+                    if let Err(e) = window.edit_environment(&name) {
+                        window.toast_error(e);
+                    }
+                }))
+                .build();
+
+            let action_edit_cookies = ActionEntry::builder("edit-cookies")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    if let Err(e) = window.edit_cookies() {
+                        window.toast_error(e);
+                    }
+                }))
+                .build();
+
+            let action_preferences = ActionEntry::builder("preferences")
+                .activate(glib::clone!(@weak self as window => move |_, _, _| {
+                    let dialog = PreferencesDialog::default();
+                    dialog.present(&*window.obj());
+                }))
+                .build();
+
             let action_about = ActionEntry::builder("about")
                 .activate(glib::clone!(@weak self as window => move |_, _, _| {
                     let about = AboutWindow::builder()
@@ -519,11 +1170,24 @@ mod imp {
             let obj = self.obj();
             obj.add_action_entries([
                 action_new,
+                action_new_websocket,
                 action_request,
                 action_open,
                 action_save,
                 action_save_as,
                 action_close,
+                action_paste_curl,
+                action_export_request,
+                action_import_postman,
+                action_import_openapi,
+                action_import_har,
+                action_export_har,
+                action_open_collection,
+                action_new_collection,
+                action_close_collection,
+                action_edit_environment,
+                action_edit_cookies,
+                action_preferences,
                 action_about,
             ]);
             self.update_tab_actions();
@@ -592,6 +1256,26 @@ impl CarteroWindow {
         imp.toast_error(e);
     }
 
+    pub fn toast_message(&self, message: &str) {
+        self.imp().toast_message(message);
+    }
+
+    /// The file that keeps the cookies sent from this window, if cookies are enabled.
+    pub fn cookie_jar_path(&self) -> Option<PathBuf> {
+        self.imp().cookie_jar_path()
+    }
+
+    /// Opens a request from the history in a new tab.
+    pub fn open_history_entry(&self, entry: &HistoryEntry) {
+        self.imp().open_history_entry(entry);
+    }
+
+    /// The environment selected in the window, if any.
+    pub fn active_environment(&self) -> Result<Option<Environment>, CarteroError> {
+        let imp = self.imp();
+        imp.active_environment()
+    }
+
     pub fn sync_open_files(&self) {
         let imp = self.imp();
         imp.save_visible_tabs();
@@ -608,5 +1292,11 @@ impl CarteroWindow {
                 self.add_endpoint(Some(&path)).await;
             }
         }
+
+        if let Some(root) = settings.get::<Option<String>>("open-collection") {
+            if let Err(e) = self.imp().open_collection(Path::new(&root)) {
+                self.toast_error(e);
+            }
+        }
     }
 }